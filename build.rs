@@ -30,13 +30,19 @@
 //!
 //! ### d) Response Type Deduplication (deduplicate_response_types)
 //! **CRITICAL FOR PROGENITOR**: Progenitor 0.11.0 has an assertion that fails when an API
-//! operation has multiple success responses (e.g., 200, 201, 204). We simplify each operation
-//! to have only one response to avoid this limitation.
+//! operation has multiple success response types (e.g., 200, 201, 204). We merge the success
+//! responses of each operation into one `oneOf` type with a variant per status code, so no
+//! response shape is lost.
 //!
 //! ## 4. Code Generation (generate_client_code)
 //! Uses the progenitor library to generate Rust code from the processed OpenAPI spec.
 //! The workflow: YAML → JSON → OpenAPI struct → proc-macro tokens → syn AST → formatted code
 //!
+//! Before formatting, every string enum in the syn AST gets an `UnknownValue(String)`
+//! catch-all variant (add_unknown_enum_variants), so values DigitalOcean adds later
+//! (new regions, sizes, statuses) deserialize instead of failing the whole call.
+//! List responses also get an impl of `pagination::Paginated` (add_paginated_impls).
+//!
 //! ## Fallback Strategy
 //! If any stage fails, a minimal stub client is generated instead of failing the build.
 //! This allows the crate to compile even if the OpenAPI spec is temporarily unavailable.
@@ -55,6 +61,9 @@ use std::{
 /// 2. Process spec with full reference resolution and fixups
 /// 3. Generate Rust client code using progenitor
 /// 4. Write generated code to OUT_DIR/codegen.rs
+/// 5. Write the operation metadata registry to OUT_DIR/operations.rs
+/// 6. Write `*_all`, `*_with` and, where tags filter the list, `*_by_tag_stream`
+///    methods per paginated list operation to OUT_DIR/list_all.rs
 ///
 /// ## Error Handling:
 /// If any stage fails, writes a fallback stub client instead of failing the build.
@@ -63,6 +72,8 @@ fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let spec_dir = Path::new(&out_dir).join("digitalocean-openapi");
     let output_path = Path::new(&out_dir).join("codegen.rs");
+    let operations_path = Path::new(&out_dir).join("operations.rs");
+    let list_all_path = Path::new(&out_dir).join("list_all.rs");
 
     // Download and extract OpenAPI specification
     if !spec_dir.exists() {
@@ -70,6 +81,8 @@ fn main() {
             eprintln!("Failed to download OpenAPI spec: {}", e);
             println!("cargo:warning=Failed to download OpenAPI spec, using fallback stub");
             write_stub_client(&output_path);
+            write_operation_registry(&operations_path, &[]);
+            write_list_methods(&list_all_path, &[], &HashMap::new());
             return;
         }
     }
@@ -78,15 +91,20 @@ fn main() {
     let spec_path = spec_dir.join("specification/DigitalOcean-public.v2.yaml");
     match process_openapi_spec(&spec_path) {
         Ok(resolved_spec) => {
+            // Record per-operation metadata before progenitor sees the spec
+            let operations = collect_operations(&resolved_spec);
+            write_operation_registry(&operations_path, &operations);
+
             // Generate client using progenitor
-            match generate_client_code(&resolved_spec) {
-                Ok(generated_code) => {
+            let item_types = match generate_client_code(&resolved_spec) {
+                Ok((generated_code, item_types)) => {
                     fs::write(&output_path, generated_code)
                         .unwrap_or_else(|e| panic!("Failed to write generated client code: {}", e));
                     println!(
                         "Generated DigitalOcean client code at: {}",
                         output_path.display()
                     );
+                    item_types
                 }
                 Err(e) => {
                     eprintln!("Failed to generate client code: {}", e);
@@ -95,8 +113,10 @@ fn main() {
                         e
                     );
                     write_stub_client(&output_path);
+                    HashMap::new()
                 }
-            }
+            };
+            write_list_methods(&list_all_path, &operations, &item_types);
         }
         Err(e) => {
             eprintln!("Failed to process OpenAPI spec: {}", e);
@@ -105,6 +125,8 @@ fn main() {
                 e
             );
             write_stub_client(&output_path);
+            write_operation_registry(&operations_path, &[]);
+            write_list_methods(&list_all_path, &[], &HashMap::new());
         }
     }
 }
@@ -1044,28 +1066,42 @@ properties:
     /// thread 'main' panicked at 'assertion failed: success_responses.len() <= 1'
     /// ```
     ///
+    /// The assertion counts distinct success *types*, not status codes, so several
+    /// status codes may share one type.
+    ///
     /// ### The Solution:
     /// For any operation with multiple 2xx responses (or >2 total responses), we:
-    /// 1. Keep ONLY the first success response (200, 201, etc.)
-    /// 2. Remove all other success responses
-    /// 3. Also simplify content-types (keep only first, e.g., `application/json`)
+    /// 1. Simplify content-types (keep only first, e.g., `application/json`)
+    /// 2. Keep every success status code whose body has the same schema as the others
+    /// 3. When the bodies differ, add a `<operationId>_response_<status>` component
+    ///    schema per status code plus a `<operationId>_response` `oneOf` of them, and
+    ///    point every success status code at the `oneOf`
+    /// 4. Drop bodiless success responses (204) when others have a body, since `()`
+    ///    and a JSON type cannot be merged
+    /// 5. Remove the error responses, as before
     ///
     /// ### Impact:
-    /// This means generated Rust functions will only return the first response type.
-    /// Users won't get distinct types for 200 vs 201 vs 204. This is a limitation
-    /// of progenitor 0.11.0, not the DigitalOcean API.
+    /// Every status code with a body is still accepted, and each response shape gets
+    /// its own variant in the generated response enum (`Status200(..)`-style variants
+    /// named after the per-status schemas). The enum is untagged, so a body matching
+    /// several shapes decodes as the lowest status code's variant.
     ///
     /// ### Example Transformation:
     /// ```yaml
     /// # Before:
     /// responses:
-    ///   200: { ... }
-    ///   201: { ... }
-    ///   204: { ... }
+    ///   200: { content: { application/json: { schema: A } } }
+    ///   202: { content: { application/json: { schema: B } } }
     ///
     /// # After:
     /// responses:
-    ///   200: { ... }  # Only first success response kept
+    ///   200: { content: { application/json: { schema: $ref op_response } } }
+    ///   202: { content: { application/json: { schema: $ref op_response } } }
+    /// components:
+    ///   schemas:
+    ///     op_response_200: A
+    ///     op_response_202: B
+    ///     op_response: { oneOf: [$ref op_response_200, $ref op_response_202] }
     /// ```
     fn deduplicate_response_types(
         &self,
@@ -1074,6 +1110,7 @@ properties:
         println!("Deduplicating response types to prevent progenitor assertion failures...");
         let mut operations_modified = 0;
         let mut total_responses_removed = 0;
+        let mut merged_schemas = Vec::new();
 
         if let Some(obj) = value.as_mapping_mut() {
             if let Some(paths) = obj.get_mut(&Value::String("paths".to_string())) {
@@ -1148,56 +1185,18 @@ properties:
 
                                                     responses_map.clear();
 
-                                                    // Keep only one response: prefer first success, then first error, then default
-                                                    if let Some((first_status, first_response)) =
-                                                        success_responses.into_iter().next()
-                                                    {
-                                                        // Also simplify the response content to avoid multiple content types
-                                                        let mut simplified_response =
-                                                            first_response;
-                                                        if let Some(response_obj) =
-                                                            simplified_response.as_mapping_mut()
+                                                    // Keep every success response, merged into one
+                                                    // type; otherwise the first error, then default
+                                                    if !success_responses.is_empty() {
+                                                        for (status, response) in
+                                                            merge_success_responses(
+                                                                &operation_id,
+                                                                success_responses,
+                                                                &mut merged_schemas,
+                                                            )
                                                         {
-                                                            if let Some(content) = response_obj
-                                                                .get_mut(&Value::String(
-                                                                    "content".to_string(),
-                                                                ))
-                                                            {
-                                                                if let Some(content_map) =
-                                                                    content.as_mapping_mut()
-                                                                {
-                                                                    // Keep only the first content type to avoid multiple response types
-                                                                    if content_map.len() > 1 {
-                                                                        let first_content_type =
-                                                                            content_map
-                                                                                .keys()
-                                                                                .next()
-                                                                                .cloned();
-                                                                        if let Some(first_key) =
-                                                                            first_content_type
-                                                                        {
-                                                                            let first_value =
-                                                                                content_map
-                                                                                    .get(&first_key)
-                                                                                    .cloned();
-                                                                            content_map.clear();
-                                                                            if let Some(value) =
-                                                                                first_value
-                                                                            {
-                                                                                content_map.insert(
-                                                                                    first_key,
-                                                                                    value,
-                                                                                );
-                                                                            }
-                                                                        }
-                                                                    }
-                                                                }
-                                                            }
+                                                            responses_map.insert(status, response);
                                                         }
-                                                        responses_map.insert(
-                                                            first_status,
-                                                            simplified_response,
-                                                        );
                                                     } else if let Some((
                                                         first_status,
                                                         first_response,
@@ -1253,6 +1252,14 @@ properties:
             }
         }
 
+        if !merged_schemas.is_empty() {
+            println!(
+                "Adding {} schemas for operations with several success response shapes",
+                merged_schemas.len()
+            );
+            add_component_schemas(value, merged_schemas);
+        }
+
         if operations_modified > 0 {
             println!(
                 "Modified {} operations, removed {} duplicate responses",
@@ -1578,6 +1585,143 @@ properties:
     }
 }
 
+/// Merges the 2xx responses of `operation_id` so progenitor sees a single success type
+/// without losing any status code or response shape.
+///
+/// Returns the responses to keep. Component schemas needed by the merged responses are
+/// appended to `new_schemas`. See `deduplicate_response_types` for the full rules.
+fn merge_success_responses(
+    operation_id: &str,
+    mut successes: Vec<(Value, Value)>,
+    new_schemas: &mut Vec<(String, Value)>,
+) -> Vec<(Value, Value)> {
+    for (_, response) in &mut successes {
+        keep_first_content_type(response);
+    }
+    let (with_body, without_body): (Vec<_>, Vec<_>) = successes
+        .into_iter()
+        .partition(|(_, response)| response_schema(response).is_some());
+    if with_body.is_empty() {
+        // Bodiless responses all map to `()`.
+        return without_body;
+    }
+    for (status, _) in &without_body {
+        println!(
+            "Operation '{}': dropping bodiless {} response in favour of the typed ones",
+            operation_id,
+            status_text(status)
+        );
+    }
+
+    let first_schema = response_schema(&with_body[0].1).cloned();
+    if with_body
+        .iter()
+        .all(|(_, response)| response_schema(response) == first_schema.as_ref())
+    {
+        return with_body;
+    }
+
+    let union_name = format!("{operation_id}_response");
+    let mut variants = Vec::new();
+    for (status, response) in &with_body {
+        let name = format!("{union_name}_{}", status_text(status));
+        if let Some(schema) = response_schema(response) {
+            new_schemas.push((name.clone(), schema.clone()));
+        }
+        variants.push(schema_ref(&name));
+    }
+    let mut union = serde_yaml::Mapping::new();
+    union.insert(
+        Value::String("oneOf".to_string()),
+        Value::Sequence(variants),
+    );
+    new_schemas.push((union_name.clone(), Value::Mapping(union)));
+    println!(
+        "Operation '{}' has {} success response shapes, merged into {}",
+        operation_id,
+        with_body.len(),
+        union_name
+    );
+
+    with_body
+        .into_iter()
+        .map(|(status, mut response)| {
+            if let Some(media) = response
+                .get_mut("content")
+                .and_then(Value::as_mapping_mut)
+                .and_then(|content| content.values_mut().next())
+                .and_then(Value::as_mapping_mut)
+            {
+                media.insert(Value::String("schema".to_string()), schema_ref(&union_name));
+            }
+            (status, response)
+        })
+        .collect()
+}
+
+/// Keeps only the first content type of a response, so it maps to a single type.
+fn keep_first_content_type(response: &mut Value) {
+    let Some(content) = response.get_mut("content").and_then(Value::as_mapping_mut) else {
+        return;
+    };
+    if content.len() > 1 {
+        let first = content.iter().next().map(|(k, v)| (k.clone(), v.clone()));
+        content.clear();
+        if let Some((key, value)) = first {
+            content.insert(key, value);
+        }
+    }
+}
+
+/// The body schema of a response, if it has one.
+fn response_schema(response: &Value) -> Option<&Value> {
+    response
+        .get("content")?
+        .as_mapping()?
+        .values()
+        .next()?
+        .get("schema")
+}
+
+/// A status code key as text; YAML may parse unquoted codes as numbers.
+fn status_text(status: &Value) -> String {
+    match status {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => "default".to_string(),
+    }
+}
+
+fn schema_ref(name: &str) -> Value {
+    let mut reference = serde_yaml::Mapping::new();
+    reference.insert(
+        Value::String("$ref".to_string()),
+        Value::String(format!("#/components/schemas/{name}")),
+    );
+    Value::Mapping(reference)
+}
+
+/// Adds `schemas` to `components.schemas`, creating the sections if missing.
+fn add_component_schemas(spec: &mut Value, schemas: Vec<(String, Value)>) {
+    let Some(spec) = spec.as_mapping_mut() else {
+        return;
+    };
+    let components = spec
+        .entry(Value::String("components".to_string()))
+        .or_insert_with(|| Value::Mapping(serde_yaml::Mapping::new()));
+    let Some(components) = components.as_mapping_mut() else {
+        return;
+    };
+    let existing = components
+        .entry(Value::String("schemas".to_string()))
+        .or_insert_with(|| Value::Mapping(serde_yaml::Mapping::new()));
+    if let Some(existing) = existing.as_mapping_mut() {
+        for (name, schema) in schemas {
+            existing.insert(Value::String(name), schema);
+        }
+    }
+}
+
 /// Generates Rust client code from the processed OpenAPI specification.
 ///
 /// ## Code Generation Pipeline:
@@ -1619,7 +1763,9 @@ properties:
 /// - `Client` struct with 500+ async methods
 /// - `types` module with schema definitions
 /// - Error types and response wrappers
-fn generate_client_code(spec: &Value) -> Result<String, Box<dyn std::error::Error>> {
+fn generate_client_code(
+    spec: &Value,
+) -> Result<(String, HashMap<String, String>), Box<dyn std::error::Error>> {
     println!("Generating Rust client code using progenitor...");
 
     // Convert YAML to JSON for progenitor
@@ -1654,9 +1800,12 @@ fn generate_client_code(spec: &Value) -> Result<String, Box<dyn std::error::Erro
             }
         };
 
-    // Generate the client using progenitor
+    // Generate the client using progenitor. The inner type carries rsdo's per-client
+    // transport configuration (retry policy etc.) alongside the reqwest client.
     println!("Creating progenitor generator...");
-    let mut generator = progenitor::Generator::default();
+    let mut settings = progenitor::GenerationSettings::default();
+    settings.with_inner_type(quote::quote!(crate::ClientState));
+    let mut generator = progenitor::Generator::new(&settings);
 
     println!("Starting token generation with progenitor...");
     let tokens = match generator.generate_tokens(&openapi_spec) {
@@ -1684,7 +1833,7 @@ fn generate_client_code(spec: &Value) -> Result<String, Box<dyn std::error::Erro
     };
 
     println!("Parsing generated tokens into syntax tree...");
-    let syntax_tree = match syn::parse2(tokens) {
+    let mut syntax_tree: syn::File = match syn::parse2(tokens) {
         Ok(tree) => {
             println!("Successfully parsed generated tokens");
             tree
@@ -1695,6 +1844,23 @@ fn generate_client_code(spec: &Value) -> Result<String, Box<dyn std::error::Erro
         }
     };
 
+    // Let string enums accept values added to the API after generation
+    let lenient_enums = add_unknown_enum_variants(&mut syntax_tree);
+    println!(
+        "Added an {} variant to {} string enums",
+        UNKNOWN_VARIANT, lenient_enums
+    );
+
+    // Give list responses a common interface for generic pagination tooling
+    let paginated = add_paginated_impls(&mut syntax_tree);
+    println!("Implemented Paginated for {} list responses", paginated.len());
+    let item_types = list_item_types(&syntax_tree, &paginated);
+
+    // Keep `Client::new`/`new_with_client` callable without a `ClientState`
+    if !keep_stateless_constructors(&mut syntax_tree) {
+        println!("cargo:warning=Could not find progenitor's Client constructors; kept them as generated");
+    }
+
     println!("Converting syntax tree to formatted code...");
     let mut code = prettyplease::unparse(&syntax_tree);
 
@@ -1732,11 +1898,804 @@ fn generate_client_code(spec: &Value) -> Result<String, Box<dyn std::error::Erro
         "#[allow(mismatched_lifetime_syntaxes)]",
     );
 
+    // Route every generated operation through rsdo's transport layer
+    code = install_client_hooks(code);
+    code = route_response_decoding(code);
+
     println!(
         "Successfully generated {} characters of Rust client code (with lint suppressions)",
         code.len()
     );
-    Ok(code)
+    Ok((code, item_types))
+}
+
+/// The empty `ClientHooks` implementation progenitor emits when no hooks are configured.
+const DEFAULT_CLIENT_HOOKS: &str = "impl ClientHooks<crate::ClientState> for &Client {}";
+
+/// `ClientHooks` implementation that forwards to `crate::transport`.
+const RSDO_CLIENT_HOOKS: &str = r#"impl ClientHooks<crate::ClientState> for &Client {
+    async fn exec(
+        &self,
+        request: reqwest::Request,
+        info: &OperationInfo,
+    ) -> reqwest::Result<reqwest::Response> {
+        crate::transport::execute_generated(self.client(), self.inner(), request, info.operation_id)
+            .await
+    }
+}"#;
+
+/// Replaces progenitor's no-op `ClientHooks` impl with one that calls into rsdo.
+///
+/// ## Why This Exists:
+/// Progenitor invokes `pre`/`exec`/`post` hooks around every generated operation,
+/// but only offers closures over an inner type for customizing them. Swapping the
+/// empty impl lets hand-written code in `src/transport.rs` see (and adjust) every
+/// outgoing request, e.g. to clamp `per_page` to the operation's documented maximum,
+/// and own its execution, e.g. to retry connection failures of idempotent requests.
+/// Both happen in `exec`: `pre` could only fail with `Error::Custom` and a message,
+/// while `exec` hands errors such as `ReadOnlyViolation` or `Cancelled` back to the
+/// caller intact (see `transport::execute_generated`).
+///
+/// If the expected impl is not found (progenitor changed its output), the code is
+/// returned unchanged and a cargo warning is emitted; the client still works, just
+/// without the transport adjustments.
+fn install_client_hooks(code: String) -> String {
+    if code.contains(DEFAULT_CLIENT_HOOKS) {
+        code.replace(DEFAULT_CLIENT_HOOKS, RSDO_CLIENT_HOOKS)
+    } else {
+        println!(
+            "cargo:warning=Could not find progenitor's ClientHooks impl; transport hooks not installed"
+        );
+        code
+    }
+}
+
+/// How progenitor decodes the JSON body of a response.
+const DEFAULT_RESPONSE_DECODING: &str = "ResponseValue::from_response(";
+
+/// Replaces progenitor's response decoding with `crate::transport::decode_response`.
+///
+/// ## Why This Exists:
+/// A body that fails to decode surfaces as `InvalidResponsePayload`, which keeps the
+/// body but not the status or the call, so the converted `rsdo::Error` could not say
+/// which operation got the unexpected payload. `decode_response` decodes the same way
+/// and keeps both on the error, so the failure converts into `Error::InvalidResponse`.
+/// It also passes on the errors the transport hooks carry instead of decoding them.
+///
+/// If progenitor no longer decodes this way, the code is returned unchanged and a
+/// cargo warning is emitted.
+fn route_response_decoding(code: String) -> String {
+    if code.contains(DEFAULT_RESPONSE_DECODING) {
+        code.replace(
+            DEFAULT_RESPONSE_DECODING,
+            "crate::transport::decode_response(",
+        )
+    } else {
+        println!(
+            "cargo:warning=Could not find progenitor's response decoding; decode errors will not name their operation"
+        );
+        code
+    }
+}
+
+/// Renames progenitor's `Client` constructors and adds the ones without a state.
+///
+/// ## Why This Exists:
+/// With an inner type configured, progenitor's `Client::new(baseurl, inner)` and
+/// `Client::new_with_client(baseurl, client, inner)` require a `ClientState`, which
+/// broke every caller of the two-argument `new_with_client`. The generated
+/// constructors become `new_with_state` and `new_with_client_and_state`, and `new`
+/// and `new_with_client` are added back with their original signatures, using
+/// `ClientState::default()`.
+///
+/// Returns whether the constructors were found.
+fn keep_stateless_constructors(file: &mut syn::File) -> bool {
+    let client_impl = file.items.iter_mut().find_map(|item| match item {
+        syn::Item::Impl(item)
+            if item.trait_.is_none()
+                && matches!(&*item.self_ty, syn::Type::Path(ty) if ty.path.is_ident("Client"))
+                && item.items.iter().any(|item| {
+                    matches!(item, syn::ImplItem::Fn(f) if f.sig.ident == "new_with_client")
+                }) =>
+        {
+            Some(item)
+        }
+        _ => None,
+    });
+    let Some(client_impl) = client_impl else {
+        return false;
+    };
+    for item in &mut client_impl.items {
+        let syn::ImplItem::Fn(f) = item else {
+            continue;
+        };
+        let renamed = match f.sig.ident.to_string().as_str() {
+            "new" => "new_with_state",
+            "new_with_client" => "new_with_client_and_state",
+            _ => continue,
+        };
+        f.sig.ident = quote::format_ident!("{}", renamed);
+        let block = &f.block;
+        let body = quote::quote!(#block)
+            .to_string()
+            .replace("Self :: new_with_client (", "Self :: new_with_client_and_state (");
+        if let Ok(block) = syn::parse_str(&body) {
+            f.block = block;
+        }
+    }
+    client_impl.items.push(syn::parse_quote! {
+        /// Create a new client with default rsdo settings.
+        ///
+        /// `baseurl` is the base URL provided to the internal `reqwest::Client`, and
+        /// should include a scheme and hostname, as well as port and a path stem if
+        /// applicable.
+        pub fn new(baseurl: &str) -> Self {
+            Self::new_with_state(baseurl, crate::ClientState::default())
+        }
+    });
+    client_impl.items.push(syn::parse_quote! {
+        /// Construct a new client with an existing `reqwest::Client` and default rsdo
+        /// settings, allowing more control over its configuration.
+        ///
+        /// `baseurl` is the base URL provided to the internal `reqwest::Client`, and
+        /// should include a scheme and hostname, as well as port and a path stem if
+        /// applicable.
+        pub fn new_with_client(baseurl: &str, client: reqwest::Client) -> Self {
+            Self::new_with_client_and_state(baseurl, client, crate::ClientState::default())
+        }
+    });
+    true
+}
+
+/// Name of the catch-all variant added to every generated string enum.
+const UNKNOWN_VARIANT: &str = "UnknownValue";
+
+/// Adds a catch-all `UnknownValue(String)` variant to every generated string enum.
+///
+/// ## Why This Exists:
+/// DigitalOcean regularly adds regions, sizes and statuses without bumping the API
+/// version. Typify turns each `enum:` list into a closed Rust enum, so a single new
+/// value in a response used to fail the whole call with a decode error. With the
+/// catch-all, unknown strings deserialize into `UnknownValue(..)` and serialize back
+/// unchanged.
+///
+/// ## What Changes Per Enum:
+/// Only enums made exclusively of unit variants (typify's "simple" enums, which map
+/// 1:1 to strings) are touched:
+/// - `#[serde(untagged)] UnknownValue(String)` is appended; serde tries it only after
+///   every named variant fails
+/// - `Copy` is removed from the derives, since the variant owns a `String`
+/// - The `Display` impl writes the raw value and `FromStr` (and so the `TryFrom`
+///   impls) accept any string instead of failing with "invalid value"
+///
+/// Enums that already have a variant with that name are left alone.
+fn add_unknown_enum_variants(file: &mut syn::File) -> usize {
+    let mut lenient = std::collections::HashSet::new();
+    make_enums_lenient(&mut file.items, &mut lenient);
+    patch_lenient_enum_impls(&mut file.items, &lenient);
+    lenient.len()
+}
+
+/// Rewrites the declarations of simple enums in `items` (recursing into inline
+/// modules) and records their names in `lenient`.
+fn make_enums_lenient(items: &mut [syn::Item], lenient: &mut std::collections::HashSet<String>) {
+    for item in items {
+        match item {
+            syn::Item::Mod(module) => {
+                if let Some((_, items)) = &mut module.content {
+                    make_enums_lenient(items, lenient);
+                }
+            }
+            syn::Item::Enum(item) => {
+                let simple = !item.variants.is_empty()
+                    && item
+                        .variants
+                        .iter()
+                        .all(|variant| matches!(variant.fields, syn::Fields::Unit))
+                    && !item
+                        .variants
+                        .iter()
+                        .any(|variant| variant.ident == UNKNOWN_VARIANT)
+                    && !item.attrs.iter().any(|attr| attr.path().is_ident("serde"));
+                let derives_deserialize = item.attrs.iter().any(|attr| {
+                    attr.path().is_ident("derive")
+                        && quote::quote!(#attr).to_string().contains("Deserialize")
+                });
+                if !simple || !derives_deserialize {
+                    continue;
+                }
+
+                for attr in &mut item.attrs {
+                    if !attr.path().is_ident("derive") {
+                        continue;
+                    }
+                    let Ok(derives) = attr.parse_args_with(
+                        syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+                    ) else {
+                        continue;
+                    };
+                    let derives = derives.into_iter().filter(|path| !path.is_ident("Copy"));
+                    *attr = syn::parse_quote!(#[derive(#(#derives),*)]);
+                }
+                let unknown = quote::format_ident!("{}", UNKNOWN_VARIANT);
+                item.variants.push(syn::parse_quote! {
+                    /// A value added to the API after this client was generated.
+                    #[serde(untagged)]
+                    #unknown(::std::string::String)
+                });
+                lenient.insert(item.ident.to_string());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Extends the typify `Display` and `FromStr` impls of the enums in `lenient` to
+/// cover the catch-all variant.
+fn patch_lenient_enum_impls(items: &mut [syn::Item], lenient: &std::collections::HashSet<String>) {
+    let unknown = quote::format_ident!("{}", UNKNOWN_VARIANT);
+    for item in items {
+        let item = match item {
+            syn::Item::Mod(module) => {
+                if let Some((_, items)) = &mut module.content {
+                    patch_lenient_enum_impls(items, lenient);
+                }
+                continue;
+            }
+            syn::Item::Impl(item) => item,
+            _ => continue,
+        };
+        let Some((_, trait_path, _)) = &item.trait_ else {
+            continue;
+        };
+        let trait_name = trait_path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string());
+        let syn::Type::Path(self_ty) = item.self_ty.as_ref() else {
+            continue;
+        };
+        let Some(self_name) = self_ty.path.get_ident() else {
+            continue;
+        };
+        if !lenient.contains(&self_name.to_string()) {
+            continue;
+        }
+
+        let Some(syn::ImplItem::Fn(method)) = item
+            .items
+            .iter_mut()
+            .find(|item| matches!(item, syn::ImplItem::Fn(_)))
+        else {
+            continue;
+        };
+        let Some(syn::Stmt::Expr(syn::Expr::Match(body), _)) = method.block.stmts.last_mut() else {
+            continue;
+        };
+        match trait_name.as_deref() {
+            Some("Display") => body.arms.push(syn::parse_quote! {
+                Self::#unknown(ref value) => f.write_str(value),
+            }),
+            Some("FromStr") => {
+                if let Some(arm) = body
+                    .arms
+                    .iter_mut()
+                    .find(|arm| matches!(arm.pat, syn::Pat::Wild(_)))
+                {
+                    *arm.body = syn::parse_quote!(Ok(Self::#unknown(value.to_string())));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Implements `crate::pagination::Paginated` for every generated list response.
+///
+/// ## Why This Exists:
+/// Each list operation gets its own response type (`DropletsListResponse`,
+/// `VolumesListResponse`, ...) with the items under a different field name and
+/// `links`/`meta` typed per response. The trait gives them one interface, so tooling
+/// can be written once over any listable resource.
+///
+/// ## Which Types:
+/// Structs with a `links` field and exactly one other array field (`Vec<T>` or
+/// `Option<Vec<T>>`), which holds the items. `links` and `meta` are read through
+/// serde, so it does not matter which generated types they have or whether `meta`
+/// is present at all.
+///
+/// Returns the item type of each response it implemented the trait for, by type name.
+fn add_paginated_impls(file: &mut syn::File) -> HashMap<String, syn::Type> {
+    let mut item_types = HashMap::new();
+    add_paginated_impls_to(&mut file.items, &mut item_types);
+    item_types
+}
+
+/// Appends the `Paginated` impls for the list responses declared in `items`,
+/// recursing into inline modules.
+fn add_paginated_impls_to(items: &mut Vec<syn::Item>, item_types: &mut HashMap<String, syn::Type>) {
+    let mut impls: Vec<syn::Item> = Vec::new();
+    for item in items.iter_mut() {
+        let item = match item {
+            syn::Item::Mod(module) => {
+                if let Some((_, items)) = &mut module.content {
+                    add_paginated_impls_to(items, item_types);
+                }
+                continue;
+            }
+            syn::Item::Struct(item) => item,
+            _ => continue,
+        };
+        let syn::Fields::Named(fields) = &item.fields else {
+            continue;
+        };
+        let has_field = |name: &str| {
+            fields
+                .named
+                .iter()
+                .any(|field| field.ident.as_ref().is_some_and(|ident| ident == name))
+        };
+        if !has_field("links") || !item.generics.params.is_empty() {
+            continue;
+        }
+        let mut lists = fields.named.iter().filter_map(|field| {
+            let ident = field.ident.as_ref()?;
+            if ident == "links" || ident == "meta" {
+                return None;
+            }
+            vec_item_type(&field.ty).map(|(item_ty, optional)| (ident, item_ty, optional))
+        });
+        let (Some((field, item_ty, optional)), None) = (lists.next(), lists.next()) else {
+            continue;
+        };
+
+        let (items_body, into_items_body) = if optional {
+            (
+                quote::quote!(self.#field.as_deref().unwrap_or_default()),
+                quote::quote!(self.#field.unwrap_or_default()),
+            )
+        } else {
+            (quote::quote!(&self.#field), quote::quote!(self.#field))
+        };
+        let total_body = if has_field("meta") {
+            quote::quote!(crate::pagination::meta_total(&self.meta))
+        } else {
+            quote::quote!(None)
+        };
+        let name = &item.ident;
+        item_types.insert(name.to_string(), item_ty.clone());
+        impls.push(syn::parse_quote! {
+            impl crate::pagination::Paginated for #name {
+                type Item = #item_ty;
+
+                fn items(&self) -> &[Self::Item] {
+                    #items_body
+                }
+
+                fn into_items(self) -> ::std::vec::Vec<Self::Item> {
+                    #into_items_body
+                }
+
+                fn total(&self) -> ::std::option::Option<u64> {
+                    #total_body
+                }
+
+                fn links(&self) -> crate::pagination::PageLinks {
+                    crate::pagination::PageLinks::from_links(&self.links)
+                }
+            }
+        });
+    }
+    items.extend(impls);
+}
+
+/// The item type of each generated list method, by method name, as written outside
+/// the generated code.
+///
+/// A method's items are those of the `Paginated` response it returns
+/// (`Result<ResponseValue<types::DropletsListResponse>, _>`). Methods whose item type
+/// cannot be named from outside are left out.
+fn list_item_types(
+    file: &syn::File,
+    response_items: &HashMap<String, syn::Type>,
+) -> HashMap<String, String> {
+    let mut item_types = HashMap::new();
+    for item in &file.items {
+        let syn::Item::Impl(block) = item else {
+            continue;
+        };
+        let syn::Type::Path(self_ty) = &*block.self_ty else {
+            continue;
+        };
+        if block.trait_.is_some() || !self_ty.path.is_ident("Client") {
+            continue;
+        }
+        for item in &block.items {
+            let syn::ImplItem::Fn(method) = item else {
+                continue;
+            };
+            let syn::ReturnType::Type(_, output) = &method.sig.output else {
+                continue;
+            };
+            let item_type = response_type_name(output)
+                .and_then(|response| response_items.get(&response))
+                .and_then(public_type_path);
+            if let Some(item_type) = item_type {
+                item_types.insert(method.sig.ident.to_string(), item_type);
+            }
+        }
+    }
+    item_types
+}
+
+/// The name of `T` in a method's `Result<ResponseValue<T>, E>` return type.
+fn response_type_name(output: &syn::Type) -> Option<String> {
+    let result = type_arguments(output, "Result")?;
+    let value = type_arguments(result.first()?, "ResponseValue")?;
+    let syn::Type::Path(path) = value.first()? else {
+        return None;
+    };
+    Some(path.path.segments.last()?.ident.to_string())
+}
+
+/// The type arguments of `ty` if it is the generic type `name`, e.g. `T` and `E` for
+/// `Result<T, E>`.
+fn type_arguments<'a>(ty: &'a syn::Type, name: &str) -> Option<Vec<&'a syn::Type>> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    Some(
+        arguments
+            .args
+            .iter()
+            .filter_map(|argument| match argument {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// `ty`, named inside the generated `types` module, as a path from anywhere in the
+/// crate: `Droplet` becomes `crate::types::Droplet` and absolute paths such as
+/// `::serde_json::Value` are kept. `None` for generic or relative multi-segment types.
+fn public_type_path(ty: &syn::Type) -> Option<String> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segments = &path.path.segments;
+    if path.qself.is_some() || segments.iter().any(|segment| !segment.arguments.is_empty()) {
+        return None;
+    }
+    let name = quote::quote!(#path).to_string().replace(' ', "");
+    if path.path.leading_colon.is_some() {
+        Some(name)
+    } else if segments.len() == 1 {
+        Some(format!("crate::types::{name}"))
+    } else {
+        None
+    }
+}
+
+/// The element type of a `Vec<T>` or `Option<Vec<T>>`, and whether it is optional.
+fn vec_item_type(ty: &syn::Type) -> Option<(syn::Type, bool)> {
+    fn single_argument<'a>(ty: &'a syn::Type, wrapper: &str) -> Option<&'a syn::Type> {
+        let syn::Type::Path(path) = ty else {
+            return None;
+        };
+        let segment = path.path.segments.last()?;
+        if segment.ident != wrapper {
+            return None;
+        }
+        let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+            return None;
+        };
+        match arguments.args.first()? {
+            syn::GenericArgument::Type(inner) if arguments.args.len() == 1 => Some(inner),
+            _ => None,
+        }
+    }
+
+    if let Some(item) = single_argument(ty, "Vec") {
+        return Some((item.clone(), false));
+    }
+    let inner = single_argument(ty, "Option")?;
+    single_argument(inner, "Vec").map(|item| (item.clone(), true))
+}
+
+/// Metadata for a single API operation, as recorded in the generated registry.
+struct OperationRecord {
+    operation_id: String,
+    method_name: String,
+    method: String,
+    path: String,
+    paginated: bool,
+    /// Whether the operation accepts the `tag_name` query filter.
+    tag_filter: bool,
+    max_per_page: Option<u64>,
+    docs_url: String,
+}
+
+/// Collects per-operation metadata from the resolved specification.
+///
+/// ## What Gets Recorded:
+/// - `operation_id` as written in the spec (e.g. `floatingIPs_list`)
+/// - `method_name`, the snake_case name progenitor gives the generated method
+///   (e.g. `floating_i_ps_list`)
+/// - HTTP method and path template
+/// - Whether the operation accepts `page`, and the `maximum` of its `per_page`
+///   parameter, so the client can clamp oversized values instead of letting the
+///   API silently truncate or reject them
+/// - Whether the operation can be filtered by `tag_name`
+/// - The operation's page in the API reference, built from its first tag and
+///   operation ID the same way the published docs anchor them
+///
+/// Parameters can be declared on the path item as well as on the operation, so
+/// both lists are inspected.
+fn collect_operations(spec: &Value) -> Vec<OperationRecord> {
+    let mut operations = Vec::new();
+
+    let paths = match spec.get("paths").and_then(|p| p.as_mapping()) {
+        Some(paths) => paths,
+        None => return operations,
+    };
+
+    for (path_key, path_value) in paths {
+        let (Some(path), Some(path_obj)) = (path_key.as_str(), path_value.as_mapping()) else {
+            continue;
+        };
+        let shared_parameters = path_obj
+            .get(&Value::String("parameters".to_string()))
+            .and_then(|p| p.as_sequence())
+            .cloned()
+            .unwrap_or_default();
+
+        for (method_key, operation) in path_obj {
+            let Some(method) = method_key.as_str() else {
+                continue;
+            };
+            if !["get", "post", "put", "patch", "delete", "head"].contains(&method) {
+                continue;
+            }
+            let Some(operation_id) = operation.get("operationId").and_then(|v| v.as_str()) else {
+                continue;
+            };
+
+            let mut parameters = shared_parameters.clone();
+            if let Some(own) = operation.get("parameters").and_then(|p| p.as_sequence()) {
+                parameters.extend(own.iter().cloned());
+            }
+            let query_param = |name: &str| {
+                parameters.iter().find(|p| {
+                    p.get("name").and_then(|n| n.as_str()) == Some(name)
+                        && p.get("in").and_then(|i| i.as_str()) == Some("query")
+                })
+            };
+
+            let max_per_page = query_param("per_page").and_then(|p| {
+                p.get("schema")
+                    .and_then(|s| s.get("maximum"))
+                    .or_else(|| p.get("maximum"))
+                    .and_then(|m| m.as_u64())
+            });
+
+            let tag = operation
+                .get("tags")
+                .and_then(|t| t.as_sequence())
+                .and_then(|t| t.first())
+                .and_then(|t| t.as_str());
+
+            operations.push(OperationRecord {
+                operation_id: operation_id.to_string(),
+                method_name: to_snake_case(operation_id),
+                method: method.to_uppercase(),
+                path: path.to_string(),
+                paginated: query_param("page").is_some(),
+                tag_filter: query_param("tag_name").is_some(),
+                max_per_page,
+                docs_url: docs_url(tag, operation_id),
+            });
+        }
+    }
+
+    operations.sort_by(|a, b| a.method_name.cmp(&b.method_name));
+    println!("Recorded metadata for {} operations", operations.len());
+    operations
+}
+
+/// Base URL of the published API reference.
+const API_DOCS_URL: &str = "https://docs.digitalocean.com/reference/api/digitalocean/";
+
+/// Builds the API reference URL for an operation.
+///
+/// The published reference anchors operations as `#tag/<Tag>/operation/<id>`, with
+/// spaces in tag names replaced by dashes (`1-Click Applications` →
+/// `1-Click-Applications`).
+fn docs_url(tag: Option<&str>, operation_id: &str) -> String {
+    match tag {
+        Some(tag) => format!(
+            "{}#tag/{}/operation/{}",
+            API_DOCS_URL,
+            tag.replace(' ', "-"),
+            operation_id
+        ),
+        None => format!("{}#operation/{}", API_DOCS_URL, operation_id),
+    }
+}
+
+/// Converts an operation ID to snake_case the same way progenitor names methods.
+///
+/// Word boundaries are placed between a lowercase letter or digit and a following
+/// uppercase letter, and before the last capital of an acronym that is followed by
+/// a lowercase letter (`floatingIPs_list` → `floating_i_ps_list`).
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+            continue;
+        }
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            let boundary = prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower);
+            if boundary && !out.ends_with('_') {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+
+    out.trim_end_matches('_').to_string()
+}
+
+/// Writes the operation registry consumed by `src/operations.rs`.
+///
+/// The file is a single `static` slice of `OperationMeta` literals, so lookups at
+/// runtime need no parsing. An empty registry is written when the spec could not be
+/// processed, which simply disables metadata-driven behaviour.
+fn write_operation_registry(output_path: &Path, operations: &[OperationRecord]) {
+    let mut content = String::from(
+        "// Generated operation metadata - do not edit\n\npub(crate) static OPERATIONS: &[OperationMeta] = &[\n",
+    );
+    for op in operations {
+        content.push_str(&format!(
+            "    OperationMeta {{ operation_id: {:?}, method_name: {:?}, method: {:?}, path: {:?}, paginated: {}, max_per_page: {:?}, docs_url: {:?} }},\n",
+            op.operation_id, op.method_name, op.method, op.path, op.paginated, op.max_per_page, op.docs_url,
+        ));
+    }
+    content.push_str("];\n");
+
+    fs::write(output_path, content)
+        .unwrap_or_else(|e| panic!("Failed to write operation registry: {}", e));
+}
+
+/// Writes a `{method_name}_all` method for every paginated list operation.
+///
+/// ## Why This Exists:
+/// Gathering a whole list means following `links.pages.next` until the last page,
+/// which every consumer would otherwise reimplement. Each generated method fills the
+/// operation's path parameters and defers to `Paginate::collect_all`, which enforces
+/// the client's cap on the number of items. The page size is left to the client's
+/// `default_per_page`.
+///
+/// Items come back as the operation's generated model, taken from `item_types` (method
+/// name to item type, see [`list_item_types`]). Operations missing from it, and every
+/// operation when the stub client is in use, yield `serde_json::Value`.
+///
+/// The file is included into `src/pagination.rs`. Methods whose name would clash with
+/// a generated operation are skipped.
+fn write_list_methods(
+    output_path: &Path,
+    operations: &[OperationRecord],
+    item_types: &HashMap<String, String>,
+) {
+    let method_names: std::collections::HashSet<&str> = operations
+        .iter()
+        .map(|op| op.method_name.as_str())
+        .collect();
+    let mut content = String::from("// Generated list methods - do not edit\n\nimpl Client {\n");
+    for op in operations
+        .iter()
+        .filter(|op| op.paginated && op.method == "GET")
+    {
+        let params = path_params(&op.path);
+        let args: String = params
+            .iter()
+            .map(|param| format!(", {}: impl ToString", rust_ident(param)))
+            .collect();
+        let fills: String = params
+            .iter()
+            .map(|param| {
+                format!(
+                    "\n            .path_param({:?}, {})",
+                    param,
+                    rust_ident(param)
+                )
+            })
+            .collect();
+        let item = item_types
+            .get(&op.method_name)
+            .map_or("serde_json::Value", String::as_str);
+        let all_name = format!("{}_all", op.method_name);
+        if !method_names.contains(all_name.as_str()) {
+            content.push_str(&format!(
+            "    /// Every item of `{id}` (`{method} {path}`), gathered from all pages.\n    ///\n    /// Fails instead of returning a partial list when there are more items than the\n    /// cap; see [`Paginate::collect_all`].\n    pub async fn {name}(&self{args}) -> Result<Vec<{item}>, Error> {{\n        self.paginate({id:?}){fills}\n            .collect_all()\n            .await\n    }}\n\n",
+                name = all_name,
+                id = op.operation_id,
+                method = op.method,
+                path = op.path,
+            ));
+        }
+        let with_name = format!("{}_with", op.method_name);
+        if !method_names.contains(with_name.as_str()) {
+            content.push_str(&format!(
+            "    /// One page of `{id}` (`{method} {path}`), with named list options instead of\n    /// positional arguments.\n    pub async fn {name}(&self{args}, options: &ListOptions) -> Result<Page<{item}>, Error> {{\n        self.paginate({id:?}){fills}\n            .options(options)\n            .page()\n            .await\n    }}\n\n",
+                name = with_name,
+                id = op.operation_id,
+                method = op.method,
+                path = op.path,
+            ));
+        }
+        let tag_name = format!("{}_by_tag_stream", tag_stream_resource(&op.method_name));
+        if op.tag_filter && !method_names.contains(tag_name.as_str()) {
+            content.push_str(&format!(
+            "    /// Every item of `{id}` (`{method} {path}`) tagged `tag_name`, fetched page\n    /// by page as the stream is polled.\n    pub fn {name}(&self{args}, tag_name: impl ToString) -> impl Stream<Item = Result<{item}, Error>> + Send + 'static {{\n        self.paginate({id:?}){fills}\n            .query(\"tag_name\", tag_name)\n            .stream()\n    }}\n\n",
+                name = tag_name,
+                id = op.operation_id,
+                method = op.method,
+                path = op.path,
+            ));
+        }
+    }
+    content.push_str("}\n");
+
+    fs::write(output_path, content)
+        .unwrap_or_else(|e| panic!("Failed to write list methods: {}", e));
+}
+
+/// The resource part of a list method name: `droplets` for `droplets_list`,
+/// `databases_clusters` for `databases_list_clusters`.
+fn tag_stream_resource(method_name: &str) -> String {
+    match method_name.strip_suffix("_list") {
+        Some(resource) => resource.to_string(),
+        None => method_name.replacen("_list_", "_", 1),
+    }
+}
+
+/// Names of the `{placeholders}` in a path template, in order.
+fn path_params(path: &str) -> Vec<String> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}'))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// A snake_case Rust identifier for a parameter name, raw if it is a keyword.
+fn rust_ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "type", "ref", "match", "mod", "move", "use", "where", "impl",
+    ];
+    let ident = to_snake_case(name).replace('-', "_");
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{ident}")
+    } else {
+        ident
+    }
 }
 
 /// Writes a minimal fallback client stub when code generation fails.
@@ -1752,14 +2711,17 @@ fn generate_client_code(spec: &Value) -> Result<String, Box<dyn std::error::Erro
 /// 4. Documents what went wrong in the comments
 ///
 /// ## What's Included:
-/// - `Client` struct with `new_with_client()` constructor
+/// - `Client` struct with `new_with_client()` and `new_with_client_and_state()`
+///   constructors and `ClientInfo` impl
 /// - `types` module with common types (Response, Links, ErrorResponse)
 /// - `Error` enum with basic error variants
 /// - `ResponseValue<T>` wrapper
 ///
 /// ## Limitations:
 /// The stub doesn't include any actual API methods. Users will get compile
-/// errors if they try to call methods like `client.droplets_list()`.
+/// errors if they try to call methods like `client.droplets_list()`. The crate's own
+/// modules only need the items above, so they keep building; the generated list
+/// wrappers fall back to `serde_json::Value` items (see [`write_list_methods`]).
 ///
 /// ## When This Runs:
 /// Only when one of these stages fails:
@@ -1778,31 +2740,55 @@ fn write_stub_client(output_path: &Path) {
 // specification. However, the resolved spec (19MB JSON) appears to contain constructs
 // that the current version of progenitor cannot handle.
 
+pub use progenitor_client::ClientInfo;
+
 /// Enhanced client implementation with authentication support
 #[derive(Debug, Clone)]
 pub struct Client {
     base_url: String,
     client: reqwest::Client,
+    inner: crate::ClientState,
 }
 
 impl Client {
     /// Create a new client with the specified base URL and HTTP client
     pub fn new_with_client(base_url: impl Into<String>, client: reqwest::Client) -> Self {
+        Self::new_with_client_and_state(base_url, client, crate::ClientState::default())
+    }
+
+    /// Create a new client with the specified base URL, HTTP client and client state
+    pub fn new_with_client_and_state(
+        base_url: impl Into<String>,
+        client: reqwest::Client,
+        inner: crate::ClientState,
+    ) -> Self {
         Self {
             base_url: base_url.into(),
             client,
+            inner,
         }
     }
-    
+}
+
+impl ClientInfo<crate::ClientState> for Client {
+    fn api_version() -> &'static str {
+        "2.0"
+    }
+
     /// Get the base URL
-    pub fn baseurl(&self) -> &str {
+    fn baseurl(&self) -> &str {
         &self.base_url
     }
-    
+
     /// Get a reference to the underlying HTTP client
-    pub fn client(&self) -> &reqwest::Client {
+    fn client(&self) -> &reqwest::Client {
         &self.client
     }
+
+    /// Get the rsdo client state
+    fn inner(&self) -> &crate::ClientState {
+        &self.inner
+    }
 }
 
 /// Types module with common DigitalOcean API types
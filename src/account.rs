@@ -0,0 +1,177 @@
+//! Account status checks.
+//!
+//! An unverified or locked account can still authenticate, but most write operations
+//! then fail with a bare `403 Forbidden` partway through a run. Provisioning pipelines
+//! should call [`Client::require_verified_account`] up front to fail fast with an error
+//! that says what is actually wrong.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let account = client.require_verified_account().await?;
+//! println!("provisioning as {} (droplet limit {})", account.email, account.droplet_limit);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Standing of an account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum AccountStatus {
+    Active,
+    /// Usable, but DigitalOcean has flagged something that needs attention, usually
+    /// billing. See [`Account::status_message`].
+    Warning,
+    /// Resources cannot be created or modified.
+    Locked,
+    /// A status this version of rsdo does not know about yet.
+    Unknown(String),
+}
+
+impl From<String> for AccountStatus {
+    fn from(value: String) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "active" => Self::Active,
+            "warning" => Self::Warning,
+            "locked" => Self::Locked,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<AccountStatus> for String {
+    fn from(value: AccountStatus) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for AccountStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Active => "active",
+            Self::Warning => "warning",
+            Self::Locked => "locked",
+            Self::Unknown(s) => s,
+        };
+        f.write_str(s)
+    }
+}
+
+/// The account the client's token belongs to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Account {
+    pub uuid: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub status: AccountStatus,
+    /// Explanation accompanying a non-active status.
+    #[serde(default)]
+    pub status_message: String,
+    #[serde(default)]
+    pub droplet_limit: u64,
+    #[serde(default)]
+    pub floating_ip_limit: u64,
+    #[serde(default)]
+    pub volume_limit: u64,
+    #[serde(default)]
+    pub team: Option<AccountTeam>,
+}
+
+/// Team the token is scoped to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct AccountTeam {
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct AccountEnvelope {
+    account: Account,
+}
+
+impl Account {
+    /// Fail with [`Error::AccountLocked`] or [`Error::AccountNotVerified`] unless the
+    /// account can be used to provision resources.
+    pub fn ensure_usable(&self) -> Result<(), Error> {
+        if self.status == AccountStatus::Locked {
+            return Err(Error::AccountLocked {
+                message: self.status_message.clone(),
+            });
+        }
+        if !self.email_verified {
+            return Err(Error::AccountNotVerified {
+                email: self.email.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Fetch the account the client's token belongs to.
+    pub async fn account(&self) -> Result<Account, Error> {
+        let envelope: AccountEnvelope = self
+            .send_json(ApiRequest::get("account_get", "/v2/account"))
+            .await?;
+        Ok(envelope.account)
+    }
+
+    /// Fetch the account and fail unless it is verified and not locked.
+    ///
+    /// Accounts in `warning` status pass, with the status message logged as a warning.
+    pub async fn require_verified_account(&self) -> Result<Account, Error> {
+        let account = self.account().await?;
+        account.ensure_usable()?;
+        if account.status == AccountStatus::Warning {
+            tracing::warn!(
+                message = %account.status_message,
+                "DigitalOcean account has a warning status"
+            );
+        }
+        Ok(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn account(status: &str, email_verified: bool) -> Account {
+        serde_json::from_value(json!({
+            "droplet_limit": 25,
+            "floating_ip_limit": 5,
+            "email": "sammy@digitalocean.com",
+            "uuid": "b6fr89dbf6d9156cace5f3c78dc9851d957381ef",
+            "email_verified": email_verified,
+            "status": status,
+            "status_message": "Payment overdue"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_usable_accounts() {
+        assert!(account("active", true).ensure_usable().is_ok());
+        assert!(account("warning", true).ensure_usable().is_ok());
+    }
+
+    #[test]
+    fn test_locked_and_unverified_accounts() {
+        assert!(matches!(
+            account("locked", true).ensure_usable(),
+            Err(Error::AccountLocked { .. })
+        ));
+        assert!(matches!(
+            account("active", false).ensure_usable(),
+            Err(Error::AccountNotVerified { .. })
+        ));
+    }
+}
@@ -0,0 +1,541 @@
+//! Waiting for and watching actions.
+//!
+//! Most mutating calls on droplets, volumes, images and floating IPs return an
+//! action that runs in the background. [`Client::wait_for_action`] polls it until it
+//! completes or errors, so imperative scripts can carry on once the change has
+//! actually happened. [`Client::actions_stream_for`] instead follows every new action
+//! on one resource, whoever started it.
+//!
+//! A droplet action posted by tag starts one action per tagged droplet.
+//! [`Client::droplet_actions_by_tag`] returns them in a [`BulkActionTracker`], which
+//! polls them concurrently and reports which droplets failed.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::actions::WaitOptions;
+//! use std::time::Duration;
+//!
+//! # async fn run(client: rsdo::Client, action_id: u64) -> Result<(), rsdo::error::Error> {
+//! let action = client
+//!     .wait_for_action(action_id, WaitOptions::default().timeout(Duration::from_secs(600)))
+//!     .await?;
+//! println!("{} finished at {:?}", action.kind, action.completed_at);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Watching a droplet:
+//!
+//! ```rust,no_run
+//! use futures::TryStreamExt;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let mut actions = std::pin::pin!(client.actions_stream_for("droplet", 3164444));
+//! while let Some(action) = actions.try_next().await? {
+//!     println!("{} {} ({})", action.id, action.kind, action.status);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cancellation;
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Utc};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use crate::wait::{WaitOptions, WaitProgress};
+
+/// Progress of an action.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ActionStatus {
+    InProgress,
+    Completed,
+    Errored,
+    /// A status this version of rsdo does not know about yet.
+    Unknown(String),
+}
+
+impl ActionStatus {
+    /// Whether the action has stopped running, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Errored)
+    }
+}
+
+/// How long an action of type `kind` usually runs, for progress estimates.
+pub(crate) fn typical_duration(kind: &str) -> Option<Duration> {
+    let secs = match kind {
+        "reboot" | "power_cycle" | "power_on" | "power_off" | "shutdown" => 30,
+        "create" | "enable_ipv6" | "enable_private_networking" | "attach" | "detach" => 60,
+        "resize" | "rebuild" | "restore" | "password_reset" => 180,
+        "snapshot" | "enable_backups" => 300,
+        "transfer" | "convert" => 600,
+        _ => return None,
+    };
+    Some(Duration::from_secs(secs))
+}
+
+impl From<String> for ActionStatus {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "in-progress" => Self::InProgress,
+            "completed" => Self::Completed,
+            "errored" => Self::Errored,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<ActionStatus> for String {
+    fn from(status: ActionStatus) -> Self {
+        status.to_string()
+    }
+}
+
+impl fmt::Display for ActionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::InProgress => "in-progress",
+            Self::Completed => "completed",
+            Self::Errored => "errored",
+            Self::Unknown(status) => status,
+        })
+    }
+}
+
+/// An action on a resource, such as a droplet reboot or an image transfer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Action {
+    pub id: u64,
+    pub status: ActionStatus,
+    /// The action's `type`, e.g. `create`, `reboot` or `transfer`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub resource_id: Option<u64>,
+    /// e.g. `droplet`, `volume` or `image`.
+    pub resource_type: Option<String>,
+    pub region_slug: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ActionEnvelope {
+    action: Action,
+}
+
+#[derive(Deserialize)]
+struct ActionsEnvelope {
+    actions: Vec<Action>,
+}
+
+/// How often [`Client::actions_stream_for`] polls the action list.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Actions already seen by [`Client::actions_stream_for`].
+#[derive(Debug, Default)]
+struct ActionFeed {
+    /// Start of the newest action seen, by the API's clock; older actions are not new.
+    since: Option<DateTime<Utc>>,
+    /// Actions seen that started at or after `since`, or whose start is unknown.
+    seen: HashMap<u64, Option<DateTime<Utc>>>,
+}
+
+impl ActionFeed {
+    /// Whether `action` started before anything new could have.
+    fn is_old(&self, action: &Action) -> bool {
+        matches!((self.since, action.started_at), (Some(since), Some(started)) if started < since)
+    }
+
+    /// Take in `actions` (newest first, as listed), and return those on resource
+    /// `resource_type`/`resource_id` not seen before, oldest first.
+    fn observe(
+        &mut self,
+        actions: Vec<Action>,
+        resource_type: &str,
+        resource_id: u64,
+    ) -> Vec<Action> {
+        let first_poll = self.since.is_none();
+        let mut new = Vec::new();
+        for action in actions {
+            if self.is_old(&action) || self.seen.contains_key(&action.id) {
+                continue;
+            }
+            self.seen.insert(action.id, action.started_at);
+            let on_resource = action.resource_type.as_deref() == Some(resource_type)
+                && action.resource_id == Some(resource_id);
+            if on_resource && !first_poll {
+                new.push(action);
+            }
+        }
+        self.since = self.seen.values().flatten().max().copied().or(self.since);
+        let since = self.since;
+        self.seen.retain(|_, started| match (since, *started) {
+            (Some(since), Some(started)) => started >= since,
+            _ => true,
+        });
+        new.reverse();
+        new
+    }
+}
+
+/// Aggregate state of a [`BulkActionTracker`], reported after every action that
+/// finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkProgress {
+    pub total: usize,
+    pub completed: usize,
+    /// Actions that errored or could not be polled.
+    pub failed: usize,
+}
+
+impl BulkProgress {
+    /// Actions still running.
+    pub fn in_progress(&self) -> usize {
+        self.total - self.completed - self.failed
+    }
+}
+
+/// An action of a bulk operation that did not complete.
+#[derive(Debug)]
+pub struct BulkFailure {
+    pub action_id: u64,
+    /// The resource the action ran on, e.g. a droplet ID.
+    pub resource_id: Option<u64>,
+    pub error: Error,
+}
+
+/// Outcome of [`BulkActionTracker::wait`].
+#[derive(Debug, Default)]
+pub struct BulkOutcome {
+    pub completed: Vec<Action>,
+    pub failed: Vec<BulkFailure>,
+}
+
+impl BulkOutcome {
+    /// Whether every action completed.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+type BulkProgressCallback = dyn Fn(&BulkProgress) + Send + Sync;
+
+/// Waits for many actions at once, such as those started by a droplet action posted
+/// by tag.
+#[derive(Clone)]
+pub struct BulkActionTracker {
+    actions: Vec<Action>,
+    concurrency: usize,
+    options: WaitOptions,
+    on_progress: Option<Arc<BulkProgressCallback>>,
+}
+
+impl fmt::Debug for BulkActionTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BulkActionTracker")
+            .field("actions", &self.actions.len())
+            .field("concurrency", &self.concurrency)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BulkActionTracker {
+    /// Track `actions`, as returned by the call that started them.
+    pub fn new(actions: impl IntoIterator<Item = Action>) -> Self {
+        Self {
+            actions: actions.into_iter().collect(),
+            concurrency: 10,
+            options: WaitOptions::default(),
+            on_progress: None,
+        }
+    }
+
+    /// The tracked actions, as last seen.
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    /// How many actions to poll at once. Defaults to 10.
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// How to poll each action, and how long to wait for it.
+    pub fn options(mut self, options: WaitOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Call `callback` with the aggregate progress after every action that finishes.
+    pub fn on_progress(mut self, callback: impl Fn(&BulkProgress) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Wait for every action to complete or fail.
+    ///
+    /// A failed action does not stop the others; its error, such as
+    /// [`Error::ActionErrored`] or [`Error::Timeout`], is kept in
+    /// [`BulkOutcome::failed`] with the resource it ran on.
+    pub async fn wait(self, client: &Client) -> BulkOutcome {
+        let mut progress = BulkProgress {
+            total: self.actions.len(),
+            completed: 0,
+            failed: 0,
+        };
+        let options = &self.options;
+        let mut finished = std::pin::pin!(stream::iter(self.actions)
+            .map(|action| async move {
+                let (id, resource_id) = (action.id, action.resource_id);
+                let result = match action.status {
+                    ActionStatus::Completed => Ok(action),
+                    ActionStatus::Errored => Err(Error::ActionErrored(Box::new(action))),
+                    _ => client.wait_for_action(id, options.clone()).await,
+                };
+                result.map_err(|error| BulkFailure {
+                    action_id: id,
+                    resource_id,
+                    error,
+                })
+            })
+            .buffer_unordered(self.concurrency));
+
+        let mut outcome = BulkOutcome::default();
+        while let Some(result) = finished.next().await {
+            match result {
+                Ok(action) => {
+                    progress.completed += 1;
+                    outcome.completed.push(action);
+                }
+                Err(failure) => {
+                    progress.failed += 1;
+                    outcome.failed.push(failure);
+                }
+            }
+            if let Some(callback) = &self.on_progress {
+                callback(&progress);
+            }
+        }
+        outcome
+    }
+}
+
+/// An action started by a helper, to check on or wait for later.
+#[derive(Debug, Clone)]
+pub struct ActionHandle {
+    client: Client,
+    id: u64,
+}
+
+impl ActionHandle {
+    pub(crate) fn new(client: &Client, id: u64) -> Self {
+        Self {
+            client: client.clone(),
+            id,
+        }
+    }
+
+    /// The action's ID.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Fetch the action's current state.
+    pub async fn get(&self) -> Result<Action, Error> {
+        self.client.action(self.id).await
+    }
+
+    /// Wait for the action to complete; see [`Client::wait_for_action`].
+    pub async fn wait(&self, options: WaitOptions) -> Result<Action, Error> {
+        self.client.wait_for_action(self.id, options).await
+    }
+}
+
+impl Client {
+    /// Post droplet action `body`, e.g. `{"type": "power_cycle"}`, to every droplet
+    /// tagged `tag`, and return a tracker for the actions it started.
+    pub async fn droplet_actions_by_tag(
+        &self,
+        tag: &str,
+        body: &impl Serialize,
+    ) -> Result<BulkActionTracker, Error> {
+        let envelope: ActionsEnvelope = self
+            .send_json(
+                ApiRequest::post("dropletActions_post_byTag", "/v2/droplets/actions")
+                    .query("tag_name", tag)
+                    .json(serde_json::to_value(body)?),
+            )
+            .await?;
+        Ok(BulkActionTracker::new(envelope.actions))
+    }
+
+    /// Send `request`, which starts an action, and return a handle on the action.
+    pub(crate) async fn start_action(&self, request: ApiRequest) -> Result<ActionHandle, Error> {
+        let envelope: ActionEnvelope = self.send_json(request).await?;
+        Ok(ActionHandle::new(self, envelope.action.id))
+    }
+
+    /// Fetch action `id`.
+    pub async fn action(&self, id: u64) -> Result<Action, Error> {
+        let envelope: ActionEnvelope = self
+            .send_json(ApiRequest::get("actions_get", format!("/v2/actions/{id}")))
+            .await?;
+        Ok(envelope.action)
+    }
+
+    /// Poll action `id` until it completes, and return it.
+    ///
+    /// Fails with [`Error::ActionErrored`] if the action errors, or with
+    /// [`Error::Timeout`] if it is still running after the options' timeout.
+    pub async fn wait_for_action(&self, id: u64, options: WaitOptions) -> Result<Action, Error> {
+        let workflow = Workflow::new(self.inner(), "wait_for_action", format!("action {id}"));
+        let waiter = self
+            .waiter(format!("action {id} to complete"), || self.action(id))
+            .until(|action| action.status == ActionStatus::Completed)
+            .fail_if(|action| {
+                (action.status == ActionStatus::Errored)
+                    .then(|| Error::ActionErrored(Box::new(action.clone())))
+            })
+            .state(|action| action.status.to_string())
+            .typical_duration(|action| typical_duration(&action.kind))
+            .options(options);
+        workflow.step("wait", waiter.wait()).await
+    }
+
+    /// Follow the actions on one resource, e.g. `("droplet", 3164444)`, yielding each
+    /// action started after the stream was first polled, oldest first.
+    ///
+    /// Polls `actions_list` every 5 seconds, reading pages only until it reaches
+    /// actions it has already seen. The stream ends after the first error, or when
+    /// the client is cancelled.
+    pub fn actions_stream_for(
+        &self,
+        resource_type: impl Into<String>,
+        resource_id: u64,
+    ) -> impl Stream<Item = Result<Action, Error>> + Send + 'static {
+        let resource_type = resource_type.into();
+        let state = (self.clone(), ActionFeed::default(), resource_type);
+        stream::try_unfold(state, move |(client, mut feed, resource_type)| async move {
+            if feed.since.is_some() && !cancellation::sleep(client.inner(), WATCH_INTERVAL).await {
+                return Ok::<_, Error>(None);
+            }
+            let list = client
+                .paginate::<Action>("actions_list")
+                .items_key("actions")
+                .per_page(50);
+            let actions = if feed.since.is_none() {
+                list.page().await?.items
+            } else {
+                let mut pages = std::pin::pin!(list.stream());
+                let mut actions = Vec::new();
+                while let Some(action) = pages.try_next().await? {
+                    if feed.is_old(&action) {
+                        break;
+                    }
+                    actions.push(action);
+                }
+                actions
+            };
+            let new = feed.observe(actions, &resource_type, resource_id);
+            Ok(Some((
+                stream::iter(new.into_iter().map(Ok)),
+                (client, feed, resource_type),
+            )))
+        })
+        .try_flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(id: u64, resource_id: u64, started_at: &str) -> Action {
+        Action {
+            id,
+            status: ActionStatus::InProgress,
+            kind: "reboot".to_string(),
+            started_at: Some(started_at.parse().unwrap()),
+            completed_at: None,
+            resource_id: Some(resource_id),
+            resource_type: Some("droplet".to_string()),
+            region_slug: None,
+        }
+    }
+
+    #[test]
+    fn test_feed_yields_new_actions_once() {
+        let mut feed = ActionFeed::default();
+        let first = vec![
+            action(2, 42, "2024-01-01T10:00:00Z"),
+            action(1, 42, "2024-01-01T09:00:00Z"),
+        ];
+        assert!(feed.observe(first, "droplet", 42).is_empty());
+
+        let second = vec![
+            action(5, 42, "2024-01-01T10:05:00Z"),
+            action(4, 7, "2024-01-01T10:01:00Z"),
+            action(3, 42, "2024-01-01T10:00:00Z"),
+            action(2, 42, "2024-01-01T10:00:00Z"),
+        ];
+        let ids: Vec<u64> = feed
+            .observe(second.clone(), "droplet", 42)
+            .iter()
+            .map(|a| a.id)
+            .collect();
+        assert_eq!(ids, [3, 5]);
+        assert!(feed.observe(second, "droplet", 42).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_tracker_reports_failures_per_resource() {
+        let client = crate::Client::from_token("test-token");
+        let mut done = action(1, 10, "2024-01-01T10:00:00Z");
+        done.status = ActionStatus::Completed;
+        let mut errored = action(2, 20, "2024-01-01T10:00:00Z");
+        errored.status = ActionStatus::Errored;
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let outcome = BulkActionTracker::new([done, errored])
+            .on_progress(move |progress| log.lock().unwrap().push(*progress))
+            .wait(&client)
+            .await;
+
+        assert!(!outcome.is_success());
+        assert_eq!(outcome.completed[0].id, 1);
+        assert_eq!(outcome.failed[0].resource_id, Some(20));
+        assert!(matches!(outcome.failed[0].error, Error::ActionErrored(_)));
+        let last = *seen.lock().unwrap().last().unwrap();
+        assert_eq!((last.completed, last.failed, last.in_progress()), (1, 1, 0));
+    }
+
+    #[test]
+    fn test_action_decodes() {
+        let envelope: ActionEnvelope = serde_json::from_str(
+            r#"{"action": {"id": 36804636, "status": "in-progress", "type": "create",
+                "started_at": "2020-11-14T16:29:21Z", "completed_at": null,
+                "resource_id": 3164444, "resource_type": "droplet", "region_slug": "nyc3"}}"#,
+        )
+        .unwrap();
+        let action = envelope.action;
+        assert_eq!(action.status, ActionStatus::InProgress);
+        assert!(!action.status.is_finished());
+        assert_eq!(action.kind, "create");
+        assert_eq!(action.resource_id, Some(3164444));
+        assert_eq!(serde_json::to_value(&action.status).unwrap(), "in-progress");
+    }
+}
@@ -0,0 +1,292 @@
+//! Firewall sources and database trusted sources from a resource selection.
+//!
+//! Security rules written as raw IP addresses break as soon as a droplet is rebuilt or
+//! a cluster scales. Cloud firewalls and database firewalls can instead reference
+//! droplets, tags and Kubernetes clusters directly. [`Client::firewall_sources`] and
+//! [`Client::database_trusted_sources`] turn a list of [`AllowSource`]s into the entries
+//! each expects, using IDs and tags wherever the target supports them and falling back
+//! to addresses only where it does not.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::allowlist::AllowSource;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let sources = [
+//!     AllowSource::Tag("web".to_string()),
+//!     AllowSource::DropletName("bastion".to_string()),
+//!     AllowSource::KubernetesCluster("bd5f5959-5e1e-4205-a714-a914373942af".to_string()),
+//! ];
+//! let inbound = client.firewall_sources(&sources).await?;
+//! let trusted = client.database_trusted_sources(&sources).await?;
+//! println!("{}", serde_json::to_string(&inbound)?);
+//! println!("{}", serde_json::to_string(&trusted)?);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::droplets::Droplet;
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A resource allowed to connect.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AllowSource {
+    Droplet(u64),
+    /// A droplet looked up by its exact name.
+    DropletName(String),
+    /// Every droplet with the tag, including ones created later.
+    Tag(String),
+    /// The nodes of a Kubernetes cluster, by cluster UUID.
+    KubernetesCluster(String),
+    /// A load balancer, by UUID.
+    LoadBalancer(String),
+    /// An App Platform app, by UUID.
+    App(String),
+    /// An IPv4/IPv6 address or CIDR block.
+    Address(String),
+}
+
+/// The `sources` of a cloud firewall inbound rule.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirewallSources {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub droplet_ids: Vec<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kubernetes_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub load_balancer_uids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl FirewallSources {
+    /// Firewall sources for `sources`, which must not contain
+    /// [`AllowSource::DropletName`]; see [`Client::firewall_sources`].
+    ///
+    /// Fails for apps, which firewalls cannot reference.
+    pub fn from_sources(sources: &[AllowSource]) -> Result<Self, Error> {
+        fn push<T: PartialEq>(list: &mut Vec<T>, value: T) {
+            if !list.contains(&value) {
+                list.push(value);
+            }
+        }
+
+        let mut result = Self::default();
+        for source in sources {
+            match source {
+                AllowSource::Droplet(id) => push(&mut result.droplet_ids, *id),
+                AllowSource::Tag(tag) => push(&mut result.tags, tag.clone()),
+                AllowSource::KubernetesCluster(id) => push(&mut result.kubernetes_ids, id.clone()),
+                AllowSource::LoadBalancer(id) => push(&mut result.load_balancer_uids, id.clone()),
+                AllowSource::Address(address) => push(&mut result.addresses, address.clone()),
+                AllowSource::App(id) => {
+                    return Err(Error::InvalidInput(format!(
+                        "cloud firewalls cannot allow app {id}; apps have no fixed source"
+                    )));
+                }
+                AllowSource::DropletName(name) => {
+                    return Err(Error::InvalidInput(format!(
+                        "droplet name {name:?} must be resolved to an ID first"
+                    )));
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Kind of a database trusted source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrustedSourceType {
+    #[serde(rename = "droplet")]
+    Droplet,
+    #[serde(rename = "k8s")]
+    Kubernetes,
+    #[serde(rename = "tag")]
+    Tag,
+    #[serde(rename = "app")]
+    App,
+    #[serde(rename = "ip_addr")]
+    IpAddress,
+}
+
+/// One rule of a database cluster's firewall (trusted sources).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TrustedSource {
+    #[serde(rename = "type")]
+    pub kind: TrustedSourceType,
+    pub value: String,
+}
+
+impl TrustedSource {
+    /// The trusted source for `source`, or `None` for sources a database firewall
+    /// cannot reference by ID (load balancers and unresolved droplet names).
+    pub fn from_source(source: &AllowSource) -> Option<Self> {
+        let (kind, value) = match source {
+            AllowSource::Droplet(id) => (TrustedSourceType::Droplet, id.to_string()),
+            AllowSource::Tag(tag) => (TrustedSourceType::Tag, tag.clone()),
+            AllowSource::KubernetesCluster(id) => (TrustedSourceType::Kubernetes, id.clone()),
+            AllowSource::App(id) => (TrustedSourceType::App, id.clone()),
+            AllowSource::Address(address) => (TrustedSourceType::IpAddress, address.clone()),
+            AllowSource::LoadBalancer(_) | AllowSource::DropletName(_) => return None,
+        };
+        Some(Self { kind, value })
+    }
+}
+
+impl fmt::Display for TrustedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            TrustedSourceType::Droplet => "droplet",
+            TrustedSourceType::Kubernetes => "k8s",
+            TrustedSourceType::Tag => "tag",
+            TrustedSourceType::App => "app",
+            TrustedSourceType::IpAddress => "ip_addr",
+        };
+        write!(f, "{kind}:{}", self.value)
+    }
+}
+
+#[derive(Deserialize)]
+struct LoadBalancerEnvelope {
+    load_balancer: LoadBalancerIp,
+}
+
+#[derive(Deserialize)]
+struct LoadBalancerIp {
+    #[serde(default)]
+    ip: String,
+}
+
+impl Client {
+    /// Inbound rule sources for a cloud firewall allowing `sources`.
+    ///
+    /// Droplet names are resolved to IDs. Apps cannot be referenced by firewalls and
+    /// are rejected with [`Error::InvalidInput`].
+    pub async fn firewall_sources(
+        &self,
+        sources: &[AllowSource],
+    ) -> Result<FirewallSources, Error> {
+        let sources = self.resolve_droplet_names(sources).await?;
+        FirewallSources::from_sources(&sources)
+    }
+
+    /// Trusted sources for a database cluster allowing `sources`.
+    ///
+    /// Droplet names are resolved to IDs. Load balancers, which database firewalls
+    /// cannot reference, are allowed by their current IP address instead.
+    pub async fn database_trusted_sources(
+        &self,
+        sources: &[AllowSource],
+    ) -> Result<Vec<TrustedSource>, Error> {
+        let mut trusted = Vec::new();
+        for source in self.resolve_droplet_names(sources).await? {
+            let source = match source {
+                AllowSource::LoadBalancer(id) => {
+                    let envelope: LoadBalancerEnvelope = self
+                        .send_json(ApiRequest::get(
+                            "loadBalancers_get",
+                            format!("/v2/load_balancers/{id}"),
+                        ))
+                        .await?;
+                    if envelope.load_balancer.ip.is_empty() {
+                        return Err(Error::Other(format!(
+                            "load balancer {id} has no IP address yet"
+                        )));
+                    }
+                    AllowSource::Address(envelope.load_balancer.ip)
+                }
+                source => source,
+            };
+            if let Some(entry) = TrustedSource::from_source(&source) {
+                if !trusted.contains(&entry) {
+                    trusted.push(entry);
+                }
+            }
+        }
+        Ok(trusted)
+    }
+
+    /// Replace every [`AllowSource::DropletName`] with the droplets of that name.
+    async fn resolve_droplet_names(
+        &self,
+        sources: &[AllowSource],
+    ) -> Result<Vec<AllowSource>, Error> {
+        let mut resolved = Vec::with_capacity(sources.len());
+        for source in sources {
+            let AllowSource::DropletName(name) = source else {
+                resolved.push(source.clone());
+                continue;
+            };
+            let droplets: Vec<Droplet> = self
+                .paginate("droplets_list")
+                .query("name", name)
+                .items_key("droplets")
+                .per_page(200)
+                .stream()
+                .try_collect()
+                .await?;
+            if droplets.is_empty() {
+                return Err(Error::InvalidInput(format!("no droplet is named {name:?}")));
+            }
+            resolved.extend(
+                droplets
+                    .into_iter()
+                    .map(|droplet| AllowSource::Droplet(droplet.id)),
+            );
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_firewall_sources_prefer_ids() {
+        let sources = [
+            AllowSource::Tag("web".to_string()),
+            AllowSource::Droplet(3164494),
+            AllowSource::Droplet(3164494),
+            AllowSource::KubernetesCluster("bd5f5959".to_string()),
+            AllowSource::Address("203.0.113.0/24".to_string()),
+        ];
+        let firewall = FirewallSources::from_sources(&sources).unwrap();
+        assert_eq!(
+            serde_json::to_value(&firewall).unwrap(),
+            json!({
+                "addresses": ["203.0.113.0/24"],
+                "droplet_ids": [3164494],
+                "kubernetes_ids": ["bd5f5959"],
+                "tags": ["web"]
+            })
+        );
+        assert!(FirewallSources::from_sources(&[AllowSource::App("a".to_string())]).is_err());
+    }
+
+    #[test]
+    fn test_trusted_sources() {
+        let trusted =
+            TrustedSource::from_source(&AllowSource::KubernetesCluster("bd5f5959".to_string()))
+                .unwrap();
+        assert_eq!(
+            serde_json::to_value(&trusted).unwrap(),
+            json!({"type": "k8s", "value": "bd5f5959"})
+        );
+        assert_eq!(trusted.to_string(), "k8s:bd5f5959");
+        assert_eq!(
+            TrustedSource::from_source(&AllowSource::LoadBalancer("lb".to_string())),
+            None
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! App Platform bandwidth and build-minute usage.
+//!
+//! App Platform bills outbound bandwidth above the plan allowance and build minutes
+//! beyond the free tier. These helpers collect both per app over a date range so costs
+//! can be attributed to the teams owning each app:
+//!
+//! - Bandwidth comes from the daily bandwidth metrics endpoints, queried once per day
+//!   of the range.
+//! - Build minutes are derived from the `build` step timings of the app's deployments
+//!   created within the range.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use chrono::NaiveDate;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let start = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+//! let end = NaiveDate::from_ymd_opt(2026, 1, 31).unwrap();
+//! let report = client
+//!     .app_usage(&["4f6c71e2-1e90-4762-9fee-6cc4a0a9f2cf"], start, end)
+//!     .await?;
+//! for app in &report.apps {
+//!     println!(
+//!         "{}: {} bytes, {:.1} build minutes",
+//!         app.app_id, app.bandwidth_bytes, app.build_minutes
+//!     );
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Deserializer};
+use serde_json::json;
+
+/// Bandwidth used by one app on one day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyBandwidth {
+    pub app_id: String,
+    pub date: NaiveDate,
+    pub bandwidth_bytes: u64,
+}
+
+/// Usage of a single app over a date range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppUsage {
+    pub app_id: String,
+    /// Outbound bandwidth over the whole range.
+    pub bandwidth_bytes: u64,
+    /// Bandwidth per day, in date order.
+    pub daily_bandwidth: Vec<DailyBandwidth>,
+    /// Deployments created within the range.
+    pub deployments: usize,
+    /// Time spent in the build step of those deployments.
+    pub build_minutes: f64,
+}
+
+/// Usage of several apps over a date range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppUsageReport {
+    /// First day of the range.
+    pub start: NaiveDate,
+    /// Last day of the range, inclusive.
+    pub end: NaiveDate,
+    /// One entry per requested app, in request order.
+    pub apps: Vec<AppUsage>,
+}
+
+impl AppUsageReport {
+    /// Bandwidth of all apps over the range.
+    pub fn total_bandwidth_bytes(&self) -> u64 {
+        self.apps.iter().map(|app| app.bandwidth_bytes).sum()
+    }
+
+    /// Build minutes of all apps over the range.
+    pub fn total_build_minutes(&self) -> f64 {
+        self.apps.iter().map(|app| app.build_minutes).sum()
+    }
+}
+
+/// Build activity of one app over a date range.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BuildMinutes {
+    /// Deployments created within the range.
+    pub deployments: usize,
+    /// Time spent in their build step.
+    pub minutes: f64,
+}
+
+#[derive(Deserialize)]
+struct BandwidthResponse {
+    #[serde(default)]
+    app_bandwidth_usage: Vec<BandwidthUsage>,
+}
+
+#[derive(Deserialize)]
+struct BandwidthUsage {
+    app_id: String,
+    #[serde(default, deserialize_with = "u64_from_string_or_number")]
+    bandwidth_bytes: u64,
+}
+
+#[derive(Deserialize)]
+struct Deployment {
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    progress: Option<DeploymentProgress>,
+}
+
+#[derive(Deserialize)]
+struct DeploymentProgress {
+    #[serde(default)]
+    steps: Vec<DeploymentStep>,
+}
+
+#[derive(Deserialize)]
+struct DeploymentStep {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    ended_at: Option<DateTime<Utc>>,
+}
+
+impl Deployment {
+    /// Minutes spent in the deployment's build step; zero if it never finished.
+    fn build_minutes(&self) -> f64 {
+        self.progress
+            .iter()
+            .flat_map(|progress| &progress.steps)
+            .filter(|step| step.name == "build")
+            .filter_map(|step| Some((step.ended_at? - step.started_at?).num_milliseconds()))
+            .map(|millis| millis.max(0) as f64 / 60_000.0)
+            .sum()
+    }
+}
+
+impl Client {
+    /// Bandwidth used by each of `app_ids` on `date`.
+    pub async fn apps_bandwidth_on(
+        &self,
+        app_ids: &[&str],
+        date: NaiveDate,
+    ) -> Result<Vec<DailyBandwidth>, Error> {
+        let request = ApiRequest::post(
+            "apps_list_metrics_bandwidth_daily",
+            "/v2/apps/metrics/bandwidth_daily",
+        )
+        .json(json!({ "app_ids": app_ids, "date": start_of_day(date) }));
+        let response: BandwidthResponse = self.send_json(request).await?;
+
+        Ok(response
+            .app_bandwidth_usage
+            .into_iter()
+            .map(|usage| DailyBandwidth {
+                app_id: usage.app_id,
+                date,
+                bandwidth_bytes: usage.bandwidth_bytes,
+            })
+            .collect())
+    }
+
+    /// Daily bandwidth of each of `app_ids` from `start` to `end`, inclusive.
+    ///
+    /// Issues one request per day of the range.
+    pub async fn apps_bandwidth_range(
+        &self,
+        app_ids: &[&str],
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyBandwidth>, Error> {
+        check_range(start, end)?;
+        let mut usage = Vec::new();
+        for date in start.iter_days().take_while(|date| *date <= end) {
+            usage.extend(self.apps_bandwidth_on(app_ids, date).await?);
+        }
+        Ok(usage)
+    }
+
+    /// Build minutes of `app_id` for deployments created from `start` to `end`,
+    /// inclusive.
+    pub async fn app_build_minutes(
+        &self,
+        app_id: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<BuildMinutes, Error> {
+        check_range(start, end)?;
+        let from = start_of_day(start);
+        let until = start_of_day(end + chrono::Days::new(1));
+
+        // Deployments are listed newest first, so stop at the first one before `start`.
+        let deployments: Vec<Deployment> = self
+            .paginate("apps_list_deployments")
+            .path_param("app_id", app_id)
+            .items_key("deployments")
+            .stream()
+            .try_take_while(|deployment: &Deployment| {
+                futures::future::ready(Ok(deployment.created_at >= from))
+            })
+            .try_collect()
+            .await?;
+
+        Ok(deployments
+            .iter()
+            .filter(|deployment| deployment.created_at < until)
+            .fold(BuildMinutes::default(), |total, deployment| BuildMinutes {
+                deployments: total.deployments + 1,
+                minutes: total.minutes + deployment.build_minutes(),
+            }))
+    }
+
+    /// Bandwidth and build minutes of each of `app_ids` from `start` to `end`,
+    /// inclusive.
+    pub async fn app_usage(
+        &self,
+        app_ids: &[&str],
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<AppUsageReport, Error> {
+        let bandwidth = self.apps_bandwidth_range(app_ids, start, end).await?;
+
+        let mut apps = Vec::with_capacity(app_ids.len());
+        for &app_id in app_ids {
+            let builds = self.app_build_minutes(app_id, start, end).await?;
+            let daily_bandwidth: Vec<DailyBandwidth> = bandwidth
+                .iter()
+                .filter(|usage| usage.app_id == app_id)
+                .cloned()
+                .collect();
+            apps.push(AppUsage {
+                app_id: app_id.to_string(),
+                bandwidth_bytes: daily_bandwidth.iter().map(|d| d.bandwidth_bytes).sum(),
+                daily_bandwidth,
+                deployments: builds.deployments,
+                build_minutes: builds.minutes,
+            });
+        }
+
+        Ok(AppUsageReport { start, end, apps })
+    }
+}
+
+fn check_range(start: NaiveDate, end: NaiveDate) -> Result<(), Error> {
+    if start > end {
+        return Err(Error::InvalidInput(format!(
+            "date range starts after it ends: {start} > {end}"
+        )));
+    }
+    Ok(())
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// The API reports byte counts as strings to avoid precision loss in JavaScript
+/// clients; accept plain numbers too.
+fn u64_from_string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Number(u64),
+        String(String),
+    }
+
+    match Raw::deserialize(deserializer)? {
+        Raw::Number(n) => Ok(n),
+        Raw::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bandwidth_bytes_accepts_strings() {
+        let response: BandwidthResponse = serde_json::from_value(json!({
+            "app_bandwidth_usage": [
+                {"app_id": "a", "bandwidth_bytes": "513668"},
+                {"app_id": "b", "bandwidth_bytes": 42}
+            ],
+            "date": "2026-01-17T00:00:00Z"
+        }))
+        .unwrap();
+        let bytes: Vec<u64> = response
+            .app_bandwidth_usage
+            .iter()
+            .map(|u| u.bandwidth_bytes)
+            .collect();
+        assert_eq!(bytes, vec![513668, 42]);
+    }
+
+    #[test]
+    fn test_build_minutes_from_steps() {
+        let deployment: Deployment = serde_json::from_value(json!({
+            "created_at": "2026-01-17T10:00:00Z",
+            "progress": {"steps": [
+                {"name": "build", "started_at": "2026-01-17T10:00:00Z", "ended_at": "2026-01-17T10:04:30Z"},
+                {"name": "deploy", "started_at": "2026-01-17T10:04:30Z", "ended_at": "2026-01-17T10:06:00Z"}
+            ]}
+        }))
+        .unwrap();
+        assert!((deployment.build_minutes() - 4.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reversed_range_is_rejected() {
+        let start = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        assert!(matches!(
+            check_range(start, end),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+//! Authentication: bearer tokens, per-client overrides and rotating credentials.
+//!
+//! By default a client sends the token it was built with. Two mechanisms change that
+//! without rebuilding the underlying `reqwest::Client`:
+//!
+//! - [`Client::with_token`] switches a copy of the client to another fixed token.
+//! - [`Client::with_token_provider`] (or [`ClientBuilder::token_provider`]) consults a
+//!   [`TokenProvider`] before every request, so credentials loaded from Vault or a
+//!   credentials file can rotate at runtime.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use futures::future::BoxFuture;
+//! use rsdo::auth::TokenProvider;
+//! use rsdo::error::Error;
+//! use rsdo::ClientBuilder;
+//! use std::sync::Arc;
+//!
+//! #[derive(Debug)]
+//! struct TokenFile(std::path::PathBuf);
+//!
+//! impl TokenProvider for TokenFile {
+//!     fn token(&self) -> BoxFuture<'_, Result<String, Error>> {
+//!         Box::pin(async move {
+//!             tokio::fs::read_to_string(&self.0)
+//!                 .await
+//!                 .map_err(|err| Error::Other(format!("reading token file: {err}")))
+//!         })
+//!     }
+//! }
+//!
+//! let client = ClientBuilder::default()
+//!     .token_provider(Arc::new(TokenFile("/run/secrets/do-token".into())))
+//!     .build()?;
+//! # Ok::<(), Error>(())
+//! ```
+//!
+//! [`ClientBuilder::token_provider`]: crate::ClientBuilder::token_provider
+
+use crate::error::Error;
+use crate::rate_limit::RateLimiter;
+use crate::{Client, ClientInfo, ClientState};
+use futures::future::BoxFuture;
+use reqwest::header::HeaderValue;
+use std::fmt;
+use std::sync::Arc;
+
+/// Supplies the bearer token for each request.
+///
+/// `token` is called before every request, including retries, so implementations
+/// that fetch from a remote secret store should cache the value and only refresh it
+/// when it is close to expiry.
+pub trait TokenProvider: Send + Sync + fmt::Debug {
+    /// The token to send with the next request.
+    fn token(&self) -> BoxFuture<'_, Result<String, Error>>;
+
+    /// Called when the API rejected a request with `401 Unauthorized`.
+    ///
+    /// Return `Ok(true)` after obtaining a new token to have the request sent once
+    /// more with it. The default gives up immediately.
+    fn on_unauthorized(&self) -> BoxFuture<'_, Result<bool, Error>> {
+        Box::pin(async { Ok(false) })
+    }
+}
+
+/// A [`TokenProvider`] that always returns the same token.
+#[derive(Clone)]
+pub struct StaticToken(String);
+
+impl StaticToken {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self(token.into())
+    }
+}
+
+impl fmt::Debug for StaticToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StaticToken(..)")
+    }
+}
+
+impl TokenProvider for StaticToken {
+    fn token(&self) -> BoxFuture<'_, Result<String, Error>> {
+        Box::pin(async move { Ok(self.0.clone()) })
+    }
+}
+
+/// Build the `Authorization` header value for a personal access token.
+///
+/// The value is marked sensitive so it is redacted from `Debug` output.
+pub(crate) fn bearer(token: &str) -> Result<HeaderValue, Error> {
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", token.trim()))
+        .map_err(|_| Error::InvalidInput("token contains invalid characters".to_string()))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// The `Authorization` header for the next request, if the client overrides the one
+/// built into its `reqwest::Client`.
+pub(crate) async fn authorization(state: &ClientState) -> Result<Option<HeaderValue>, Error> {
+    match &state.token_provider {
+        Some(provider) => Ok(Some(bearer(&provider.token().await?)?)),
+        None => Ok(None),
+    }
+}
+
+impl Client {
+    /// Return a copy of this client that authenticates with `token`.
+    ///
+    /// The copy shares the connection pool and every other setting with `self`, so
+    /// it is cheap enough to create per call when working across several teams:
+    ///
+    /// ```rust,no_run
+    /// # async fn run(client: rsdo::Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// let staging = client.with_token("staging-team-token")?;
+    /// let production = client.with_token("production-team-token")?;
+    ///
+    /// let staging_droplets = staging.droplets_list(None, None, None, None, None).await?;
+    /// let production_droplets = production.droplets_list(None, None, None, None, None).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Fails with [`Error::InvalidInput`] if the token contains characters that are not
+    /// valid in an HTTP header.
+    pub fn with_token(&self, token: &str) -> Result<Self, Error> {
+        bearer(token)?;
+        Ok(self.with_token_provider(Arc::new(StaticToken::new(token))))
+    }
+
+    /// Return a copy of this client that asks `provider` for the token before every
+    /// request.
+    pub fn with_token_provider(&self, provider: Arc<dyn TokenProvider>) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.token_provider = Some(provider);
+        state.rate_limit = Default::default();
+        state.ssh_keys = Default::default();
+        state.idempotency_memory = Default::default();
+        state.rate_limiter = state.rate_limiter.as_ref().map(RateLimiter::fresh);
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_bearer_is_sensitive() {
+        let value = bearer("dop_v1_abc\n").unwrap();
+        assert_eq!(value.to_str().unwrap(), "Bearer dop_v1_abc");
+        assert!(value.is_sensitive());
+    }
+
+    #[tokio::test]
+    async fn test_with_token_overrides_authorization() {
+        let client = Client::from_token("team-a").with_token("team-b").unwrap();
+        let mut request = reqwest::Request::new(
+            reqwest::Method::GET,
+            "https://api.digitalocean.com/v2/account".parse().unwrap(),
+        );
+        crate::transport::prepare(&mut request, client.inner(), "account_get")
+            .await
+            .unwrap();
+        assert_eq!(
+            request.headers()[reqwest::header::AUTHORIZATION],
+            "Bearer team-b"
+        );
+        assert!(matches!(
+            client.with_token("team\nc"),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[derive(Debug, Default)]
+    struct Rotating(AtomicUsize);
+
+    impl TokenProvider for Rotating {
+        fn token(&self) -> BoxFuture<'_, Result<String, Error>> {
+            Box::pin(async move { Ok(format!("token-{}", self.0.fetch_add(1, Ordering::SeqCst))) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_is_consulted_per_request() {
+        let client =
+            Client::from_token("unused").with_token_provider(Arc::new(Rotating::default()));
+        for expected in ["Bearer token-0", "Bearer token-1"] {
+            let header = authorization(client.inner()).await.unwrap().unwrap();
+            assert_eq!(header, expected);
+        }
+    }
+}
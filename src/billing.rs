@@ -0,0 +1,249 @@
+//! Warnings about resources that cost money while doing nothing.
+//!
+//! Several kinds of resources keep billing when idle: a reserved IP that is not
+//! assigned to a droplet, a droplet that is powered off (droplets are billed until they
+//! are destroyed), and a block storage volume that is not attached to anything.
+//! [`Client::billing_warnings`] combines the reserved IP, droplet and volume listings
+//! into a single report with an estimate of the monthly spend on each idle resource.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let report = client.billing_warnings().await?;
+//! for warning in &report.warnings {
+//!     println!("{warning}");
+//! }
+//! println!("~${:.2}/month spent on idle resources", report.estimated_monthly_waste());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::Client;
+use serde::Deserialize;
+use std::fmt;
+
+/// Monthly charge for a reserved IPv4 address that is not assigned to a droplet, in USD.
+pub const UNASSIGNED_RESERVED_IP_PRICE_MONTHLY: f64 = 5.0;
+
+/// Block storage price, in USD per GiB per month.
+pub const VOLUME_PRICE_PER_GIB_MONTH: f64 = 0.10;
+
+/// Kind of idle resource behind a [`BillingWarning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdleResourceKind {
+    /// A reserved IP not assigned to any droplet.
+    UnassignedReservedIp,
+    /// A droplet that is powered off but still billed at its full size price.
+    PoweredOffDroplet,
+    /// A volume not attached to any droplet.
+    UnattachedVolume,
+}
+
+impl fmt::Display for IdleResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::UnassignedReservedIp => "unassigned reserved IP",
+            Self::PoweredOffDroplet => "powered-off droplet",
+            Self::UnattachedVolume => "unattached volume",
+        })
+    }
+}
+
+/// A single resource incurring charges while idle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BillingWarning {
+    pub kind: IdleResourceKind,
+    /// Droplet ID, volume ID or the reserved IP address itself.
+    pub resource_id: String,
+    /// Human-readable name; the address for reserved IPs.
+    pub name: String,
+    pub region: Option<String>,
+    /// Estimated charge per month, in USD.
+    pub estimated_monthly_waste: f64,
+}
+
+impl fmt::Display for BillingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ({})", self.kind, self.name, self.resource_id)?;
+        if let Some(region) = &self.region {
+            write!(f, " in {region}")?;
+        }
+        write!(f, ": ~${:.2}/month", self.estimated_monthly_waste)
+    }
+}
+
+/// Idle resources found in an account, most expensive first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BillingWarnings {
+    pub warnings: Vec<BillingWarning>,
+}
+
+impl BillingWarnings {
+    /// Estimated monthly spend on all idle resources, in USD.
+    pub fn estimated_monthly_waste(&self) -> f64 {
+        self.warnings
+            .iter()
+            .map(|w| w.estimated_monthly_waste)
+            .sum()
+    }
+
+    /// Whether nothing idle was found.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Build the report from already-fetched listings.
+    pub fn from_resources(
+        reserved_ips: &[ReservedIp],
+        droplets: &[DropletBilling],
+        volumes: &[VolumeBilling],
+    ) -> Self {
+        let mut warnings = Vec::new();
+
+        for ip in reserved_ips.iter().filter(|ip| ip.droplet.is_none()) {
+            warnings.push(BillingWarning {
+                kind: IdleResourceKind::UnassignedReservedIp,
+                resource_id: ip.ip.clone(),
+                name: ip.ip.clone(),
+                region: ip.region.as_ref().map(|r| r.slug.clone()),
+                estimated_monthly_waste: UNASSIGNED_RESERVED_IP_PRICE_MONTHLY,
+            });
+        }
+
+        for droplet in droplets.iter().filter(|d| d.status == "off") {
+            warnings.push(BillingWarning {
+                kind: IdleResourceKind::PoweredOffDroplet,
+                resource_id: droplet.id.to_string(),
+                name: droplet.name.clone(),
+                region: droplet.region.as_ref().map(|r| r.slug.clone()),
+                estimated_monthly_waste: droplet.size.as_ref().map_or(0.0, |s| s.price_monthly),
+            });
+        }
+
+        for volume in volumes.iter().filter(|v| v.droplet_ids.is_empty()) {
+            warnings.push(BillingWarning {
+                kind: IdleResourceKind::UnattachedVolume,
+                resource_id: volume.id.clone(),
+                name: volume.name.clone(),
+                region: volume.region.as_ref().map(|r| r.slug.clone()),
+                estimated_monthly_waste: volume.size_gigabytes * VOLUME_PRICE_PER_GIB_MONTH,
+            });
+        }
+
+        warnings.sort_by(|a, b| {
+            b.estimated_monthly_waste
+                .total_cmp(&a.estimated_monthly_waste)
+        });
+        Self { warnings }
+    }
+}
+
+/// Region reference embedded in resource listings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct RegionRef {
+    pub slug: String,
+}
+
+/// The fields of a reserved IP needed to detect idle addresses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReservedIp {
+    pub ip: String,
+    #[serde(default)]
+    pub region: Option<RegionRef>,
+    /// The droplet the address is assigned to, if any.
+    #[serde(default)]
+    pub droplet: Option<serde_json::Value>,
+}
+
+/// The fields of a droplet needed to detect idle droplets.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DropletBilling {
+    pub id: u64,
+    pub name: String,
+    /// `new`, `active`, `off` or `archive`.
+    pub status: String,
+    #[serde(default)]
+    pub region: Option<RegionRef>,
+    #[serde(default)]
+    pub size: Option<SizePrice>,
+}
+
+/// Monthly price of a droplet size.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SizePrice {
+    #[serde(default)]
+    pub price_monthly: f64,
+}
+
+/// The fields of a volume needed to detect unattached volumes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct VolumeBilling {
+    pub id: String,
+    pub name: String,
+    pub size_gigabytes: f64,
+    #[serde(default)]
+    pub droplet_ids: Vec<u64>,
+    #[serde(default)]
+    pub region: Option<RegionRef>,
+}
+
+impl Client {
+    /// Find reserved IPs, droplets and volumes that are billed while idle.
+    ///
+    /// The three listings are fetched concurrently, following pagination.
+    pub async fn billing_warnings(&self) -> Result<BillingWarnings, Error> {
+        let (reserved_ips, droplets, volumes) = futures::try_join!(
+            self.collect_pages::<ReservedIp>("reservedIPs_list", "reserved_ips"),
+            self.collect_pages::<DropletBilling>("droplets_list", "droplets"),
+            self.collect_pages::<VolumeBilling>("volumes_list", "volumes"),
+        )?;
+        Ok(BillingWarnings::from_resources(
+            &reserved_ips,
+            &droplets,
+            &volumes,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_idle_resources_are_reported() {
+        let reserved_ips: Vec<ReservedIp> = serde_json::from_value(json!([
+            {"ip": "45.55.96.47", "region": {"slug": "nyc3"}, "droplet": null},
+            {"ip": "45.55.96.48", "region": {"slug": "nyc3"}, "droplet": {"id": 1}}
+        ]))
+        .unwrap();
+        let droplets: Vec<DropletBilling> = serde_json::from_value(json!([
+            {"id": 1, "name": "web-1", "status": "active", "size": {"price_monthly": 12.0}},
+            {"id": 2, "name": "old-db", "status": "off", "region": {"slug": "ams3"}, "size": {"price_monthly": 48.0}}
+        ]))
+        .unwrap();
+        let volumes: Vec<VolumeBilling> = serde_json::from_value(json!([
+            {"id": "vol-1", "name": "data", "size_gigabytes": 100, "droplet_ids": []},
+            {"id": "vol-2", "name": "logs", "size_gigabytes": 10, "droplet_ids": [1]}
+        ]))
+        .unwrap();
+
+        let report = BillingWarnings::from_resources(&reserved_ips, &droplets, &volumes);
+        let kinds: Vec<_> = report.warnings.iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                IdleResourceKind::PoweredOffDroplet,
+                IdleResourceKind::UnattachedVolume,
+                IdleResourceKind::UnassignedReservedIp,
+            ]
+        );
+        assert!((report.estimated_monthly_waste() - 63.0).abs() < 1e-9);
+        assert_eq!(
+            report.warnings[0].to_string(),
+            "powered-off droplet old-db (2) in ams3: ~$48.00/month"
+        );
+    }
+}
@@ -0,0 +1,505 @@
+//! Configurable construction of [`Client`].
+
+use crate::auth::{self, TokenProvider};
+use crate::circuit_breaker::CircuitBreaker;
+use crate::error::Error;
+use crate::events::{EventHandler, WorkflowEvent};
+use crate::hedging::HedgePolicy;
+use crate::idempotency::IdempotencyStore;
+use crate::interceptor::Interceptor;
+use crate::rate_limit::{AdaptiveThrottle, RateLimiter};
+use crate::wait::{ProgressHandler, WaitProgress};
+use crate::{Client, ClientInfo, ClientState};
+use futures::future::BoxFuture;
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Base URL of the public DigitalOcean API.
+pub(crate) const DEFAULT_BASE_URL: &str = "https://api.digitalocean.com";
+
+/// Timeout for establishing a connection, unless overridden.
+pub(crate) const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Timeout for a whole request, unless overridden.
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// User agent sent with every request; a suffix can be appended with
+/// [`ClientBuilder::user_agent_suffix`].
+pub(crate) const DEFAULT_USER_AGENT: &str = "rsdo/0.1.0";
+
+/// Builder for a [`Client`] with non-default HTTP settings.
+///
+/// [`Client::from_token`] covers the common case; the builder exposes the settings it
+/// hardcodes: timeouts, the base URL (for proxies and sandboxes), the user agent and
+/// extra default headers. [`http_client`](Self::http_client) replaces the HTTP client
+/// altogether, e.g. to route traffic over a unix socket.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use rsdo::ClientBuilder;
+/// use std::time::Duration;
+///
+/// let client = ClientBuilder::new("your-digitalocean-token")
+///     .base_url("https://do-proxy.internal.example.com")
+///     .timeout(Duration::from_secs(120))
+///     .user_agent_suffix("inventory-sync/2.1")
+///     .default_header("X-Team", "platform")
+///     .build()?;
+/// # Ok::<(), rsdo::error::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+#[must_use = "call `.build()` to create the client"]
+pub struct ClientBuilder {
+    token: Option<String>,
+    base_url: String,
+    connect_timeout: Duration,
+    timeout: Duration,
+    user_agent_suffix: Option<String>,
+    headers: Vec<(String, String)>,
+    http_client: Option<reqwest::Client>,
+    state: ClientState,
+}
+
+impl Default for ClientBuilder {
+    /// A builder without credentials; set a [`token_provider`](Self::token_provider)
+    /// before building.
+    fn default() -> Self {
+        Self {
+            token: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            timeout: DEFAULT_TIMEOUT,
+            user_agent_suffix: None,
+            headers: Vec::new(),
+            http_client: None,
+            state: ClientState::default(),
+        }
+    }
+}
+
+impl ClientBuilder {
+    /// Start configuring a client that authenticates with a personal access token.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: Some(token.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Ask `provider` for the token before every request instead of sending a fixed
+    /// token. See [`TokenProvider`].
+    pub fn token_provider(mut self, provider: Arc<dyn TokenProvider>) -> Self {
+        self.state.token_provider = Some(provider);
+        self
+    }
+
+    /// Send requests to `base_url` instead of `https://api.digitalocean.com`.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Run `interceptor` around every request. See [`Interceptor`].
+    pub fn interceptor(mut self, interceptor: Arc<dyn Interceptor>) -> Self {
+        self.state.interceptors.push(interceptor);
+        self
+    }
+
+    /// Call `handler` with the progress events of workflow helpers. See
+    /// [`events`](crate::events).
+    pub fn on_event(mut self, handler: impl Fn(&WorkflowEvent) + Send + Sync + 'static) -> Self {
+        self.state.events = Some(EventHandler(Arc::new(handler)));
+        self
+    }
+
+    /// Call `callback` after every poll of every waiter that did not finish the wait.
+    /// See [`wait`](crate::wait).
+    pub fn on_wait_progress(
+        mut self,
+        callback: impl Fn(&WaitProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.state.wait_progress = Some(ProgressHandler(Arc::new(callback)));
+        self
+    }
+
+    /// Refuse to send anything but `GET` and `HEAD` requests, failing them locally with
+    /// [`Error::ReadOnlyViolation`]. For auditing and reporting tools that must never
+    /// change infrastructure.
+    ///
+    /// A few read-like operations use `POST` (e.g. App Platform bandwidth metrics) and
+    /// are refused as well.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.state.read_only = read_only;
+        self
+    }
+
+    /// Pace requests so that no more than `requests_per_hour` are sent per hour, after
+    /// an initial `burst` sent at once. The budget is shared by all clones of the
+    /// client; DigitalOcean allows 5,000 requests per hour and 250 per minute.
+    ///
+    /// Requests over the budget wait rather than fail, and retries count against it.
+    pub fn rate_limit(mut self, requests_per_hour: u32, burst: u32) -> Self {
+        self.state.rate_limiter = Some(RateLimiter::new(requests_per_hour, burst));
+        self
+    }
+
+    /// Slow down once `RateLimit-Remaining` drops below `slow_below`, spreading the
+    /// remaining requests over the time until `RateLimit-Reset`, and pause until the
+    /// reset when none are left. A warning is logged when throttling engages.
+    ///
+    /// Unlike [`rate_limit`](Self::rate_limit), this follows the API's own count, so
+    /// it also accounts for other programs using the same token.
+    pub fn adaptive_rate_limit(mut self, slow_below: u64) -> Self {
+        self.state.throttle = Some(AdaptiveThrottle::new(slow_below));
+        self
+    }
+
+    /// Send a second copy of `GET` and `HEAD` requests that are slower than the
+    /// policy's percentile of recent reads, using whichever answers first. See
+    /// [`hedging`](crate::hedging).
+    pub fn hedge_reads(mut self, policy: HedgePolicy) -> Self {
+        self.state.hedge = Some(policy);
+        self
+    }
+
+    /// Fail requests fast with [`Error::CircuitOpen`] after repeated `5xx` responses
+    /// or connection failures, probing for recovery after a cool-down. See
+    /// [`circuit_breaker`](crate::circuit_breaker).
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.state.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Record responses of requests sent with an
+    /// [`IdempotencyKey`](crate::idempotency::IdempotencyKey) in `store` instead of
+    /// the client's memory.
+    pub fn idempotency_store(mut self, store: Arc<dyn IdempotencyStore>) -> Self {
+        self.state.idempotency_store = Some(store);
+        self
+    }
+
+    /// Which requests are retried and how; see [`RetryPolicy`](crate::retry::RetryPolicy).
+    pub fn retry_policy(mut self, policy: crate::retry::RetryPolicy) -> Self {
+        self.state.retry = policy;
+        self
+    }
+
+    /// Most items the `*_all` list methods gather before failing. Defaults to
+    /// [`DEFAULT_MAX_ITEMS`](crate::pagination::DEFAULT_MAX_ITEMS).
+    pub fn max_list_items(mut self, max_items: usize) -> Self {
+        self.state.max_list_items = Some(max_items);
+        self
+    }
+
+    /// `per_page` for every list call and pagination stream that does not set its own,
+    /// clamped to each operation's maximum. Defaults to the API's own (20).
+    pub fn default_per_page(mut self, per_page: u64) -> Self {
+        self.state.default_per_page = Some(per_page);
+        self
+    }
+
+    /// Timeout for every request of `operation`, by operation ID or generated method
+    /// name, overriding [`timeout`](Self::timeout). For example, allow minutes for
+    /// image transfers but only seconds for `account_get`.
+    pub fn operation_timeout(mut self, operation: impl Into<String>, timeout: Duration) -> Self {
+        Arc::make_mut(&mut self.state.operation_timeouts).insert(operation.into(), timeout);
+        self
+    }
+
+    /// Timeout for establishing a connection. Defaults to 15 seconds.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Timeout for a whole request, from connecting until the response body has been
+    /// read. Defaults to 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Append `suffix` to the `rsdo/<version>` user agent, e.g. `my-tool/1.2`.
+    pub fn user_agent_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.user_agent_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Send an extra header with every request.
+    ///
+    /// Invalid names or values are reported by [`build`](Self::build). The
+    /// `Authorization` header is always derived from the token.
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Send requests with `http_client` instead of one built from these settings.
+    ///
+    /// Use this for transports the builder does not expose, such as a unix socket or a
+    /// custom TLS setup. The token and default headers are still sent with every
+    /// request, including pagination follow-ups, and the base URL still applies;
+    /// timeouts and the user agent are whatever `http_client` was configured with.
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(unix)]
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let http_client = reqwest::Client::builder()
+    ///     .unix_socket("/run/do-proxy.sock")
+    ///     .build()?;
+    /// let client = rsdo::ClientBuilder::new("your-digitalocean-token")
+    ///     .http_client(http_client)
+    ///     .base_url("http://do-proxy")
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Build the client.
+    ///
+    /// Fails with [`Error::InvalidInput`] if neither a token nor a token provider was
+    /// configured, or if the token, base URL or a header cannot be used in a request.
+    pub fn build(mut self) -> Result<Client, Error> {
+        let base_url = reqwest::Url::parse(&self.base_url).map_err(|err| {
+            Error::InvalidInput(format!("invalid base URL {:?}: {err}", self.base_url))
+        })?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| Error::InvalidInput(format!("invalid header name {name:?}")))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| Error::InvalidInput(format!("invalid value for header {name}")))?;
+            headers.append(name, value);
+        }
+        match &self.token {
+            Some(token) => {
+                headers.insert(header::AUTHORIZATION, auth::bearer(token)?);
+            }
+            None if self.state.token_provider.is_none() => {
+                return Err(Error::InvalidInput(
+                    "a token or token provider is required".to_string(),
+                ));
+            }
+            None => {}
+        }
+
+        if let Some(http_client) = self.http_client {
+            // The custom client has no default headers, so attach them per request.
+            if let Some(token) = self.token {
+                if self.state.token_provider.is_none() {
+                    self.state.token_provider = Some(Arc::new(auth::StaticToken::new(token)));
+                }
+                headers.remove(header::AUTHORIZATION);
+            }
+            if !headers.is_empty() {
+                self.state
+                    .interceptors
+                    .insert(0, Arc::new(DefaultHeaders(headers)));
+            }
+            return Ok(Client::new_with_client_and_state(
+                base_url.as_str().trim_end_matches('/'),
+                http_client,
+                self.state,
+            ));
+        }
+
+        let user_agent = match &self.user_agent_suffix {
+            Some(suffix) => format!("{DEFAULT_USER_AGENT} {suffix}"),
+            None => DEFAULT_USER_AGENT.to_string(),
+        };
+
+        self.state.client_timeout = Some(self.timeout);
+        let http_client = reqwest::ClientBuilder::new()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout)
+            .default_headers(headers)
+            .user_agent(user_agent)
+            .build()
+            .map_err(|err| Error::Other(format!("failed to build HTTP client: {err}")))?;
+
+        Ok(Client::new_with_client_and_state(
+            base_url.as_str().trim_end_matches('/'),
+            http_client,
+            self.state,
+        ))
+    }
+}
+
+/// Adds the builder's default headers to requests sent with a custom HTTP client.
+#[derive(Debug)]
+struct DefaultHeaders(HeaderMap);
+
+impl Interceptor for DefaultHeaders {
+    fn before_send<'a>(
+        &'a self,
+        request: &'a mut reqwest::Request,
+        _operation_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        for name in self.0.keys() {
+            if !request.headers().contains_key(name) {
+                for value in self.0.get_all(name) {
+                    request.headers_mut().append(name, value.clone());
+                }
+            }
+        }
+        Box::pin(async { Ok(()) })
+    }
+}
+
+impl Client {
+    /// Start configuring a client with [`ClientBuilder`].
+    pub fn builder(token: impl Into<String>) -> ClientBuilder {
+        ClientBuilder::new(token)
+    }
+
+    /// Return a copy of this client that refuses mutating requests. See
+    /// [`ClientBuilder::read_only`].
+    pub fn with_read_only(&self, read_only: bool) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.read_only = read_only;
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+
+    /// Return a copy of this client with another default `per_page`. See
+    /// [`ClientBuilder::default_per_page`].
+    pub fn with_default_per_page(&self, per_page: u64) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.default_per_page = Some(per_page);
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+
+    /// Return a copy of this client whose requests time out after `timeout`,
+    /// overriding the client-wide and per-operation timeouts. Use it for a single
+    /// call: `client.with_timeout(Duration::from_secs(120)).droplets_create(&body)`.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.timeout = Some(timeout);
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+
+    /// Return a copy of this client with another cap for the `*_all` list methods.
+    /// See [`ClientBuilder::max_list_items`].
+    pub fn with_max_list_items(&self, max_items: usize) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.max_list_items = Some(max_items);
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_base_url() {
+        let client = ClientBuilder::new("test-token")
+            .base_url("http://localhost:8080/")
+            .build()
+            .unwrap();
+        assert_eq!(client.baseurl(), "http://localhost:8080");
+    }
+
+    #[test]
+    fn test_invalid_base_url_is_rejected() {
+        let result = ClientBuilder::new("test-token")
+            .base_url("not a url")
+            .build();
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_credentials_are_required() {
+        let result = ClientBuilder::default().build();
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+
+        let provider = Arc::new(crate::auth::StaticToken::new("test-token"));
+        assert!(ClientBuilder::default()
+            .token_provider(provider)
+            .build()
+            .is_ok());
+    }
+
+    /// Serves the two pages of a droplet listing over a unix socket, recording the
+    /// request line and headers of each request.
+    #[cfg(unix)]
+    async fn serve_pages(listener: tokio::net::UnixListener) -> Vec<String> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let pages = [
+            // The proxy rewrites links to its own prefix; the client must not double it.
+            r#"{"droplets":[{"id":1}],"links":{"pages":{"next":"http://do-proxy/api/v2/droplets?page=2&per_page=200"}}}"#,
+            r#"{"droplets":[{"id":2}],"links":{}}"#,
+        ];
+        let mut requests = Vec::new();
+        for page in pages {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            requests.push(String::from_utf8(request).unwrap().to_ascii_lowercase());
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{page}",
+                page.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+        requests
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_custom_http_client_end_to_end() {
+        use futures::TryStreamExt;
+
+        let socket = std::env::temp_dir().join(format!("rsdo-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let server = tokio::spawn(serve_pages(
+            tokio::net::UnixListener::bind(&socket).unwrap(),
+        ));
+
+        let http_client = reqwest::Client::builder()
+            .unix_socket(socket.as_path())
+            .build()
+            .unwrap();
+        let client = ClientBuilder::new("test-token")
+            .http_client(http_client)
+            .base_url("http://do-proxy/api")
+            .default_header("X-Team", "platform")
+            .build()
+            .unwrap();
+        let droplets: Vec<serde_json::Value> = client
+            .paginate("droplets_list")
+            .stream()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(droplets.len(), 2);
+
+        let requests = server.await.unwrap();
+        let _ = std::fs::remove_file(&socket);
+        assert!(requests[0].starts_with("get /api/v2/droplets "));
+        assert!(requests[1].starts_with("get /api/v2/droplets?page=2&per_page=200 "));
+        for request in &requests {
+            assert!(request.contains("authorization: bearer test-token\r\n"));
+            assert!(request.contains("x-team: platform\r\n"));
+        }
+    }
+
+    #[test]
+    fn test_invalid_header_is_rejected() {
+        let result = ClientBuilder::new("test-token")
+            .default_header("X-Bad", "line\nbreak")
+            .build();
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+    }
+}
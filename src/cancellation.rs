@@ -0,0 +1,245 @@
+//! Giving up on long workflows.
+//!
+//! Pagination streams, waiters and batch helpers can issue requests and poll for
+//! minutes. A client from [`Client::with_cancellation`] or [`Client::with_deadline`]
+//! stops them promptly once the caller gives up: requests are not sent after the token
+//! is cancelled or the deadline has passed, failing with
+//! [`Error::Cancelled`](crate::error::Error::Cancelled) or
+//! [`Error::DeadlineExceeded`](crate::error::Error::DeadlineExceeded), pauses between
+//! polls and retries end early, and so do a request in flight and its wait for the
+//! client-side rate limit, with the same errors.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::cancellation::CancellationToken;
+//! use std::time::{Duration, Instant};
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let token = CancellationToken::new();
+//! let client = client
+//!     .with_cancellation(token.clone())
+//!     .with_deadline(Instant::now() + Duration::from_secs(600));
+//!
+//! // Elsewhere, e.g. on Ctrl-C: token.cancel();
+//! client
+//!     .delete_droplet_and_wait_gone(3164494, Duration::from_secs(5), Duration::from_secs(900))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Error, OperationContext};
+use crate::{Client, ClientInfo, ClientState};
+use futures::future::{self, Either};
+use std::future::Future;
+use std::pin::pin;
+use std::time::{Duration, Instant};
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Time left until the client's deadline, if it has one.
+fn remaining(state: &ClientState) -> Option<Duration> {
+    state
+        .deadline
+        .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+/// Fails if the client's token was cancelled or its deadline has passed.
+pub(crate) fn check(
+    state: &ClientState,
+    context: impl FnOnce() -> OperationContext,
+) -> Result<(), Error> {
+    if state
+        .cancellation
+        .as_ref()
+        .is_some_and(CancellationToken::is_cancelled)
+    {
+        return Err(Error::Cancelled { context: context() });
+    }
+    if remaining(state).is_some_and(|left| left.is_zero()) {
+        return Err(Error::DeadlineExceeded { context: context() });
+    }
+    Ok(())
+}
+
+/// Timeout for a request with `timeout` set, shortened to end at the deadline. Without
+/// one, the `reqwest::Client`'s timeout (if the builder set it) is shortened instead.
+pub(crate) fn cap_timeout(state: &ClientState, timeout: Option<Duration>) -> Option<Duration> {
+    let Some(left) = remaining(state) else {
+        return timeout;
+    };
+    Some(match timeout.or(state.client_timeout) {
+        Some(timeout) => timeout.min(left),
+        None => left,
+    })
+}
+
+/// Sleep for `duration`, waking early when the client's token is cancelled or its
+/// deadline arrives. Returns whether the whole `duration` passed.
+pub(crate) async fn sleep(state: &ClientState, duration: Duration) -> bool {
+    let (duration, cut_short) = match remaining(state) {
+        Some(left) if left < duration => (left, true),
+        _ => (duration, false),
+    };
+    let sleep = pin!(tokio::time::sleep(duration));
+    match &state.cancellation {
+        Some(token) => match future::select(pin!(token.cancelled()), sleep).await {
+            Either::Left(_) => false,
+            Either::Right(_) => !cut_short,
+        },
+        None => {
+            sleep.await;
+            !cut_short
+        }
+    }
+}
+
+/// Run `work`, giving up with [`Error::Cancelled`] or [`Error::DeadlineExceeded`] as
+/// soon as the client's token is cancelled or its deadline arrives.
+pub(crate) async fn guard<T>(
+    state: &ClientState,
+    context: impl FnOnce() -> OperationContext,
+    work: impl Future<Output = T>,
+) -> Result<T, Error> {
+    let cancelled = async {
+        match &state.cancellation {
+            Some(token) => token.cancelled().await,
+            None => future::pending().await,
+        }
+    };
+    let deadline = async {
+        match state.deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+            None => future::pending().await,
+        }
+    };
+    let (cancelled, deadline) = (pin!(cancelled), pin!(deadline));
+    match future::select(pin!(work), future::select(cancelled, deadline)).await {
+        Either::Left((value, _)) => Ok(value),
+        Either::Right((Either::Left(_), _)) => Err(Error::Cancelled { context: context() }),
+        Either::Right((Either::Right(_), _)) => Err(Error::DeadlineExceeded { context: context() }),
+    }
+}
+
+impl Client {
+    /// Return a copy of this client that stops sending requests and waiting once
+    /// `token` is cancelled.
+    ///
+    /// The copy shares the underlying connection pool with `self`.
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.cancellation = Some(token);
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+
+    /// Return a copy of this client that stops sending requests and waiting at
+    /// `deadline`.
+    ///
+    /// The copy shares the underlying connection pool with `self`.
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.deadline = Some(deadline);
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Method;
+
+    #[tokio::test]
+    async fn test_cancel_ends_sleep_and_blocks_requests() {
+        let token = CancellationToken::new();
+        let state = ClientState {
+            cancellation: Some(token.clone()),
+            ..ClientState::default()
+        };
+        let context =
+            || OperationContext::new("droplets_get", Method::GET, "/v2/droplets/1", false);
+        assert!(check(&state, context).is_ok());
+
+        let canceller = tokio::spawn(async move { token.cancel() });
+        assert!(!sleep(&state, Duration::from_secs(60)).await);
+        canceller.await.unwrap();
+        assert!(matches!(
+            check(&state, context),
+            Err(Error::Cancelled { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generated_call_reports_cancellation_and_deadline() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let client = Client::from_token("test-token").with_cancellation(token);
+        let err = Error::from(client.account_get().await.unwrap_err());
+        assert!(matches!(err, Error::Cancelled { .. }), "{err:?}");
+
+        let client = Client::from_token("test-token").with_deadline(Instant::now());
+        let err = Error::from(client.account_get().await.unwrap_err());
+        assert!(matches!(err, Error::DeadlineExceeded { .. }), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn test_throttled_request_stops_on_cancel_and_deadline() {
+        let client = Client::builder("test-token")
+            .adaptive_rate_limit(100)
+            .build()
+            .unwrap();
+        // Budget used up until an hour from now: the throttle waits for the reset.
+        let reset = chrono::Utc::now().timestamp() + 3600;
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("ratelimit-limit", "5000".parse().unwrap());
+        headers.insert("ratelimit-remaining", "0".parse().unwrap());
+        headers.insert("ratelimit-reset", reset.to_string().parse().unwrap());
+        client.inner().rate_limit.record(&headers);
+
+        let token = CancellationToken::new();
+        let cancelled = client.with_cancellation(token.clone());
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            token.cancel();
+        });
+        let err = tokio::time::timeout(Duration::from_secs(5), cancelled.account_get())
+            .await
+            .expect("cancellation ends the throttle's wait")
+            .unwrap_err();
+        canceller.await.unwrap();
+        assert!(matches!(Error::from(err), Error::Cancelled { .. }));
+
+        let late = client.with_deadline(Instant::now() + Duration::from_millis(50));
+        let err = tokio::time::timeout(Duration::from_secs(5), late.account_get())
+            .await
+            .expect("the deadline ends the throttle's wait")
+            .unwrap_err();
+        assert!(matches!(Error::from(err), Error::DeadlineExceeded { .. }));
+    }
+
+    #[test]
+    fn test_deadline_caps_timeouts() {
+        let state = ClientState {
+            deadline: Some(Instant::now() + Duration::from_secs(10)),
+            client_timeout: Some(Duration::from_secs(30)),
+            ..ClientState::default()
+        };
+        let capped = cap_timeout(&state, None).unwrap();
+        assert!(capped <= Duration::from_secs(10) && capped > Duration::from_secs(9));
+        assert_eq!(
+            cap_timeout(&state, Some(Duration::from_secs(2))),
+            Some(Duration::from_secs(2))
+        );
+
+        let expired = ClientState {
+            deadline: Some(Instant::now()),
+            ..ClientState::default()
+        };
+        let context =
+            || OperationContext::new("droplets_get", Method::GET, "/v2/droplets/1", false);
+        assert!(matches!(
+            check(&expired, context),
+            Err(Error::DeadlineExceeded { .. })
+        ));
+    }
+}
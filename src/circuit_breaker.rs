@@ -0,0 +1,366 @@
+//! Failing fast while DigitalOcean is unavailable.
+//!
+//! During an outage every request waits for its timeout (and retries) before failing,
+//! so services embedding the client pile up slow, doomed requests. A
+//! [`CircuitBreaker`] counts consecutive `5xx` responses and connection failures or
+//! timeouts. Once there are too many, it *opens*: requests fail at once with
+//! [`Error::CircuitOpen`](crate::error::Error::CircuitOpen) without being sent. After
+//! a cool-down it lets a few probe requests through (*half-open*); if they succeed the
+//! circuit closes again, and if one fails it reopens.
+//!
+//! State changes are logged and passed to an optional callback, and
+//! [`CircuitBreaker::metrics`] reports counters for dashboards.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::circuit_breaker::CircuitBreaker;
+//! use rsdo::ClientBuilder;
+//! use std::time::Duration;
+//!
+//! # fn run() -> Result<(), rsdo::error::Error> {
+//! let breaker = CircuitBreaker::new(5)
+//!     .open_for(Duration::from_secs(30))
+//!     .on_state_change(|change| println!("DigitalOcean API circuit: {change}"));
+//! let client = ClientBuilder::new("your-digitalocean-token")
+//!     .circuit_breaker(breaker)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::retry;
+use crate::{Client, ClientInfo};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Whether requests are currently let through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent normally.
+    Closed,
+    /// Requests fail without being sent.
+    Open,
+    /// A limited number of probe requests are sent to test recovery.
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
+            Self::HalfOpen => "half-open",
+        })
+    }
+}
+
+/// A change of [`CircuitState`], as passed to
+/// [`CircuitBreaker::on_state_change`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitTransition {
+    pub from: CircuitState,
+    pub to: CircuitState,
+    /// Consecutive failures seen when the change happened.
+    pub consecutive_failures: u32,
+}
+
+impl fmt::Display for CircuitTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} after {} consecutive failures",
+            self.from, self.to, self.consecutive_failures
+        )
+    }
+}
+
+/// Counters of a [`CircuitBreaker`], shared by all clones of the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitMetrics {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// How often the circuit has opened.
+    pub times_opened: u64,
+    /// Requests failed without being sent because the circuit was open.
+    pub rejected: u64,
+}
+
+type Callback = dyn Fn(&CircuitTransition) + Send + Sync;
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// When the circuit last opened, or the last probe was let through.
+    since: Instant,
+    probes_sent: u32,
+    probes_succeeded: u32,
+    times_opened: u64,
+    rejected: u64,
+}
+
+/// Opens after repeated upstream failures so requests fail fast during outages.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    half_open_probes: u32,
+    on_change: Option<Arc<Callback>>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("failure_threshold", &self.failure_threshold)
+            .field("open_for", &self.open_for)
+            .field("half_open_probes", &self.half_open_probes)
+            .field("metrics", &self.metrics())
+            .finish()
+    }
+}
+
+impl CircuitBreaker {
+    /// Open after `failure_threshold` consecutive failures, for 30 seconds, then close
+    /// again after one successful probe.
+    pub fn new(failure_threshold: u32) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_for: Duration::from_secs(30),
+            half_open_probes: 1,
+            on_change: None,
+            inner: Arc::new(Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                since: Instant::now(),
+                probes_sent: 0,
+                probes_succeeded: 0,
+                times_opened: 0,
+                rejected: 0,
+            })),
+        }
+    }
+
+    /// How long the circuit stays open before probing. Also how long an unanswered
+    /// probe blocks the next one.
+    pub fn open_for(mut self, open_for: Duration) -> Self {
+        self.open_for = open_for;
+        self
+    }
+
+    /// Successful probes needed to close the circuit again. Defaults to 1.
+    pub fn half_open_probes(mut self, probes: u32) -> Self {
+        self.half_open_probes = probes.max(1);
+        self
+    }
+
+    /// Call `callback` on every state change, e.g. to update a metrics gauge.
+    pub fn on_state_change(
+        mut self,
+        callback: impl Fn(&CircuitTransition) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_change = Some(Arc::new(callback));
+        self
+    }
+
+    /// Current state and counters.
+    pub fn metrics(&self) -> CircuitMetrics {
+        let inner = self.lock();
+        CircuitMetrics {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            times_opened: inner.times_opened,
+            rejected: inner.rejected,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Whether a request may be sent at `now`; otherwise how long until the circuit
+    /// will next let one through.
+    pub(crate) fn admit(&self, now: Instant) -> Result<(), Duration> {
+        let mut inner = self.lock();
+        let waited = now.saturating_duration_since(inner.since);
+        let mut change = None;
+        let admitted = match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open if waited >= self.open_for => {
+                change = Some(self.transition(&mut inner, CircuitState::HalfOpen));
+                true
+            }
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                inner.probes_sent < self.half_open_probes || waited >= self.open_for
+            }
+        };
+        let result = if admitted {
+            if inner.state == CircuitState::HalfOpen {
+                inner.probes_sent += 1;
+                inner.since = now;
+            }
+            Ok(())
+        } else {
+            inner.rejected += 1;
+            Err(self.open_for.saturating_sub(waited))
+        };
+        drop(inner);
+        self.notify(change);
+        result
+    }
+
+    /// Record the outcome of a request that was sent.
+    pub(crate) fn record(&self, failed: bool, now: Instant) {
+        let mut inner = self.lock();
+        let mut change = None;
+        if !failed {
+            inner.consecutive_failures = 0;
+            if inner.state == CircuitState::HalfOpen {
+                inner.probes_succeeded += 1;
+                if inner.probes_succeeded >= self.half_open_probes {
+                    change = Some(self.transition(&mut inner, CircuitState::Closed));
+                }
+            }
+        } else {
+            inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+            let trips = match inner.state {
+                CircuitState::Closed => inner.consecutive_failures >= self.failure_threshold,
+                CircuitState::HalfOpen => true,
+                CircuitState::Open => false,
+            };
+            if trips {
+                inner.since = now;
+                inner.times_opened += 1;
+                change = Some(self.transition(&mut inner, CircuitState::Open));
+            }
+        }
+        drop(inner);
+        self.notify(change);
+    }
+
+    /// Move to state `to`, returning the change to report once the lock is released.
+    fn transition(&self, inner: &mut Inner, to: CircuitState) -> CircuitTransition {
+        let change = CircuitTransition {
+            from: inner.state,
+            to,
+            consecutive_failures: inner.consecutive_failures,
+        };
+        inner.state = to;
+        inner.probes_sent = 0;
+        inner.probes_succeeded = 0;
+        change
+    }
+
+    /// Log `change` and pass it to the `on_state_change` callback. Called without the
+    /// lock held, so the callback may inspect the breaker.
+    fn notify(&self, change: Option<CircuitTransition>) {
+        let Some(change) = change else {
+            return;
+        };
+        match change.to {
+            CircuitState::Open => tracing::warn!(%change, "API circuit opened; failing fast"),
+            _ => tracing::info!(%change, "API circuit state changed"),
+        }
+        if let Some(callback) = &self.on_change {
+            callback(&change);
+        }
+    }
+}
+
+/// Whether the outcome of a request counts as an upstream failure: a `5xx` response,
+/// or a connection failure or timeout.
+pub(crate) fn is_failure(result: Result<&reqwest::Response, &reqwest::Error>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(err) => retry::is_transient(err),
+    }
+}
+
+impl Client {
+    /// The client's circuit breaker, if one was configured.
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.inner().circuit_breaker.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::OnceLock;
+
+    #[test]
+    fn test_opens_probes_and_closes() {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let seen = changes.clone();
+        let observed = Arc::new(OnceLock::<CircuitBreaker>::new());
+        let breaker_slot = observed.clone();
+        let breaker = CircuitBreaker::new(2)
+            .open_for(Duration::from_secs(10))
+            .on_state_change(move |change| {
+                // The callback may look at the breaker it observes.
+                let state = breaker_slot.get().map(|breaker| breaker.metrics().state);
+                assert!(state.is_none_or(|state| state == change.to));
+                seen.lock().unwrap().push(change.to);
+            });
+        observed.set(breaker.clone()).unwrap();
+        let start = Instant::now();
+
+        breaker.record(true, start);
+        assert!(breaker.admit(start).is_ok());
+        breaker.record(true, start);
+        assert_eq!(
+            breaker.admit(start + Duration::from_secs(4)),
+            Err(Duration::from_secs(6))
+        );
+
+        // After the cool-down a single probe goes through; its failure reopens.
+        let probe = start + Duration::from_secs(10);
+        assert!(breaker.admit(probe).is_ok());
+        assert!(breaker.admit(probe).is_err());
+        breaker.record(true, probe);
+        assert_eq!(breaker.metrics().state, CircuitState::Open);
+
+        let probe = probe + Duration::from_secs(10);
+        assert!(breaker.admit(probe).is_ok());
+        breaker.record(false, probe);
+        assert_eq!(
+            breaker.metrics(),
+            CircuitMetrics {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                times_opened: 2,
+                rejected: 2,
+            }
+        );
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Closed,
+            ]
+        );
+    }
+    #[tokio::test]
+    async fn test_generated_call_reports_open_circuit() {
+        let breaker = CircuitBreaker::new(1).open_for(Duration::from_secs(60));
+        let client = Client::builder("test-token")
+            .circuit_breaker(breaker.clone())
+            .build()
+            .unwrap();
+        breaker.record(true, Instant::now());
+
+        let err = crate::Error::from(client.account_get().await.unwrap_err());
+        let crate::Error::CircuitOpen { retry_after, .. } = err else {
+            panic!("expected CircuitOpen, got {err:?}");
+        };
+        assert!(retry_after > Duration::from_secs(50));
+    }
+}
@@ -0,0 +1,205 @@
+//! Database backups and point-in-time restore.
+//!
+//! DigitalOcean backs clusters up daily and keeps the write-ahead logs in between, so
+//! a cluster can be restored to any moment since its oldest backup. Restoring always
+//! creates a new cluster: [`Client::restore_to_new_cluster`] copies the source's
+//! engine, version, region, size and node count into the create request, points it at
+//! the source by name and waits for the new cluster to come online.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use chrono::{Duration, Utc};
+//! use rsdo::wait::WaitOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster_id = "9cc10173-e9ea-4176-9dbc-a4cee4c4ff30";
+//! for backup in client.database_backups(cluster_id).await? {
+//!     println!("{} ({} GiB)", backup.created_at, backup.size_gigabytes);
+//! }
+//! let restored = client
+//!     .restore_to_new_cluster(
+//!         cluster_id,
+//!         Utc::now() - Duration::minutes(30),
+//!         "orders-restored",
+//!         WaitOptions::default(),
+//!     )
+//!     .await?;
+//! println!("restored into {}", restored.id);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{DatabaseCluster, DatabaseEnvelope};
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// A backup of a database cluster.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DatabaseBackup {
+    pub created_at: DateTime<Utc>,
+    pub size_gigabytes: f64,
+}
+
+#[derive(Deserialize)]
+struct BackupsEnvelope {
+    #[serde(default)]
+    backups: Vec<DatabaseBackup>,
+}
+
+/// The `databases_create_cluster` body restoring `source` as it was at `at` into a
+/// new cluster `name`.
+///
+/// Fails with [`Error::InvalidInput`] if `at` is after `now` or before the oldest of
+/// `backups`.
+fn restore_body(
+    source: &DatabaseCluster,
+    backups: &[DatabaseBackup],
+    at: DateTime<Utc>,
+    name: &str,
+    now: DateTime<Utc>,
+) -> Result<Value, Error> {
+    if name.is_empty() {
+        return Err(Error::InvalidInput(
+            "the restored cluster needs a name".to_string(),
+        ));
+    }
+    if at > now {
+        return Err(Error::InvalidInput(format!(
+            "cannot restore database cluster {} to {at}, which is in the future",
+            source.name
+        )));
+    }
+    let Some(oldest) = backups.iter().map(|backup| backup.created_at).min() else {
+        return Err(Error::InvalidInput(format!(
+            "database cluster {} has no backups to restore from",
+            source.name
+        )));
+    };
+    if at < oldest {
+        return Err(Error::InvalidInput(format!(
+            "cannot restore database cluster {} to {at}, before its oldest backup at {oldest}",
+            source.name
+        )));
+    }
+    Ok(json!({
+        "name": name,
+        "engine": source.engine,
+        "version": source.version,
+        "region": source.region,
+        "size": source.size,
+        "num_nodes": source.num_nodes,
+        "backup_restore": {
+            "database_name": source.name,
+            "backup_created_at": at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        },
+    }))
+}
+
+impl Client {
+    /// List the backups of database cluster `cluster_id`.
+    pub async fn database_backups(&self, cluster_id: &str) -> Result<Vec<DatabaseBackup>, Error> {
+        let envelope: BackupsEnvelope = self
+            .send_json(ApiRequest::get(
+                "databases_list_backups",
+                format!("/v2/databases/{cluster_id}/backups"),
+            ))
+            .await?;
+        Ok(envelope.backups)
+    }
+
+    /// Restore database cluster `cluster_id` as it was at `at` into a new cluster
+    /// `name` with the same engine, version, region, size and node count, and wait
+    /// until it is online.
+    ///
+    /// Fails with [`Error::InvalidInput`] if `at` is in the future or before the
+    /// cluster's oldest backup, or with [`Error::Timeout`].
+    pub async fn restore_to_new_cluster(
+        &self,
+        cluster_id: &str,
+        at: DateTime<Utc>,
+        name: &str,
+        options: WaitOptions,
+    ) -> Result<DatabaseCluster, Error> {
+        let workflow = Workflow::new(
+            self.inner(),
+            "restore_to_new_cluster",
+            format!("database cluster {cluster_id} at {at} into {name}"),
+        );
+        let source = workflow
+            .step("check", self.database_cluster(cluster_id))
+            .await?;
+        let backups = workflow
+            .step("list_backups", self.database_backups(cluster_id))
+            .await?;
+        let body = restore_body(&source, &backups, at, name, Utc::now())?;
+        let created: DatabaseEnvelope = workflow
+            .step(
+                "create",
+                self.send_json(
+                    ApiRequest::post("databases_create_cluster", "/v2/databases").json(body),
+                ),
+            )
+            .await?;
+        workflow
+            .step(
+                "wait",
+                self.wait_for_database_online(&created.database.id, options),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_body() {
+        let source: DatabaseCluster = serde_json::from_value(json!({
+            "id": "9cc10173-e9ea-4176-9dbc-a4cee4c4ff30",
+            "name": "orders",
+            "engine": "pg",
+            "version": "16",
+            "status": "online",
+            "num_nodes": 2,
+            "size": "db-s-2vcpu-4gb",
+            "region": "nyc3",
+            "created_at": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        let backups: Vec<DatabaseBackup> = serde_json::from_value(json!([
+            { "created_at": "2024-05-02T00:00:00Z", "size_gigabytes": 1.5 },
+            { "created_at": "2024-05-01T00:00:00Z", "size_gigabytes": 1.4 },
+        ]))
+        .unwrap();
+        let now: DateTime<Utc> = "2024-05-03T00:00:00Z".parse().unwrap();
+        let at: DateTime<Utc> = "2024-05-02T12:30:15.250Z".parse().unwrap();
+
+        assert_eq!(
+            restore_body(&source, &backups, at, "orders-restored", now).unwrap(),
+            json!({
+                "name": "orders-restored",
+                "engine": "pg",
+                "version": "16",
+                "region": "nyc3",
+                "size": "db-s-2vcpu-4gb",
+                "num_nodes": 2,
+                "backup_restore": {
+                    "database_name": "orders",
+                    "backup_created_at": "2024-05-02T12:30:15Z",
+                },
+            })
+        );
+        let early = "2024-04-30T00:00:00Z".parse().unwrap();
+        assert!(restore_body(&source, &backups, early, "r", now).is_err());
+        assert!(restore_body(&source, &backups, now + chrono::Days::new(1), "r", now).is_err());
+        assert!(restore_body(&source, &[], at, "r", now).is_err());
+    }
+}
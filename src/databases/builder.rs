@@ -0,0 +1,406 @@
+//! Building a database cluster create request.
+//!
+//! Not every engine, version, size, region and node count combination exists: the
+//! smallest size has no standby nodes, Redis clusters take no extra storage, and so
+//! on. [`DatabaseClusterBuilder::validate`] catches the rules that are known up front,
+//! and [`DatabaseClusterBuilder::body`] checks the rest against the options the API
+//! lists for the engine, so a bad combination fails before anything is created.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::databases::{DatabaseClusterBuilder, DatabaseEngine};
+//! use rsdo::wait::WaitOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster = DatabaseClusterBuilder::new("orders", DatabaseEngine::Postgres)
+//!     .version("16")
+//!     .region("nyc3")
+//!     .size("db-s-2vcpu-4gb")
+//!     .num_nodes(2)
+//!     .create_and_wait(&client, WaitOptions::default())
+//!     .await?;
+//! println!("{} is {}", cluster.name, cluster.status);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{DatabaseCluster, DatabaseEnvelope};
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The smallest size, which only runs single-node clusters.
+const SMALLEST_SIZE: &str = "db-s-1vcpu-1gb";
+
+/// Engine of a database cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DatabaseEngine {
+    #[serde(rename = "pg")]
+    Postgres,
+    #[serde(rename = "mysql")]
+    MySql,
+    #[serde(rename = "redis")]
+    Redis,
+    #[serde(rename = "valkey")]
+    Valkey,
+    #[serde(rename = "mongodb")]
+    MongoDb,
+    #[serde(rename = "kafka")]
+    Kafka,
+    #[serde(rename = "opensearch")]
+    OpenSearch,
+}
+
+impl DatabaseEngine {
+    /// The engine's slug, e.g. `pg`.
+    pub fn slug(self) -> &'static str {
+        match self {
+            Self::Postgres => "pg",
+            Self::MySql => "mysql",
+            Self::Redis => "redis",
+            Self::Valkey => "valkey",
+            Self::MongoDb => "mongodb",
+            Self::Kafka => "kafka",
+            Self::OpenSearch => "opensearch",
+        }
+    }
+
+    /// Node counts a cluster of this engine can have.
+    fn node_counts(self) -> &'static [u32] {
+        match self {
+            Self::MongoDb => &[1, 3],
+            Self::Kafka => &[3],
+            _ => &[1, 2, 3],
+        }
+    }
+}
+
+impl fmt::Display for DatabaseEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.slug())
+    }
+}
+
+/// What the API offers for one engine.
+#[derive(Debug, Default, Deserialize)]
+struct EngineOptions {
+    #[serde(default)]
+    regions: Vec<String>,
+    #[serde(default)]
+    versions: Vec<String>,
+    #[serde(default)]
+    layouts: Vec<Layout>,
+}
+
+/// The sizes available for a node count.
+#[derive(Debug, Deserialize)]
+struct Layout {
+    num_nodes: u32,
+    #[serde(default)]
+    sizes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct OptionsEnvelope {
+    options: HashMap<String, EngineOptions>,
+}
+
+/// Builder for creating a database cluster.
+#[derive(Debug, Clone)]
+#[must_use = "call `.create()` or `.create_and_wait()` to create the cluster"]
+pub struct DatabaseClusterBuilder {
+    name: String,
+    engine: DatabaseEngine,
+    version: Option<String>,
+    region: Option<String>,
+    size: Option<String>,
+    num_nodes: u32,
+    storage_size_mib: Option<u64>,
+    private_network_uuid: Option<String>,
+    project_id: Option<String>,
+    tags: Vec<String>,
+}
+
+impl DatabaseClusterBuilder {
+    /// Start a single-node cluster named `name`, on the engine's default version.
+    pub fn new(name: impl Into<String>, engine: DatabaseEngine) -> Self {
+        Self {
+            name: name.into(),
+            engine,
+            version: None,
+            region: None,
+            size: None,
+            num_nodes: 1,
+            storage_size_mib: None,
+            private_network_uuid: None,
+            project_id: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Major version of the engine, e.g. `16` for PostgreSQL.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Region slug, e.g. `nyc3`. Required.
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Size slug of the nodes, e.g. `db-s-2vcpu-4gb`. Required.
+    pub fn size(mut self, size: impl Into<String>) -> Self {
+        self.size = Some(size.into());
+        self
+    }
+
+    /// Number of nodes: the primary plus standby nodes.
+    pub fn num_nodes(mut self, num_nodes: u32) -> Self {
+        self.num_nodes = num_nodes;
+        self
+    }
+
+    /// Disk size beyond the size's default, in MiB.
+    pub fn storage_size_mib(mut self, storage_size_mib: u64) -> Self {
+        self.storage_size_mib = Some(storage_size_mib);
+        self
+    }
+
+    /// VPC to place the cluster in, instead of the region's default.
+    pub fn vpc(mut self, vpc_uuid: impl Into<String>) -> Self {
+        self.private_network_uuid = Some(vpc_uuid.into());
+        self
+    }
+
+    /// Project to assign the cluster to, instead of the default project.
+    pub fn project(mut self, project_id: impl Into<String>) -> Self {
+        self.project_id = Some(project_id.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Check the request without sending it.
+    ///
+    /// Fails with [`Error::InvalidInput`] listing every problem: a missing name,
+    /// region or size, a node count the engine does not support, standby nodes on the
+    /// smallest size, or extra storage for Redis or Valkey.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.check(self.problems())
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.name.is_empty() {
+            problems.push("name is empty".to_string());
+        }
+        if self.region.is_none() {
+            problems.push("region is required".to_string());
+        }
+        if self.size.is_none() {
+            problems.push("size is required".to_string());
+        }
+        let counts = self.engine.node_counts();
+        if !counts.contains(&self.num_nodes) {
+            problems.push(format!(
+                "{} clusters have {} nodes, not {}",
+                self.engine,
+                counts
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" or "),
+                self.num_nodes
+            ));
+        }
+        if self.size.as_deref() == Some(SMALLEST_SIZE) && self.num_nodes > 1 {
+            problems.push(format!("{SMALLEST_SIZE} clusters have no standby nodes"));
+        }
+        if self.storage_size_mib.is_some()
+            && matches!(self.engine, DatabaseEngine::Redis | DatabaseEngine::Valkey)
+        {
+            problems.push(format!("{} clusters take no extra storage", self.engine));
+        }
+        problems
+    }
+
+    /// The parts of the request the API's `offered` options for the engine lack.
+    fn unoffered(&self, offered: Option<&EngineOptions>) -> Vec<String> {
+        let Some(offered) = offered else {
+            return vec![format!("{} clusters are not offered", self.engine)];
+        };
+        let mut problems = Vec::new();
+        if let Some(region) = self
+            .region
+            .as_ref()
+            .filter(|r| !offered.regions.contains(r))
+        {
+            problems.push(format!("{} is not offered in {region}", self.engine));
+        }
+        if let Some(version) = self
+            .version
+            .as_ref()
+            .filter(|v| !offered.versions.contains(v))
+        {
+            problems.push(format!(
+                "{} {version} is not offered; versions are {}",
+                self.engine,
+                offered.versions.join(", ")
+            ));
+        }
+        let sizes = offered
+            .layouts
+            .iter()
+            .find(|layout| layout.num_nodes == self.num_nodes)
+            .map(|layout| layout.sizes.as_slice())
+            .unwrap_or_default();
+        if let Some(size) = self.size.as_ref().filter(|s| !sizes.contains(s)) {
+            problems.push(format!(
+                "{size} is not offered for {}-node {} clusters",
+                self.num_nodes, self.engine
+            ));
+        }
+        problems
+    }
+
+    fn check(&self, problems: Vec<String>) -> Result<(), Error> {
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(format!(
+                "invalid database cluster {:?}: {}",
+                self.name,
+                problems.join("; ")
+            )))
+        }
+    }
+
+    /// The `databases_create_cluster` body, validated locally and against the
+    /// options the API offers for the engine.
+    ///
+    /// Fails with [`Error::InvalidInput`] if the engine is not offered in the region,
+    /// or the version or the size for the node count is not offered.
+    pub async fn body(&self, client: &Client) -> Result<Value, Error> {
+        self.validate()?;
+        let options: OptionsEnvelope = client
+            .send_json(ApiRequest::get(
+                "databases_list_options",
+                "/v2/databases/options",
+            ))
+            .await?;
+        self.check(self.unoffered(options.options.get(self.engine.slug())))?;
+
+        let mut body = json!({
+            "name": self.name,
+            "engine": self.engine,
+            "region": self.region,
+            "size": self.size,
+            "num_nodes": self.num_nodes,
+        });
+        for (key, value) in [
+            ("version", &self.version),
+            ("private_network_uuid", &self.private_network_uuid),
+            ("project_id", &self.project_id),
+        ] {
+            if let Some(value) = value {
+                body[key] = json!(value);
+            }
+        }
+        if let Some(storage_size_mib) = self.storage_size_mib {
+            body["storage_size_mib"] = json!(storage_size_mib);
+        }
+        if !self.tags.is_empty() {
+            body["tags"] = json!(self.tags);
+        }
+        Ok(body)
+    }
+
+    /// Create the cluster and return it as the API first reports it, creating.
+    pub async fn create(&self, client: &Client) -> Result<DatabaseCluster, Error> {
+        let body = self.body(client).await?;
+        let envelope: DatabaseEnvelope = client
+            .send_json(ApiRequest::post("databases_create_cluster", "/v2/databases").json(body))
+            .await?;
+        Ok(envelope.database)
+    }
+
+    /// Create the cluster and poll it until it is online.
+    ///
+    /// Fails with [`Error::Timeout`] if it is not online after the options' timeout.
+    pub async fn create_and_wait(
+        &self,
+        client: &Client,
+        options: WaitOptions,
+    ) -> Result<DatabaseCluster, Error> {
+        let workflow = Workflow::new(
+            client.inner(),
+            "create_database_cluster_and_wait",
+            format!("database cluster {}", self.name),
+        );
+        let created = workflow.step("create", self.create(client)).await?;
+        workflow
+            .step(
+                "wait",
+                client.wait_for_database_online(&created.id, options),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_specific_validation() {
+        let err = DatabaseClusterBuilder::new("cache", DatabaseEngine::Redis)
+            .size(SMALLEST_SIZE)
+            .num_nodes(2)
+            .storage_size_mib(20480)
+            .validate()
+            .unwrap_err();
+        let Error::InvalidInput(message) = err else {
+            panic!("expected invalid input");
+        };
+        assert!(message.contains("region is required"));
+        assert!(message.contains("db-s-1vcpu-1gb clusters have no standby nodes"));
+        assert!(message.contains("redis clusters take no extra storage"));
+
+        let builder = DatabaseClusterBuilder::new("events", DatabaseEngine::Kafka)
+            .region("nyc3")
+            .size("gd-2vcpu-8gb")
+            .version("3.5");
+        assert!(builder
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("kafka clusters have 3 nodes, not 1"));
+
+        let options: OptionsEnvelope = serde_json::from_value(json!({
+            "options": { "kafka": {
+                "regions": ["nyc3", "ams3"],
+                "versions": ["3.7"],
+                "layouts": [{ "num_nodes": 3, "sizes": ["gd-2vcpu-8gb"] }],
+            }}
+        }))
+        .unwrap();
+        let builder = builder.num_nodes(3);
+        assert_eq!(
+            builder.unoffered(options.options.get("kafka")),
+            ["kafka 3.5 is not offered; versions are 3.7"]
+        );
+        assert_eq!(builder.unoffered(None), ["kafka clusters are not offered"]);
+    }
+}
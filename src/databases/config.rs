@@ -0,0 +1,435 @@
+//! Typed engine configuration.
+//!
+//! The config endpoints take a different JSON object per engine. [`PostgresConfig`],
+//! [`MysqlConfig`], [`RedisConfig`] and [`KafkaConfig`] name the common tuning
+//! parameters with their types, and [`DatabaseConfig::validate`] checks them against
+//! the ranges the API accepts. Parameters without a field are kept in `other`, so a
+//! config read with [`Client::get_config`] loses nothing.
+//!
+//! [`Client::patch_config`] only sends the fields that are set, after checking the
+//! values and that the cluster runs the config's engine.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::databases::PostgresConfig;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster_id = "9cc10173-e9ea-4176-9dbc-a4cee4c4ff30";
+//! let current: PostgresConfig = client.get_config(cluster_id).await?;
+//! println!("work_mem is {:?} MB", current.work_mem);
+//!
+//! let patch = PostgresConfig {
+//!     work_mem: Some(16),
+//!     log_min_duration_statement: Some(500),
+//!     ..PostgresConfig::default()
+//! };
+//! client.patch_config(cluster_id, &patch).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fmt::Display;
+
+/// The configuration of one database engine.
+pub trait DatabaseConfig: Serialize + DeserializeOwned {
+    /// Slug of the engine, e.g. `pg`.
+    const ENGINE: &'static str;
+
+    /// The values outside the ranges the API accepts.
+    fn problems(&self) -> Vec<String>;
+
+    /// Check the values against the ranges the API accepts.
+    ///
+    /// Fails with [`Error::InvalidInput`] listing every value out of range.
+    fn validate(&self) -> Result<(), Error> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(format!(
+                "invalid {} config: {}",
+                Self::ENGINE,
+                problems.join("; ")
+            )))
+        }
+    }
+}
+
+/// Record a problem if `value` is set and outside `min..=max`.
+fn check_range<T: PartialOrd + Display + Copy>(
+    problems: &mut Vec<String>,
+    name: &str,
+    value: Option<T>,
+    min: T,
+    max: T,
+) {
+    if let Some(value) = value.filter(|value| *value < min || *value > max) {
+        problems.push(format!("{name} is {value}, outside {min}..={max}"));
+    }
+}
+
+/// PostgreSQL settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PostgresConfig {
+    /// Seconds between autovacuum runs on a database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autovacuum_naptime: Option<u32>,
+    /// Fraction of a table that must change before it is vacuumed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autovacuum_vacuum_scale_factor: Option<f64>,
+    /// Fraction of a table that must change before it is analyzed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autovacuum_analyze_scale_factor: Option<f64>,
+    /// Milliseconds after which sessions idle in a transaction are ended; 0 disables.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_in_transaction_session_timeout: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jit: Option<bool>,
+    /// Log statements running longer than this many milliseconds; -1 disables.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_min_duration_statement: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallel_workers: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallel_workers_per_gather: Option<u32>,
+    /// Share of memory used for shared buffers, in percent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_buffers_percentage: Option<f64>,
+    /// Memory per sort or hash operation, in MB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_mem: Option<u32>,
+    /// e.g. `Europe/Helsinki`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Settings without a field.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl DatabaseConfig for PostgresConfig {
+    const ENGINE: &'static str = "pg";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let p = &mut problems;
+        check_range(p, "autovacuum_naptime", self.autovacuum_naptime, 1, 86400);
+        for (name, value) in [
+            (
+                "autovacuum_vacuum_scale_factor",
+                self.autovacuum_vacuum_scale_factor,
+            ),
+            (
+                "autovacuum_analyze_scale_factor",
+                self.autovacuum_analyze_scale_factor,
+            ),
+        ] {
+            check_range(p, name, value, 0.0, 1.0);
+        }
+        check_range(
+            p,
+            "idle_in_transaction_session_timeout",
+            self.idle_in_transaction_session_timeout,
+            0,
+            604_800_000,
+        );
+        check_range(
+            p,
+            "log_min_duration_statement",
+            self.log_min_duration_statement,
+            -1,
+            86_400_000,
+        );
+        check_range(p, "max_parallel_workers", self.max_parallel_workers, 0, 96);
+        check_range(
+            p,
+            "max_parallel_workers_per_gather",
+            self.max_parallel_workers_per_gather,
+            0,
+            96,
+        );
+        check_range(
+            p,
+            "shared_buffers_percentage",
+            self.shared_buffers_percentage,
+            20.0,
+            60.0,
+        );
+        check_range(p, "work_mem", self.work_mem, 1, 1024);
+        problems
+    }
+}
+
+/// MySQL settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MysqlConfig {
+    /// Seconds to wait for a connection handshake.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u32>,
+    /// e.g. `+03:00` or `SYSTEM`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_time_zone: Option<String>,
+    /// Seconds a transaction waits for a row lock.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub innodb_lock_wait_timeout: Option<u32>,
+    /// Seconds before an idle interactive connection is closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interactive_timeout: Option<u32>,
+    /// Largest packet, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_allowed_packet: Option<u32>,
+    /// Seconds before an idle connection is closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait_timeout: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slow_query_log: Option<bool>,
+    /// Seconds a query must run to be logged as slow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_query_time: Option<f64>,
+    /// Comma-separated SQL modes, e.g. `ANSI,TRADITIONAL`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sql_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sql_require_primary_key: Option<bool>,
+    /// Settings without a field.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl DatabaseConfig for MysqlConfig {
+    const ENGINE: &'static str = "mysql";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let p = &mut problems;
+        check_range(p, "connect_timeout", self.connect_timeout, 2, 3600);
+        check_range(
+            p,
+            "innodb_lock_wait_timeout",
+            self.innodb_lock_wait_timeout,
+            1,
+            3600,
+        );
+        check_range(
+            p,
+            "interactive_timeout",
+            self.interactive_timeout,
+            30,
+            604_800,
+        );
+        check_range(
+            p,
+            "max_allowed_packet",
+            self.max_allowed_packet,
+            102_400,
+            1_073_741_824,
+        );
+        check_range(p, "wait_timeout", self.wait_timeout, 1, 2_147_483);
+        check_range(p, "long_query_time", self.long_query_time, 0.0, 3600.0);
+        problems
+    }
+}
+
+/// What Redis evicts when it reaches its memory limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MaxmemoryPolicy {
+    Noeviction,
+    AllkeysLru,
+    AllkeysRandom,
+    VolatileLru,
+    VolatileRandom,
+    VolatileTtl,
+}
+
+/// Redis settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RedisConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis_maxmemory_policy: Option<MaxmemoryPolicy>,
+    /// Seconds before an idle client is disconnected; 0 never.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis_timeout: Option<u32>,
+    /// Keyspace events to publish, e.g. `Ex`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis_notify_keyspace_events: Option<String>,
+    /// `rdb` to persist snapshots to disk, `off` to keep data in memory only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis_persistence: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis_ssl: Option<bool>,
+    /// Settings without a field.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl DatabaseConfig for RedisConfig {
+    const ENGINE: &'static str = "redis";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        check_range(
+            &mut problems,
+            "redis_timeout",
+            self.redis_timeout,
+            0,
+            31_536_000,
+        );
+        if let Some(persistence) = self
+            .redis_persistence
+            .as_deref()
+            .filter(|p| !["off", "rdb"].contains(p))
+        {
+            problems.push(format!(
+                "redis_persistence is {persistence:?}, not \"off\" or \"rdb\""
+            ));
+        }
+        problems
+    }
+}
+
+/// Kafka settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KafkaConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_create_topics_enable: Option<bool>,
+    /// Milliseconds a consumer group waits for members before the first rebalance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_initial_rebalance_delay_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_min_session_timeout_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_max_session_timeout_ms: Option<u32>,
+    /// Largest record batch, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_max_bytes: Option<u32>,
+    /// Default retention of topics, in hours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_retention_hours: Option<u32>,
+    /// Settings without a field.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl DatabaseConfig for KafkaConfig {
+    const ENGINE: &'static str = "kafka";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let p = &mut problems;
+        check_range(
+            p,
+            "group_initial_rebalance_delay_ms",
+            self.group_initial_rebalance_delay_ms,
+            0,
+            300_000,
+        );
+        check_range(
+            p,
+            "message_max_bytes",
+            self.message_max_bytes,
+            0,
+            100_001_200,
+        );
+        if let (Some(min), Some(max)) = (
+            self.group_min_session_timeout_ms,
+            self.group_max_session_timeout_ms,
+        ) {
+            if min > max {
+                p.push(format!(
+                    "group_min_session_timeout_ms ({min}) exceeds group_max_session_timeout_ms ({max})"
+                ));
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Deserialize)]
+struct ConfigEnvelope<C> {
+    config: C,
+}
+
+impl Client {
+    /// Fetch the configuration of database cluster `cluster_id` as `C`, e.g.
+    /// [`PostgresConfig`].
+    pub async fn get_config<C: DatabaseConfig>(&self, cluster_id: &str) -> Result<C, Error> {
+        let envelope: ConfigEnvelope<C> = self
+            .send_json(ApiRequest::get(
+                "databases_get_config",
+                format!("/v2/databases/{cluster_id}/config"),
+            ))
+            .await?;
+        Ok(envelope.config)
+    }
+
+    /// Change the settings of database cluster `cluster_id` that are set in `config`,
+    /// leaving the others as they are.
+    ///
+    /// Fails with [`Error::InvalidInput`] if a value is out of range or the cluster
+    /// does not run `C`'s engine.
+    pub async fn patch_config<C: DatabaseConfig>(
+        &self,
+        cluster_id: &str,
+        config: &C,
+    ) -> Result<(), Error> {
+        config.validate()?;
+        let cluster = self.database_cluster(cluster_id).await?;
+        if cluster.engine != C::ENGINE {
+            return Err(Error::InvalidInput(format!(
+                "database cluster {cluster_id} runs {}, not {}",
+                cluster.engine,
+                C::ENGINE
+            )));
+        }
+        self.send_empty(
+            ApiRequest::patch(
+                "databases_patch_config",
+                format!("/v2/databases/{cluster_id}/config"),
+            )
+            .json(json!({ "config": config })),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_round_trip_and_validation() {
+        let config: PostgresConfig = serde_json::from_value(json!({
+            "work_mem": 4,
+            "jit": true,
+            "pg_stat_statements.track": "top",
+        }))
+        .unwrap();
+        assert_eq!(config.work_mem, Some(4));
+        assert_eq!(
+            json!(config),
+            json!({ "work_mem": 4, "jit": true, "pg_stat_statements.track": "top" })
+        );
+
+        let patch = PostgresConfig {
+            work_mem: Some(2048),
+            autovacuum_vacuum_scale_factor: Some(0.2),
+            ..PostgresConfig::default()
+        };
+        assert_eq!(patch.problems(), ["work_mem is 2048, outside 1..=1024"]);
+
+        let redis = RedisConfig {
+            redis_maxmemory_policy: Some(MaxmemoryPolicy::AllkeysLru),
+            redis_persistence: Some("aof".to_string()),
+            ..RedisConfig::default()
+        };
+        assert_eq!(json!(redis)["redis_maxmemory_policy"], "allkeys-lru");
+        assert!(redis.validate().is_err());
+    }
+}
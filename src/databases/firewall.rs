@@ -0,0 +1,277 @@
+//! Database firewall (trusted sources) management.
+//!
+//! A cluster only accepts connections from its trusted sources. The API replaces the
+//! whole list on every update, so [`Client::allow_source`] and
+//! [`Client::revoke_source`] read the current rules and write them back with the one
+//! change, and [`Client::sync_sources`] converges the list on a desired set, leaving
+//! rules that stay untouched and skipping the update when nothing changes.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::databases::Source;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let diff = client
+//!     .sync_sources(
+//!         "9cc10173-e9ea-4176-9dbc-a4cee4c4ff30",
+//!         [
+//!             Source::Tag("backend".to_string()),
+//!             Source::K8s("bd5f5959-5e1e-4205-a714-a914373942af".to_string()),
+//!             Source::Ip("203.0.113.0/24".to_string()),
+//!         ],
+//!     )
+//!     .await?;
+//! println!("added {:?}, removed {:?}", diff.added, diff.removed);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+
+/// A source of connections a cluster trusts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "RawSource", into = "RawSource")]
+pub enum Source {
+    Droplet(u64),
+    /// An IP address or CIDR range, e.g. `203.0.113.0/24`.
+    Ip(String),
+    /// A Kubernetes cluster, by ID.
+    K8s(String),
+    /// Droplets with a tag.
+    Tag(String),
+    /// An App Platform app, by ID.
+    App(String),
+    /// A rule type this version of rsdo does not know about yet.
+    Unknown {
+        kind: String,
+        value: String,
+    },
+}
+
+/// A rule as the API represents it.
+#[derive(Serialize, Deserialize)]
+struct RawSource {
+    #[serde(rename = "type")]
+    kind: String,
+    value: String,
+}
+
+impl From<RawSource> for Source {
+    fn from(raw: RawSource) -> Self {
+        match raw.kind.as_str() {
+            "droplet" => match raw.value.parse() {
+                Ok(id) => Self::Droplet(id),
+                Err(_) => Self::Unknown {
+                    kind: raw.kind,
+                    value: raw.value,
+                },
+            },
+            "ip_addr" => Self::Ip(raw.value),
+            "k8s" => Self::K8s(raw.value),
+            "tag" => Self::Tag(raw.value),
+            "app" => Self::App(raw.value),
+            _ => Self::Unknown {
+                kind: raw.kind,
+                value: raw.value,
+            },
+        }
+    }
+}
+
+impl From<Source> for RawSource {
+    fn from(source: Source) -> Self {
+        let (kind, value) = match source {
+            Source::Droplet(id) => ("droplet".to_string(), id.to_string()),
+            Source::Ip(value) => ("ip_addr".to_string(), value),
+            Source::K8s(value) => ("k8s".to_string(), value),
+            Source::Tag(value) => ("tag".to_string(), value),
+            Source::App(value) => ("app".to_string(), value),
+            Source::Unknown { kind, value } => (kind, value),
+        };
+        Self { kind, value }
+    }
+}
+
+/// A trusted source of a cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FirewallRule {
+    pub uuid: String,
+    #[serde(flatten)]
+    pub source: Source,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// What [`Client::sync_sources`] changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceDiff {
+    pub added: Vec<Source>,
+    pub removed: Vec<Source>,
+}
+
+impl SourceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+struct Rules {
+    #[serde(default)]
+    rules: Vec<FirewallRule>,
+}
+
+/// The changes turning `current` into `desired`, in the order of each.
+fn diff(current: &[FirewallRule], desired: &[Source]) -> SourceDiff {
+    let existing: HashSet<&Source> = current.iter().map(|rule| &rule.source).collect();
+    let wanted: HashSet<&Source> = desired.iter().collect();
+    let mut added: Vec<Source> = Vec::new();
+    for source in desired {
+        if !existing.contains(source) && !added.contains(source) {
+            added.push(source.clone());
+        }
+    }
+    SourceDiff {
+        added,
+        removed: current
+            .iter()
+            .map(|rule| rule.source.clone())
+            .filter(|source| !wanted.contains(source))
+            .collect(),
+    }
+}
+
+impl Client {
+    /// List the trusted sources of database cluster `cluster_id`.
+    pub async fn database_firewall(&self, cluster_id: &str) -> Result<Vec<FirewallRule>, Error> {
+        let rules: Rules = self
+            .send_json(ApiRequest::get(
+                "databases_list_firewall_rules",
+                format!("/v2/databases/{cluster_id}/firewall"),
+            ))
+            .await?;
+        Ok(rules.rules)
+    }
+
+    /// Trust `source` on database cluster `cluster_id`. Returns whether it was added,
+    /// `false` if it was trusted already.
+    pub async fn allow_source(&self, cluster_id: &str, source: Source) -> Result<bool, Error> {
+        let current = self.database_firewall(cluster_id).await?;
+        let mut desired: Vec<Source> = current.iter().map(|rule| rule.source.clone()).collect();
+        desired.push(source);
+        let diff = self.apply_sources(cluster_id, &current, &desired).await?;
+        Ok(!diff.is_empty())
+    }
+
+    /// Stop trusting `source` on database cluster `cluster_id`. Returns whether it was
+    /// removed, `false` if it was not trusted.
+    pub async fn revoke_source(&self, cluster_id: &str, source: &Source) -> Result<bool, Error> {
+        let current = self.database_firewall(cluster_id).await?;
+        let desired: Vec<Source> = current
+            .iter()
+            .map(|rule| rule.source.clone())
+            .filter(|existing| existing != source)
+            .collect();
+        let diff = self.apply_sources(cluster_id, &current, &desired).await?;
+        Ok(!diff.is_empty())
+    }
+
+    /// Make `desired` the exact set of trusted sources of database cluster
+    /// `cluster_id`, and return what changed.
+    ///
+    /// Rules that stay keep their IDs; nothing is written if the sets already match.
+    /// Sources are compared as written, so `10.0.0.1` and `10.0.0.1/32` differ. An
+    /// empty `desired` removes every rule, which opens the cluster to all sources.
+    pub async fn sync_sources(
+        &self,
+        cluster_id: &str,
+        desired: impl IntoIterator<Item = Source>,
+    ) -> Result<SourceDiff, Error> {
+        let desired: Vec<Source> = desired.into_iter().collect();
+        let workflow = Workflow::new(
+            self.inner(),
+            "sync_sources",
+            format!("database cluster {cluster_id}"),
+        );
+        let current = workflow
+            .step("fetch", self.database_firewall(cluster_id))
+            .await?;
+        workflow
+            .step("apply", self.apply_sources(cluster_id, &current, &desired))
+            .await
+    }
+
+    /// Replace the `current` rules with `desired` if they differ, keeping the IDs of
+    /// rules that stay.
+    async fn apply_sources(
+        &self,
+        cluster_id: &str,
+        current: &[FirewallRule],
+        desired: &[Source],
+    ) -> Result<SourceDiff, Error> {
+        let diff = diff(current, desired);
+        if diff.is_empty() {
+            return Ok(diff);
+        }
+        let kept = current
+            .iter()
+            .filter(|rule| !diff.removed.contains(&rule.source))
+            .map(|rule| {
+                let mut value = json!(rule.source);
+                value["uuid"] = json!(rule.uuid);
+                value
+            });
+        let rules: Vec<_> = kept
+            .chain(diff.added.iter().map(|source| json!(source)))
+            .collect();
+        self.send_empty(
+            ApiRequest::put(
+                "databases_update_firewall_rules",
+                format!("/v2/databases/{cluster_id}/firewall"),
+            )
+            .json(json!({ "rules": rules })),
+        )
+        .await?;
+        Ok(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_is_minimal() {
+        let current: Vec<FirewallRule> = serde_json::from_value(json!([
+            { "uuid": "79f26d28", "type": "droplet", "value": "163973392" },
+            { "uuid": "adfe81a8", "type": "ip_addr", "value": "203.0.113.0/24" },
+            { "uuid": "b9d01f6c", "type": "app", "value": "f81d4fae" },
+        ]))
+        .unwrap();
+        assert_eq!(current[0].source, Source::Droplet(163973392));
+
+        let diff = diff(
+            &current,
+            &[
+                Source::Droplet(163973392),
+                Source::Tag("backend".to_string()),
+                Source::Tag("backend".to_string()),
+                Source::App("f81d4fae".to_string()),
+            ],
+        );
+        assert_eq!(diff.added, [Source::Tag("backend".to_string())]);
+        assert_eq!(diff.removed, [Source::Ip("203.0.113.0/24".to_string())]);
+        assert_eq!(
+            json!(Source::K8s("bd5f5959".to_string())),
+            json!({ "type": "k8s", "value": "bd5f5959" })
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Maintenance windows and major version upgrades.
+//!
+//! [`Client::set_maintenance_window`] moves the weekly slot in which DigitalOcean
+//! applies updates. [`Client::upgrade_major_version`] checks that the target is an
+//! upgrade, starts it and polls until the cluster is online on the new version.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::droplets::Day;
+//! use rsdo::wait::WaitOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster_id = "9cc10173-e9ea-4176-9dbc-a4cee4c4ff30";
+//! client.set_maintenance_window(cluster_id, Day::Sun, 3).await?;
+//! let cluster = client
+//!     .upgrade_major_version(cluster_id, "16", WaitOptions::default())
+//!     .await?;
+//! println!("{} runs {} {}", cluster.name, cluster.engine, cluster.version);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{DatabaseCluster, DatabaseStatus};
+use crate::droplets::Day;
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use serde_json::json;
+use std::time::Duration;
+
+/// The maintenance window body for `day` at `hour` UTC.
+fn window_body(day: Day, hour: u8) -> Result<serde_json::Value, Error> {
+    if hour > 23 {
+        return Err(Error::InvalidInput(format!(
+            "{hour} is not an hour of the day"
+        )));
+    }
+    let day = match day {
+        Day::Sun => "sunday",
+        Day::Mon => "monday",
+        Day::Tue => "tuesday",
+        Day::Wed => "wednesday",
+        Day::Thu => "thursday",
+        Day::Fri => "friday",
+        Day::Sat => "saturday",
+    };
+    Ok(json!({ "day": day, "hour": format!("{hour:02}:00") }))
+}
+
+/// Check that `target` is a newer major version than `current`.
+fn check_upgrade(current: &str, target: &str) -> Result<(), Error> {
+    let major = |version: &str| version.split('.').next()?.parse::<u32>().ok();
+    match (major(current), major(target)) {
+        (Some(current), Some(target)) if target > current => Ok(()),
+        (Some(_), Some(_)) => Err(Error::InvalidInput(format!(
+            "version {target} is not newer than {current}"
+        ))),
+        _ => Err(Error::InvalidInput(format!(
+            "cannot compare versions {current:?} and {target:?}"
+        ))),
+    }
+}
+
+impl Client {
+    /// Move the maintenance window of database cluster `cluster_id` to `day` at
+    /// `hour` (0-23) UTC.
+    pub async fn set_maintenance_window(
+        &self,
+        cluster_id: &str,
+        day: Day,
+        hour: u8,
+    ) -> Result<(), Error> {
+        let body = window_body(day, hour)?;
+        self.send_empty(
+            ApiRequest::put(
+                "databases_update_maintenanceWindow",
+                format!("/v2/databases/{cluster_id}/maintenance"),
+            )
+            .json(body),
+        )
+        .await
+    }
+
+    /// Upgrade database cluster `cluster_id` to major version `target`, e.g. `16`,
+    /// and wait until it is online on it.
+    ///
+    /// Returns the cluster unchanged if it already runs `target`. Fails with
+    /// [`Error::InvalidInput`] if the cluster is not online or `target` is not newer,
+    /// or with [`Error::Timeout`].
+    pub async fn upgrade_major_version(
+        &self,
+        cluster_id: &str,
+        target: &str,
+        options: WaitOptions,
+    ) -> Result<DatabaseCluster, Error> {
+        let workflow = Workflow::new(
+            self.inner(),
+            "upgrade_major_version",
+            format!("database cluster {cluster_id}"),
+        );
+        let cluster = workflow
+            .step("check", self.database_cluster(cluster_id))
+            .await?;
+        if cluster.version == target {
+            return Ok(cluster);
+        }
+        if cluster.status != DatabaseStatus::Online {
+            return Err(Error::InvalidInput(format!(
+                "database cluster {cluster_id} is {} and cannot be upgraded",
+                cluster.status
+            )));
+        }
+        check_upgrade(&cluster.version, target)?;
+        workflow
+            .step(
+                "upgrade",
+                self.send_empty(
+                    ApiRequest::put(
+                        "databases_update_major_version",
+                        format!("/v2/databases/{cluster_id}/upgrade"),
+                    )
+                    .json(json!({ "version": target })),
+                ),
+            )
+            .await?;
+        let waiter = self
+            .waiter(
+                format!("database cluster {cluster_id} to run version {target}"),
+                || self.database_cluster(cluster_id),
+            )
+            .until(|cluster| cluster.version == target && cluster.status == DatabaseStatus::Online)
+            .state(|cluster| format!("{}, version {}", cluster.status, cluster.version))
+            .typical_duration(|_| Some(Duration::from_secs(15 * 60)))
+            .options(options);
+        workflow.step("wait", waiter.wait()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_body_and_upgrade_check() {
+        assert_eq!(
+            window_body(Day::Tue, 4).unwrap(),
+            json!({ "day": "tuesday", "hour": "04:00" })
+        );
+        assert!(window_body(Day::Tue, 24).is_err());
+        assert!(check_upgrade("15", "16").is_ok());
+        assert!(check_upgrade("8", "8").is_err());
+        assert!(check_upgrade("16", "15").is_err());
+        assert!(check_upgrade("", "16").is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! Managed database helpers layered on top of the generated database operations.
+//!
+//! Deploy tooling usually needs to know two things about a cluster: whether it is
+//! online, and whether maintenance is about to start. [`Client::wait_for_database_online`]
+//! waits for the first, and [`DatabaseCluster::maintenance_due_within`] answers the
+//! second. [`DatabaseCluster::connection_uri`] turns its connection details into a
+//! URI for the application.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::wait::WaitOptions;
+//! use std::time::Duration;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster = client
+//!     .wait_for_database_online("9cc10173-e9ea-4176-9dbc-a4cee4c4ff30", WaitOptions::default())
+//!     .await?;
+//! if let Some(start) = cluster.maintenance_due_within(Duration::from_secs(2 * 3600)) {
+//!     println!("maintenance starts at {start}; postponing the migration");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod backups;
+mod builder;
+mod config;
+mod connection;
+mod firewall;
+mod maintenance;
+mod pools;
+mod replicas;
+mod users;
+
+pub use backups::DatabaseBackup;
+pub use builder::{DatabaseClusterBuilder, DatabaseEngine};
+pub use config::{
+    DatabaseConfig, KafkaConfig, MaxmemoryPolicy, MysqlConfig, PostgresConfig, RedisConfig,
+};
+#[cfg(feature = "sqlx")]
+pub use connection::SqlxConnectOptions;
+pub use connection::{ConnectionInfo, Role, Ssl};
+pub use firewall::{FirewallRule, Source, SourceDiff};
+pub use pools::{
+    pool_connection_limit, recommend_pool_size, ConnectionPool, ConnectionPoolSpec, PoolMode,
+};
+pub use replicas::{PromotionChecks, ReadReplica, ReplicaHandle, ReplicaPreflight};
+pub use users::{
+    DatabaseUser, DatabaseUserOptions, Grant, KafkaAcl, KafkaPermission, MysqlAuthPlugin,
+    MysqlSettings, OpenSearchAcl, OpenSearchPermission, UserAcl, UserSettings,
+};
+
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Datelike, Days, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Lifecycle status of a database cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum DatabaseStatus {
+    Creating,
+    Online,
+    Resizing,
+    Migrating,
+    Forking,
+    /// A status this version of rsdo does not know about yet.
+    Unknown(String),
+}
+
+impl From<String> for DatabaseStatus {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "creating" => Self::Creating,
+            "online" => Self::Online,
+            "resizing" => Self::Resizing,
+            "migrating" => Self::Migrating,
+            "forking" => Self::Forking,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<DatabaseStatus> for String {
+    fn from(status: DatabaseStatus) -> Self {
+        status.to_string()
+    }
+}
+
+impl fmt::Display for DatabaseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Creating => "creating",
+            Self::Online => "online",
+            Self::Resizing => "resizing",
+            Self::Migrating => "migrating",
+            Self::Forking => "forking",
+            Self::Unknown(status) => status,
+        })
+    }
+}
+
+/// The weekly slot in which DigitalOcean applies updates to a cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MaintenanceWindow {
+    /// Day of the week, e.g. `tuesday`.
+    pub day: String,
+    /// Start time in UTC, e.g. `14:00:00`.
+    pub hour: String,
+    /// Whether updates are waiting for the next window.
+    #[serde(default)]
+    pub pending: bool,
+    /// The pending updates.
+    #[serde(default)]
+    pub description: Vec<String>,
+}
+
+impl MaintenanceWindow {
+    /// Start of the first window after `after`, or `None` if the day or hour cannot
+    /// be parsed.
+    pub fn next_start(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let day: Weekday = self.day.parse().ok()?;
+        let hour = NaiveTime::parse_from_str(&self.hour, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(&self.hour, "%H:%M"))
+            .ok()?;
+        let today = after.date_naive();
+        let ahead = (day.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        let start = (today + Days::new(ahead.into())).and_time(hour).and_utc();
+        Some(if start > after {
+            start
+        } else {
+            start + Days::new(7)
+        })
+    }
+}
+
+/// Where a cluster serves Prometheus metrics, at `https://{host}:{port}/metrics`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MetricsEndpoint {
+    pub host: String,
+    pub port: u16,
+}
+
+/// A managed database cluster.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatabaseCluster {
+    pub id: String,
+    pub name: String,
+    /// e.g. `pg`, `mysql` or `redis`.
+    pub engine: String,
+    #[serde(default)]
+    pub version: String,
+    pub status: DatabaseStatus,
+    #[serde(default)]
+    pub num_nodes: u32,
+    #[serde(default)]
+    pub size: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub maintenance_window: Option<MaintenanceWindow>,
+    /// Prometheus endpoints exposing the cluster's metrics.
+    #[serde(default)]
+    pub metrics_endpoints: Vec<MetricsEndpoint>,
+    /// Public connection to the primary node.
+    #[serde(default)]
+    pub connection: Option<ConnectionInfo>,
+    /// Connection to the primary node within the VPC.
+    #[serde(default)]
+    pub private_connection: Option<ConnectionInfo>,
+    #[serde(default)]
+    pub standby_connection: Option<ConnectionInfo>,
+    #[serde(default)]
+    pub standby_private_connection: Option<ConnectionInfo>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DatabaseCluster {
+    /// Start of the next maintenance window if updates are pending and it begins
+    /// within `within` from now.
+    pub fn maintenance_due_within(&self, within: Duration) -> Option<DateTime<Utc>> {
+        let window = self.maintenance_window.as_ref().filter(|w| w.pending)?;
+        let now = Utc::now();
+        let start = window.next_start(now)?;
+        let within = chrono::Duration::from_std(within).ok()?;
+        (start - now <= within).then_some(start)
+    }
+}
+
+#[derive(Deserialize)]
+struct DatabaseEnvelope {
+    database: DatabaseCluster,
+}
+
+impl Client {
+    /// Poll database cluster `id` until the API no longer returns it.
+    ///
+    /// Fails with [`Error::Timeout`] if the cluster is still present after the
+    /// options' timeout.
+    pub async fn wait_until_database_cluster_deleted(
+        &self,
+        id: &str,
+        options: WaitOptions,
+    ) -> Result<(), Error> {
+        self.wait_until_deleted_at(
+            ApiRequest::get("databases_get_cluster", format!("/v2/databases/{id}")),
+            &format!("database cluster {id}"),
+            options,
+        )
+        .await
+    }
+
+    /// Fetch database cluster `id`.
+    pub async fn database_cluster(&self, id: &str) -> Result<DatabaseCluster, Error> {
+        let envelope: DatabaseEnvelope = self
+            .send_json(ApiRequest::get(
+                "databases_get_cluster",
+                format!("/v2/databases/{id}"),
+            ))
+            .await?;
+        Ok(envelope.database)
+    }
+
+    /// Poll database cluster `id` until it is online, and return it.
+    ///
+    /// Clusters being created, resized, migrated or forked are waited for; progress
+    /// callbacks see the current status. Fails with [`Error::Timeout`] if the cluster
+    /// is not online after the options' timeout.
+    pub async fn wait_for_database_online(
+        &self,
+        id: &str,
+        options: WaitOptions,
+    ) -> Result<DatabaseCluster, Error> {
+        let workflow = Workflow::new(
+            self.inner(),
+            "wait_for_database_online",
+            format!("database cluster {id}"),
+        );
+        let waiter = self
+            .waiter(format!("database cluster {id} to come online"), || {
+                self.database_cluster(id)
+            })
+            .until(|cluster| cluster.status == DatabaseStatus::Online)
+            .state(|cluster| cluster.status.to_string())
+            .typical_duration(|cluster| {
+                let minutes = match cluster.status {
+                    DatabaseStatus::Creating | DatabaseStatus::Forking => 5,
+                    DatabaseStatus::Resizing => 10,
+                    DatabaseStatus::Migrating => 15,
+                    _ => return None,
+                };
+                Some(Duration::from_secs(minutes * 60))
+            })
+            .options(options);
+        workflow.step("wait", waiter.wait()).await
+    }
+
+    /// Delete a database cluster and wait until the API no longer returns it.
+    ///
+    /// Fails with [`Error::Timeout`] if the cluster is still present after `timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Database cluster UUID
+    /// * `interval` - Delay between polls
+    /// * `timeout` - Upper bound on the total wait
+    pub async fn delete_database_cluster_and_wait_gone(
+        &self,
+        id: &str,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let path = format!("/v2/databases/{id}");
+        self.delete_and_wait_gone(
+            ApiRequest::delete("databases_destroy_cluster", path.clone()),
+            ApiRequest::get("databases_get_cluster", path),
+            &format!("database cluster {id}"),
+            interval,
+            timeout,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_maintenance_start() {
+        let window = MaintenanceWindow {
+            day: "tuesday".to_string(),
+            hour: "14:00:00".to_string(),
+            pending: true,
+            description: Vec::new(),
+        };
+        // 2024-01-02 is a Tuesday.
+        let before: DateTime<Utc> = "2024-01-02T13:00:00Z".parse().unwrap();
+        let after: DateTime<Utc> = "2024-01-02T15:00:00Z".parse().unwrap();
+        assert_eq!(
+            window.next_start(before),
+            Some("2024-01-02T14:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            window.next_start(after),
+            Some("2024-01-09T14:00:00Z".parse().unwrap())
+        );
+    }
+}
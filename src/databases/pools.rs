@@ -0,0 +1,281 @@
+//! PgBouncer connection pools of PostgreSQL clusters.
+//!
+//! Every pool holds open a share of the backend connections a node allows: 25 per GiB
+//! of memory, less 3 reserved for maintenance. [`recommend_pool_size`] derives a pool
+//! size from the cluster's size slug, and [`Client::create_connection_pool`] and
+//! [`Client::resize_connection_pool`] refuse sizes that would take the pools of a
+//! cluster past that limit.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::databases::{recommend_pool_size, ConnectionPoolSpec, PoolMode};
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster_id = "9cc10173-e9ea-4176-9dbc-a4cee4c4ff30";
+//! let cluster = client.database_cluster(cluster_id).await?;
+//! let size = recommend_pool_size(&cluster.size, 2).unwrap_or(10);
+//! let pool = client
+//!     .create_connection_pool(
+//!         cluster_id,
+//!         ConnectionPoolSpec::new("app", "defaultdb", size).mode(PoolMode::Transaction),
+//!     )
+//!     .await?;
+//! println!("pool {} holds {} connections", pool.name, pool.size);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::ConnectionInfo;
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Backend connections per GiB of node memory.
+const CONNECTIONS_PER_GIB: u32 = 25;
+
+/// Backend connections reserved for DigitalOcean's maintenance.
+const RESERVED_CONNECTIONS: u32 = 3;
+
+/// How PgBouncer assigns backend connections to clients.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolMode {
+    /// For the duration of a transaction. Suits most applications.
+    #[default]
+    Transaction,
+    /// For the whole client session, needed for session-level features.
+    Session,
+    /// For a single statement; multi-statement transactions are not allowed.
+    Statement,
+}
+
+/// A connection pool of a cluster.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConnectionPool {
+    pub name: String,
+    pub mode: PoolMode,
+    /// Backend connections the pool holds.
+    pub size: u32,
+    /// Database the pool connects to.
+    pub db: String,
+    /// User the pool connects as; empty when clients' own credentials are used.
+    #[serde(default)]
+    pub user: String,
+    #[serde(default)]
+    pub connection: Option<ConnectionInfo>,
+    #[serde(default)]
+    pub private_connection: Option<ConnectionInfo>,
+}
+
+/// A pool to create with [`Client::create_connection_pool`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionPoolSpec {
+    name: String,
+    mode: PoolMode,
+    size: u32,
+    db: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+}
+
+impl ConnectionPoolSpec {
+    /// A transaction-mode pool `name` of `size` connections to database `db`, used
+    /// with the connecting clients' own credentials.
+    pub fn new(name: impl Into<String>, db: impl Into<String>, size: u32) -> Self {
+        Self {
+            name: name.into(),
+            mode: PoolMode::Transaction,
+            size,
+            db: db.into(),
+            user: None,
+        }
+    }
+
+    pub fn mode(mut self, mode: PoolMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Connect as `user` whatever credentials clients use.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct PoolEnvelope {
+    pool: ConnectionPool,
+}
+
+#[derive(Deserialize)]
+struct PoolsEnvelope {
+    #[serde(default)]
+    pools: Vec<ConnectionPool>,
+}
+
+/// Backend connections available to pools on nodes of `size`, e.g. `db-s-2vcpu-4gb`,
+/// or `None` if the slug does not end in the memory size.
+pub fn pool_connection_limit(size: &str) -> Option<u32> {
+    let gib: u32 = size.rsplit('-').next()?.strip_suffix("gb")?.parse().ok()?;
+    gib.checked_mul(CONNECTIONS_PER_GIB)?
+        .checked_sub(RESERVED_CONNECTIONS)
+}
+
+/// A size for each of `pools` pools on nodes of `size` that together use nine tenths
+/// of the connection limit, leaving the rest for direct connections.
+pub fn recommend_pool_size(size: &str, pools: u32) -> Option<u32> {
+    let limit = pool_connection_limit(size)?;
+    let share = limit * 9 / 10 / pools.max(1);
+    (share > 0).then_some(share)
+}
+
+impl Client {
+    /// List the connection pools of database cluster `cluster_id`.
+    pub async fn connection_pools(&self, cluster_id: &str) -> Result<Vec<ConnectionPool>, Error> {
+        let envelope: PoolsEnvelope = self
+            .send_json(ApiRequest::get(
+                "databases_list_connectionPools",
+                format!("/v2/databases/{cluster_id}/pools"),
+            ))
+            .await?;
+        Ok(envelope.pools)
+    }
+
+    /// Fetch connection pool `name` of database cluster `cluster_id`.
+    pub async fn connection_pool(
+        &self,
+        cluster_id: &str,
+        name: &str,
+    ) -> Result<ConnectionPool, Error> {
+        let envelope: PoolEnvelope = self
+            .send_json(ApiRequest::get(
+                "databases_get_connectionPool",
+                format!("/v2/databases/{cluster_id}/pools/{name}"),
+            ))
+            .await?;
+        Ok(envelope.pool)
+    }
+
+    /// Create a connection pool on PostgreSQL cluster `cluster_id`.
+    ///
+    /// Fails with [`Error::InvalidInput`] if the cluster is not PostgreSQL or the
+    /// pools would hold more connections than its nodes allow.
+    pub async fn create_connection_pool(
+        &self,
+        cluster_id: &str,
+        spec: ConnectionPoolSpec,
+    ) -> Result<ConnectionPool, Error> {
+        self.check_pool_budget(cluster_id, &spec.name, spec.size)
+            .await?;
+        let envelope: PoolEnvelope = self
+            .send_json(
+                ApiRequest::post(
+                    "databases_add_connectionPool",
+                    format!("/v2/databases/{cluster_id}/pools"),
+                )
+                .json(serde_json::to_value(&spec)?),
+            )
+            .await?;
+        Ok(envelope.pool)
+    }
+
+    /// Change connection pool `name` of database cluster `cluster_id` to hold `size`
+    /// connections, keeping its mode, database and user.
+    ///
+    /// Fails with [`Error::InvalidInput`] if the pools would hold more connections
+    /// than the cluster's nodes allow.
+    pub async fn resize_connection_pool(
+        &self,
+        cluster_id: &str,
+        name: &str,
+        size: u32,
+    ) -> Result<(), Error> {
+        let pool = self.connection_pool(cluster_id, name).await?;
+        self.check_pool_budget(cluster_id, name, size).await?;
+        let mut body = json!({ "mode": pool.mode, "size": size, "db": pool.db });
+        if !pool.user.is_empty() {
+            body["user"] = json!(pool.user);
+        }
+        self.send_empty(
+            ApiRequest::put(
+                "databases_update_connectionPool",
+                format!("/v2/databases/{cluster_id}/pools/{name}"),
+            )
+            .json(body),
+        )
+        .await
+    }
+
+    /// Delete connection pool `name` of database cluster `cluster_id`.
+    pub async fn delete_connection_pool(&self, cluster_id: &str, name: &str) -> Result<(), Error> {
+        self.send_empty(ApiRequest::delete(
+            "databases_delete_connectionPool",
+            format!("/v2/databases/{cluster_id}/pools/{name}"),
+        ))
+        .await
+    }
+
+    /// Check that pool `name` can hold `size` connections next to the cluster's other
+    /// pools. Sizes whose limit is unknown are left to the API.
+    async fn check_pool_budget(
+        &self,
+        cluster_id: &str,
+        name: &str,
+        size: u32,
+    ) -> Result<(), Error> {
+        if size == 0 {
+            return Err(Error::InvalidInput(format!(
+                "connection pool {name} needs at least one connection"
+            )));
+        }
+        let cluster = self.database_cluster(cluster_id).await?;
+        if cluster.engine != "pg" {
+            return Err(Error::InvalidInput(format!(
+                "connection pools need a PostgreSQL cluster, not {}",
+                cluster.engine
+            )));
+        }
+        let Some(limit) = pool_connection_limit(&cluster.size) else {
+            return Ok(());
+        };
+        let others: u32 = self
+            .connection_pools(cluster_id)
+            .await?
+            .iter()
+            .filter(|pool| pool.name != name)
+            .map(|pool| pool.size)
+            .sum();
+        if others + size > limit {
+            return Err(Error::InvalidInput(format!(
+                "connection pool {name} needs {size} connections, but {} nodes allow {limit} \
+                 and other pools hold {others}",
+                cluster.size
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_sizing() {
+        assert_eq!(pool_connection_limit("db-s-1vcpu-1gb"), Some(22));
+        assert_eq!(pool_connection_limit("gd-2vcpu-8gb"), Some(197));
+        assert_eq!(pool_connection_limit("db-s-2vcpu-4gb"), Some(97));
+        assert_eq!(pool_connection_limit("custom"), None);
+        assert_eq!(recommend_pool_size("db-s-2vcpu-4gb", 2), Some(43));
+        assert_eq!(recommend_pool_size("db-s-1vcpu-1gb", 0), Some(19));
+        assert_eq!(recommend_pool_size("db-s-1vcpu-1gb", 100), None);
+        assert_eq!(
+            json!(ConnectionPoolSpec::new("app", "defaultdb", 10).mode(PoolMode::Session)),
+            json!({ "name": "app", "mode": "session", "size": 10, "db": "defaultdb" })
+        );
+    }
+}
@@ -0,0 +1,369 @@
+//! Read replica lifecycle and promotion.
+//!
+//! [`Client::create_read_replica`] starts a replica and returns a [`ReplicaHandle`] to
+//! wait for it to come online. [`Client::promote_replica`] is the failover step: it
+//! checks the replica first, promotes it to a standalone cluster and waits until that
+//! cluster is online.
+//!
+//! The pre-flight check ([`Client::replica_preflight`]) requires the replica to be
+//! online and, where the cluster's Prometheus endpoint is reachable, reads the
+//! replication lag from it. During a failover the primary, and with it the endpoint,
+//! may be gone, so a missing lag reading only fails the check when
+//! [`PromotionChecks::require_lag`] is set.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::databases::PromotionChecks;
+//! use rsdo::wait::WaitOptions;
+//! use std::time::Duration;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster_id = "9cc10173-e9ea-4176-9dbc-a4cee4c4ff30";
+//! let replica = client
+//!     .create_read_replica(cluster_id, "orders-sfo3", "sfo3", "db-s-2vcpu-4gb")
+//!     .await?
+//!     .wait(WaitOptions::default())
+//!     .await?;
+//!
+//! // Later, when the primary's region is down:
+//! let promoted = client
+//!     .promote_replica(
+//!         cluster_id,
+//!         &replica.name,
+//!         PromotionChecks::default().max_lag(Duration::from_secs(5)),
+//!     )
+//!     .await?;
+//! println!("{} is now a standalone cluster", promoted.name);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{ConnectionInfo, DatabaseCluster, DatabaseStatus};
+use crate::error::{Error, OperationContext};
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// A read-only replica of a database cluster.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReadReplica {
+    pub id: String,
+    pub name: String,
+    pub region: String,
+    #[serde(default)]
+    pub size: String,
+    pub status: DatabaseStatus,
+    #[serde(default)]
+    pub connection: Option<ConnectionInfo>,
+    #[serde(default)]
+    pub private_connection: Option<ConnectionInfo>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct ReplicaEnvelope {
+    replica: ReadReplica,
+}
+
+/// A replica being created, to check on or wait for later.
+#[derive(Debug, Clone)]
+pub struct ReplicaHandle {
+    client: Client,
+    cluster_id: String,
+    name: String,
+}
+
+impl ReplicaHandle {
+    /// The replica's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Fetch the replica's current state.
+    pub async fn get(&self) -> Result<ReadReplica, Error> {
+        self.client.read_replica(&self.cluster_id, &self.name).await
+    }
+
+    /// Poll the replica until it is online, and return it.
+    ///
+    /// Fails with [`Error::Timeout`] if it is not online after the options' timeout.
+    pub async fn wait(&self, options: WaitOptions) -> Result<ReadReplica, Error> {
+        let waiter = self
+            .client
+            .waiter(format!("read replica {} to come online", self.name), || {
+                self.get()
+            })
+            .until(|replica| replica.status == DatabaseStatus::Online)
+            .state(|replica| replica.status.to_string())
+            .typical_duration(|_| Some(Duration::from_secs(5 * 60)))
+            .options(options);
+        waiter.wait().await
+    }
+}
+
+/// What [`Client::promote_replica`] checks before promoting.
+#[derive(Debug, Clone)]
+pub struct PromotionChecks {
+    max_lag: Duration,
+    require_lag: bool,
+    options: WaitOptions,
+}
+
+impl Default for PromotionChecks {
+    fn default() -> Self {
+        Self {
+            max_lag: Duration::from_secs(30),
+            require_lag: false,
+            options: WaitOptions::default(),
+        }
+    }
+}
+
+impl PromotionChecks {
+    /// Refuse to promote if the replica lags further behind. Defaults to 30 seconds.
+    pub fn max_lag(mut self, max_lag: Duration) -> Self {
+        self.max_lag = max_lag;
+        self
+    }
+
+    /// Refuse to promote if the lag cannot be read.
+    pub fn require_lag(mut self, required: bool) -> Self {
+        self.require_lag = required;
+        self
+    }
+
+    /// How to wait for the promoted cluster to come online.
+    pub fn wait_options(mut self, options: WaitOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+/// Outcome of [`Client::replica_preflight`].
+#[derive(Debug, Clone)]
+pub struct ReplicaPreflight {
+    pub replica: ReadReplica,
+    /// Replication lag, or `None` if the metrics endpoint did not report it.
+    pub lag: Option<Duration>,
+}
+
+#[derive(Deserialize)]
+struct MetricsCredentialsEnvelope {
+    credentials: MetricsCredentials,
+}
+
+#[derive(Deserialize)]
+struct MetricsCredentials {
+    basic_auth_username: String,
+    basic_auth_password: String,
+}
+
+/// Whether a Prometheus metric reports replication lag in seconds.
+fn is_lag_metric(name: &str) -> bool {
+    name.contains("replication_lag")
+        || name.contains("seconds_behind_master")
+        || name.contains("seconds_behind_source")
+}
+
+/// The largest replication lag sample in Prometheus text exposition `text`.
+fn max_lag(text: &str) -> Option<Duration> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let name_end = line.find(['{', ' '])?;
+            let rest = match line[name_end..].strip_prefix('{') {
+                Some(labelled) => &labelled[labelled.rfind('}')? + 1..],
+                None => &line[name_end..],
+            };
+            let value: f64 = rest.split_whitespace().next()?.parse().ok()?;
+            is_lag_metric(&line[..name_end]).then_some(value)
+        })
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .reduce(f64::max)
+        .map(Duration::from_secs_f64)
+}
+
+impl Client {
+    /// Start a read replica `name` of database cluster `cluster_id` in `region` on
+    /// `size` nodes, and return a handle to wait for it.
+    pub async fn create_read_replica(
+        &self,
+        cluster_id: &str,
+        name: &str,
+        region: &str,
+        size: &str,
+    ) -> Result<ReplicaHandle, Error> {
+        let envelope: ReplicaEnvelope = self
+            .send_json(
+                ApiRequest::post(
+                    "databases_create_replica",
+                    format!("/v2/databases/{cluster_id}/replicas"),
+                )
+                .json(json!({ "name": name, "region": region, "size": size })),
+            )
+            .await?;
+        Ok(ReplicaHandle {
+            client: self.clone(),
+            cluster_id: cluster_id.to_string(),
+            name: envelope.replica.name,
+        })
+    }
+
+    /// Fetch read replica `name` of database cluster `cluster_id`.
+    pub async fn read_replica(&self, cluster_id: &str, name: &str) -> Result<ReadReplica, Error> {
+        let envelope: ReplicaEnvelope = self
+            .send_json(ApiRequest::get(
+                "databases_get_replica",
+                format!("/v2/databases/{cluster_id}/replicas/{name}"),
+            ))
+            .await?;
+        Ok(envelope.replica)
+    }
+
+    /// Delete read replica `name` of database cluster `cluster_id`.
+    pub async fn delete_read_replica(&self, cluster_id: &str, name: &str) -> Result<(), Error> {
+        self.send_empty(ApiRequest::delete(
+            "databases_destroy_replica",
+            format!("/v2/databases/{cluster_id}/replicas/{name}"),
+        ))
+        .await
+    }
+
+    /// Fetch read replica `name` and, where the cluster's metrics endpoint reports
+    /// it, its replication lag.
+    ///
+    /// The lag is the largest `*replication_lag*` or `*seconds_behind_*` sample the
+    /// endpoint exposes. Failures to reach the endpoint leave it `None`.
+    pub async fn replica_preflight(
+        &self,
+        cluster_id: &str,
+        name: &str,
+    ) -> Result<ReplicaPreflight, Error> {
+        let replica = self.read_replica(cluster_id, name).await?;
+        let lag = match self.database_cluster(cluster_id).await {
+            Ok(cluster) => self.scrape_lag(&cluster).await.ok().flatten(),
+            Err(_) => None,
+        };
+        Ok(ReplicaPreflight { replica, lag })
+    }
+
+    /// Promote read replica `name` of database cluster `cluster_id` to a standalone
+    /// cluster once it passes `checks`, and wait for that cluster to come online.
+    ///
+    /// Fails with [`Error::InvalidInput`] if the replica is not online, lags more than
+    /// allowed, or its lag is unknown but required, or with [`Error::Timeout`].
+    pub async fn promote_replica(
+        &self,
+        cluster_id: &str,
+        name: &str,
+        checks: PromotionChecks,
+    ) -> Result<DatabaseCluster, Error> {
+        let workflow = Workflow::new(
+            self.inner(),
+            "promote_replica",
+            format!("read replica {name} of database cluster {cluster_id}"),
+        );
+        let preflight = workflow
+            .step("preflight", self.replica_preflight(cluster_id, name))
+            .await?;
+        if preflight.replica.status != DatabaseStatus::Online {
+            return Err(Error::InvalidInput(format!(
+                "read replica {name} is {} and cannot be promoted",
+                preflight.replica.status
+            )));
+        }
+        match preflight.lag {
+            Some(lag) if lag > checks.max_lag => {
+                return Err(Error::InvalidInput(format!(
+                    "read replica {name} lags {lag:?} behind, more than the allowed {:?}",
+                    checks.max_lag
+                )))
+            }
+            None if checks.require_lag => {
+                return Err(Error::InvalidInput(format!(
+                    "the replication lag of read replica {name} is not available"
+                )))
+            }
+            _ => {}
+        }
+        workflow
+            .step(
+                "promote",
+                self.send_empty(ApiRequest::put(
+                    "databases_promote_replica",
+                    format!("/v2/databases/{cluster_id}/replicas/{name}/promote"),
+                )),
+            )
+            .await?;
+        workflow
+            .step(
+                "wait",
+                self.wait_for_database_online(&preflight.replica.id, checks.options),
+            )
+            .await
+    }
+
+    /// Read the replication lag from the first metrics endpoint of `cluster`.
+    async fn scrape_lag(&self, cluster: &DatabaseCluster) -> Result<Option<Duration>, Error> {
+        let Some(endpoint) = cluster.metrics_endpoints.first() else {
+            return Ok(None);
+        };
+        let credentials: MetricsCredentialsEnvelope = self
+            .send_json(ApiRequest::get(
+                "databases_get_cluster_metricsCredentials",
+                "/v2/databases/metrics/credentials",
+            ))
+            .await?;
+        let url = format!("https://{}:{}/metrics", endpoint.host, endpoint.port);
+        let context = OperationContext::new("database_metrics", Method::GET, &url, false);
+        let request_error = |source| Error::Request {
+            context: context.clone(),
+            source,
+        };
+        let response = self
+            .client()
+            .get(&url)
+            .basic_auth(
+                &credentials.credentials.basic_auth_username,
+                Some(&credentials.credentials.basic_auth_password),
+            )
+            .send()
+            .await
+            .map_err(request_error)?;
+        let status = response.status();
+        let body = response.text().await.map_err(request_error)?;
+        if !status.is_success() {
+            return Err(Error::Response {
+                context,
+                status,
+                body,
+            });
+        }
+        Ok(max_lag(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_lag_from_prometheus_text() {
+        let text = r#"# HELP pg_replication_lag Replication lag in seconds
+# TYPE pg_replication_lag gauge
+pg_replication_lag{host="replica-1",application_name="a b"} 1.5 1712000000000
+pg_replication_lag{host="replica-2"} 4
+pg_stat_database_xact_commit{datname="defaultdb"} 9000
+mysql_slave_status_seconds_behind_master 2
+"#;
+        assert_eq!(max_lag(text), Some(Duration::from_secs(4)));
+        assert_eq!(max_lag("pg_up 1\n"), None);
+    }
+}
@@ -0,0 +1,409 @@
+//! Database users and their access rules.
+//!
+//! [`Client::create_db_user`] creates a user with its engine-specific settings: the
+//! MySQL authentication plugin, or the ACLs that scope Kafka users to topics and
+//! OpenSearch users to indexes. [`Client::grant_db_user`] and
+//! [`Client::revoke_db_user_grant`] change one ACL entry of an existing user, keeping
+//! the others.
+//!
+//! PostgreSQL and MySQL privileges on individual databases are not part of the API;
+//! grant them in SQL as the cluster's admin user.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::databases::{DatabaseUserOptions, Grant, KafkaAcl, KafkaPermission, UserAcl};
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster_id = "9cc10173-e9ea-4176-9dbc-a4cee4c4ff30";
+//! let user = client
+//!     .create_db_user(
+//!         cluster_id,
+//!         "billing",
+//!         DatabaseUserOptions {
+//!             acl: UserAcl::Kafka(vec![KafkaAcl::new("invoices.*", KafkaPermission::Consume)]),
+//!             ..DatabaseUserOptions::default()
+//!         },
+//!     )
+//!     .await?;
+//! client
+//!     .grant_db_user(cluster_id, &user.name, Grant::topic("payments", KafkaPermission::Produce))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Authentication plugin of a MySQL user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MysqlAuthPlugin {
+    /// The default on MySQL 8.
+    CachingSha2Password,
+    /// For clients too old for `caching_sha2_password`.
+    MysqlNativePassword,
+}
+
+/// What a Kafka user may do with matching topics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KafkaPermission {
+    Admin,
+    Consume,
+    Produce,
+    ProduceConsume,
+}
+
+/// A Kafka user's permission on the topics matching a pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KafkaAcl {
+    /// Set by the API once the rule exists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Topic name, or a pattern with `*` wildcards.
+    pub topic: String,
+    pub permission: KafkaPermission,
+}
+
+impl KafkaAcl {
+    pub fn new(topic: impl Into<String>, permission: KafkaPermission) -> Self {
+        Self {
+            id: None,
+            topic: topic.into(),
+            permission,
+        }
+    }
+}
+
+/// What an OpenSearch user may do with matching indexes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenSearchPermission {
+    Deny,
+    Admin,
+    Read,
+    Write,
+    ReadWrite,
+}
+
+/// An OpenSearch user's permission on the indexes matching a pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenSearchAcl {
+    /// Index name, or a pattern with `*` wildcards.
+    pub index: String,
+    pub permission: OpenSearchPermission,
+}
+
+impl OpenSearchAcl {
+    pub fn new(index: impl Into<String>, permission: OpenSearchPermission) -> Self {
+        Self {
+            index: index.into(),
+            permission,
+        }
+    }
+}
+
+/// The access rules of a new user.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum UserAcl {
+    /// No rules: full access on Kafka and OpenSearch, the only choice elsewhere.
+    #[default]
+    None,
+    Kafka(Vec<KafkaAcl>),
+    OpenSearch(Vec<OpenSearchAcl>),
+}
+
+/// Settings of a user created with [`Client::create_db_user`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatabaseUserOptions {
+    /// MySQL clusters only; the API default is `caching_sha2_password`.
+    pub mysql_auth_plugin: Option<MysqlAuthPlugin>,
+    pub acl: UserAcl,
+}
+
+/// One ACL entry for [`Client::grant_db_user`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Grant {
+    Topic(KafkaAcl),
+    Index(OpenSearchAcl),
+}
+
+impl Grant {
+    pub fn topic(topic: impl Into<String>, permission: KafkaPermission) -> Self {
+        Self::Topic(KafkaAcl::new(topic, permission))
+    }
+
+    pub fn index(index: impl Into<String>, permission: OpenSearchPermission) -> Self {
+        Self::Index(OpenSearchAcl::new(index, permission))
+    }
+}
+
+/// MySQL settings of a user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MysqlSettings {
+    pub auth_plugin: MysqlAuthPlugin,
+}
+
+/// Kafka and OpenSearch settings of a user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserSettings {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub acl: Vec<KafkaAcl>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub opensearch_acl: Vec<OpenSearchAcl>,
+}
+
+impl UserSettings {
+    /// Replace the rule for the grant's topic or index, or add it.
+    fn grant(&mut self, grant: Grant) {
+        match grant {
+            Grant::Topic(acl) => match self.acl.iter_mut().find(|a| a.topic == acl.topic) {
+                Some(existing) => existing.permission = acl.permission,
+                None => self.acl.push(acl),
+            },
+            Grant::Index(acl) => {
+                match self
+                    .opensearch_acl
+                    .iter_mut()
+                    .find(|a| a.index == acl.index)
+                {
+                    Some(existing) => existing.permission = acl.permission,
+                    None => self.opensearch_acl.push(acl),
+                }
+            }
+        }
+    }
+
+    /// Remove the rules for topic or index `pattern`. Returns whether any existed.
+    fn revoke(&mut self, pattern: &str) -> bool {
+        let before = self.acl.len() + self.opensearch_acl.len();
+        self.acl.retain(|acl| acl.topic != pattern);
+        self.opensearch_acl.retain(|acl| acl.index != pattern);
+        self.acl.len() + self.opensearch_acl.len() != before
+    }
+
+    /// The settings as an update body. Lists that `before` had are sent even when now
+    /// empty, so that removing their last entry is not mistaken for leaving them out.
+    fn update_body(&self, before: &UserSettings) -> serde_json::Value {
+        let mut settings = json!(self);
+        if !before.acl.is_empty() {
+            settings["acl"] = json!(self.acl);
+        }
+        if !before.opensearch_acl.is_empty() {
+            settings["opensearch_acl"] = json!(self.opensearch_acl);
+        }
+        json!({ "settings": settings })
+    }
+}
+
+/// A user of a database cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DatabaseUser {
+    pub name: String,
+    /// `primary` for the admin user, `normal` otherwise.
+    #[serde(default)]
+    pub role: String,
+    /// Empty when the API does not reveal it.
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub mysql_settings: Option<MysqlSettings>,
+    #[serde(default)]
+    pub settings: Option<UserSettings>,
+}
+
+#[derive(Deserialize)]
+struct UserEnvelope {
+    user: DatabaseUser,
+}
+
+impl DatabaseUserOptions {
+    fn body(&self, name: &str) -> serde_json::Value {
+        let mut body = json!({ "name": name });
+        if let Some(auth_plugin) = self.mysql_auth_plugin {
+            body["mysql_settings"] = json!(MysqlSettings { auth_plugin });
+        }
+        let settings = match &self.acl {
+            UserAcl::None => None,
+            UserAcl::Kafka(acl) => Some(UserSettings {
+                acl: acl.clone(),
+                ..UserSettings::default()
+            }),
+            UserAcl::OpenSearch(opensearch_acl) => Some(UserSettings {
+                opensearch_acl: opensearch_acl.clone(),
+                ..UserSettings::default()
+            }),
+        };
+        if let Some(settings) = settings {
+            body["settings"] = json!(settings);
+        }
+        body
+    }
+}
+
+impl Client {
+    /// Create user `name` on database cluster `cluster_id` and return it with its
+    /// generated password.
+    pub async fn create_db_user(
+        &self,
+        cluster_id: &str,
+        name: &str,
+        options: DatabaseUserOptions,
+    ) -> Result<DatabaseUser, Error> {
+        let envelope: UserEnvelope = self
+            .send_json(
+                ApiRequest::post(
+                    "databases_add_user",
+                    format!("/v2/databases/{cluster_id}/users"),
+                )
+                .json(options.body(name)),
+            )
+            .await?;
+        Ok(envelope.user)
+    }
+
+    /// Fetch user `name` of database cluster `cluster_id`.
+    pub async fn database_user(&self, cluster_id: &str, name: &str) -> Result<DatabaseUser, Error> {
+        let envelope: UserEnvelope = self
+            .send_json(ApiRequest::get(
+                "databases_get_user",
+                format!("/v2/databases/{cluster_id}/users/{name}"),
+            ))
+            .await?;
+        Ok(envelope.user)
+    }
+
+    /// Generate a new password for user `name` of database cluster `cluster_id`,
+    /// keeping its MySQL authentication plugin, and return the user with it.
+    pub async fn reset_db_user_password(
+        &self,
+        cluster_id: &str,
+        name: &str,
+    ) -> Result<DatabaseUser, Error> {
+        let user = self.database_user(cluster_id, name).await?;
+        let body = match user.mysql_settings {
+            Some(mysql_settings) => json!({ "mysql_settings": mysql_settings }),
+            None => json!({}),
+        };
+        let envelope: UserEnvelope = self
+            .send_json(
+                ApiRequest::post(
+                    "databases_reset_auth",
+                    format!("/v2/databases/{cluster_id}/users/{name}/reset_auth"),
+                )
+                .json(body),
+            )
+            .await?;
+        Ok(envelope.user)
+    }
+
+    /// Delete user `name` of database cluster `cluster_id`.
+    pub async fn delete_db_user(&self, cluster_id: &str, name: &str) -> Result<(), Error> {
+        self.send_empty(ApiRequest::delete(
+            "databases_delete_user",
+            format!("/v2/databases/{cluster_id}/users/{name}"),
+        ))
+        .await
+    }
+
+    /// Give Kafka or OpenSearch user `name` the grant's permission on its topic or
+    /// index pattern, replacing any permission it had on that pattern.
+    pub async fn grant_db_user(
+        &self,
+        cluster_id: &str,
+        name: &str,
+        grant: Grant,
+    ) -> Result<DatabaseUser, Error> {
+        let user = self.database_user(cluster_id, name).await?;
+        let before = user.settings.unwrap_or_default();
+        let mut settings = before.clone();
+        settings.grant(grant);
+        self.update_user(cluster_id, name, settings.update_body(&before))
+            .await
+    }
+
+    /// Remove the ACL entries of Kafka or OpenSearch user `name` for topic or index
+    /// `pattern`. Returns the user unchanged if it had none.
+    ///
+    /// Removing a user's last entry leaves it without ACLs, which gives it full access.
+    pub async fn revoke_db_user_grant(
+        &self,
+        cluster_id: &str,
+        name: &str,
+        pattern: &str,
+    ) -> Result<DatabaseUser, Error> {
+        let user = self.database_user(cluster_id, name).await?;
+        let before = user.settings.clone().unwrap_or_default();
+        let mut settings = before.clone();
+        if !settings.revoke(pattern) {
+            return Ok(user);
+        }
+        self.update_user(cluster_id, name, settings.update_body(&before))
+            .await
+    }
+
+    async fn update_user(
+        &self,
+        cluster_id: &str,
+        name: &str,
+        body: serde_json::Value,
+    ) -> Result<DatabaseUser, Error> {
+        let envelope: UserEnvelope = self
+            .send_json(
+                ApiRequest::put(
+                    "databases_update_user",
+                    format!("/v2/databases/{cluster_id}/users/{name}"),
+                )
+                .json(body),
+            )
+            .await?;
+        Ok(envelope.user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_body_and_grants() {
+        let options = DatabaseUserOptions {
+            mysql_auth_plugin: Some(MysqlAuthPlugin::MysqlNativePassword),
+            acl: UserAcl::Kafka(vec![KafkaAcl::new("invoices.*", KafkaPermission::Consume)]),
+        };
+        assert_eq!(
+            options.body("billing"),
+            json!({
+                "name": "billing",
+                "mysql_settings": { "auth_plugin": "mysql_native_password" },
+                "settings": { "acl": [{ "topic": "invoices.*", "permission": "consume" }] },
+            })
+        );
+
+        let mut settings: UserSettings = serde_json::from_value(json!({
+            "acl": [{ "id": "f9Ai3ld", "topic": "invoices.*", "permission": "consume" }],
+        }))
+        .unwrap();
+        settings.grant(Grant::topic("invoices.*", KafkaPermission::ProduceConsume));
+        settings.grant(Grant::index("logs-*", OpenSearchPermission::ReadWrite));
+        assert_eq!(
+            json!(settings),
+            json!({
+                "acl": [{ "id": "f9Ai3ld", "topic": "invoices.*", "permission": "produceconsume" }],
+                "opensearch_acl": [{ "index": "logs-*", "permission": "readwrite" }],
+            })
+        );
+        let before = settings.clone();
+        assert!(settings.revoke("logs-*"));
+        assert!(!settings.revoke("logs-*"));
+        assert_eq!(
+            settings.update_body(&before)["settings"]["opensearch_acl"],
+            json!([])
+        );
+    }
+}
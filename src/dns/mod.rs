@@ -0,0 +1,599 @@
+//! DNS record helpers layered on top of the generated domain operations.
+//!
+//! Dynamic DNS updaters and certificate automation all need the same primitive: make
+//! sure a record exists with the given data, touching nothing if it already does.
+//! [`Client::ensure_record`] looks up the records of the spec's type and name, then
+//! creates, updates or leaves them alone, and reports which it did.
+//!
+//! [`Client::sync_zone`] goes further and reconciles a whole zone with a desired set
+//! of records.
+//!
+//! Record names are relative to the domain, with `@` for the apex. Specs may also
+//! use the fully qualified name, with or without the trailing dot.
+//!
+//! Specs are checked before anything is sent (see [`RecordSpec::validate`]), so an
+//! MX record without a priority or an A record holding a hostname fails locally.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::dns::{RecordSpec, RecordType};
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let spec = RecordSpec::new(RecordType::A, "home", "203.0.113.7").ttl(300);
+//! let ensured = client.ensure_record("example.com", spec).await?;
+//! if ensured.changed() {
+//!     println!("home.example.com now points at {}", ensured.record().data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod sync;
+
+pub use sync::{Change, ZonePlan, ZoneSync, ZoneSyncReport};
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Shortest TTL the API accepts, in seconds.
+const MIN_TTL: u32 = 30;
+
+/// Longest TTL DNS allows (RFC 2181), in seconds.
+const MAX_TTL: u32 = i32::MAX as u32;
+
+/// Type of a DNS record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum RecordType {
+    A,
+    Aaaa,
+    Cname,
+    Mx,
+    Txt,
+    Srv,
+    Caa,
+    Ns,
+    Soa,
+    /// A type this version of rsdo does not know about yet.
+    Unknown(String),
+}
+
+impl From<String> for RecordType {
+    fn from(value: String) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "A" => Self::A,
+            "AAAA" => Self::Aaaa,
+            "CNAME" => Self::Cname,
+            "MX" => Self::Mx,
+            "TXT" => Self::Txt,
+            "SRV" => Self::Srv,
+            "CAA" => Self::Caa,
+            "NS" => Self::Ns,
+            "SOA" => Self::Soa,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<RecordType> for String {
+    fn from(record_type: RecordType) -> Self {
+        record_type.to_string()
+    }
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::A => "A",
+            Self::Aaaa => "AAAA",
+            Self::Cname => "CNAME",
+            Self::Mx => "MX",
+            Self::Txt => "TXT",
+            Self::Srv => "SRV",
+            Self::Caa => "CAA",
+            Self::Ns => "NS",
+            Self::Soa => "SOA",
+            Self::Unknown(record_type) => record_type,
+        })
+    }
+}
+
+/// A record of a domain.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DomainRecord {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub record_type: RecordType,
+    /// Name relative to the domain, `@` for the apex.
+    pub name: String,
+    pub data: String,
+    #[serde(default)]
+    pub priority: Option<u32>,
+    #[serde(default)]
+    pub port: Option<u32>,
+    #[serde(default)]
+    pub ttl: u32,
+    #[serde(default)]
+    pub weight: Option<u32>,
+    #[serde(default)]
+    pub flags: Option<u8>,
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+/// A record that should exist, for [`Client::ensure_record`].
+///
+/// Fields left unset are not compared with existing records and keep the API's
+/// defaults on creation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecordSpec {
+    #[serde(rename = "type")]
+    record_type: RecordType,
+    name: String,
+    data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weight: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flags: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+}
+
+impl RecordSpec {
+    /// A `record_type` record `name` holding `data`.
+    pub fn new(record_type: RecordType, name: impl Into<String>, data: impl Into<String>) -> Self {
+        Self {
+            record_type,
+            name: name.into(),
+            data: data.into(),
+            ttl: None,
+            priority: None,
+            port: None,
+            weight: None,
+            flags: None,
+            tag: None,
+        }
+    }
+
+    /// Time to live, in seconds, from 30.
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Priority of an `MX` or `SRV` record.
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Port of an `SRV` record.
+    pub fn port(mut self, port: u32) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Weight of an `SRV` record.
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+
+    /// Flags and tag, e.g. `issue`, of a `CAA` record.
+    pub fn caa(mut self, flags: u8, tag: impl Into<String>) -> Self {
+        self.flags = Some(flags);
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Check the spec without sending it.
+    ///
+    /// Fails with [`Error::InvalidInput`] listing every problem: an empty name or
+    /// data, a TTL out of bounds, an A or AAAA record not holding an address of its
+    /// family, a CNAME at the apex, an MX or SRV record without a priority, an SRV
+    /// record without a port or weight, a CAA record without flags and a known tag,
+    /// settings the type does not take, or an SOA or unknown type.
+    pub fn validate(&self) -> Result<(), Error> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(format!(
+                "invalid {} record {:?}: {}",
+                self.record_type,
+                self.name,
+                problems.join("; ")
+            )))
+        }
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.name.is_empty() {
+            problems.push("name is empty".to_string());
+        }
+        if self.data.is_empty() {
+            problems.push("data is empty".to_string());
+        }
+        if let Some(ttl) = self.ttl.filter(|ttl| !(MIN_TTL..=MAX_TTL).contains(ttl)) {
+            problems.push(format!("ttl {ttl} is outside {MIN_TTL}..={MAX_TTL}"));
+        }
+        let takes = |field: &str| match field {
+            "priority" => matches!(self.record_type, RecordType::Mx | RecordType::Srv),
+            "port" | "weight" => matches!(self.record_type, RecordType::Srv),
+            _ => matches!(self.record_type, RecordType::Caa),
+        };
+        for (field, set) in [
+            ("priority", self.priority.is_some()),
+            ("port", self.port.is_some()),
+            ("weight", self.weight.is_some()),
+            ("flags", self.flags.is_some()),
+            ("tag", self.tag.is_some()),
+        ] {
+            if set && !takes(field) {
+                problems.push(format!("{} records take no {field}", self.record_type));
+            } else if !set && takes(field) {
+                problems.push(format!("{} records need a {field}", self.record_type));
+            }
+        }
+        match &self.record_type {
+            RecordType::A if self.data.parse::<Ipv4Addr>().is_err() => {
+                problems.push(format!("{:?} is not an IPv4 address", self.data));
+            }
+            RecordType::Aaaa if self.data.parse::<Ipv6Addr>().is_err() => {
+                problems.push(format!("{:?} is not an IPv6 address", self.data));
+            }
+            RecordType::Cname if self.name == "@" => {
+                problems.push("the apex cannot hold a CNAME record".to_string());
+            }
+            RecordType::Caa => {
+                if let Some(tag) = self
+                    .tag
+                    .as_ref()
+                    .filter(|tag| !["issue", "issuewild", "iodef"].contains(&tag.as_str()))
+                {
+                    problems.push(format!("tag {tag:?} is not issue, issuewild or iodef"));
+                }
+            }
+            RecordType::Soa => {
+                problems.push("SOA records are managed by DigitalOcean".to_string());
+            }
+            RecordType::Unknown(_) => problems.push("the type is not supported".to_string()),
+            _ => {}
+        }
+        problems
+    }
+
+    /// The spec with its name made relative to `domain`.
+    fn relative_to(&self, domain: &str) -> Self {
+        Self {
+            name: relative_name(&self.name, domain),
+            ..self.clone()
+        }
+    }
+
+    /// Whether `record` has the spec's type and name.
+    fn names(&self, record: &DomainRecord) -> bool {
+        record.record_type == self.record_type && record.name.eq_ignore_ascii_case(&self.name)
+    }
+
+    /// Whether `record` holds the spec's data.
+    fn holds_data(&self, record: &DomainRecord) -> bool {
+        let normalize = |data: &str| {
+            if self.record_type == RecordType::Txt {
+                data.to_string()
+            } else {
+                data.trim_end_matches('.').to_ascii_lowercase()
+            }
+        };
+        normalize(&record.data) == normalize(&self.data)
+    }
+
+    /// Whether `record` holds the spec's data and every field the spec sets.
+    fn matches(&self, record: &DomainRecord) -> bool {
+        let numbers = [
+            (self.priority, record.priority),
+            (self.port, record.port),
+            (self.weight, record.weight),
+        ];
+        self.holds_data(record)
+            && self.ttl.is_none_or(|ttl| ttl == record.ttl)
+            && numbers
+                .iter()
+                .all(|(wanted, actual)| wanted.is_none() || wanted == actual)
+            && self.flags.is_none_or(|flags| record.flags == Some(flags))
+            && (self.tag.is_none() || self.tag == record.tag)
+    }
+}
+
+/// `name` relative to `domain`: `@` for the apex, without the domain otherwise.
+fn relative_name(name: &str, domain: &str) -> String {
+    let name = name.trim_end_matches('.');
+    let domain = domain.trim_end_matches('.');
+    if name.is_empty() || name.eq_ignore_ascii_case(domain) {
+        return "@".to_string();
+    }
+    match name.len().checked_sub(domain.len() + 1) {
+        Some(end)
+            if name.is_char_boundary(end)
+                && name[end..].starts_with('.')
+                && name[end + 1..].eq_ignore_ascii_case(domain) =>
+        {
+            name[..end].to_string()
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// The fully qualified form of relative `name` in `domain`.
+fn qualified_name(name: &str, domain: &str) -> String {
+    if name == "@" {
+        domain.to_string()
+    } else {
+        format!("{name}.{domain}")
+    }
+}
+
+/// What [`Client::ensure_record`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ensured {
+    /// No record matched, so this one was created.
+    Created(DomainRecord),
+    /// A record with different data or settings was changed to this.
+    Updated(DomainRecord),
+    /// This record already matched.
+    Unchanged(DomainRecord),
+}
+
+impl Ensured {
+    /// Whether a record was created or updated.
+    pub fn changed(&self) -> bool {
+        !matches!(self, Self::Unchanged(_))
+    }
+
+    /// The record as it now is.
+    pub fn record(&self) -> &DomainRecord {
+        match self {
+            Self::Created(record) | Self::Updated(record) | Self::Unchanged(record) => record,
+        }
+    }
+}
+
+/// What to do to make a record match `spec`.
+#[derive(Debug, PartialEq, Eq)]
+enum Plan {
+    Create,
+    Update(u64),
+    Keep(DomainRecord),
+}
+
+/// Decide how to make `existing` records hold `spec`, whose name is relative.
+///
+/// A record holding the spec's data is kept, or updated if its settings differ.
+/// Otherwise a lone record of the type and name is updated, and none is created.
+/// Several records with other data are ambiguous: which to replace is unknown.
+fn plan(existing: &[DomainRecord], spec: &RecordSpec, domain: &str) -> Result<Plan, Error> {
+    let named: Vec<&DomainRecord> = existing.iter().filter(|r| spec.names(r)).collect();
+    if let Some(record) = named.iter().find(|r| spec.holds_data(r)) {
+        return Ok(if spec.matches(record) {
+            Plan::Keep((*record).clone())
+        } else {
+            Plan::Update(record.id)
+        });
+    }
+    match named.as_slice() {
+        [] => Ok(Plan::Create),
+        [record] => Ok(Plan::Update(record.id)),
+        _ => Err(Error::InvalidInput(format!(
+            "{} has {} {} records, none holding {:?}; cannot tell which to replace",
+            qualified_name(&spec.name, domain),
+            named.len(),
+            spec.record_type,
+            spec.data
+        ))),
+    }
+}
+
+#[derive(Deserialize)]
+struct RecordEnvelope {
+    domain_record: DomainRecord,
+}
+
+impl Client {
+    /// List the records of `domain`.
+    pub async fn domain_records(&self, domain: &str) -> Result<Vec<DomainRecord>, Error> {
+        self.records_matching(domain, &[]).await
+    }
+
+    /// List the records of `domain` matching the `type` and `name` filters in `query`.
+    async fn records_matching(
+        &self,
+        domain: &str,
+        query: &[(&str, String)],
+    ) -> Result<Vec<DomainRecord>, Error> {
+        query
+            .iter()
+            .fold(
+                self.paginate("domains_list_records")
+                    .path_param("domain_name", domain)
+                    .items_key("domain_records")
+                    .per_page(200),
+                |listing, (key, value)| listing.query(*key, value),
+            )
+            .stream()
+            .try_collect()
+            .await
+    }
+
+    /// Make sure `domain` has a record as described by `spec`, creating or updating
+    /// one if needed.
+    ///
+    /// An existing record of the spec's type and name is updated in place if it holds
+    /// the spec's data with other settings, or if it is the only one. Fails with
+    /// [`Error::InvalidInput`] if the spec is invalid, or if several records of the
+    /// type and name exist and none holds the data.
+    pub async fn ensure_record(&self, domain: &str, spec: RecordSpec) -> Result<Ensured, Error> {
+        let spec = spec.relative_to(domain);
+        spec.validate()?;
+        let filters = [
+            ("type", spec.record_type.to_string()),
+            ("name", qualified_name(&spec.name, domain)),
+        ];
+        let existing = self.records_matching(domain, &filters).await?;
+        let body = serde_json::to_value(&spec)?;
+        match plan(&existing, &spec, domain)? {
+            Plan::Keep(record) => Ok(Ensured::Unchanged(record)),
+            Plan::Create => self
+                .write_record(
+                    ApiRequest::post(
+                        "domains_create_record",
+                        format!("/v2/domains/{domain}/records"),
+                    ),
+                    body,
+                )
+                .await
+                .map(Ensured::Created),
+            Plan::Update(id) => self
+                .write_record(
+                    ApiRequest::put(
+                        "domains_update_record",
+                        format!("/v2/domains/{domain}/records/{id}"),
+                    ),
+                    body,
+                )
+                .await
+                .map(Ensured::Updated),
+        }
+    }
+
+    /// Send `body` with `request` and return the record it responds with.
+    async fn write_record(&self, request: ApiRequest, body: Value) -> Result<DomainRecord, Error> {
+        let envelope: RecordEnvelope = self.send_json(request.json(body)).await?;
+        Ok(envelope.domain_record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(id: u64, record_type: &str, name: &str, data: &str) -> DomainRecord {
+        serde_json::from_value(json!({
+            "id": id, "type": record_type, "name": name, "data": data, "ttl": 1800,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plan_record() {
+        assert_eq!(relative_name("www.Example.com.", "example.com"), "www");
+        assert_eq!(relative_name("example.com", "example.com"), "@");
+        assert_eq!(relative_name("www", "example.com"), "www");
+
+        let existing = [
+            record(1, "A", "home", "203.0.113.7"),
+            record(2, "TXT", "_acme-challenge", "one"),
+            record(3, "TXT", "_acme-challenge", "two"),
+            record(4, "CNAME", "www", "example.com."),
+        ];
+        let ip = |data| {
+            RecordSpec::new(RecordType::A, "home.example.com", data).relative_to("example.com")
+        };
+        assert_eq!(
+            plan(&existing, &ip("203.0.113.7"), "example.com").unwrap(),
+            Plan::Keep(existing[0].clone())
+        );
+        assert_eq!(
+            plan(&existing, &ip("203.0.113.7").ttl(60), "example.com").unwrap(),
+            Plan::Update(1)
+        );
+        assert_eq!(
+            plan(&existing, &ip("198.51.100.1"), "example.com").unwrap(),
+            Plan::Update(1)
+        );
+        assert_eq!(
+            plan(
+                &existing,
+                &RecordSpec::new(RecordType::Cname, "www", "Example.com"),
+                "example.com"
+            )
+            .unwrap(),
+            Plan::Keep(existing[3].clone())
+        );
+        assert_eq!(
+            plan(
+                &existing,
+                &RecordSpec::new(RecordType::Txt, "_acme-challenge", "two"),
+                "example.com"
+            )
+            .unwrap(),
+            Plan::Keep(existing[2].clone())
+        );
+        assert!(plan(
+            &existing,
+            &RecordSpec::new(RecordType::Txt, "_acme-challenge", "three"),
+            "example.com"
+        )
+        .is_err());
+        assert_eq!(
+            plan(
+                &existing,
+                &RecordSpec::new(RecordType::Aaaa, "home", "2001:db8::1"),
+                "example.com"
+            )
+            .unwrap(),
+            Plan::Create
+        );
+    }
+
+    #[test]
+    fn test_validate_record_spec() {
+        assert!(RecordSpec::new(RecordType::Mx, "@", "mail.example.com.")
+            .priority(10)
+            .ttl(300)
+            .validate()
+            .is_ok());
+        let problems = |spec: RecordSpec| spec.problems();
+        assert_eq!(
+            problems(RecordSpec::new(RecordType::Mx, "@", "mail.example.com.").ttl(5)),
+            [
+                "ttl 5 is outside 30..=2147483647",
+                "MX records need a priority"
+            ]
+        );
+        assert_eq!(
+            problems(RecordSpec::new(RecordType::A, "www", "example.com").priority(1)),
+            [
+                "A records take no priority",
+                "\"example.com\" is not an IPv4 address"
+            ]
+        );
+        assert_eq!(
+            problems(RecordSpec::new(RecordType::Caa, "@", "letsencrypt.org").caa(0, "issues")),
+            ["tag \"issues\" is not issue, issuewild or iodef"]
+        );
+        assert_eq!(
+            problems(RecordSpec::new(RecordType::Srv, "_sip._tcp", "sip.example.com").priority(10)),
+            ["SRV records need a port", "SRV records need a weight"]
+        );
+        assert_eq!(RecordType::from("cname".to_string()), RecordType::Cname);
+        assert_eq!(
+            json!(RecordSpec::new(RecordType::Aaaa, "home", "2001:db8::1")),
+            json!({ "type": "AAAA", "name": "home", "data": "2001:db8::1" })
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! Declarative zone reconciliation.
+//!
+//! [`Client::sync_zone`] takes the records a domain should have and brings the zone
+//! in line with them. Records are grouped by type and name: every group the desired
+//! set mentions is owned, so its records end up holding exactly the desired data,
+//! reusing existing records through updates where possible. Groups the desired set
+//! does not mention are left alone unless [`ZoneSync::prune`] is set, in which case
+//! they are deleted. The SOA record and the apex NS records are DigitalOcean's and
+//! are never pruned.
+//!
+//! [`ZoneSync::plan`] computes the changes without making them, and its [`ZonePlan`]
+//! prints as a reviewable diff. [`ZoneSync::apply`] makes them with bounded
+//! concurrency: deletions first, so a CNAME can replace records of another type, then
+//! updates and creations. Failures are reported per change.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::dns::{RecordSpec, RecordType};
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let sync = client
+//!     .sync_zone(
+//!         "example.com",
+//!         [
+//!             RecordSpec::new(RecordType::A, "@", "203.0.113.7"),
+//!             RecordSpec::new(RecordType::Cname, "www", "example.com."),
+//!             RecordSpec::new(RecordType::Mx, "@", "mail.example.com.").priority(10),
+//!         ],
+//!     )
+//!     .prune(true);
+//! let plan = sync.plan().await?;
+//! print!("{plan}");
+//! let report = sync.apply(&plan).await;
+//! for (change, err) in &report.failed {
+//!     eprintln!("{change}: {err}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{relative_name, DomainRecord, RecordSpec, RecordType};
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use futures::StreamExt;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// One change of a [`ZonePlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Create(RecordSpec),
+    /// Change `record` to hold `spec`.
+    Update {
+        record: DomainRecord,
+        spec: RecordSpec,
+    },
+    Delete(DomainRecord),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Create(spec) => {
+                write!(f, "+ {} {} {}", spec.record_type, spec.name, spec.data)?;
+                if let Some(ttl) = spec.ttl {
+                    write!(f, " (ttl {ttl})")?;
+                }
+                Ok(())
+            }
+            Self::Update { record, spec } => {
+                write!(
+                    f,
+                    "~ {} {} {} -> {}",
+                    record.record_type, record.name, record.data, spec.data
+                )?;
+                match spec.ttl {
+                    Some(ttl) if ttl != record.ttl => write!(f, " (ttl {} -> {ttl})", record.ttl),
+                    _ => Ok(()),
+                }
+            }
+            Self::Delete(record) => write!(
+                f,
+                "- {} {} {}",
+                record.record_type, record.name, record.data
+            ),
+        }
+    }
+}
+
+/// The changes that bring a zone in line with the desired records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZonePlan {
+    pub domain: String,
+    /// Deletions first, then updates and creations.
+    pub changes: Vec<Change>,
+    /// Records that already match.
+    pub unchanged: usize,
+}
+
+impl ZonePlan {
+    /// Whether the zone already matches.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for ZonePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        writeln!(
+            f,
+            "{}: {} to change, {} unchanged",
+            self.domain,
+            self.changes.len(),
+            self.unchanged
+        )
+    }
+}
+
+/// Outcome of [`ZoneSync::apply`].
+#[derive(Debug, Default)]
+pub struct ZoneSyncReport {
+    pub applied: Vec<Change>,
+    pub failed: Vec<(Change, Error)>,
+}
+
+impl ZoneSyncReport {
+    /// Whether every change was applied.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Whether pruning leaves `record` alone because DigitalOcean manages it.
+fn is_managed(record: &DomainRecord) -> bool {
+    match record.record_type {
+        RecordType::Soa => true,
+        RecordType::Ns => record.name == "@",
+        _ => false,
+    }
+}
+
+/// The changes making `existing` hold `desired`, whose names are relative.
+///
+/// Fails with [`Error::InvalidInput`] if a desired record is invalid or listed twice.
+fn diff(
+    domain: &str,
+    existing: &[DomainRecord],
+    desired: &[RecordSpec],
+    prune: bool,
+) -> Result<ZonePlan, Error> {
+    for spec in desired {
+        spec.validate()?;
+    }
+    type Group<'a> = (Vec<&'a DomainRecord>, Vec<&'a RecordSpec>);
+    let key =
+        |record_type: &RecordType, name: &str| (name.to_ascii_lowercase(), record_type.to_string());
+    let mut groups: BTreeMap<(String, String), Group> = BTreeMap::new();
+    for record in existing {
+        groups
+            .entry(key(&record.record_type, &record.name))
+            .or_default()
+            .0
+            .push(record);
+    }
+    for spec in desired {
+        let (_, specs) = groups
+            .entry(key(&spec.record_type, &spec.name))
+            .or_default();
+        if specs.iter().any(|other| other.data == spec.data) {
+            return Err(Error::InvalidInput(format!(
+                "{} {} {:?} is listed twice",
+                spec.record_type, spec.name, spec.data
+            )));
+        }
+        specs.push(spec);
+    }
+
+    let mut deletes = Vec::new();
+    let mut writes = Vec::new();
+    let mut unchanged = 0;
+    for (records, specs) in groups.into_values() {
+        if specs.is_empty() {
+            if prune {
+                deletes.extend(
+                    records
+                        .into_iter()
+                        .filter(|record| !is_managed(record))
+                        .map(|record| Change::Delete(record.clone())),
+                );
+            }
+            continue;
+        }
+        let mut spare = records;
+        let mut missing = Vec::new();
+        for spec in specs {
+            match spare.iter().position(|record| spec.holds_data(record)) {
+                Some(index) => {
+                    let record = spare.remove(index);
+                    if spec.matches(record) {
+                        unchanged += 1;
+                    } else {
+                        writes.push(Change::Update {
+                            record: record.clone(),
+                            spec: spec.clone(),
+                        });
+                    }
+                }
+                None => missing.push(spec),
+            }
+        }
+        let mut spare = spare.into_iter();
+        for spec in missing {
+            writes.push(match spare.next() {
+                Some(record) => Change::Update {
+                    record: record.clone(),
+                    spec: spec.clone(),
+                },
+                None => Change::Create(spec.clone()),
+            });
+        }
+        deletes.extend(spare.map(|record| Change::Delete(record.clone())));
+    }
+    deletes.append(&mut writes);
+    Ok(ZonePlan {
+        domain: domain.to_string(),
+        changes: deletes,
+        unchanged,
+    })
+}
+
+/// A zone reconciliation, returned by [`Client::sync_zone`].
+#[derive(Debug, Clone)]
+#[must_use = "call `.plan()` or `.run()` to reconcile the zone"]
+pub struct ZoneSync {
+    client: Client,
+    domain: String,
+    desired: Vec<RecordSpec>,
+    prune: bool,
+    max_concurrent_requests: usize,
+}
+
+impl ZoneSync {
+    /// Delete records whose type and name are not in the desired set. Off by default.
+    pub fn prune(mut self, prune: bool) -> Self {
+        self.prune = prune;
+        self
+    }
+
+    /// Changes in flight at once. Defaults to 4.
+    pub fn max_concurrent_requests(mut self, limit: usize) -> Self {
+        self.max_concurrent_requests = limit.max(1);
+        self
+    }
+
+    /// Compute the changes without making them.
+    ///
+    /// Fails with [`Error::InvalidInput`] if a desired record is invalid or listed
+    /// twice.
+    pub async fn plan(&self) -> Result<ZonePlan, Error> {
+        let existing = self.client.domain_records(&self.domain).await?;
+        diff(&self.domain, &existing, &self.desired, self.prune)
+    }
+
+    /// Make the changes of `plan`, deletions first.
+    pub async fn apply(&self, plan: &ZonePlan) -> ZoneSyncReport {
+        let (deletes, writes): (Vec<_>, Vec<_>) = plan
+            .changes
+            .iter()
+            .cloned()
+            .partition(|change| matches!(change, Change::Delete(_)));
+        let mut report = ZoneSyncReport::default();
+        for phase in [deletes, writes] {
+            let results: Vec<_> = futures::stream::iter(phase)
+                .map(|change| async move {
+                    let result = self.apply_change(&change).await;
+                    (change, result)
+                })
+                .buffer_unordered(self.max_concurrent_requests)
+                .collect()
+                .await;
+            for (change, result) in results {
+                match result {
+                    Ok(()) => report.applied.push(change),
+                    Err(err) => report.failed.push((change, err)),
+                }
+            }
+        }
+        report
+    }
+
+    /// Plan the changes and make them.
+    pub async fn run(&self) -> Result<ZoneSyncReport, Error> {
+        let plan = self.plan().await?;
+        Ok(self.apply(&plan).await)
+    }
+
+    async fn apply_change(&self, change: &Change) -> Result<(), Error> {
+        let records = format!("/v2/domains/{}/records", self.domain);
+        let request = match change {
+            Change::Create(spec) => {
+                ApiRequest::post("domains_create_record", records).json(serde_json::to_value(spec)?)
+            }
+            Change::Update { record, spec } => {
+                ApiRequest::put("domains_update_record", format!("{records}/{}", record.id))
+                    .json(serde_json::to_value(spec)?)
+            }
+            Change::Delete(record) => {
+                ApiRequest::delete("domains_delete_record", format!("{records}/{}", record.id))
+            }
+        };
+        self.client.send_empty(request).await
+    }
+}
+
+impl Client {
+    /// Start reconciling the records of `domain` with `desired`.
+    pub fn sync_zone(
+        &self,
+        domain: &str,
+        desired: impl IntoIterator<Item = RecordSpec>,
+    ) -> ZoneSync {
+        ZoneSync {
+            client: self.clone(),
+            domain: domain.to_string(),
+            desired: desired
+                .into_iter()
+                .map(|spec| RecordSpec {
+                    name: relative_name(&spec.name, domain),
+                    ..spec
+                })
+                .collect(),
+            prune: false,
+            max_concurrent_requests: 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(id: u64, record_type: &str, name: &str, data: &str) -> DomainRecord {
+        serde_json::from_value(json!({
+            "id": id, "type": record_type, "name": name, "data": data, "ttl": 1800,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff_zone() {
+        let existing = [
+            record(1, "SOA", "@", "1800"),
+            record(2, "NS", "@", "ns1.digitalocean.com"),
+            record(3, "A", "@", "198.51.100.1"),
+            record(4, "A", "www", "198.51.100.1"),
+            record(5, "TXT", "@", "v=spf1 -all"),
+            record(6, "TXT", "@", "stale"),
+            record(7, "A", "old", "198.51.100.9"),
+        ];
+        let desired = [
+            RecordSpec::new(RecordType::A, "@", "203.0.113.7"),
+            RecordSpec::new(RecordType::Cname, "www", "example.com."),
+            RecordSpec::new(RecordType::Txt, "@", "v=spf1 -all").ttl(300),
+        ];
+
+        let plan = diff("example.com", &existing, &desired, false).unwrap();
+        assert_eq!(
+            plan.to_string(),
+            "- TXT @ stale\n\
+             ~ A @ 198.51.100.1 -> 203.0.113.7\n\
+             ~ TXT @ v=spf1 -all -> v=spf1 -all (ttl 1800 -> 300)\n\
+             + CNAME www example.com.\n\
+             example.com: 4 to change, 0 unchanged\n"
+        );
+
+        let plan = diff("example.com", &existing, &desired, true).unwrap();
+        let deleted: Vec<u64> = plan
+            .changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::Delete(record) => Some(record.id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deleted, [6, 7, 4]);
+
+        let twice = [desired[0].clone(), desired[0].clone()];
+        assert!(diff("example.com", &existing, &twice, false).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Typed droplet actions.
+//!
+//! `dropletActions_post` takes one body per action type, which the generated client
+//! models as a polymorphic enum. The helpers here take each action's parameters
+//! directly and return an [`ActionHandle`] to wait on.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::wait::WaitOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! client.droplet_snapshot(3164494, "before-upgrade").await?.wait(WaitOptions::default()).await?;
+//! client.droplet_resize(3164494, "s-2vcpu-4gb", false).await?.wait(WaitOptions::default()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::Image;
+use crate::actions::ActionHandle;
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use serde_json::{json, Value};
+
+impl Client {
+    /// Start an action on droplet `id` with `body`, e.g. `{"type": "reboot"}`.
+    pub(super) async fn droplet_action(&self, id: u64, body: Value) -> Result<ActionHandle, Error> {
+        self.start_action(
+            ApiRequest::post("dropletActions_post", format!("/v2/droplets/{id}/actions"))
+                .json(body),
+        )
+        .await
+    }
+
+    /// Reboot droplet `id` through ACPI.
+    pub async fn droplet_reboot(&self, id: u64) -> Result<ActionHandle, Error> {
+        self.droplet_action(id, json!({ "type": "reboot" })).await
+    }
+
+    /// Resize droplet `id` to `size`. With `resize_disk`, the disk grows too, which
+    /// cannot be undone. The droplet must be powered off.
+    pub async fn droplet_resize(
+        &self,
+        id: u64,
+        size: &str,
+        resize_disk: bool,
+    ) -> Result<ActionHandle, Error> {
+        let body = json!({ "type": "resize", "size": size, "disk": resize_disk });
+        self.droplet_action(id, body).await
+    }
+
+    /// Take a snapshot of droplet `id` named `name`.
+    pub async fn droplet_snapshot(&self, id: u64, name: &str) -> Result<ActionHandle, Error> {
+        self.droplet_action(id, json!({ "type": "snapshot", "name": name }))
+            .await
+    }
+
+    /// Rebuild droplet `id` from `image`, erasing its disk but keeping its IP
+    /// addresses.
+    pub async fn droplet_rebuild(
+        &self,
+        id: u64,
+        image: impl Into<Image>,
+    ) -> Result<ActionHandle, Error> {
+        let body = json!({ "type": "rebuild", "image": image.into() });
+        self.droplet_action(id, body).await
+    }
+
+    /// Restore droplet `id` from one of its backups or snapshots, by image ID.
+    pub async fn droplet_restore(&self, id: u64, image: u64) -> Result<ActionHandle, Error> {
+        self.droplet_action(id, json!({ "type": "restore", "image": image }))
+            .await
+    }
+
+    /// Rename droplet `id`.
+    pub async fn droplet_rename(&self, id: u64, name: &str) -> Result<ActionHandle, Error> {
+        self.droplet_action(id, json!({ "type": "rename", "name": name }))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_rebuild_posts_typed_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"}") {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"action": {"id": 7, "status": "in-progress", "type": "rebuild"}}"#;
+            let response = format!(
+                "HTTP/1.1 201 Created\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let client = crate::ClientBuilder::new("test-token")
+            .base_url(base_url)
+            .build()
+            .unwrap();
+        let handle = client.droplet_rebuild(42, 7555620).await.unwrap();
+        assert_eq!(handle.id(), 7);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v2/droplets/42/actions "));
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "type": "rebuild", "image": 7555620 })
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Droplet agent and metrics agent visibility.
+//!
+//! Two optional agents run inside droplets: the droplet agent, which powers the web
+//! console, and the metrics agent (`do-agent`), which feeds monitoring graphs and
+//! alerts. Whether they are present is reported through the droplet's `features`. The
+//! API can only request the droplet agent at creation time (`with_droplet_agent`), so
+//! for existing droplets [`Client::ensure_droplet_agent`] reports what is missing
+//! together with the command that installs it from inside the droplet.
+
+use super::{Droplet, Kernel};
+use crate::error::Error;
+use crate::Client;
+use std::fmt;
+
+/// An agent that can run inside a droplet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropletAgent {
+    /// The droplet agent, required for the web console.
+    Console,
+    /// The metrics agent (`do-agent`), required for monitoring graphs and alerts.
+    Metrics,
+}
+
+impl DropletAgent {
+    /// Name of the droplet feature that reports this agent.
+    pub fn feature(self) -> &'static str {
+        match self {
+            Self::Console => "droplet_agent",
+            Self::Metrics => "monitoring",
+        }
+    }
+
+    /// Shell command that installs the agent when run as root inside the droplet.
+    pub fn install_command(self) -> &'static str {
+        match self {
+            Self::Console => {
+                "wget -qO- https://repos-droplet.digitalocean.com/install.sh | sudo bash"
+            }
+            Self::Metrics => {
+                "curl -sSL https://repos.insights.digitalocean.com/install.sh | sudo bash"
+            }
+        }
+    }
+
+    /// Installation guide for the agent.
+    pub fn docs_url(self) -> &'static str {
+        match self {
+            Self::Console => "https://docs.digitalocean.com/products/droplets/how-to/manage-agent/",
+            Self::Metrics => {
+                "https://docs.digitalocean.com/products/monitoring/how-to/install-metrics-agent/"
+            }
+        }
+    }
+}
+
+impl fmt::Display for DropletAgent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Console => "droplet agent",
+            Self::Metrics => "metrics agent",
+        })
+    }
+}
+
+/// Which agents a droplet reports, and the kernel it boots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DropletAgentStatus {
+    pub droplet_id: u64,
+    pub console_agent: bool,
+    pub metrics_agent: bool,
+    /// Externally managed kernel, for droplets that still use one.
+    pub kernel: Option<Kernel>,
+}
+
+impl DropletAgentStatus {
+    /// Whether `agent` is installed.
+    pub fn has(&self, agent: DropletAgent) -> bool {
+        match agent {
+            DropletAgent::Console => self.console_agent,
+            DropletAgent::Metrics => self.metrics_agent,
+        }
+    }
+}
+
+/// Result of [`Client::ensure_droplet_agent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentCheck {
+    /// The agent is already installed; nothing to do.
+    Installed,
+    /// The agent is missing. The API cannot install it on an existing droplet, so it
+    /// has to be installed from inside the droplet.
+    Missing {
+        agent: DropletAgent,
+        install_command: &'static str,
+        docs_url: &'static str,
+    },
+}
+
+impl From<Droplet> for DropletAgentStatus {
+    fn from(droplet: Droplet) -> Self {
+        Self {
+            droplet_id: droplet.id,
+            console_agent: droplet.has_feature(DropletAgent::Console.feature()),
+            metrics_agent: droplet.has_feature(DropletAgent::Metrics.feature()),
+            kernel: droplet.kernel,
+        }
+    }
+}
+
+impl Client {
+    /// Report which agents a droplet has installed.
+    pub async fn droplet_agent_status(&self, id: u64) -> Result<DropletAgentStatus, Error> {
+        Ok(self.droplet(id).await?.into())
+    }
+
+    /// Check that `agent` is installed on a droplet, returning installation guidance
+    /// if it is not.
+    pub async fn ensure_droplet_agent(
+        &self,
+        id: u64,
+        agent: DropletAgent,
+    ) -> Result<AgentCheck, Error> {
+        let status = self.droplet_agent_status(id).await?;
+        if status.has(agent) {
+            Ok(AgentCheck::Installed)
+        } else {
+            Ok(AgentCheck::Missing {
+                agent,
+                install_command: agent.install_command(),
+                docs_url: agent.docs_url(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_status_from_features() {
+        let droplet: Droplet = serde_json::from_value(json!({
+            "id": 3164444,
+            "name": "example.com",
+            "status": "active",
+            "created_at": "2020-07-21T18:37:44Z",
+            "features": ["backups", "monitoring"],
+            "kernel": null
+        }))
+        .unwrap();
+        let status = DropletAgentStatus::from(droplet);
+        assert!(status.has(DropletAgent::Metrics));
+        assert!(!status.has(DropletAgent::Console));
+        assert_eq!(status.kernel, None);
+    }
+}
@@ -0,0 +1,176 @@
+//! Droplet backup policies.
+//!
+//! Backups run daily or weekly in a four-hour window starting at one of six hours of
+//! the day (UTC). [`BackupPolicy`] names the schedule with typed days and hours, so a
+//! window the API would reject cannot be built. [`Client::enable_backups`] and
+//! [`Client::change_backup_policy`] start the corresponding droplet actions;
+//! [`Client::list_backups`] lists the backups taken so far.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::droplets::{BackupPolicy, Day, Hour};
+//! use rsdo::wait::WaitOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! client
+//!     .enable_backups(3164494, BackupPolicy::weekly(Day::Sun, Hour::H4))
+//!     .await?
+//!     .wait(WaitOptions::default())
+//!     .await?;
+//! for backup in client.list_backups(3164494).await? {
+//!     println!("{} ({} GiB)", backup.name, backup.size_gigabytes);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::actions::ActionHandle;
+use crate::error::Error;
+use crate::Client;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Day of the week a weekly backup runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Day {
+    Sun,
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+}
+
+/// Hour of the day (UTC) a backup window starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum Hour {
+    H0,
+    H4,
+    H8,
+    H12,
+    H16,
+    H20,
+}
+
+impl From<Hour> for u8 {
+    fn from(hour: Hour) -> Self {
+        match hour {
+            Hour::H0 => 0,
+            Hour::H4 => 4,
+            Hour::H8 => 8,
+            Hour::H12 => 12,
+            Hour::H16 => 16,
+            Hour::H20 => 20,
+        }
+    }
+}
+
+impl TryFrom<u8> for Hour {
+    type Error = String;
+
+    fn try_from(hour: u8) -> Result<Self, Self::Error> {
+        match hour {
+            0 => Ok(Self::H0),
+            4 => Ok(Self::H4),
+            8 => Ok(Self::H8),
+            12 => Ok(Self::H12),
+            16 => Ok(Self::H16),
+            20 => Ok(Self::H20),
+            _ => Err(format!("{hour} is not a backup window hour")),
+        }
+    }
+}
+
+/// When a droplet's backups run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "plan", rename_all = "lowercase")]
+pub enum BackupPolicy {
+    Daily { hour: Hour },
+    Weekly { weekday: Day, hour: Hour },
+}
+
+impl BackupPolicy {
+    pub fn daily(hour: Hour) -> Self {
+        Self::Daily { hour }
+    }
+
+    pub fn weekly(weekday: Day, hour: Hour) -> Self {
+        Self::Weekly { weekday, hour }
+    }
+}
+
+/// A backup image of a droplet.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Backup {
+    pub id: u64,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub size_gigabytes: f64,
+    /// Smallest disk, in GiB, a droplet restored from the backup needs.
+    #[serde(default)]
+    pub min_disk_size: u64,
+    #[serde(default)]
+    pub regions: Vec<String>,
+}
+
+impl Client {
+    /// Enable backups on droplet `id`, running on `policy`'s schedule.
+    pub async fn enable_backups(
+        &self,
+        id: u64,
+        policy: BackupPolicy,
+    ) -> Result<ActionHandle, Error> {
+        let body = json!({ "type": "enable_backups", "backup_policy": policy });
+        self.droplet_action(id, body).await
+    }
+
+    /// Move the backups of droplet `id`, which must already be enabled, to
+    /// `policy`'s schedule.
+    pub async fn change_backup_policy(
+        &self,
+        id: u64,
+        policy: BackupPolicy,
+    ) -> Result<ActionHandle, Error> {
+        let body = json!({ "type": "change_backup_policy", "backup_policy": policy });
+        self.droplet_action(id, body).await
+    }
+
+    /// List the backups of droplet `id`.
+    pub async fn list_backups(&self, id: u64) -> Result<Vec<Backup>, Error> {
+        self.paginate("droplets_list_backups")
+            .path_param("droplet_id", id)
+            .items_key("backups")
+            .per_page(200)
+            .stream()
+            .try_collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_serializes_as_api_schedule() {
+        assert_eq!(
+            serde_json::to_value(BackupPolicy::weekly(Day::Sun, Hour::H4)).unwrap(),
+            json!({ "plan": "weekly", "weekday": "SUN", "hour": 4 })
+        );
+        let policy: BackupPolicy = serde_json::from_value(
+            json!({ "plan": "daily", "hour": 20, "window_length_hours": 4 }),
+        )
+        .unwrap();
+        assert_eq!(policy, BackupPolicy::daily(Hour::H20));
+        assert!(
+            serde_json::from_value::<BackupPolicy>(json!({ "plan": "daily", "hour": 3 })).is_err()
+        );
+    }
+}
@@ -0,0 +1,488 @@
+//! Creating many droplets at once.
+//!
+//! `droplets_create` accepts up to ten `names` per request and answers with every new
+//! droplet plus one create action each. [`Client::create_droplets_batch`] splits any
+//! number of names into such requests, sends them concurrently, then follows every
+//! create action until the droplet is ready. Failures are reported per droplet, so one
+//! rejected request or failed build does not hide the droplets that did come up.
+//!
+//! [`Client::create_droplets_from_pattern`] takes the names as a pattern such as
+//! `web-{01..10}` instead, and [`CreateDropletsBatch::create`] returns as soon as the
+//! droplets exist, with a handle on each create action.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::droplets::DropletTemplate;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let template = DropletTemplate::new("nyc3", "s-1vcpu-1gb", "ubuntu-24-04-x64");
+//! let batch = client
+//!     .create_droplets_batch(&["web-1", "web-2", "web-3"], template)
+//!     .wait_all()
+//!     .await?;
+//! for droplet in batch.created() {
+//!     println!("{} is up", droplet.name);
+//! }
+//! for (name, err) in batch.failed() {
+//!     eprintln!("{name} failed: {err}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{Droplet, DropletStatus};
+use crate::actions::{self, ActionHandle};
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use futures::future::join_all;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Most names `droplets_create` accepts in one request.
+const MAX_NAMES_PER_REQUEST: usize = 10;
+
+/// Settings shared by every droplet of a batch.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DropletTemplate {
+    /// Region slug, e.g. `nyc3`.
+    pub region: String,
+    /// Size slug, e.g. `s-1vcpu-1gb`.
+    pub size: String,
+    /// Image slug, or the numeric ID of a snapshot or custom image.
+    pub image: String,
+    /// IDs or fingerprints of SSH keys to install.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ssh_keys: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub backups: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub ipv6: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub monitoring: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vpc_uuid: Option<String>,
+}
+
+impl DropletTemplate {
+    pub fn new(
+        region: impl Into<String>,
+        size: impl Into<String>,
+        image: impl Into<String>,
+    ) -> Self {
+        Self {
+            region: region.into(),
+            size: size.into(),
+            image: image.into(),
+            ..Self::default()
+        }
+    }
+
+    /// The `droplets_create` body creating `names` from this template.
+    fn body(&self, names: &[String]) -> Result<Value, Error> {
+        let mut body = serde_json::to_value(self)?;
+        body["names"] = json!(names);
+        // Snapshots and custom images are referenced by numeric ID.
+        if let Ok(id) = self.image.parse::<u64>() {
+            body["image"] = json!(id);
+        }
+        Ok(body)
+    }
+}
+
+/// Outcome for one droplet of a batch.
+#[derive(Debug)]
+pub struct BatchDroplet {
+    pub name: String,
+    /// ID of the droplet, if the API created it.
+    pub id: Option<u64>,
+    /// The ready droplet, or why it is not available. Droplets named in the same
+    /// rejected request share its error.
+    pub result: Result<Droplet, Arc<Error>>,
+}
+
+/// Outcome of [`CreateDropletsBatch::wait_all`], in the order the names were given.
+#[derive(Debug)]
+pub struct DropletBatch {
+    pub droplets: Vec<BatchDroplet>,
+}
+
+impl DropletBatch {
+    /// Droplets that were created and became ready.
+    pub fn created(&self) -> impl Iterator<Item = &Droplet> {
+        self.droplets.iter().filter_map(|d| d.result.as_ref().ok())
+    }
+
+    /// Names of the droplets that failed, with the reason.
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &Error)> {
+        self.droplets
+            .iter()
+            .filter_map(|d| d.result.as_ref().err().map(|err| (d.name.as_str(), &**err)))
+    }
+
+    /// Whether every droplet was created and became ready.
+    pub fn all_created(&self) -> bool {
+        self.droplets.iter().all(|d| d.result.is_ok())
+    }
+}
+
+/// Builder for a batch of droplets created from one template.
+///
+/// Created by [`Client::create_droplets_batch`].
+#[derive(Debug, Clone)]
+#[must_use = "call `.wait_all()` to create the droplets"]
+pub struct CreateDropletsBatch {
+    client: Client,
+    names: Vec<String>,
+    template: DropletTemplate,
+    interval: Duration,
+    timeout: Duration,
+    names_per_request: usize,
+    max_concurrent_requests: usize,
+}
+
+/// A droplet created by a batch, not necessarily ready yet.
+#[derive(Debug)]
+pub struct CreatedDroplet {
+    /// The droplet as the API first reported it.
+    pub droplet: Droplet,
+    /// Its create action, if the API linked one.
+    pub action: Option<ActionHandle>,
+}
+
+/// Outcome of [`CreateDropletsBatch::create`].
+#[derive(Debug)]
+pub struct CreatedBatch {
+    pub created: Vec<CreatedDroplet>,
+    /// Names of the droplets whose request was rejected, with the reason. Droplets
+    /// named in the same request share its error.
+    pub failed: Vec<(String, Arc<Error>)>,
+}
+
+#[derive(Deserialize)]
+struct CreatedDroplets {
+    droplets: Vec<Droplet>,
+    #[serde(default)]
+    links: CreatedLinks,
+}
+
+#[derive(Default, Deserialize)]
+struct CreatedLinks {
+    #[serde(default)]
+    actions: Vec<ActionLink>,
+}
+
+#[derive(Deserialize)]
+struct ActionLink {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct ActionEnvelope {
+    action: Action,
+}
+
+#[derive(Deserialize)]
+struct Action {
+    status: String,
+}
+
+impl Client {
+    /// Create one droplet per name from `template`.
+    ///
+    /// Nothing is sent until [`CreateDropletsBatch::wait_all`] is called.
+    pub fn create_droplets_batch(
+        &self,
+        names: &[&str],
+        template: DropletTemplate,
+    ) -> CreateDropletsBatch {
+        CreateDropletsBatch {
+            client: self.clone(),
+            names: names.iter().map(|name| name.to_string()).collect(),
+            template,
+            interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(600),
+            names_per_request: MAX_NAMES_PER_REQUEST,
+            max_concurrent_requests: 4,
+        }
+    }
+
+    /// Create one droplet per name that `pattern` expands to; see
+    /// [`expand_name_pattern`].
+    pub fn create_droplets_from_pattern(
+        &self,
+        pattern: &str,
+        template: DropletTemplate,
+    ) -> Result<CreateDropletsBatch, Error> {
+        let names = expand_name_pattern(pattern)?;
+        let mut batch = self.create_droplets_batch(&[], template);
+        batch.names = names;
+        Ok(batch)
+    }
+}
+
+/// Expand each `{start..end}` range in `pattern` into one name per number, so
+/// `web-{01..10}` gives `web-01` to `web-10`.
+///
+/// Numbers are zero-padded to the width of the bounds when either starts with `0`,
+/// as in the shell. A pattern without ranges is a single name.
+pub fn expand_name_pattern(pattern: &str) -> Result<Vec<String>, Error> {
+    let invalid = |reason: &str| Error::InvalidInput(format!("name pattern {pattern:?} {reason}"));
+    let mut names = vec![String::new()];
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .map(|i| open + i)
+            .ok_or_else(|| invalid("has an unclosed `{`"))?;
+        let (start, end) = rest[open + 1..close]
+            .split_once("..")
+            .ok_or_else(|| invalid("needs ranges written as `{start..end}`"))?;
+        let (first, last) = match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(first), Ok(last)) if first <= last => (first, last),
+            _ => return Err(invalid("has a range that is not two ascending numbers")),
+        };
+        let padded = |bound: &str| bound.len() > 1 && bound.starts_with('0');
+        let width = if padded(start) || padded(end) {
+            start.len().max(end.len())
+        } else {
+            0
+        };
+        let prefix = &rest[..open];
+        names = names
+            .iter()
+            .flat_map(|name| (first..=last).map(move |n| format!("{name}{prefix}{n:0width$}")))
+            .collect();
+        rest = &rest[close + 1..];
+    }
+    for name in &mut names {
+        name.push_str(rest);
+    }
+    Ok(names)
+}
+
+impl CreateDropletsBatch {
+    /// Delay between polls of each create action. Defaults to 5 seconds.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// How long to wait for each droplet to become ready. Defaults to 10 minutes.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Names sent in each create request, from 1 to 10 (the default and the API's
+    /// limit).
+    pub fn names_per_request(mut self, names: usize) -> Self {
+        self.names_per_request = names.clamp(1, MAX_NAMES_PER_REQUEST);
+        self
+    }
+
+    /// Create requests in flight at once. Defaults to 4.
+    pub fn max_concurrent_requests(mut self, limit: usize) -> Self {
+        self.max_concurrent_requests = limit.max(1);
+        self
+    }
+
+    /// Send the create requests, returning the droplets that were created and the
+    /// names whose request failed.
+    async fn send(&self) -> Result<(Vec<(Droplet, Option<u64>)>, Vec<BatchDroplet>), Error> {
+        if self.names.is_empty() {
+            return Err(Error::InvalidInput(
+                "a droplet batch needs at least one name".to_string(),
+            ));
+        }
+        let chunks: Vec<&[String]> = self.names.chunks(self.names_per_request).collect();
+        let bodies = chunks
+            .iter()
+            .map(|names| self.template.body(names))
+            .collect::<Result<Vec<_>, _>>()?;
+        let responses: Vec<_> = futures::stream::iter(bodies)
+            .map(|body| {
+                self.client.send_json::<CreatedDroplets>(
+                    ApiRequest::post("droplets_create", "/v2/droplets").json(body),
+                )
+            })
+            .buffered(self.max_concurrent_requests)
+            .collect()
+            .await;
+
+        let mut created = Vec::new();
+        let mut failed = Vec::new();
+        for (names, response) in chunks.into_iter().zip(responses) {
+            match response {
+                Ok(response) => {
+                    // One create action per droplet, in the same order.
+                    let mut actions = response.links.actions.into_iter().map(|link| link.id);
+                    for droplet in response.droplets {
+                        created.push((droplet, actions.next()));
+                    }
+                }
+                Err(err) => {
+                    let err = Arc::new(err);
+                    failed.extend(names.iter().map(|name| BatchDroplet {
+                        name: name.clone(),
+                        id: None,
+                        result: Err(err.clone()),
+                    }));
+                }
+            }
+        }
+        Ok((created, failed))
+    }
+
+    /// Create the droplets without waiting for them to become ready.
+    ///
+    /// Only invalid input fails the whole call; rejected requests are reported in
+    /// [`CreatedBatch::failed`].
+    pub async fn create(self) -> Result<CreatedBatch, Error> {
+        let (created, failed) = self.send().await?;
+        Ok(CreatedBatch {
+            created: created
+                .into_iter()
+                .map(|(droplet, action)| CreatedDroplet {
+                    droplet,
+                    action: action.map(|id| ActionHandle::new(&self.client, id)),
+                })
+                .collect(),
+            failed: failed
+                .into_iter()
+                .filter_map(|failure| Some((failure.name, failure.result.err()?)))
+                .collect(),
+        })
+    }
+
+    /// Create the droplets and wait for every create action to finish.
+    ///
+    /// Only invalid input fails the whole call; API failures are reported per droplet
+    /// in the returned [`DropletBatch`].
+    pub async fn wait_all(self) -> Result<DropletBatch, Error> {
+        let (pending, mut droplets) = self.send().await?;
+
+        let this = &self;
+        let ready = join_all(pending.into_iter().map(|(droplet, action)| async move {
+            let workflow = Workflow::new(
+                this.client.inner(),
+                "create_droplets_batch",
+                format!("droplet {}", droplet.name),
+            );
+            let result = workflow
+                .step("wait_active", this.wait_ready(droplet.id, action))
+                .await;
+            BatchDroplet {
+                name: droplet.name,
+                id: Some(droplet.id),
+                result: result.map_err(Arc::new),
+            }
+        }))
+        .await;
+        droplets.extend(ready);
+
+        let position = |name: &str| self.names.iter().position(|n| n == name);
+        droplets.sort_by_key(|droplet| position(&droplet.name));
+        Ok(DropletBatch { droplets })
+    }
+
+    /// Wait for the create action of droplet `id`, or for the droplet to turn active
+    /// when the API did not link an action, and return the ready droplet.
+    async fn wait_ready(&self, id: u64, action: Option<u64>) -> Result<Droplet, Error> {
+        let client = &self.client;
+        let options = WaitOptions::default()
+            .interval(self.interval)
+            .timeout(self.timeout);
+        let waiting_for = format!("droplet {id} to be created");
+        match action {
+            Some(action) => {
+                client
+                    .waiter(waiting_for, || async move {
+                        let envelope: ActionEnvelope = client
+                            .send_json(ApiRequest::get(
+                                "actions_get",
+                                format!("/v2/actions/{action}"),
+                            ))
+                            .await?;
+                        Ok(envelope.action.status)
+                    })
+                    .until(|status| status == "completed")
+                    .fail_if(|status| {
+                        (status == "errored").then(|| {
+                            Error::Other(format!("create action {action} of droplet {id} errored"))
+                        })
+                    })
+                    .state(String::clone)
+                    .typical_duration(|_| actions::typical_duration("create"))
+                    .options(options)
+                    .wait()
+                    .await?;
+                client.droplet(id).await
+            }
+            None => {
+                client
+                    .waiter(waiting_for, || client.droplet(id))
+                    .until(|droplet| droplet.status == DropletStatus::Active)
+                    .state(|droplet| droplet.status.to_string())
+                    .typical_duration(|_| actions::typical_duration("create"))
+                    .options(options)
+                    .wait()
+                    .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_name_pattern() {
+        assert_eq!(
+            expand_name_pattern("web-{08..10}").unwrap(),
+            ["web-08", "web-09", "web-10"]
+        );
+        let err = expand_name_pattern("db-{1..2}-{a..b}").unwrap_err();
+        assert!(err.to_string().contains("db-{1..2}-{a..b}"));
+        assert_eq!(
+            expand_name_pattern("{1..2}.{9..10}").unwrap(),
+            ["1.9", "1.10", "2.9", "2.10"]
+        );
+        assert_eq!(expand_name_pattern("api").unwrap(), ["api"]);
+        assert!(expand_name_pattern("web-{3..1}").is_err());
+    }
+
+    #[test]
+    fn test_template_body() {
+        let mut template = DropletTemplate::new("nyc3", "s-1vcpu-1gb", "ubuntu-24-04-x64");
+        template.tags = vec!["web".to_string()];
+        let names = vec!["web-1".to_string(), "web-2".to_string()];
+        assert_eq!(
+            template.body(&names).unwrap(),
+            json!({
+                "names": ["web-1", "web-2"],
+                "region": "nyc3",
+                "size": "s-1vcpu-1gb",
+                "image": "ubuntu-24-04-x64",
+                "tags": ["web"]
+            })
+        );
+
+        template.image = "7555620".to_string();
+        template.monitoring = true;
+        let body = template.body(&names).unwrap();
+        assert_eq!(body["image"], json!(7555620));
+        assert_eq!(body["monitoring"], json!(true));
+    }
+}
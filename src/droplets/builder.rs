@@ -0,0 +1,405 @@
+//! Building a single droplet create request.
+//!
+//! The generated `DropletsCreateBody` mirrors every variant of the spec, which makes
+//! a plain create awkward to write. [`DropletBuilder`] takes the common settings
+//! through fluent setters, checks locally that the request is complete and well
+//! formed, and sends it with [`create`](DropletBuilder::create) or
+//! [`create_and_wait`](DropletBuilder::create_and_wait).
+//!
+//! SSH keys may be given by name. Before sending, names are resolved to key IDs
+//! through `sshKeys_list`; the IDs are cached on the client, which only lists the
+//! keys again when a name is not in the cache.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::droplets::{DropletBuilder, Image};
+//! use rsdo::wait::WaitOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let droplet = DropletBuilder::new("web-1")
+//!     .region("nyc1")
+//!     .size("s-1vcpu-1gb")
+//!     .image(Image::Slug("ubuntu-24-04-x64".into()))
+//!     .tag("web")
+//!     .ssh_key("deploy")
+//!     .create_and_wait(&client, WaitOptions::default())
+//!     .await?;
+//! println!("{} is {}", droplet.name, droplet.status);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{Droplet, DropletEnvelope};
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Most bytes of user data the API accepts.
+const MAX_USER_DATA: usize = 64 * 1024;
+
+/// The image a droplet is created from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Image {
+    /// A public image, e.g. `ubuntu-24-04-x64`.
+    Slug(String),
+    /// A snapshot, backup or custom image.
+    Id(u64),
+}
+
+impl From<&str> for Image {
+    fn from(slug: &str) -> Self {
+        Self::Slug(slug.to_string())
+    }
+}
+
+impl From<u64> for Image {
+    fn from(id: u64) -> Self {
+        Self::Id(id)
+    }
+}
+
+/// IDs of the account's SSH keys by name, shared by a client and its clones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SshKeyCache(Arc<Mutex<HashMap<String, Vec<u64>>>>);
+
+impl SshKeyCache {
+    /// The IDs of the keys named `names`, or the names that are not cached.
+    fn lookup(&self, names: &[&str]) -> Result<Vec<u64>, Vec<String>> {
+        let cache = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let mut ids = Vec::with_capacity(names.len());
+        let mut unknown = Vec::new();
+        for name in names {
+            match cache.get(*name) {
+                Some(found) => ids.extend(found),
+                None => unknown.push(name.to_string()),
+            }
+        }
+        if unknown.is_empty() {
+            Ok(ids)
+        } else {
+            Err(unknown)
+        }
+    }
+
+    fn replace(&self, keys: Vec<SshKey>) {
+        let mut by_name: HashMap<String, Vec<u64>> = HashMap::new();
+        for key in keys {
+            by_name.entry(key.name).or_default().push(key.id);
+        }
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = by_name;
+    }
+}
+
+#[derive(Deserialize)]
+struct SshKey {
+    id: u64,
+    name: String,
+}
+
+/// Whether `key` is an SSH key ID or MD5 fingerprint rather than a key name.
+fn is_id_or_fingerprint(key: &str) -> bool {
+    let is_hex_pair = |part: &str| part.len() == 2 && part.bytes().all(|b| b.is_ascii_hexdigit());
+    key.bytes().all(|b| b.is_ascii_digit())
+        || (key.split(':').count() == 16 && key.split(':').all(is_hex_pair))
+}
+
+/// Builder for creating one droplet.
+#[derive(Debug, Clone, Default, Serialize)]
+#[must_use = "call `.create()` or `.create_and_wait()` to create the droplet"]
+pub struct DropletBuilder {
+    name: String,
+    region: Option<String>,
+    size: Option<String>,
+    image: Option<Image>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ssh_keys: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    backups: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    ipv6: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    monitoring: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vpc_uuid: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    volumes: Vec<String>,
+}
+
+impl DropletBuilder {
+    /// Start a droplet named `name`, which is also its hostname.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Region slug, e.g. `nyc1`. Required.
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Size slug, e.g. `s-1vcpu-1gb`. Required.
+    pub fn size(mut self, size: impl Into<String>) -> Self {
+        self.size = Some(size.into());
+        self
+    }
+
+    /// Image to create the droplet from. Required.
+    pub fn image(mut self, image: impl Into<Image>) -> Self {
+        self.image = Some(image.into());
+        self
+    }
+
+    /// Install the SSH key with this ID, fingerprint or name.
+    ///
+    /// A name that is all digits is taken for an ID.
+    pub fn ssh_key(mut self, key: impl Into<String>) -> Self {
+        self.ssh_keys.push(key.into());
+        self
+    }
+
+    pub fn backups(mut self, enabled: bool) -> Self {
+        self.backups = enabled;
+        self
+    }
+
+    pub fn ipv6(mut self, enabled: bool) -> Self {
+        self.ipv6 = enabled;
+        self
+    }
+
+    /// Install the metrics agent.
+    pub fn monitoring(mut self, enabled: bool) -> Self {
+        self.monitoring = enabled;
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Cloud-init user data, at most 64 KiB.
+    pub fn user_data(mut self, user_data: impl Into<String>) -> Self {
+        self.user_data = Some(user_data.into());
+        self
+    }
+
+    /// VPC to place the droplet in, instead of the region's default.
+    pub fn vpc(mut self, vpc_uuid: impl Into<String>) -> Self {
+        self.vpc_uuid = Some(vpc_uuid.into());
+        self
+    }
+
+    /// Attach the volume with this ID.
+    pub fn volume(mut self, volume_id: impl Into<String>) -> Self {
+        self.volumes.push(volume_id.into());
+        self
+    }
+
+    /// Check the request without sending it.
+    ///
+    /// Fails with [`Error::InvalidInput`] listing every problem: missing region, size
+    /// or image, a name that is not a valid hostname, tags the API would reject, or
+    /// user data over 64 KiB.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut problems = Vec::new();
+        let is_hostname_char = |c: char| c.is_ascii_alphanumeric() || c == '.' || c == '-';
+        if self.name.is_empty() {
+            problems.push("name is empty".to_string());
+        } else if self.name.len() > 255 || !self.name.chars().all(is_hostname_char) {
+            problems.push(format!(
+                "name {:?} is not a valid hostname (letters, digits, `.` and `-`)",
+                self.name
+            ));
+        }
+        for (field, missing) in [
+            ("region", self.region.is_none()),
+            ("size", self.size.is_none()),
+            ("image", self.image.is_none()),
+        ] {
+            if missing {
+                problems.push(format!("{field} is required"));
+            }
+        }
+        let is_tag_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, ':' | '-' | '_');
+        for tag in &self.tags {
+            if tag.is_empty() || tag.len() > 255 || !tag.chars().all(is_tag_char) {
+                problems.push(format!("tag {tag:?} is invalid"));
+            }
+        }
+        if let Some(user_data) = &self.user_data {
+            if user_data.len() > MAX_USER_DATA {
+                problems.push(format!(
+                    "user data is {} bytes; the limit is {MAX_USER_DATA}",
+                    user_data.len()
+                ));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(format!(
+                "invalid droplet {:?}: {}",
+                self.name,
+                problems.join("; ")
+            )))
+        }
+    }
+
+    /// The validated `droplets_create` body.
+    ///
+    /// SSH keys given by name are left as they are; [`create`](Self::create) and
+    /// [`create_and_wait`](Self::create_and_wait) resolve them first.
+    pub fn body(&self) -> Result<Value, Error> {
+        self.validate()?;
+        Ok(serde_json::to_value(self)?)
+    }
+
+    /// The validated body with SSH key names replaced by key IDs.
+    ///
+    /// Fails with [`Error::InvalidInput`] listing every name no key of the account has.
+    async fn resolved_body(&self, client: &Client) -> Result<Value, Error> {
+        let mut body = self.body()?;
+        let names: Vec<&str> = self
+            .ssh_keys
+            .iter()
+            .map(String::as_str)
+            .filter(|key| !is_id_or_fingerprint(key))
+            .collect();
+        if names.is_empty() {
+            return Ok(body);
+        }
+        let cache = &client.inner().ssh_keys;
+        let ids = match cache.lookup(&names) {
+            Ok(ids) => ids,
+            Err(_) => {
+                let keys: Vec<SshKey> = client
+                    .paginate("sshKeys_list")
+                    .items_key("ssh_keys")
+                    .per_page(200)
+                    .stream()
+                    .try_collect()
+                    .await?;
+                cache.replace(keys);
+                cache.lookup(&names).map_err(|unknown| {
+                    Error::InvalidInput(format!(
+                        "droplet {:?} names unknown SSH keys: {}",
+                        self.name,
+                        unknown.join(", ")
+                    ))
+                })?
+            }
+        };
+        let mut keys: Vec<Value> = self
+            .ssh_keys
+            .iter()
+            .filter(|key| is_id_or_fingerprint(key))
+            .map(|key| Value::from(key.as_str()))
+            .collect();
+        keys.extend(ids.into_iter().map(Value::from));
+        body["ssh_keys"] = Value::Array(keys);
+        Ok(body)
+    }
+
+    /// Create the droplet and return it as the API first reports it, still `new`.
+    pub async fn create(&self, client: &Client) -> Result<Droplet, Error> {
+        let body = self.resolved_body(client).await?;
+        let envelope: DropletEnvelope = client
+            .send_json(ApiRequest::post("droplets_create", "/v2/droplets").json(body))
+            .await?;
+        Ok(envelope.droplet)
+    }
+
+    /// Create the droplet and wait until it is ready; see
+    /// [`Client::create_droplet_and_wait`].
+    pub async fn create_and_wait(
+        &self,
+        client: &Client,
+        options: WaitOptions,
+    ) -> Result<Droplet, Error> {
+        let body = self.resolved_body(client).await?;
+        client.create_droplet_and_wait(&body, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_body_and_validation() {
+        let body = DropletBuilder::new("web-1")
+            .region("nyc1")
+            .size("s-1vcpu-1gb")
+            .image(7555620)
+            .tag("env:prod")
+            .body()
+            .unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "name": "web-1",
+                "region": "nyc1",
+                "size": "s-1vcpu-1gb",
+                "image": 7555620,
+                "tags": ["env:prod"]
+            })
+        );
+
+        let err = DropletBuilder::new("web_1")
+            .region("nyc1")
+            .tag("bad tag")
+            .validate()
+            .unwrap_err();
+        let Error::InvalidInput(message) = err else {
+            panic!("expected invalid input");
+        };
+        assert!(message.contains("not a valid hostname"));
+        assert!(message.contains("size is required"));
+        assert!(message.contains("image is required"));
+        assert!(message.contains("tag \"bad tag\" is invalid"));
+    }
+
+    #[tokio::test]
+    async fn test_ssh_key_names_resolve_through_cache() {
+        assert!(is_id_or_fingerprint("512189"));
+        assert!(is_id_or_fingerprint(
+            "3b:16:bf:e4:8b:00:8b:b8:59:8c:a9:d3:f0:19:45:fa"
+        ));
+        assert!(!is_id_or_fingerprint("deploy"));
+
+        let client = crate::Client::from_token("test-token");
+        client.inner().ssh_keys.replace(vec![SshKey {
+            id: 512190,
+            name: "deploy".to_string(),
+        }]);
+        let builder = DropletBuilder::new("web-1")
+            .region("nyc1")
+            .size("s-1vcpu-1gb")
+            .image("ubuntu-24-04-x64")
+            .ssh_key("512189")
+            .ssh_key("deploy");
+        let body = builder.resolved_body(&client).await.unwrap();
+        assert_eq!(body["ssh_keys"], json!(["512189", 512190]));
+        assert_eq!(
+            client.inner().ssh_keys.lookup(&["deploy", "laptop"]),
+            Err(vec!["laptop".to_string()])
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Destroying a droplet together with its associated resources.
+//!
+//! Volumes, snapshots and reserved IPs outlive the droplet they belong to and keep
+//! costing money. [`Client::destroy_droplet_cascade`] lists what is associated with a
+//! droplet, destroys the droplet along with everything the [`CascadePolicy`] does not
+//! keep, and polls until DigitalOcean reports the destroy finished. Kept volumes are
+//! detached and kept reserved IPs unassigned by the destroy itself.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::droplets::CascadePolicy;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let status = client
+//!     .destroy_droplet_cascade(3164494, CascadePolicy::destroy_all().keep_volumes())
+//!     .await?;
+//! for resource in status.failed() {
+//!     eprintln!("{} was not destroyed: {:?}", resource.name, resource.error_message);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// A billable resource associated with a droplet.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AssociatedResource {
+    pub id: String,
+    pub name: String,
+    /// Monthly cost in USD, e.g. `0.05`.
+    #[serde(default)]
+    pub cost: Option<String>,
+}
+
+/// The resources associated with a droplet, by kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct AssociatedResources {
+    #[serde(default)]
+    pub reserved_ips: Vec<AssociatedResource>,
+    /// The same addresses as `reserved_ips`, under their former name.
+    #[serde(default)]
+    pub floating_ips: Vec<AssociatedResource>,
+    #[serde(default)]
+    pub snapshots: Vec<AssociatedResource>,
+    #[serde(default)]
+    pub volumes: Vec<AssociatedResource>,
+    #[serde(default)]
+    pub volume_snapshots: Vec<AssociatedResource>,
+}
+
+/// A resource in a [`CascadeStatus`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DestroyedResource {
+    pub id: String,
+    pub name: String,
+    pub destroyed_at: Option<DateTime<Utc>>,
+    /// Why the resource could not be destroyed.
+    #[serde(default)]
+    pub error_message: Option<String>,
+}
+
+/// The resources of a cascading destroy, by kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct DestroyedResources {
+    #[serde(default)]
+    pub reserved_ips: Vec<DestroyedResource>,
+    #[serde(default)]
+    pub floating_ips: Vec<DestroyedResource>,
+    #[serde(default)]
+    pub snapshots: Vec<DestroyedResource>,
+    #[serde(default)]
+    pub volumes: Vec<DestroyedResource>,
+    #[serde(default)]
+    pub volume_snapshots: Vec<DestroyedResource>,
+}
+
+/// Progress of a cascading destroy, from the destroy status endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CascadeStatus {
+    pub droplet: DestroyedResource,
+    pub resources: DestroyedResources,
+    /// When every resource was destroyed or failed; `None` while in progress.
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Number of resources that could not be destroyed.
+    #[serde(default)]
+    pub failures: u32,
+}
+
+impl CascadeStatus {
+    /// The droplet and resources that could not be destroyed.
+    pub fn failed(&self) -> impl Iterator<Item = &DestroyedResource> {
+        let resources = &self.resources;
+        std::iter::once(&self.droplet)
+            .chain(&resources.reserved_ips)
+            .chain(&resources.floating_ips)
+            .chain(&resources.snapshots)
+            .chain(&resources.volumes)
+            .chain(&resources.volume_snapshots)
+            .filter(|resource| resource.error_message.is_some())
+    }
+}
+
+/// Which associated resources [`Client::destroy_droplet_cascade`] keeps.
+#[derive(Debug, Clone, Default)]
+pub struct CascadePolicy {
+    keep_reserved_ips: bool,
+    keep_snapshots: bool,
+    keep_volumes: bool,
+    keep_volume_snapshots: bool,
+    keep: HashSet<String>,
+    options: WaitOptions,
+}
+
+impl CascadePolicy {
+    /// Destroy every associated resource with the droplet.
+    pub fn destroy_all() -> Self {
+        Self::default()
+    }
+
+    /// Destroy only the droplet, keeping every associated resource.
+    pub fn keep_all() -> Self {
+        Self::default()
+            .keep_reserved_ips()
+            .keep_snapshots()
+            .keep_volumes()
+            .keep_volume_snapshots()
+    }
+
+    /// Keep reserved IPs; they are unassigned from the droplet.
+    pub fn keep_reserved_ips(mut self) -> Self {
+        self.keep_reserved_ips = true;
+        self
+    }
+
+    /// Keep the droplet's snapshots.
+    pub fn keep_snapshots(mut self) -> Self {
+        self.keep_snapshots = true;
+        self
+    }
+
+    /// Keep volumes; they are detached from the droplet.
+    pub fn keep_volumes(mut self) -> Self {
+        self.keep_volumes = true;
+        self
+    }
+
+    /// Keep snapshots of the droplet's volumes.
+    pub fn keep_volume_snapshots(mut self) -> Self {
+        self.keep_volume_snapshots = true;
+        self
+    }
+
+    /// Keep the resource with this ID, whatever its kind.
+    pub fn keep(mut self, id: impl Into<String>) -> Self {
+        self.keep.insert(id.into());
+        self
+    }
+
+    /// How to poll the destroy status. Defaults to every 5 seconds for 10 minutes.
+    pub fn wait_options(mut self, options: WaitOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The body of a selective destroy of `associated`, or `None` when nothing is
+    /// kept and everything can go.
+    fn selection(&self, associated: &AssociatedResources) -> Option<serde_json::Value> {
+        let mut kept_any = false;
+        let mut destroy = |resources: &[AssociatedResource], keep_kind: bool| {
+            let ids: Vec<String> = resources
+                .iter()
+                .filter(|resource| !keep_kind && !self.keep.contains(&resource.id))
+                .map(|resource| resource.id.clone())
+                .collect();
+            kept_any |= ids.len() < resources.len();
+            ids
+        };
+        let body = json!({
+            "reserved_ips": destroy(&associated.reserved_ips, self.keep_reserved_ips),
+            "floating_ips": destroy(&associated.floating_ips, self.keep_reserved_ips),
+            "snapshots": destroy(&associated.snapshots, self.keep_snapshots),
+            "volumes": destroy(&associated.volumes, self.keep_volumes),
+            "volume_snapshots": destroy(&associated.volume_snapshots, self.keep_volume_snapshots),
+        });
+        kept_any.then_some(body)
+    }
+}
+
+impl Client {
+    /// List the resources destroyed with droplet `id` by
+    /// [`destroy_droplet_cascade`](Self::destroy_droplet_cascade).
+    pub async fn droplet_associated_resources(
+        &self,
+        id: u64,
+    ) -> Result<AssociatedResources, Error> {
+        self.send_json(ApiRequest::get(
+            "droplets_list_associatedResources",
+            format!("/v2/droplets/{id}/destroy_with_associated_resources"),
+        ))
+        .await
+    }
+
+    /// Destroy droplet `id` with its associated resources, except those `policy`
+    /// keeps, and wait until the destroy finishes.
+    ///
+    /// Resources that could not be destroyed do not fail the call; see
+    /// [`CascadeStatus::failed`]. Fails with [`Error::Timeout`] if the destroy is
+    /// still running after the policy's wait timeout.
+    pub async fn destroy_droplet_cascade(
+        &self,
+        id: u64,
+        policy: CascadePolicy,
+    ) -> Result<CascadeStatus, Error> {
+        let workflow = Workflow::new(
+            self.inner(),
+            "destroy_droplet_cascade",
+            format!("droplet {id}"),
+        );
+        let base = format!("/v2/droplets/{id}/destroy_with_associated_resources");
+        let associated = workflow
+            .step("list", self.droplet_associated_resources(id))
+            .await?;
+        let destroy = match policy.selection(&associated) {
+            Some(body) => ApiRequest::delete(
+                "droplets_destroy_withAssociatedResourcesSelective",
+                format!("{base}/selective"),
+            )
+            .json(body),
+            None => ApiRequest::delete(
+                "droplets_destroy_withAssociatedResourcesDangerous",
+                format!("{base}/dangerous"),
+            )
+            .header("X-Dangerous", "true"),
+        };
+        workflow.step("destroy", self.send_empty(destroy)).await?;
+
+        let status = ApiRequest::get(
+            "droplets_get_DestroyAssociatedResourcesStatus",
+            format!("{base}/status"),
+        );
+        let waiter = self
+            .waiter(
+                format!("droplet {id} and its resources to be destroyed"),
+                || self.send_json::<CascadeStatus>(status.clone()),
+            )
+            .until(|status| status.completed_at.is_some())
+            .state(|status| format!("{} failures", status.failures))
+            .typical_duration(|_| Some(Duration::from_secs(60)))
+            .options(policy.options);
+        workflow.step("wait", waiter.wait()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_keeps_by_kind_and_id() {
+        let resource = |id: &str| AssociatedResource {
+            id: id.to_string(),
+            name: format!("resource {id}"),
+            cost: None,
+        };
+        let associated = AssociatedResources {
+            snapshots: vec![resource("61486916"), resource("61486917")],
+            volumes: vec![resource("ba49449a")],
+            ..AssociatedResources::default()
+        };
+
+        assert_eq!(CascadePolicy::destroy_all().selection(&associated), None);
+        assert_eq!(
+            CascadePolicy::destroy_all()
+                .keep_volumes()
+                .keep("61486917")
+                .selection(&associated),
+            Some(json!({
+                "reserved_ips": [],
+                "floating_ips": [],
+                "snapshots": ["61486916"],
+                "volumes": [],
+                "volume_snapshots": [],
+            }))
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Finding droplets by name or address.
+//!
+//! `droplets_list` only filters on an exact name. [`Client::droplets_find_by_name`]
+//! also takes a glob such as `web-*`, and [`Client::droplet_by_public_ip`] answers
+//! "which droplet owns this IP" by scanning the droplets' networks. Both page through
+//! the full list when the API cannot filter for them.
+
+use super::Droplet;
+use crate::error::Error;
+use crate::Client;
+use futures::TryStreamExt;
+use std::net::IpAddr;
+
+/// Whether `name` matches `pattern`, where `*` stands for any run of characters and
+/// `?` for exactly one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it currently stands in for.
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl Client {
+    /// Droplets named `exact_or_glob`, which may use `*` and `?` wildcards.
+    ///
+    /// An exact name is filtered by the API; a glob lists every droplet and matches
+    /// locally.
+    pub async fn droplets_find_by_name(&self, exact_or_glob: &str) -> Result<Vec<Droplet>, Error> {
+        let listing = self
+            .paginate::<Droplet>("droplets_list")
+            .items_key("droplets")
+            .per_page(200);
+        if !exact_or_glob.contains(['*', '?']) {
+            return listing
+                .query("name", exact_or_glob)
+                .stream()
+                .try_collect()
+                .await;
+        }
+        listing
+            .stream()
+            .try_filter(|droplet| std::future::ready(glob_match(exact_or_glob, &droplet.name)))
+            .try_collect()
+            .await
+    }
+
+    /// The droplet with public address `ip`, if any.
+    ///
+    /// Only addresses listed in the droplets' networks are found, so a reserved IP
+    /// assigned to a droplet is not.
+    pub async fn droplet_by_public_ip(&self, ip: IpAddr) -> Result<Option<Droplet>, Error> {
+        let mut droplets = std::pin::pin!(self
+            .paginate::<Droplet>("droplets_list")
+            .items_key("droplets")
+            .per_page(200)
+            .stream()
+            .try_filter(|droplet| {
+                std::future::ready(droplet.networks.addresses("public").any(|a| a == ip))
+            }));
+        droplets.try_next().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("web-*", "web-1"));
+        assert!(glob_match("web-*", "web-"));
+        assert!(glob_match("*-db-?", "prod-db-2"));
+        assert!(glob_match("a*b*c", "aXXbYbZc"));
+        assert!(!glob_match("web-?", "web-12"));
+        assert!(!glob_match("web-*", "api-1"));
+        assert!(!glob_match("a*b", "aXbX"));
+    }
+
+    #[test]
+    fn test_public_addresses_from_networks() {
+        let droplet: Droplet = serde_json::from_str(
+            r#"{"id": 3164444, "name": "web-1", "status": "active",
+                "created_at": "2020-07-21T18:37:44Z",
+                "networks": {
+                    "v4": [{"ip_address": "10.128.192.124", "netmask": "255.255.0.0",
+                            "gateway": "nil", "type": "private"},
+                           {"ip_address": "192.241.165.154", "netmask": "255.255.255.0",
+                            "gateway": "192.241.165.1", "type": "public"}],
+                    "v6": [{"ip_address": "2604:a880:0:1010::18a:a001", "netmask": 64,
+                            "gateway": "2604:a880:0:1010::1", "type": "public"}]}}"#,
+        )
+        .unwrap();
+        let public: Vec<IpAddr> = droplet.networks.addresses("public").collect();
+        assert_eq!(
+            public,
+            [
+                "192.241.165.154".parse::<IpAddr>().unwrap(),
+                "2604:a880:0:1010::18a:a001".parse().unwrap(),
+            ]
+        );
+        assert_eq!(droplet.public_ipv4(), Some(public[0]));
+        assert_eq!(droplet.public_ipv6(), Some(public[1]));
+        assert_eq!(
+            droplet.private_ipv4(),
+            Some("10.128.192.124".parse().unwrap())
+        );
+    }
+}
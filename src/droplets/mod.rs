@@ -0,0 +1,340 @@
+//! Droplet helpers layered on top of the generated droplet operations.
+//!
+//! The generated `droplets_get`/`droplets_list` types mirror the full specification,
+//! including several deeply nested inline structs. The helpers in this module use the
+//! smaller [`Droplet`] model instead, which keeps the fields tooling actually needs and
+//! tolerates fields being added or removed between spec revisions.
+
+mod actions;
+mod agent;
+mod backups;
+mod batch;
+mod builder;
+mod cascade;
+mod find;
+mod power;
+
+pub use agent::{AgentCheck, DropletAgent, DropletAgentStatus};
+pub use backups::{Backup, BackupPolicy, Day, Hour};
+pub use batch::{
+    expand_name_pattern, BatchDroplet, CreateDropletsBatch, CreatedBatch, CreatedDroplet,
+    DropletBatch, DropletTemplate,
+};
+pub(crate) use builder::SshKeyCache;
+pub use builder::{DropletBuilder, Image};
+pub use cascade::{
+    AssociatedResource, AssociatedResources, CascadePolicy, CascadeStatus, DestroyedResource,
+    DestroyedResources,
+};
+pub use power::PowerAction;
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::Client;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Lifecycle status of a droplet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum DropletStatus {
+    New,
+    Active,
+    Off,
+    Archive,
+    /// A status this version of rsdo does not know about yet.
+    Unknown(String),
+}
+
+impl From<String> for DropletStatus {
+    fn from(value: String) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "new" => Self::New,
+            "active" => Self::Active,
+            "off" => Self::Off,
+            "archive" => Self::Archive,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl DropletStatus {
+    /// Whether a droplet in this status may still reach `target`. Archived droplets
+    /// stay archived, and only new droplets are `new`.
+    pub fn can_become(&self, target: &DropletStatus) -> bool {
+        match (self, target) {
+            (current, target) if current == target => true,
+            (Self::Archive, _) | (_, Self::New) => false,
+            _ => true,
+        }
+    }
+}
+
+impl From<DropletStatus> for String {
+    fn from(value: DropletStatus) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for DropletStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::New => "new",
+            Self::Active => "active",
+            Self::Off => "off",
+            Self::Archive => "archive",
+            Self::Unknown(s) => s,
+        };
+        f.write_str(s)
+    }
+}
+
+/// A droplet, as returned by the droplet endpoints.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Droplet {
+    pub id: u64,
+    pub name: String,
+    pub status: DropletStatus,
+    /// Memory in MiB.
+    #[serde(default)]
+    pub memory: u64,
+    #[serde(default)]
+    pub vcpus: u64,
+    /// Disk size in GiB.
+    #[serde(default)]
+    pub disk: u64,
+    /// Whether the droplet is locked against actions while an event is in progress.
+    #[serde(default)]
+    pub locked: bool,
+    pub created_at: DateTime<Utc>,
+    /// Enabled features, such as `backups`, `ipv6`, `monitoring` or `droplet_agent`.
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Kernel the droplet boots, for droplets still using externally managed kernels.
+    #[serde(default)]
+    pub kernel: Option<Kernel>,
+    #[serde(default)]
+    pub size_slug: String,
+    #[serde(default)]
+    pub region: Option<DropletRegion>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub volume_ids: Vec<String>,
+    #[serde(default)]
+    pub vpc_uuid: Option<String>,
+    #[serde(default)]
+    pub networks: DropletNetworks,
+}
+
+impl Droplet {
+    /// Whether `feature` is listed in [`Droplet::features`].
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+
+    /// The droplet's public IPv4 address, once assigned.
+    pub fn public_ipv4(&self) -> Option<IpAddr> {
+        first_of_kind(&self.networks.v4, "public")
+    }
+
+    /// The droplet's address in its VPC.
+    pub fn private_ipv4(&self) -> Option<IpAddr> {
+        first_of_kind(&self.networks.v4, "private")
+    }
+
+    /// The droplet's public IPv6 address, if IPv6 is enabled.
+    pub fn public_ipv6(&self) -> Option<IpAddr> {
+        first_of_kind(&self.networks.v6, "public")
+    }
+}
+
+fn first_of_kind(networks: &[DropletNetwork], kind: &str) -> Option<IpAddr> {
+    networks
+        .iter()
+        .find(|network| network.kind == kind)
+        .map(|network| network.ip_address)
+}
+
+/// Region a droplet runs in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct DropletRegion {
+    pub slug: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Addresses assigned to a droplet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct DropletNetworks {
+    #[serde(default)]
+    pub v4: Vec<DropletNetwork>,
+    #[serde(default)]
+    pub v6: Vec<DropletNetwork>,
+}
+
+/// One address of a droplet.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DropletNetwork {
+    pub ip_address: IpAddr,
+    /// `public` or `private`.
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+impl DropletNetworks {
+    /// Every IPv4 and IPv6 address of the given kind.
+    pub(crate) fn addresses<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = IpAddr> + 'a {
+        self.v4
+            .iter()
+            .chain(&self.v6)
+            .filter(move |network| network.kind == kind)
+            .map(|network| network.ip_address)
+    }
+}
+
+/// A kernel available to a droplet.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Kernel {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+}
+
+#[derive(Deserialize)]
+struct DropletEnvelope {
+    droplet: Droplet,
+}
+
+impl Client {
+    /// Delete a droplet and wait until the API no longer returns it.
+    ///
+    /// Fails with [`Error::Timeout`] if the droplet is still present after `timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Droplet ID
+    /// * `interval` - Delay between polls
+    /// * `timeout` - Upper bound on the total wait
+    pub async fn delete_droplet_and_wait_gone(
+        &self,
+        id: u64,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let path = format!("/v2/droplets/{id}");
+        self.delete_and_wait_gone(
+            ApiRequest::delete("droplets_destroy", path.clone()),
+            ApiRequest::get("droplets_get", path),
+            &format!("droplet {id}"),
+            interval,
+            timeout,
+        )
+        .await
+    }
+
+    /// Poll droplet `id` until the API no longer returns it, e.g. after deleting it
+    /// through the generated client or by tag.
+    ///
+    /// Fails with [`Error::Timeout`] if the droplet is still present after the
+    /// options' timeout.
+    pub async fn wait_until_droplet_deleted(
+        &self,
+        id: u64,
+        options: WaitOptions,
+    ) -> Result<(), Error> {
+        self.wait_until_deleted_at(
+            ApiRequest::get("droplets_get", format!("/v2/droplets/{id}")),
+            &format!("droplet {id}"),
+            options,
+        )
+        .await
+    }
+
+    /// Fetch a single droplet.
+    pub async fn droplet(&self, id: u64) -> Result<Droplet, Error> {
+        let envelope: DropletEnvelope = self
+            .send_json(ApiRequest::get(
+                "droplets_get",
+                format!("/v2/droplets/{}", id),
+            ))
+            .await?;
+        Ok(envelope.droplet)
+    }
+
+    /// Poll droplet `id` until its status is `target`, and return it.
+    ///
+    /// Fails as soon as the droplet can no longer reach `target` (see
+    /// [`DropletStatus::can_become`]), if it is deleted, or with [`Error::Timeout`]
+    /// if it has not reached `target` after the options' timeout.
+    pub async fn wait_for_droplet_status(
+        &self,
+        id: u64,
+        target: DropletStatus,
+        options: WaitOptions,
+    ) -> Result<Droplet, Error> {
+        self.waiter(format!("droplet {id} to become {target}"), || {
+            self.droplet(id)
+        })
+        .until(|droplet| droplet.status == target)
+        .fail_if(|droplet| {
+            (!droplet.status.can_become(&target)).then(|| {
+                Error::Other(format!(
+                    "droplet {id} is {} and can never become {target}",
+                    droplet.status
+                ))
+            })
+        })
+        .state(|droplet| droplet.status.to_string())
+        .typical_duration(|droplet| match (&droplet.status, &target) {
+            (DropletStatus::New, DropletStatus::Active) => Some(Duration::from_secs(60)),
+            (_, DropletStatus::Active | DropletStatus::Off) => Some(Duration::from_secs(30)),
+            _ => None,
+        })
+        .options(options)
+        .wait()
+        .await
+    }
+
+    /// List the kernels a droplet can boot.
+    pub async fn droplet_kernels(&self, id: u64) -> Result<Vec<Kernel>, Error> {
+        self.paginate("droplets_list_kernels")
+            .path_param("droplet_id", id)
+            .items_key("kernels")
+            .per_page(200)
+            .stream()
+            .try_collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_become() {
+        use DropletStatus::*;
+        assert!(New.can_become(&Active));
+        assert!(Active.can_become(&Off));
+        assert!(Off.can_become(&Archive));
+        assert!(Unknown("migrating".into()).can_become(&Active));
+
+        // Archived droplets stay archived.
+        assert!(Archive.can_become(&Archive));
+        for target in [New, Active, Off, Unknown("migrating".into())] {
+            assert!(!Archive.can_become(&target), "archive -> {target}");
+        }
+        // Nothing returns to `new`.
+        assert!(New.can_become(&New));
+        for current in [Active, Off, Unknown("migrating".into())] {
+            assert!(!current.can_become(&New), "{current} -> new");
+        }
+    }
+}
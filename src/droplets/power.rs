@@ -0,0 +1,227 @@
+//! Power actions with waits, for rescue and incident response tooling.
+//!
+//! DigitalOcean's rescue workflow is: shut the droplet down, switch its boot source
+//! to the recovery ISO, power it on, repair it, then switch back to the hard drive
+//! and restart. The boot source is only exposed in the control panel, not in the
+//! public API, so these helpers cover the power steps around it. Each one waits for
+//! its actions to finish, and a shutdown the guest ignores falls back to a hard
+//! power off.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let (interval, timeout) = (Duration::from_secs(5), Duration::from_secs(300));
+//! client.power_off_droplet_cleanly(3164494, interval, timeout).await?;
+//! // Switch the boot source to the recovery ISO in the control panel, then:
+//! let droplet = client.power_on_droplet_and_wait(3164494, interval, timeout).await?;
+//! println!("{} is {}", droplet.name, droplet.status);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{Droplet, DropletStatus};
+use crate::actions;
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A droplet action that changes its power state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerAction {
+    /// ACPI shutdown; the guest OS may ignore it.
+    Shutdown,
+    /// Hard power off, like pulling the plug.
+    PowerOff,
+    PowerOn,
+    /// ACPI reboot.
+    Reboot,
+    /// Hard power off followed by power on.
+    PowerCycle,
+}
+
+impl PowerAction {
+    /// The action's `type` in the API.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Shutdown => "shutdown",
+            Self::PowerOff => "power_off",
+            Self::PowerOn => "power_on",
+            Self::Reboot => "reboot",
+            Self::PowerCycle => "power_cycle",
+        }
+    }
+}
+
+impl fmt::Display for PowerAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Deserialize)]
+struct ActionEnvelope {
+    action: Action,
+}
+
+#[derive(Deserialize)]
+struct Action {
+    id: u64,
+    status: String,
+}
+
+impl Client {
+    /// Run `action` on droplet `id` and wait until the action completes.
+    ///
+    /// Fails if the action errors, or with [`Error::Timeout`] if it is still running
+    /// after `timeout`.
+    pub async fn droplet_power_action(
+        &self,
+        id: u64,
+        action: PowerAction,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let started = Instant::now();
+        let envelope: ActionEnvelope = self
+            .send_json(
+                ApiRequest::post("dropletActions_post", format!("/v2/droplets/{id}/actions"))
+                    .json(json!({ "type": action.as_str() })),
+            )
+            .await?;
+        let action_id = envelope.action.id;
+        let errored = |status: &String| {
+            (status == "errored").then(|| {
+                Error::Other(format!(
+                    "{action} action {action_id} of droplet {id} errored"
+                ))
+            })
+        };
+        if envelope.action.status == "completed" {
+            return Ok(());
+        }
+        if let Some(err) = errored(&envelope.action.status) {
+            return Err(err);
+        }
+        self.waiter(format!("{action} of droplet {id}"), || async move {
+            let polled: ActionEnvelope = self
+                .send_json(ApiRequest::get(
+                    "actions_get",
+                    format!("/v2/actions/{action_id}"),
+                ))
+                .await?;
+            Ok(polled.action.status)
+        })
+        .until(|status| status == "completed")
+        .fail_if(errored)
+        .state(String::clone)
+        .typical_duration(|_| actions::typical_duration(action.as_str()))
+        .options(remaining(interval, timeout, started))
+        .wait()
+        .await?;
+        Ok(())
+    }
+
+    /// Shut droplet `id` down, powering it off hard if the guest does not complete
+    /// the shutdown within `timeout`. Returns immediately if it is already off.
+    pub async fn power_off_droplet_cleanly(
+        &self,
+        id: u64,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        if self.droplet(id).await?.status == DropletStatus::Off {
+            return Ok(());
+        }
+        let workflow = Workflow::new(self.inner(), "power_off_droplet", format!("droplet {id}"));
+        let shutdown = workflow
+            .step(
+                "shutdown",
+                self.droplet_power_action(id, PowerAction::Shutdown, interval, timeout),
+            )
+            .await;
+        match shutdown {
+            Ok(()) if self.droplet(id).await?.status == DropletStatus::Off => Ok(()),
+            Ok(()) | Err(Error::Timeout { .. } | Error::Other(_)) => {
+                tracing::info!(
+                    droplet_id = id,
+                    "shutdown did not stop droplet; powering off"
+                );
+                workflow
+                    .step(
+                        "power_off",
+                        self.droplet_power_action(id, PowerAction::PowerOff, interval, timeout),
+                    )
+                    .await
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Power droplet `id` on and wait until it reports `active`.
+    pub async fn power_on_droplet_and_wait(
+        &self,
+        id: u64,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<Droplet, Error> {
+        let workflow = Workflow::new(self.inner(), "power_on_droplet", format!("droplet {id}"));
+        let started = Instant::now();
+        workflow
+            .step(
+                "power_on",
+                self.droplet_power_action(id, PowerAction::PowerOn, interval, timeout),
+            )
+            .await?;
+        workflow
+            .step(
+                "wait_active",
+                self.wait_for_droplet_status(
+                    id,
+                    DropletStatus::Active,
+                    remaining(interval, timeout, started),
+                ),
+            )
+            .await
+    }
+
+    /// Restart droplet `id` through a clean shutdown rather than a reboot, which
+    /// also applies a boot source changed in the control panel (such as leaving the
+    /// recovery ISO). Each of the two steps may take up to `timeout`.
+    pub async fn restart_droplet_cleanly(
+        &self,
+        id: u64,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<Droplet, Error> {
+        self.power_off_droplet_cleanly(id, interval, timeout)
+            .await?;
+        self.power_on_droplet_and_wait(id, interval, timeout).await
+    }
+}
+
+/// Options polling every `interval` for what is left of `timeout` since `started`.
+fn remaining(interval: Duration, timeout: Duration, started: Instant) -> WaitOptions {
+    WaitOptions::default()
+        .interval(interval)
+        .timeout(timeout.saturating_sub(started.elapsed()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_action_types() {
+        assert_eq!(PowerAction::PowerOff.as_str(), "power_off");
+        assert_eq!(PowerAction::PowerCycle.to_string(), "power_cycle");
+    }
+}
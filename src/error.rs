@@ -0,0 +1,996 @@
+//! Error type shared by the hand-written helpers layered on top of the generated client.
+//!
+//! The generated operations keep returning progenitor's `Error<E>`; everything that lives
+//! outside the generated module (workflows, waiters, convenience wrappers) reports failures
+//! through [`Error`] instead so callers only have to deal with a single, non-generic type.
+//! Every `Error<E>` of a generated operation converts into [`Error::Generated`] with `?`,
+//! so application code can return [`Result<T>`](Result) from both:
+//!
+//! ```rust,no_run
+//! async fn droplet_name(client: &rsdo::Client, id: i64) -> rsdo::Result<String> {
+//!     let droplet = client.droplets_get(id).await?.into_inner().droplet;
+//!     Ok(droplet.name)
+//! }
+//! ```
+//!
+//! Failures that happen while talking to the API carry an [`OperationContext`], whether
+//! they come from a helper or a generated operation, so the `Display` output alone is
+//! enough for a useful log line:
+//!
+//! ```text
+//! Response error: 403 Forbidden on droplets_destroy DELETE /v2/droplets/123 - {"id":"forbidden",...}
+//! ```
+
+use crate::actions::Action;
+use crate::rate_limit::RateLimitInfo;
+use crate::request_id::ResponseRequestId;
+use crate::{operations, request_id, retry, Client, ClientInfo, ClientState};
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// `Result` with [`Error`] as the default error type.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Error of a generated operation with its error body as JSON, as kept by
+/// [`Error::Generated`].
+pub type GeneratedError = progenitor_client::Error<serde_json::Value>;
+
+/// Errors returned by the rsdo helper APIs.
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The HTTP request could not be sent or the response could not be read.
+    #[error("Request error on {context}: {source}")]
+    Request {
+        context: OperationContext,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// DigitalOcean answered with a non-success status code.
+    #[error("Response error: {status} on {context} - {body}")]
+    Response {
+        context: OperationContext,
+        status: StatusCode,
+        body: String,
+    },
+
+    /// DigitalOcean answered `429 Too Many Requests`.
+    #[error("Rate limited on {context}; retry after {retry_after:?} (limit resets at {reset_at})")]
+    RateLimited {
+        context: OperationContext,
+        /// How long to wait before retrying, from `Retry-After` or else
+        /// `RateLimit-Reset`.
+        retry_after: Duration,
+        /// When the rate-limit window resets.
+        reset_at: DateTime<Utc>,
+    },
+
+    /// A response body did not match the type it was decoded into. Carries the status
+    /// and the start of the raw body.
+    #[error(transparent)]
+    InvalidResponse(Box<InvalidResponse>),
+
+    /// An item of a response did not match the expected shape.
+    #[error("Decode error on {context}: {source}")]
+    Decode {
+        context: OperationContext,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// A request body could not be serialized.
+    #[error("Serialization error: {0}")]
+    Serialize(#[from] serde_json::Error),
+
+    /// A polling helper gave up before the resource reached the expected state.
+    #[error("Timed out after {elapsed:?} waiting for {waiting_for}")]
+    Timeout {
+        waiting_for: String,
+        elapsed: Duration,
+    },
+
+    /// An action finished with status `errored`.
+    #[error("{} action {} errored", .0.kind, .0.id)]
+    ActionErrored(Box<Action>),
+
+    /// The account is locked and cannot create or modify resources.
+    #[error("Account locked: {message}")]
+    AccountLocked { message: String },
+
+    /// The account's email address has not been verified yet.
+    #[error("Account email {email} is not verified")]
+    AccountNotVerified { email: String },
+
+    /// A local file could not be read or written.
+    #[error("I/O error on {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A read-only client refused to send a mutating request.
+    #[error("Read-only client refused {context}")]
+    ReadOnlyViolation { context: OperationContext },
+
+    /// The client's cancellation token was cancelled, so the request was not sent.
+    #[error("Cancelled before {context}")]
+    Cancelled { context: OperationContext },
+
+    /// The client's deadline passed, so the request was not sent.
+    #[error("Deadline exceeded before {context}")]
+    DeadlineExceeded { context: OperationContext },
+
+    /// The client's circuit breaker is open after repeated upstream failures, so the
+    /// request was not sent.
+    #[error("Circuit open; refused {context} (next attempt in {retry_after:?})")]
+    CircuitOpen {
+        context: OperationContext,
+        /// How long until the circuit lets a probe request through.
+        retry_after: Duration,
+    },
+
+    /// The caller supplied arguments the API would reject.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// A generated operation failed. Converted from its `Error<E>` with `From`;
+    /// invalid arguments become [`Error::InvalidInput`] instead.
+    #[error("{}", describe_generated(context.as_ref(), source))]
+    Generated {
+        /// The failed call, if the API answered it.
+        context: Option<OperationContext>,
+        source: Box<GeneratedError>,
+    },
+
+    /// Any other failure that does not fit the variants above.
+    #[error("Other error: {0}")]
+    Other(String),
+}
+
+impl<E: serde::Serialize> From<progenitor_client::Error<E>> for Error {
+    fn from(err: progenitor_client::Error<E>) -> Self {
+        use progenitor_client::Error as Generated;
+        if let Generated::UnexpectedResponse(response) = &err {
+            if let Some(carried) = take_carried(response) {
+                return carried;
+            }
+        }
+        let headers = match &err {
+            Generated::ErrorResponse(response) => Some(response.headers()),
+            Generated::UnexpectedResponse(response) => Some(response.headers()),
+            _ => None,
+        };
+        let context = headers.and_then(OperationContext::from_headers);
+        if let (Some(context), Some(headers)) = (&context, headers) {
+            if err.status() == Some(StatusCode::TOO_MANY_REQUESTS) {
+                return Error::rate_limited(context.clone(), headers);
+            }
+        }
+        let err: GeneratedError = match err {
+            Generated::InvalidRequest(message) => return Error::InvalidInput(message),
+            Generated::ErrorResponse(response) => {
+                let Ok(response) = response.map::<_, _, std::convert::Infallible>(|body| {
+                    serde_json::to_value(body).unwrap_or_default()
+                });
+                Generated::ErrorResponse(response)
+            }
+            Generated::CommunicationError(source) => Generated::CommunicationError(source),
+            Generated::InvalidUpgrade(source) => Generated::InvalidUpgrade(source),
+            Generated::ResponseBodyError(source) => Generated::ResponseBodyError(source),
+            Generated::InvalidResponsePayload(body, source) => {
+                Generated::InvalidResponsePayload(body, source)
+            }
+            Generated::UnexpectedResponse(response) => Generated::UnexpectedResponse(response),
+            Generated::Custom(message) => Generated::Custom(message),
+        };
+        Error::Generated {
+            context,
+            source: Box::new(err),
+        }
+    }
+}
+
+impl Error {
+    /// HTTP status code of the failed response, if the failure came from the API.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Response { status, .. } => Some(*status),
+            Error::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            Error::Request { source, .. } => source.status(),
+            Error::Generated { source, .. } => source.status(),
+            _ => None,
+        }
+    }
+
+    /// Whether the API answered `404 Not Found`.
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND)
+    }
+
+    /// Whether the API answered `429 Too Many Requests`.
+    pub fn is_rate_limited(&self) -> bool {
+        self.status() == Some(StatusCode::TOO_MANY_REQUESTS)
+    }
+
+    /// Whether the API rejected the token (`401 Unauthorized`).
+    pub fn is_unauthorized(&self) -> bool {
+        self.status() == Some(StatusCode::UNAUTHORIZED)
+    }
+
+    /// Whether the API reported a conflict with the resource's current state
+    /// (`409 Conflict`).
+    pub fn is_conflict(&self) -> bool {
+        self.status() == Some(StatusCode::CONFLICT)
+    }
+
+    /// Whether the failure is likely temporary: a connection failure or timeout, or a
+    /// `5xx` response from a server error, gateway or overload.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Request { source, .. } => retry::is_transient(source),
+            Error::Response { status, .. } => status.is_server_error(),
+            Error::Generated { source, .. } => match &**source {
+                progenitor_client::Error::CommunicationError(source) => retry::is_transient(source),
+                err => err.status().is_some_and(|status| status.is_server_error()),
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether sending the same request again later may succeed: the failure is
+    /// [transient](Self::is_transient) or the request was
+    /// [rate limited](Self::is_rate_limited).
+    ///
+    /// This says nothing about whether the request is safe to repeat; a `POST` may
+    /// have taken effect before a transient failure was reported.
+    pub fn is_retryable(&self) -> bool {
+        self.is_transient() || self.is_rate_limited()
+    }
+
+    /// The operation that failed, for errors raised while talking to the API.
+    pub fn operation(&self) -> Option<&OperationContext> {
+        match self {
+            Error::Request { context, .. }
+            | Error::Response { context, .. }
+            | Error::RateLimited { context, .. }
+            | Error::Decode { context, .. }
+            | Error::ReadOnlyViolation { context }
+            | Error::Cancelled { context }
+            | Error::DeadlineExceeded { context }
+            | Error::CircuitOpen { context, .. } => Some(context),
+            Error::InvalidResponse(invalid) => Some(&invalid.context),
+            Error::Generated { context, .. } => context.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Link to the API reference for the operation that failed, suitable for pointing
+    /// users at the relevant documentation from an error message.
+    pub fn docs_url(&self) -> Option<&'static str> {
+        self.operation()
+            .and_then(|context| operations::find(&context.operation_id))
+            .map(|op| op.docs_url)
+    }
+
+    /// The `x-request-id` of the API's response, for errors raised after the API
+    /// answered. Falls back to the `request_id` in the error body.
+    pub fn request_id(&self) -> Option<String> {
+        let from_headers = match self {
+            Error::Generated { source, .. } => source.request_id().map(str::to_string),
+            _ => self
+                .operation()
+                .and_then(|context| context.request_id.clone()),
+        };
+        from_headers.or_else(|| self.api_error()?.request_id().map(str::to_string))
+    }
+
+    /// The API's error response, parsed, if DigitalOcean answered with an error status.
+    pub fn api_error(&self) -> Option<ApiError> {
+        match self {
+            Error::Response { status, body, .. } => Some(ApiError::from_response(*status, body)),
+            Error::RateLimited { .. } => {
+                Some(ApiError::from_response(StatusCode::TOO_MANY_REQUESTS, ""))
+            }
+            Error::Generated { source, .. } => ApiError::from_generated(source),
+            _ => None,
+        }
+    }
+}
+
+/// An error response from the DigitalOcean API, classified by its `id`.
+///
+/// DigitalOcean reports errors as `{"id": "not_found", "message": "...",
+/// "request_id": "..."}`. The variant is chosen from `id`, falling back to the status
+/// code when the body is missing or unrecognized; every variant carries the parsed
+/// [`ApiErrorDetails`].
+///
+/// ```rust,no_run
+/// # async fn run(client: rsdo::Client) {
+/// match client.droplet(42).await {
+///     Ok(droplet) => println!("{}", droplet.name),
+///     Err(err) => match err.api_error() {
+///         Some(rsdo::ApiError::NotFound(_)) => println!("no such droplet"),
+///         Some(api) => println!("{api} (request {:?})", api.request_id()),
+///         None => println!("{err}"),
+///     },
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ApiError {
+    /// `400 bad_request`.
+    BadRequest(ApiErrorDetails),
+    /// `401 unauthorized`: the token is missing, invalid or revoked.
+    Unauthorized(ApiErrorDetails),
+    /// `403 forbidden`: the token lacks the required scope.
+    Forbidden(ApiErrorDetails),
+    /// `404 not_found`.
+    NotFound(ApiErrorDetails),
+    /// `409 conflict`.
+    Conflict(ApiErrorDetails),
+    /// `422 unprocessable_entity`: the request was understood but rejected.
+    UnprocessableEntity(ApiErrorDetails),
+    /// `429 too_many_requests`: the rate limit was exceeded.
+    RateLimit(ApiErrorDetails),
+    /// `5xx`: a failure on DigitalOcean's side.
+    ServerError(ApiErrorDetails),
+    /// Any other error response.
+    Other(ApiErrorDetails),
+}
+
+/// The fields of a DigitalOcean error response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiErrorDetails {
+    pub status: StatusCode,
+    /// Machine-readable error ID, e.g. `not_found`.
+    pub id: Option<String>,
+    /// Human-readable description.
+    pub message: Option<String>,
+    /// ID of the request, for DigitalOcean support.
+    pub request_id: Option<String>,
+    /// The response body as received.
+    pub body: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ErrorBody {
+    id: Option<String>,
+    message: Option<String>,
+    request_id: Option<String>,
+}
+
+impl ApiError {
+    /// Classify an error response from its status and body.
+    pub fn from_response(status: StatusCode, body: impl Into<String>) -> Self {
+        let body = body.into();
+        let parsed: Option<ErrorBody> = serde_json::from_str(&body).ok();
+        let (id, message, request_id) = parsed
+            .map(|b| (b.id, b.message, b.request_id))
+            .unwrap_or_default();
+        let details = ApiErrorDetails {
+            status,
+            id,
+            message,
+            request_id,
+            body,
+        };
+
+        // Known IDs win over the status code, which proxies sometimes rewrite.
+        let code = match details.id.as_deref() {
+            Some("bad_request") => 400,
+            Some("unauthorized") => 401,
+            Some("forbidden") => 403,
+            Some("not_found") => 404,
+            Some("conflict") => 409,
+            Some("unprocessable_entity") => 422,
+            Some("too_many_requests") => 429,
+            Some("server_error" | "internal_server_error" | "service_unavailable") => 500,
+            _ => status.as_u16(),
+        };
+        match code {
+            400 => Self::BadRequest(details),
+            401 => Self::Unauthorized(details),
+            403 => Self::Forbidden(details),
+            404 => Self::NotFound(details),
+            409 => Self::Conflict(details),
+            422 => Self::UnprocessableEntity(details),
+            429 => Self::RateLimit(details),
+            500..=599 => Self::ServerError(details),
+            _ => Self::Other(details),
+        }
+    }
+
+    /// Classify the error response of a generated operation.
+    ///
+    /// Returns `None` for failures that are not error responses. The body is
+    /// re-serialized from the decoded error, so [`body`](Self::body) may differ from
+    /// the bytes on the wire in formatting.
+    pub fn from_generated<E: serde::Serialize>(err: &progenitor_client::Error<E>) -> Option<Self> {
+        match err {
+            progenitor_client::Error::ErrorResponse(response) => {
+                let body = serde_json::to_string(&**response).unwrap_or_default();
+                Some(Self::from_response(response.status(), body))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn details(&self) -> &ApiErrorDetails {
+        match self {
+            Self::BadRequest(details)
+            | Self::Unauthorized(details)
+            | Self::Forbidden(details)
+            | Self::NotFound(details)
+            | Self::Conflict(details)
+            | Self::UnprocessableEntity(details)
+            | Self::RateLimit(details)
+            | Self::ServerError(details)
+            | Self::Other(details) => details,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.details().status
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.details().id.as_deref()
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.details().message.as_deref()
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.details().request_id.as_deref()
+    }
+
+    /// The raw response body.
+    pub fn body(&self) -> &str {
+        &self.details().body
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let details = self.details();
+        write!(f, "{}", details.status)?;
+        if let Some(id) = &details.id {
+            write!(f, " {id}")?;
+        }
+        if let Some(message) = &details.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Most bytes of a response body kept in [`InvalidResponse::body_snippet`].
+pub const BODY_SNIPPET_LEN: usize = 512;
+
+/// A response whose body could not be decoded, for reporting spec or codegen
+/// mismatches.
+#[derive(thiserror::Error, Debug)]
+#[error("Could not decode {status} response to {context}: {source}; body: {body_snippet}")]
+pub struct InvalidResponse {
+    pub context: OperationContext,
+    pub status: StatusCode,
+    /// The start of the raw body, at most [`BODY_SNIPPET_LEN`] bytes, with `...`
+    /// appended when truncated.
+    pub body_snippet: String,
+    #[source]
+    pub source: serde_json::Error,
+}
+
+impl InvalidResponse {
+    pub(crate) fn error(
+        context: OperationContext,
+        status: StatusCode,
+        body: &[u8],
+        source: serde_json::Error,
+    ) -> Error {
+        Error::InvalidResponse(Box::new(Self {
+            context,
+            status,
+            body_snippet: snippet(body),
+            source,
+        }))
+    }
+}
+
+/// The start of `body` as text, cut at a character boundary.
+fn snippet(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+    if text.len() <= BODY_SNIPPET_LEN {
+        return text.into_owned();
+    }
+    let mut end = BODY_SNIPPET_LEN;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &text[..end])
+}
+
+/// Wait after `429 Too Many Requests` when the response says nothing about when to retry.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+impl Error {
+    /// Build [`Error::RateLimited`] from a `429` response's headers.
+    pub(crate) fn rate_limited(context: OperationContext, headers: &HeaderMap) -> Self {
+        let now = Utc::now();
+        let retry_after = headers
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, now));
+        let reset = RateLimitInfo::from_headers(headers).map(|info| info.reset);
+        let (retry_after, reset_at) = match (retry_after, reset) {
+            (Some(retry_after), reset) => (
+                retry_after,
+                reset.unwrap_or_else(|| now + TimeDelta::from_std(retry_after).unwrap_or_default()),
+            ),
+            (None, Some(reset)) => ((reset - now).to_std().unwrap_or_default(), reset),
+            (None, None) => (
+                DEFAULT_RETRY_AFTER,
+                now + TimeDelta::from_std(DEFAULT_RETRY_AFTER).unwrap_or_default(),
+            ),
+        };
+        Error::RateLimited {
+            context,
+            retry_after,
+            reset_at,
+        }
+    }
+}
+
+/// Parse a `Retry-After` value, either delay seconds or an HTTP date.
+pub(crate) fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Identifies the API call an [`Error`] belongs to.
+///
+/// Displays as `<operation_id> <METHOD> <path>`, e.g.
+/// `droplets_destroy DELETE /v2/droplets/123`, followed by
+/// `(request id <id>)` once the API has answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationContext {
+    /// Operation ID, e.g. `droplets_destroy`.
+    pub operation_id: String,
+    /// HTTP method of the request.
+    pub method: Method,
+    /// Request path without the query string. Resource IDs are replaced by
+    /// placeholders when the client was configured to redact them.
+    pub path: String,
+    /// The `x-request-id` of the API's response, to quote in support tickets. `None`
+    /// if no response was received.
+    pub request_id: Option<String>,
+}
+
+impl OperationContext {
+    pub(crate) fn new(operation_id: &str, method: Method, path: &str, redact: bool) -> Self {
+        let context = Self {
+            operation_id: operation_id.to_string(),
+            method,
+            path: path.to_string(),
+            request_id: None,
+        };
+        if redact {
+            context.redacted()
+        } else {
+            context
+        }
+    }
+
+    /// A copy of this context with resource IDs in the path replaced by placeholders.
+    ///
+    /// Uses the operation's path template when it is known
+    /// (`/v2/droplets/{droplet_id}`); otherwise numeric and UUID-like segments are
+    /// replaced with `{id}`.
+    pub fn redacted(&self) -> Self {
+        Self {
+            operation_id: self.operation_id.clone(),
+            method: self.method.clone(),
+            path: redact_path(&self.operation_id, &self.path),
+            request_id: self.request_id.clone(),
+        }
+    }
+
+    /// Record the request ID of the response with `headers`.
+    pub(crate) fn with_response(mut self, headers: &HeaderMap) -> Self {
+        self.request_id = request_id::from_headers(headers).map(str::to_string);
+        self
+    }
+
+    /// Record this context on a response of the call, so errors converted from the
+    /// generated client's can name it. See [`OPERATION_HEADER`].
+    pub(crate) fn tag(&self, headers: &mut HeaderMap) {
+        let value = format!("{} {} {}", self.operation_id, self.method, self.path);
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(OPERATION_HEADER, value);
+        }
+    }
+
+    /// The context [`tag`](Self::tag) recorded on a response with `headers`.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let value = headers.get(OPERATION_HEADER)?.to_str().ok()?;
+        let mut parts = value.splitn(3, ' ');
+        let (operation_id, method, path) = (parts.next()?, parts.next()?, parts.next()?);
+        let method = Method::from_bytes(method.as_bytes()).ok()?;
+        Some(Self::new(operation_id, method, path, false).with_response(headers))
+    }
+}
+
+/// An [`Error`] the transport raised for a generated operation, riding on a stand-in
+/// response.
+///
+/// The generated client's hooks can only fail with a `reqwest::Error`, and its decoding
+/// errors keep nothing but the body, so any other error reaches the generated code as
+/// a response with this extension, which it returns as `UnexpectedResponse`.
+/// Converting that into [`Error`] yields the carried error itself.
+#[derive(Clone)]
+pub(crate) struct Carried(Arc<Mutex<Option<Error>>>);
+
+/// Wrap `err` in a response with `status` and `headers` for the generated code to pass
+/// back as `UnexpectedResponse`.
+pub(crate) fn carry(err: Error, status: StatusCode, headers: HeaderMap) -> reqwest::Response {
+    let mut response = http::Response::new(Vec::<u8>::new());
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    response
+        .extensions_mut()
+        .insert(Carried(Arc::new(Mutex::new(Some(err)))));
+    response.into()
+}
+
+/// The error [`carry`] wrapped in `response`, if any.
+fn take_carried(response: &reqwest::Response) -> Option<Error> {
+    let Carried(slot) = response.extensions().get::<Carried>()?;
+    slot.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+/// `Display` of [`Error::Generated`]: error responses read like [`Error::Response`],
+/// and the request ID is included whenever the response had one.
+fn describe_generated(context: Option<&OperationContext>, err: &GeneratedError) -> String {
+    use progenitor_client::Error as Generated;
+    match (context, err) {
+        (Some(context), Generated::ErrorResponse(response)) => {
+            let body = serde_json::to_string(&**response).unwrap_or_default();
+            format!(
+                "Response error: {} on {context} - {body}",
+                response.status()
+            )
+        }
+        (Some(context), Generated::UnexpectedResponse(response)) => {
+            format!("Unexpected response: {} on {context}", response.status())
+        }
+        (Some(context), err) => format!("{err} on {context}"),
+        (None, err) => match err.request_id() {
+            Some(request_id) => format!("{err} (request id {request_id})"),
+            None => err.to_string(),
+        },
+    }
+}
+
+/// Response header in which the transport records the call a response belongs to, as
+/// `<operation_id> <METHOD> <path>`.
+///
+/// The generated client's errors keep the response headers but not the request, so
+/// this is how [`Error::Generated`] learns its [`OperationContext`].
+pub const OPERATION_HEADER: &str = "x-rsdo-operation";
+
+impl fmt::Display for OperationContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.operation_id, self.method, self.path)?;
+        if let Some(request_id) = &self.request_id {
+            write!(f, " (request id {request_id})")?;
+        }
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Return a copy of this client whose errors report paths with resource IDs
+    /// replaced by placeholders (`/v2/droplets/{droplet_id}`), for logs that must not
+    /// contain identifiers.
+    pub fn with_redacted_error_paths(&self, redact: bool) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.redact_error_paths = redact;
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+}
+
+fn redact_path(operation_id: &str, path: &str) -> String {
+    if let Some(op) = operations::find(operation_id) {
+        if op.path.split('/').count() == path.split('/').count() {
+            return op.path.to_string();
+        }
+    }
+
+    path.split('/')
+        .map(|segment| {
+            if looks_like_id(segment) {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn looks_like_id(segment: &str) -> bool {
+    if segment.is_empty() {
+        return false;
+    }
+    let all_digits = segment.bytes().all(|b| b.is_ascii_digit());
+    let uuid_like = segment.len() == 36
+        && segment.bytes().filter(|&b| b == b'-').count() == 4
+        && segment.bytes().all(|b| b.is_ascii_hexdigit() || b == b'-');
+    all_digits || uuid_like
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_display() {
+        let context = OperationContext::new(
+            "droplets_destroy",
+            Method::DELETE,
+            "/v2/droplets/123",
+            false,
+        );
+        assert_eq!(
+            context.to_string(),
+            "droplets_destroy DELETE /v2/droplets/123"
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "7e1b8a52".parse().unwrap());
+        let err = Error::Response {
+            context: context.with_response(&headers),
+            status: StatusCode::NOT_FOUND,
+            body: String::new(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Response error: 404 Not Found on droplets_destroy DELETE /v2/droplets/123 \
+             (request id 7e1b8a52) - "
+        );
+        assert_eq!(err.request_id().as_deref(), Some("7e1b8a52"));
+    }
+
+    #[test]
+    fn test_redaction_without_registry_entry() {
+        let context = OperationContext::new(
+            "not_a_real_operation",
+            Method::GET,
+            "/v2/kubernetes/clusters/bd5f5959-5e1e-4205-a714-a914373942af/node_pools/42",
+            true,
+        );
+        assert_eq!(context.path, "/v2/kubernetes/clusters/{id}/node_pools/{id}");
+    }
+
+    #[test]
+    fn test_response_error_mentions_operation() {
+        let err = Error::Response {
+            context: OperationContext::new(
+                "droplets_destroy",
+                Method::DELETE,
+                "/v2/droplets/123",
+                false,
+            ),
+            status: StatusCode::FORBIDDEN,
+            body: String::new(),
+        };
+        assert!(err
+            .to_string()
+            .contains("403 Forbidden on droplets_destroy DELETE /v2/droplets/123"));
+    }
+
+    #[test]
+    fn test_api_error_classification() {
+        let err = ApiError::from_response(
+            StatusCode::NOT_FOUND,
+            r#"{"id":"not_found","message":"The resource you were accessing could not be found.","request_id":"b1f3a0c2"}"#,
+        );
+        assert!(matches!(err, ApiError::NotFound(_)));
+        assert_eq!(err.request_id(), Some("b1f3a0c2"));
+        assert_eq!(
+            err.to_string(),
+            "404 Not Found not_found: The resource you were accessing could not be found."
+        );
+
+        let rate_limited = ApiError::from_response(StatusCode::TOO_MANY_REQUESTS, "slow down");
+        assert!(matches!(rate_limited, ApiError::RateLimit(_)));
+        assert_eq!(rate_limited.body(), "slow down");
+        assert_eq!(rate_limited.id(), None);
+
+        let gateway = ApiError::from_response(StatusCode::BAD_GATEWAY, "<html>");
+        assert!(matches!(gateway, ApiError::ServerError(_)));
+        assert!(matches!(
+            ApiError::from_response(StatusCode::IM_A_TEAPOT, "{}"),
+            ApiError::Other(_)
+        ));
+    }
+
+    #[test]
+    fn test_classification() {
+        let response = |status| Error::Response {
+            context: OperationContext::new("droplets_get", Method::GET, "/v2/droplets/1", false),
+            status,
+            body: String::new(),
+        };
+        assert!(response(StatusCode::NOT_FOUND).is_not_found());
+        assert!(!response(StatusCode::NOT_FOUND).is_retryable());
+        assert!(response(StatusCode::TOO_MANY_REQUESTS).is_rate_limited());
+        assert!(response(StatusCode::TOO_MANY_REQUESTS).is_retryable());
+        assert!(!response(StatusCode::TOO_MANY_REQUESTS).is_transient());
+        assert!(response(StatusCode::SERVICE_UNAVAILABLE).is_transient());
+        assert!(!Error::InvalidInput("bad".into()).is_retryable());
+    }
+
+    #[test]
+    fn test_invalid_response_keeps_body_snippet() {
+        let context = OperationContext::new("droplets_get", Method::GET, "/v2/droplets/1", false);
+        let body = format!(
+            r#"{{"droplet": {{"id": "oops", "name": "{}"}}}}"#,
+            "é".repeat(400)
+        );
+        let source = serde_json::from_str::<u64>("\"oops\"").unwrap_err();
+        let err = InvalidResponse::error(context, StatusCode::OK, body.as_bytes(), source);
+
+        let Error::InvalidResponse(invalid) = &err else {
+            panic!("expected InvalidResponse, got {err:?}");
+        };
+        assert!(invalid
+            .body_snippet
+            .starts_with(r#"{"droplet": {"id": "oops""#));
+        assert!(invalid.body_snippet.ends_with("..."));
+        assert!(invalid.body_snippet.len() <= BODY_SNIPPET_LEN + 3);
+        assert_eq!(err.operation().unwrap().operation_id, "droplets_get");
+        assert!(err
+            .to_string()
+            .starts_with("Could not decode 200 OK response to droplets_get GET /v2/droplets/1: "));
+    }
+
+    #[test]
+    fn test_from_generated_error() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "7e1b8a52".parse().unwrap());
+        let body = serde_json::json!({"id": "not_found", "message": "gone"});
+        let response = progenitor_client::ResponseValue::new(body, StatusCode::NOT_FOUND, headers);
+        let err = Error::from(progenitor_client::Error::ErrorResponse(response));
+        assert!(err.is_not_found());
+        assert_eq!(err.request_id().as_deref(), Some("7e1b8a52"));
+        assert!(matches!(err.api_error(), Some(ApiError::NotFound(_))));
+        assert!(err.to_string().ends_with(" (request id 7e1b8a52)"));
+
+        let invalid: Error = progenitor_client::Error::<()>::InvalidRequest("bad".into()).into();
+        assert!(matches!(invalid, Error::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_generated_error_names_operation() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "7e1b8a52".parse().unwrap());
+        OperationContext::new(
+            "droplets_destroy",
+            Method::DELETE,
+            "/v2/droplets/123",
+            false,
+        )
+        .tag(&mut headers);
+        let body = serde_json::json!({"id": "forbidden", "message": "denied"});
+        let response = progenitor_client::ResponseValue::new(body, StatusCode::FORBIDDEN, headers);
+        let err = Error::from(progenitor_client::Error::ErrorResponse(response));
+
+        let context = err.operation().unwrap();
+        assert_eq!(
+            context.to_string(),
+            "droplets_destroy DELETE /v2/droplets/123 (request id 7e1b8a52)"
+        );
+        assert_eq!(
+            err.to_string(),
+            "Response error: 403 Forbidden on droplets_destroy DELETE /v2/droplets/123 \
+             (request id 7e1b8a52) - {\"id\":\"forbidden\",\"message\":\"denied\"}"
+        );
+        assert_eq!(err.status(), Some(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn test_generated_429_is_rate_limited() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "30".parse().unwrap());
+        OperationContext::new("droplets_list", Method::GET, "/v2/droplets", false)
+            .tag(&mut headers);
+        let body = serde_json::json!({"id": "too_many_requests", "message": "slow down"});
+        let response =
+            progenitor_client::ResponseValue::new(body, StatusCode::TOO_MANY_REQUESTS, headers);
+        let err = Error::from(progenitor_client::Error::ErrorResponse(response));
+        assert!(matches!(
+            err,
+            Error::RateLimited { retry_after, .. } if retry_after == Duration::from_secs(30)
+        ));
+        assert_eq!(err.operation().unwrap().operation_id, "droplets_list");
+    }
+
+    #[test]
+    fn test_rate_limited_from_headers() {
+        let context =
+            || OperationContext::new("droplets_get", Method::GET, "/v2/droplets/1", false);
+        let reset = Utc::now() + TimeDelta::seconds(30);
+        let mut headers = HeaderMap::new();
+        headers.insert("ratelimit-limit", "250".parse().unwrap());
+        headers.insert("ratelimit-remaining", "0".parse().unwrap());
+        headers.insert(
+            "ratelimit-reset",
+            reset.timestamp().to_string().parse().unwrap(),
+        );
+
+        let err = Error::rate_limited(context(), &headers);
+        assert!(err.is_rate_limited());
+        assert!(err.is_retryable());
+        let Error::RateLimited {
+            retry_after,
+            reset_at,
+            ..
+        } = err
+        else {
+            panic!("expected RateLimited, got {err:?}");
+        };
+        assert_eq!(reset_at.timestamp(), reset.timestamp());
+        assert!(retry_after <= Duration::from_secs(30));
+
+        headers.insert(RETRY_AFTER, "12".parse().unwrap());
+        let err = Error::rate_limited(context(), &headers);
+        assert!(matches!(
+            err,
+            Error::RateLimited { retry_after, .. } if retry_after == Duration::from_secs(12)
+        ));
+    }
+
+    #[test]
+    fn test_docs_url_requires_known_operation() {
+        let err = Error::Response {
+            context: OperationContext::new("not_a_real_operation", Method::GET, "/v2/x", false),
+            status: StatusCode::NOT_FOUND,
+            body: String::new(),
+        };
+        assert_eq!(err.docs_url(), None);
+        assert_eq!(Error::InvalidInput("bad".into()).docs_url(), None);
+    }
+
+    #[test]
+    fn test_generated_error_links_docs() {
+        let mut headers = HeaderMap::new();
+        OperationContext::new("droplets_list", Method::GET, "/v2/droplets", false)
+            .tag(&mut headers);
+        let body = serde_json::json!({"id": "server_error", "message": "oops"});
+        let response =
+            progenitor_client::ResponseValue::new(body, StatusCode::INTERNAL_SERVER_ERROR, headers);
+        let err = Error::from(progenitor_client::Error::ErrorResponse(response));
+        let docs_url = err.docs_url().unwrap();
+        assert!(docs_url.ends_with("/operation/droplets_list"), "{docs_url}");
+    }
+}
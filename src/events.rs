@@ -0,0 +1,170 @@
+//! Progress events from multi-step workflow helpers.
+//!
+//! Helpers that issue several requests or poll for a while (deleting and waiting for a
+//! resource to disappear, saving a kubeconfig, waiting for provisioning) report each
+//! step as a [`WorkflowEvent`]: one when it starts, and one when it completes or fails.
+//! Register a handler with [`Client::with_event_handler`] to drive progress output or
+//! structured logs, or forward events to a channel with
+//! [`Client::with_event_channel`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::events::Step;
+//! use std::time::Duration;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let client = client.with_event_handler(|event| match &event.step {
+//!     Step::Started => println!("{} {}: {}...", event.workflow, event.resource, event.name),
+//!     Step::Completed { elapsed } => println!("{}: done in {elapsed:?}", event.name),
+//!     Step::Failed { error, .. } => println!("{}: failed: {error}", event.name),
+//! });
+//! client
+//!     .delete_droplet_and_wait_gone(3164494, Duration::from_secs(5), Duration::from_secs(300))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::{Client, ClientInfo, ClientState};
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Progress of one step of a workflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Step {
+    Started,
+    Completed { elapsed: Duration },
+    Failed { elapsed: Duration, error: String },
+}
+
+/// A step of a workflow helper started, completed or failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkflowEvent {
+    /// The helper running, e.g. `delete_and_wait_gone`.
+    pub workflow: &'static str,
+    /// The resource the workflow acts on, e.g. `droplet 3164494`.
+    pub resource: String,
+    /// The step within the workflow, e.g. `delete` or `wait_gone`.
+    pub name: &'static str,
+    pub step: Step,
+}
+
+type Callback = dyn Fn(&WorkflowEvent) + Send + Sync;
+
+/// A registered event handler.
+#[derive(Clone)]
+pub(crate) struct EventHandler(pub(crate) Arc<Callback>);
+
+impl fmt::Debug for EventHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventHandler(..)")
+    }
+}
+
+/// Reports the steps of one workflow run to the client's event handler, if any.
+pub(crate) struct Workflow<'a> {
+    handler: Option<&'a EventHandler>,
+    workflow: &'static str,
+    resource: String,
+}
+
+impl<'a> Workflow<'a> {
+    pub(crate) fn new(state: &'a ClientState, workflow: &'static str, resource: String) -> Self {
+        Self {
+            handler: state.events.as_ref(),
+            workflow,
+            resource,
+        }
+    }
+
+    /// Run `step`, reporting when it starts and how it ends.
+    pub(crate) async fn step<T>(
+        &self,
+        name: &'static str,
+        step: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        let Some(handler) = self.handler else {
+            return step.await;
+        };
+        let emit = |step| {
+            (handler.0)(&WorkflowEvent {
+                workflow: self.workflow,
+                resource: self.resource.clone(),
+                name,
+                step,
+            })
+        };
+
+        emit(Step::Started);
+        let started = Instant::now();
+        let result = step.await;
+        let elapsed = started.elapsed();
+        match &result {
+            Ok(_) => emit(Step::Completed { elapsed }),
+            Err(err) => emit(Step::Failed {
+                elapsed,
+                error: err.to_string(),
+            }),
+        }
+        result
+    }
+}
+
+impl Client {
+    /// Return a copy of this client that calls `handler` with the progress events of
+    /// workflow helpers, replacing any previous handler.
+    ///
+    /// The handler runs inline, so it should return quickly.
+    pub fn with_event_handler(
+        &self,
+        handler: impl Fn(&WorkflowEvent) + Send + Sync + 'static,
+    ) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.events = Some(EventHandler(Arc::new(handler)));
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+
+    /// Return a copy of this client that sends the progress events of workflow helpers
+    /// to `sender`. Events are dropped once the receiver is gone.
+    pub fn with_event_channel(
+        &self,
+        sender: tokio::sync::mpsc::UnboundedSender<WorkflowEvent>,
+    ) -> Self {
+        self.with_event_handler(move |event| {
+            let _ = sender.send(event.clone());
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_steps_are_reported() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let client = Client::from_token("test-token").with_event_channel(sender);
+        let workflow = Workflow::new(client.inner(), "test", "droplet 1".to_string());
+
+        workflow.step("first", async { Ok(()) }).await.unwrap();
+        let failed: Result<(), Error> = workflow
+            .step("second", async { Err(Error::Other("boom".to_string())) })
+            .await;
+        assert!(failed.is_err());
+
+        let steps: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|event| (event.name, event.step))
+            .collect();
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[0], ("first", Step::Started));
+        assert!(matches!(steps[1], ("first", Step::Completed { .. })));
+        assert!(
+            matches!(&steps[3], ("second", Step::Failed { error, .. }) if error == "Other error: boom")
+        );
+    }
+}
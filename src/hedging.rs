@@ -0,0 +1,320 @@
+//! Hedged reads for latency-sensitive callers.
+//!
+//! Most API calls answer quickly, but a few take many times longer. Interactive
+//! dashboards feel that tail. With a [`HedgePolicy`], a `GET` or `HEAD` that has not
+//! answered by the given percentile of recent latencies is sent a second time, and
+//! whichever copy answers first with anything but a `5xx` or `429` is used; the other
+//! is dropped.
+//!
+//! Each hedge is one more request against the token's rate limit, and waits for the
+//! client-side [`rate_limit`](crate::ClientBuilder::rate_limit) like any other, so keep
+//! the percentile high (95th or above) to hedge only the slowest few percent.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::hedging::HedgePolicy;
+//! use rsdo::ClientBuilder;
+//!
+//! # fn run() -> Result<(), rsdo::error::Error> {
+//! let client = ClientBuilder::new("your-digitalocean-token")
+//!     .hedge_reads(HedgePolicy::new(0.95))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::rate_limit::RateLimiter;
+use futures::future::{self, Either};
+use reqwest::{Method, StatusCode};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// When to send a second copy of a slow read.
+#[derive(Debug, Clone)]
+pub struct HedgePolicy {
+    percentile: f64,
+    initial_delay: Duration,
+    min_delay: Duration,
+    window: usize,
+    latencies: Arc<Mutex<VecDeque<Duration>>>,
+    hedged: Arc<AtomicU64>,
+}
+
+impl HedgePolicy {
+    /// Hedge reads slower than `percentile` (between 0 and 1, e.g. `0.95`) of the
+    /// last 200 successful reads.
+    pub fn new(percentile: f64) -> Self {
+        Self {
+            percentile: percentile.clamp(0.0, 1.0),
+            initial_delay: Duration::from_secs(1),
+            min_delay: Duration::from_millis(50),
+            window: 200,
+            latencies: Arc::default(),
+            hedged: Arc::default(),
+        }
+    }
+
+    /// Delay before hedging until enough latencies have been seen. Defaults to one
+    /// second.
+    pub fn initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Shortest delay before hedging, however fast recent reads were. Defaults to
+    /// 50ms.
+    pub fn min_delay(mut self, delay: Duration) -> Self {
+        self.min_delay = delay;
+        self
+    }
+
+    /// How many recent latencies the percentile is taken over. Defaults to 200.
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// How many reads have been hedged so far.
+    pub fn hedged(&self) -> u64 {
+        self.hedged.load(Ordering::Relaxed)
+    }
+
+    /// How long to wait for a read before sending it again.
+    fn delay(&self) -> Duration {
+        let latencies = self.latencies.lock().unwrap_or_else(|e| e.into_inner());
+        if latencies.len() < self.window.min(20) {
+            return self.initial_delay;
+        }
+        let mut sorted: Vec<Duration> = latencies.iter().copied().collect();
+        sorted.sort_unstable();
+        let index = ((sorted.len() - 1) as f64 * self.percentile).round() as usize;
+        sorted[index].max(self.min_delay)
+    }
+
+    fn record(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock().unwrap_or_else(|e| e.into_inner());
+        if latencies.len() >= self.window {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// Send `request`, hedging it if it is a read that takes longer than usual. The
+    /// hedge takes a token from `limiter` before it is sent.
+    pub(crate) async fn execute(
+        &self,
+        http: &reqwest::Client,
+        request: reqwest::Request,
+        limiter: Option<&RateLimiter>,
+    ) -> reqwest::Result<reqwest::Response> {
+        let hedge = match *request.method() {
+            Method::GET | Method::HEAD => request.try_clone(),
+            _ => None,
+        };
+        let Some(hedge) = hedge else {
+            return http.execute(request).await;
+        };
+
+        let started = Instant::now();
+        let first = pin!(http.execute(request));
+        let result = match future::select(first, pin!(tokio::time::sleep(self.delay()))).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), first)) => {
+                self.hedged.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!(elapsed = ?started.elapsed(), "hedging slow read");
+                let second = async {
+                    if let Some(limiter) = limiter {
+                        limiter.acquire().await;
+                    }
+                    http.execute(hedge).await
+                };
+                match future::select(first, pin!(second)).await {
+                    Either::Left((result, other)) => settle(result, other).await,
+                    Either::Right((result, other)) => settle(result, other).await,
+                }
+            }
+        };
+        if result.is_ok() {
+            self.record(started.elapsed());
+        }
+        result
+    }
+}
+
+/// Whether `result` ends a hedged read: a response that is not a server error or
+/// `429 Too Many Requests`, which the other copy may still improve on.
+fn is_final(result: &reqwest::Result<reqwest::Response>) -> bool {
+    result.as_ref().is_ok_and(|response| {
+        !response.status().is_server_error() && response.status() != StatusCode::TOO_MANY_REQUESTS
+    })
+}
+
+/// The outcome of a hedged read whose first copy to finish gave `result`: that, if
+/// [final](is_final), otherwise whatever `other` gives, unless that is no better.
+async fn settle(
+    result: reqwest::Result<reqwest::Response>,
+    other: impl Future<Output = reqwest::Result<reqwest::Response>>,
+) -> reqwest::Result<reqwest::Response> {
+    if is_final(&result) {
+        return result;
+    }
+    let other = other.await;
+    if is_final(&other) || result.is_err() {
+        other
+    } else {
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answers the `n`th connection after `replies[n].0` with status `replies[n].1` and
+    /// body `n`, counting connections in `accepted`.
+    async fn serve(
+        listener: TcpListener,
+        replies: Vec<(Duration, StatusCode)>,
+        accepted: Arc<AtomicU64>,
+    ) {
+        for (n, (delay, status)) in replies.into_iter().enumerate() {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            accepted.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                while !request.ends_with(b"\r\n\r\n") {
+                    let mut buf = [0; 1024];
+                    let Ok(read @ 1..) = stream.read(&mut buf).await else {
+                        return;
+                    };
+                    request.extend_from_slice(&buf[..read]);
+                }
+                tokio::time::sleep(delay).await;
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-length: 1\r\nconnection: close\r\n\r\n{n}"
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    }
+
+    async fn send(policy: &HedgePolicy, method: Method, delays: &[u64]) -> (String, u64) {
+        let replies: Vec<_> = delays.iter().map(|&ms| (ms, StatusCode::OK)).collect();
+        let (status, body, accepted) = send_replies(policy, None, method, &replies).await;
+        assert_eq!(status, StatusCode::OK);
+        (body, accepted)
+    }
+
+    async fn send_replies(
+        policy: &HedgePolicy,
+        limiter: Option<&RateLimiter>,
+        method: Method,
+        replies: &[(u64, StatusCode)],
+    ) -> (StatusCode, String, u64) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v2/droplets", listener.local_addr().unwrap());
+        let accepted = Arc::new(AtomicU64::new(0));
+        let replies = replies
+            .iter()
+            .map(|&(ms, status)| (Duration::from_millis(ms), status))
+            .collect();
+        tokio::spawn(serve(listener, replies, accepted.clone()));
+
+        let http = reqwest::Client::new();
+        let request = http.request(method, url).build().unwrap();
+        let response = policy.execute(&http, request, limiter).await.unwrap();
+        let status = response.status();
+        let body = response.text().await.unwrap();
+        (status, body, accepted.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_slow_read_is_hedged() {
+        let policy = HedgePolicy::new(0.95).initial_delay(Duration::from_millis(50));
+
+        let started = Instant::now();
+        let (body, accepted) = send(&policy, Method::GET, &[2000, 0]).await;
+        assert_eq!(
+            (body.as_str(), accepted),
+            ("1", 2),
+            "the hedge answered first"
+        );
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(started.elapsed() < Duration::from_millis(2000));
+        assert_eq!(policy.hedged(), 1);
+
+        let (body, accepted) = send(&policy, Method::GET, &[0, 0]).await;
+        assert_eq!(
+            (body.as_str(), accepted),
+            ("0", 1),
+            "a fast read is not hedged"
+        );
+
+        let (body, accepted) = send(&policy, Method::POST, &[200, 0]).await;
+        assert_eq!((body.as_str(), accepted), ("0", 1), "POST is never hedged");
+        assert_eq!(policy.hedged(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_hedge_waits_for_slow_success() {
+        let policy = HedgePolicy::new(0.95).initial_delay(Duration::from_millis(50));
+        let replies = [(300, StatusCode::OK), (0, StatusCode::SERVICE_UNAVAILABLE)];
+        let (status, body, accepted) = send_replies(&policy, None, Method::GET, &replies).await;
+        assert_eq!((status, body.as_str(), accepted), (StatusCode::OK, "0", 2));
+
+        let replies = [
+            (300, StatusCode::SERVICE_UNAVAILABLE),
+            (0, StatusCode::TOO_MANY_REQUESTS),
+        ];
+        let (status, body, _) = send_replies(&policy, None, Method::GET, &replies).await;
+        assert_eq!(
+            (status, body.as_str()),
+            (StatusCode::TOO_MANY_REQUESTS, "1"),
+            "with no success, the first answer is kept"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hedge_waits_for_rate_limiter() {
+        let policy = HedgePolicy::new(0.95).initial_delay(Duration::from_millis(50));
+        // The read itself took the only token; the next one is due in a second.
+        let limiter = RateLimiter::new(3600, 1);
+        limiter.acquire().await;
+
+        let replies = [(300, StatusCode::OK), (0, StatusCode::OK)];
+        let (_, body, accepted) =
+            send_replies(&policy, Some(&limiter), Method::GET, &replies).await;
+        assert_eq!(
+            (body.as_str(), accepted),
+            ("0", 1),
+            "the hedge was held back"
+        );
+        assert_eq!(policy.hedged(), 1);
+    }
+
+    #[test]
+    fn test_delay_follows_percentile() {
+        let policy = HedgePolicy::new(0.9)
+            .initial_delay(Duration::from_millis(700))
+            .window(100);
+        assert_eq!(policy.delay(), Duration::from_millis(700));
+
+        for ms in 1..=100 {
+            policy.record(Duration::from_millis(ms * 10));
+        }
+        assert_eq!(policy.delay(), Duration::from_millis(900));
+
+        // Older latencies fall out of the window.
+        for _ in 0..100 {
+            policy.record(Duration::from_millis(1));
+        }
+        assert_eq!(policy.delay(), Duration::from_millis(50));
+    }
+}
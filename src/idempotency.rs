@@ -0,0 +1,481 @@
+//! Safe repeats of non-idempotent requests.
+//!
+//! Creating a droplet, volume or database twice creates two billable resources, so
+//! `POST` requests are not retried automatically. When a caller needs to repeat one
+//! anyway, for example after a crash or a failed job, it can send it with an
+//! [`IdempotencyKey`]: the first successful response for an operation and key is
+//! recorded in an [`IdempotencyStore`], and later requests for the same operation and
+//! key get the recorded response back without anything being sent.
+//!
+//! Keys apply to every request except `GET` and `HEAD` sent by a client from
+//! [`Client::with_idempotency_key`]. A key only stands for one request: it is scoped
+//! to the method, path, query and body, so a different request sent with the same key
+//! is sent and recorded on its own. Only success responses are recorded; a request
+//! that failed is sent again. A repeat sent while the first request is still in flight
+//! waits for its outcome. The most recent 1,000 responses are kept in memory, separately
+//! for each client and its clones, unless
+//! [`ClientBuilder::idempotency_store`](crate::ClientBuilder::idempotency_store)
+//! installs a persistent store.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::idempotency::IdempotencyKey;
+//!
+//! # async fn run(client: rsdo::Client, body: &rsdo::types::DropletsCreateBody) -> Result<(), rsdo::error::Error> {
+//! // Derive the key from the job, so a rerun of the job reuses it.
+//! let key = IdempotencyKey::new("nightly-build-2024-06-01");
+//! let created = client
+//!     .with_idempotency_key(key)
+//!     .droplets_create(body)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::{Client, ClientInfo, ClientState};
+use futures::future::BoxFuture;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+/// Identifies one logical request across repeats.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+
+    /// A random key, for callers that keep it themselves between attempts.
+    pub fn random() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A recorded success response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl StoredResponse {
+    fn into_response(self) -> Option<reqwest::Response> {
+        let mut builder = http::Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        builder.body(self.body).ok().map(reqwest::Response::from)
+    }
+}
+
+/// Where responses of keyed requests are recorded.
+///
+/// Implement this over a database or cache to make keys survive restarts or work
+/// across processes. The keys a store sees are the caller's keys scoped to one
+/// request, so they are longer than the ones passed to [`IdempotencyKey::new`]. They
+/// do not name the account or API endpoint, so clients using different tokens or base
+/// URLs need separate stores.
+pub trait IdempotencyStore: Send + Sync + fmt::Debug {
+    /// The response recorded for `operation_id` and `key`, if any.
+    fn get<'a>(
+        &'a self,
+        operation_id: &'a str,
+        key: &'a IdempotencyKey,
+    ) -> BoxFuture<'a, Result<Option<StoredResponse>, Error>>;
+
+    /// Record the response to `operation_id` sent with `key`.
+    fn put<'a>(
+        &'a self,
+        operation_id: &'a str,
+        key: &'a IdempotencyKey,
+        response: StoredResponse,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+/// Responses a [`MemoryIdempotencyStore`] keeps unless told otherwise.
+pub const DEFAULT_MEMORY_CAPACITY: usize = 1000;
+
+/// Keeps the most recently recorded responses in memory; the default store.
+#[derive(Debug)]
+pub struct MemoryIdempotencyStore {
+    capacity: usize,
+    responses: Mutex<MemoryResponses>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryResponses {
+    by_key: HashMap<(String, IdempotencyKey), StoredResponse>,
+    /// Keys in the order they were first recorded, oldest first.
+    order: VecDeque<(String, IdempotencyKey)>,
+}
+
+impl MemoryIdempotencyStore {
+    /// A store keeping [`DEFAULT_MEMORY_CAPACITY`] responses.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_MEMORY_CAPACITY)
+    }
+
+    /// A store keeping the `capacity` most recently recorded responses, forgetting the
+    /// oldest first.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            responses: Mutex::default(),
+        }
+    }
+}
+
+impl Default for MemoryIdempotencyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdempotencyStore for MemoryIdempotencyStore {
+    fn get<'a>(
+        &'a self,
+        operation_id: &'a str,
+        key: &'a IdempotencyKey,
+    ) -> BoxFuture<'a, Result<Option<StoredResponse>, Error>> {
+        let responses = self.responses.lock().unwrap_or_else(|e| e.into_inner());
+        let stored = responses
+            .by_key
+            .get(&(operation_id.to_string(), key.clone()))
+            .cloned();
+        Box::pin(async move { Ok(stored) })
+    }
+
+    fn put<'a>(
+        &'a self,
+        operation_id: &'a str,
+        key: &'a IdempotencyKey,
+        response: StoredResponse,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        let mut responses = self.responses.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = (operation_id.to_string(), key.clone());
+        if responses.by_key.insert(entry.clone(), response).is_none() {
+            responses.order.push_back(entry);
+        }
+        while responses.by_key.len() > self.capacity {
+            let Some(oldest) = responses.order.pop_front() else {
+                break;
+            };
+            responses.by_key.remove(&oldest);
+        }
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// The client's store, or its memory store if it has none.
+fn store(state: &ClientState) -> &dyn IdempotencyStore {
+    match &state.idempotency_store {
+        Some(store) => store.as_ref(),
+        None => state.idempotency_memory.as_ref(),
+    }
+}
+
+/// Claim `key` for a request to `operation_id`, waiting while another request with it
+/// is in flight. Hold the guard until the response is recorded.
+///
+/// Claims are process-wide, since clients may share a store; requests of different
+/// clients that happen to use the same key only wait for each other.
+pub(crate) async fn reserve(
+    operation_id: &str,
+    key: &IdempotencyKey,
+) -> tokio::sync::OwnedMutexGuard<()> {
+    type Claims = HashMap<(String, IdempotencyKey), Weak<tokio::sync::Mutex<()>>>;
+    static IN_FLIGHT: OnceLock<Mutex<Claims>> = OnceLock::new();
+    let claim = {
+        let mut in_flight = IN_FLIGHT
+            .get_or_init(Mutex::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        in_flight.retain(|_, claim| claim.strong_count() > 0);
+        let entry = in_flight
+            .entry((operation_id.to_string(), key.clone()))
+            .or_default();
+        entry.upgrade().unwrap_or_else(|| {
+            let claim = Arc::default();
+            *entry = Arc::downgrade(&claim);
+            claim
+        })
+    };
+    claim.lock_owned().await
+}
+
+/// The key applying to `request`, if any: the client's key scoped to the request's
+/// method, path, query and body.
+pub(crate) fn key_for(state: &ClientState, request: &reqwest::Request) -> Option<IdempotencyKey> {
+    if matches!(*request.method(), Method::GET | Method::HEAD) {
+        return None;
+    }
+    let key = state.idempotency_key.as_ref()?;
+    let url = request.url();
+    let body = request.body().and_then(|body| body.as_bytes());
+    Some(IdempotencyKey(format!(
+        "{key} {} {}?{} {:016x}",
+        request.method(),
+        url.path(),
+        url.query().unwrap_or_default(),
+        body_hash(body.unwrap_or_default())
+    )))
+}
+
+/// 64-bit FNV-1a of `body`; unlike `std`'s hashers it is the same in every process, so
+/// persistent stores keep matching.
+fn body_hash(body: &[u8]) -> u64 {
+    body.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The recorded response for `operation_id` and `key`, if any.
+///
+/// A failing store is logged and treated as having no record, so the request is sent.
+pub(crate) async fn replay(
+    state: &ClientState,
+    operation_id: &str,
+    key: &IdempotencyKey,
+) -> Option<reqwest::Response> {
+    match store(state).get(operation_id, key).await {
+        Ok(stored) => {
+            let response = stored?.into_response();
+            if response.is_some() {
+                tracing::debug!(operation = operation_id, %key, "replaying recorded response");
+            }
+            response
+        }
+        Err(err) => {
+            tracing::warn!(operation = operation_id, %key, error = %err, "idempotency store lookup failed");
+            None
+        }
+    }
+}
+
+/// Record `response` if it is a success, handing back an equivalent response.
+pub(crate) async fn remember(
+    state: &ClientState,
+    operation_id: &str,
+    key: &IdempotencyKey,
+    response: reqwest::Response,
+) -> reqwest::Result<reqwest::Response> {
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let body = response.bytes().await?.to_vec();
+    let stored = StoredResponse {
+        status,
+        headers,
+        body,
+    };
+    if let Err(err) = store(state).put(operation_id, key, stored.clone()).await {
+        tracing::warn!(operation = operation_id, %key, error = %err, "idempotency store write failed");
+    }
+    Ok(stored
+        .into_response()
+        .expect("headers were read from a valid response"))
+}
+
+impl Client {
+    /// Return a copy of this client that sends every request but `GET` and `HEAD` with
+    /// `key`, replaying the recorded response if the same request already succeeded
+    /// with it.
+    ///
+    /// The copy shares the underlying connection pool with `self`.
+    pub fn with_idempotency_key(&self, key: IdempotencyKey) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.idempotency_key = Some(key);
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn keyed_state(store: MemoryIdempotencyStore) -> ClientState {
+        ClientState {
+            idempotency_store: Some(Arc::new(store)),
+            idempotency_key: Some(IdempotencyKey::new("job-1")),
+            ..ClientState::default()
+        }
+    }
+
+    fn request(method: Method, url: &str, body: &str) -> reqwest::Request {
+        reqwest::Client::new()
+            .request(method, url)
+            .body(body.to_string())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_success_is_replayed_per_operation() {
+        let state = keyed_state(MemoryIdempotencyStore::new());
+        let url = "https://api.digitalocean.com/v2/droplets";
+        assert!(key_for(&state, &request(Method::GET, url, "")).is_none());
+        let key = key_for(&state, &request(Method::POST, url, "{}")).unwrap();
+        assert!(replay(&state, "droplets_create", &key).await.is_none());
+
+        let created = http::Response::builder()
+            .status(202)
+            .header("content-type", "application/json")
+            .body(r#"{"droplet":{"id":1}}"#)
+            .unwrap();
+        let response = remember(&state, "droplets_create", &key, created.into())
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), r#"{"droplet":{"id":1}}"#);
+
+        let replayed = replay(&state, "droplets_create", &key).await.unwrap();
+        assert_eq!(replayed.status(), 202);
+        let body: serde_json::Value = replayed.json().await.unwrap();
+        assert_eq!(body["droplet"]["id"], 1);
+        assert!(replay(&state, "volumes_create", &key).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_forgets_oldest() {
+        let store = MemoryIdempotencyStore::with_capacity(2);
+        let response = StoredResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+        for key in ["a", "b", "c"] {
+            let key = IdempotencyKey::new(key);
+            store.put("op", &key, response.clone()).await.unwrap();
+        }
+        for (key, kept) in [("a", false), ("b", true), ("c", true)] {
+            let stored = store.get("op", &IdempotencyKey::new(key)).await.unwrap();
+            assert_eq!(stored.is_some(), kept, "{key}");
+        }
+    }
+
+    /// Answers each request with `201 Created` and the request's body, counting them
+    /// in `served`.
+    async fn echo(listener: TcpListener, served: Arc<AtomicUsize>) {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            let body_len = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .map_or(0, |length| length.trim().parse().unwrap());
+                    if request.len() >= end + 4 + length {
+                        break length;
+                    }
+                }
+            };
+            let body = String::from_utf8_lossy(&request[request.len() - body_len..]);
+            let response = format!(
+                "HTTP/1.1 201 Created\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            served.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_for_another_request_is_sent() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v2/volumes", listener.local_addr().unwrap());
+        let served = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(echo(listener, served.clone()));
+        let state = keyed_state(MemoryIdempotencyStore::new());
+        let http = reqwest::Client::new();
+        let send = |body: &str| {
+            let request = request(Method::POST, &url, body);
+            let (http, state) = (&http, &state);
+            async move {
+                crate::transport::execute(http, state, request, "volumes_create")
+                    .await
+                    .unwrap()
+                    .text()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        assert_eq!(send(r#"{"name":"a"}"#).await, r#"{"name":"a"}"#);
+        assert_eq!(send(r#"{"name":"b"}"#).await, r#"{"name":"b"}"#);
+        assert_eq!(served.load(Ordering::SeqCst), 2);
+        assert_eq!(send(r#"{"name":"a"}"#).await, r#"{"name":"a"}"#);
+        assert_eq!(served.load(Ordering::SeqCst), 2, "the repeat was replayed");
+    }
+    #[tokio::test]
+    async fn test_concurrent_repeat_waits_for_first() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v2/volumes", listener.local_addr().unwrap());
+        let served = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(echo(listener, served.clone()));
+        let state = keyed_state(MemoryIdempotencyStore::new());
+        let http = reqwest::Client::new();
+        let send = || {
+            let request = request(Method::POST, &url, r#"{"name":"a"}"#);
+            crate::transport::execute(&http, &state, request, "volumes_create")
+        };
+
+        let (first, second) = tokio::join!(send(), send());
+        assert_eq!(first.unwrap().status(), 201);
+        assert_eq!(second.unwrap().text().await.unwrap(), r#"{"name":"a"}"#);
+        assert_eq!(served.load(Ordering::SeqCst), 1, "the repeat was replayed");
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_is_per_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v2/volumes", listener.local_addr().unwrap());
+        let served = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(echo(listener, served.clone()));
+        let keyed = || ClientState {
+            idempotency_key: Some(IdempotencyKey::new("job-1")),
+            ..ClientState::default()
+        };
+        let (team_a, team_b) = (keyed(), keyed());
+        let http = reqwest::Client::new();
+        for state in [&team_a, &team_b, &team_a.clone()] {
+            let request = request(Method::POST, &url, "{}");
+            crate::transport::execute(&http, state, request, "volumes_create")
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            served.load(Ordering::SeqCst),
+            2,
+            "only the clone of the first client replayed"
+        );
+    }
+}
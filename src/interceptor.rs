@@ -0,0 +1,153 @@
+//! Request interceptors.
+//!
+//! An [`Interceptor`] sees every request the client sends, generated operations and
+//! helpers alike, just before it goes out, and every outcome once the transport layer
+//! (retries, token refresh) is done with it. Typical uses are injecting headers,
+//! audit logging and request signing, none of which need changes to generated code.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use futures::future::BoxFuture;
+//! use rsdo::error::Error;
+//! use rsdo::interceptor::Interceptor;
+//! use rsdo::Client;
+//! use std::sync::Arc;
+//!
+//! #[derive(Debug)]
+//! struct AuditLog;
+//!
+//! impl Interceptor for AuditLog {
+//!     fn before_send<'a>(
+//!         &'a self,
+//!         request: &'a mut reqwest::Request,
+//!         _operation_id: &'a str,
+//!     ) -> BoxFuture<'a, Result<(), Error>> {
+//!         Box::pin(async move {
+//!             request
+//!                 .headers_mut()
+//!                 .insert("x-audit-user", "deploy-bot".parse().unwrap());
+//!             Ok(())
+//!         })
+//!     }
+//!
+//!     fn after_receive<'a>(
+//!         &'a self,
+//!         outcome: Result<&'a reqwest::Response, &'a reqwest::Error>,
+//!         operation_id: &'a str,
+//!     ) -> BoxFuture<'a, ()> {
+//!         Box::pin(async move {
+//!             match outcome {
+//!                 Ok(response) => println!("{operation_id}: {}", response.status()),
+//!                 Err(err) => println!("{operation_id}: {err}"),
+//!             }
+//!         })
+//!     }
+//! }
+//!
+//! let client = Client::from_token("your-digitalocean-token").with_interceptor(Arc::new(AuditLog));
+//! ```
+
+use crate::error::Error;
+use crate::{Client, ClientInfo, ClientState};
+use futures::future::BoxFuture;
+use std::fmt;
+use std::sync::Arc;
+
+/// Hooks run around every request a [`Client`] sends.
+///
+/// Both methods default to doing nothing. Interceptors run in registration order.
+pub trait Interceptor: Send + Sync + fmt::Debug {
+    /// Inspect or modify a request before it is sent.
+    ///
+    /// Runs once per call, after authentication has been applied; transport retries
+    /// resend the same request. Returning an error aborts the call with that error.
+    fn before_send<'a>(
+        &'a self,
+        request: &'a mut reqwest::Request,
+        operation_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        let _ = (request, operation_id);
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Observe the final response, or the transport error, of a call.
+    fn after_receive<'a>(
+        &'a self,
+        outcome: Result<&'a reqwest::Response, &'a reqwest::Error>,
+        operation_id: &'a str,
+    ) -> BoxFuture<'a, ()> {
+        let _ = (outcome, operation_id);
+        Box::pin(async {})
+    }
+}
+
+impl Client {
+    /// Return a copy of this client that also runs `interceptor` around every request.
+    pub fn with_interceptor(&self, interceptor: Arc<dyn Interceptor>) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.interceptors.push(interceptor);
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct AddHeader(&'static str);
+
+    impl Interceptor for AddHeader {
+        fn before_send<'a>(
+            &'a self,
+            request: &'a mut reqwest::Request,
+            _operation_id: &'a str,
+        ) -> BoxFuture<'a, Result<(), Error>> {
+            Box::pin(async move {
+                request
+                    .headers_mut()
+                    .append("x-trace", self.0.parse().unwrap());
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptors_run_in_order() {
+        let client = Client::from_token("test-token")
+            .with_interceptor(Arc::new(AddHeader("first")))
+            .with_interceptor(Arc::new(AddHeader("second")));
+        let mut request = reqwest::Request::new(
+            reqwest::Method::GET,
+            "https://api.digitalocean.com/v2/account".parse().unwrap(),
+        );
+        crate::transport::prepare(&mut request, client.inner(), "account_get")
+            .await
+            .unwrap();
+        let values: Vec<_> = request.headers().get_all("x-trace").iter().collect();
+        assert_eq!(values, ["first", "second"]);
+    }
+    #[derive(Debug)]
+    struct Deny;
+
+    impl Interceptor for Deny {
+        fn before_send<'a>(
+            &'a self,
+            _request: &'a mut reqwest::Request,
+            operation_id: &'a str,
+        ) -> BoxFuture<'a, Result<(), Error>> {
+            Box::pin(async move { Err(Error::InvalidInput(format!("{operation_id} denied"))) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generated_call_reports_interceptor_error() {
+        let client = Client::from_token("test-token").with_interceptor(Arc::new(Deny));
+        let err = Error::from(client.account_get().await.unwrap_err());
+        let Error::InvalidInput(message) = err else {
+            panic!("expected InvalidInput, got {err:?}");
+        };
+        assert_eq!(message, "account_get denied");
+    }
+}
@@ -0,0 +1,436 @@
+//! Partner Network Connect (partner interconnect attachment) helpers.
+//!
+//! Partner attachments connect one or more VPCs to a network-as-a-service provider over
+//! BGP. Provisioning is asynchronous: a freshly created attachment starts out `PENDING`
+//! and only carries traffic once it reports `ACTIVE`. These helpers wrap the
+//! `/v2/partner_network_connect/attachments` endpoints with typed models and add a
+//! waiter for the provisioning transition.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::interconnect::{BgpConfig, CreatePartnerAttachment};
+//! use rsdo::Client;
+//! use std::time::Duration;
+//!
+//! # async fn run(client: Client) -> Result<(), rsdo::error::Error> {
+//! let request = CreatePartnerAttachment {
+//!     name: "office-link".to_string(),
+//!     connection_bandwidth_in_mbps: 1000,
+//!     region: "nyc".to_string(),
+//!     naas_provider: "MEGAPORT".to_string(),
+//!     vpc_ids: vec!["c140286f-e6ce-4131-8b7b-df4590ce8d6a".to_string()],
+//!     bgp: Some(BgpConfig {
+//!         local_router_ip: Some("169.254.0.1/29".to_string()),
+//!         peer_router_asn: Some(64532),
+//!         peer_router_ip: Some("169.254.0.6/29".to_string()),
+//!         auth_key: None,
+//!     }),
+//!     parent_uuid: None,
+//! };
+//!
+//! let attachment = client.create_partner_attachment(&request).await?;
+//! let active = client
+//!     .wait_for_partner_attachment_active(
+//!         &attachment.id,
+//!         Duration::from_secs(15),
+//!         Duration::from_secs(30 * 60),
+//!     )
+//!     .await?;
+//! println!("{} is {}", active.name, active.state);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+const ATTACHMENTS_PATH: &str = "/v2/partner_network_connect/attachments";
+
+/// Provisioning state of a partner attachment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum PartnerAttachmentState {
+    Pending,
+    Configuring,
+    Active,
+    Deleting,
+    Failed,
+    /// A state this version of rsdo does not know about yet.
+    Unknown(String),
+}
+
+impl PartnerAttachmentState {
+    /// Whether the attachment can no longer progress towards `ACTIVE`.
+    pub fn is_terminal_failure(&self) -> bool {
+        matches!(self, Self::Deleting | Self::Failed)
+    }
+}
+
+impl From<String> for PartnerAttachmentState {
+    fn from(value: String) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "PENDING" => Self::Pending,
+            "CONFIGURING" => Self::Configuring,
+            "ACTIVE" => Self::Active,
+            "DELETING" => Self::Deleting,
+            "FAILED" => Self::Failed,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<PartnerAttachmentState> for String {
+    fn from(value: PartnerAttachmentState) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for PartnerAttachmentState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Pending => "PENDING",
+            Self::Configuring => "CONFIGURING",
+            Self::Active => "ACTIVE",
+            Self::Deleting => "DELETING",
+            Self::Failed => "FAILED",
+            Self::Unknown(s) => s,
+        };
+        f.write_str(s)
+    }
+}
+
+/// BGP session parameters for a partner attachment.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BgpConfig {
+    /// Link-local address (with prefix) of the DigitalOcean side of the session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_router_ip: Option<String>,
+    /// ASN of the partner router.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_router_asn: Option<u64>,
+    /// Link-local address (with prefix) of the partner router.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_router_ip: Option<String>,
+    /// MD5 authentication key. Only accepted on write; read it back with
+    /// [`Client::partner_attachment_bgp_auth_key`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_key: Option<String>,
+}
+
+/// BGP session as reported by the API.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct BgpStatus {
+    #[serde(default)]
+    pub local_asn: Option<u64>,
+    #[serde(default)]
+    pub local_router_ip: Option<String>,
+    #[serde(default)]
+    pub peer_asn: Option<u64>,
+    #[serde(default)]
+    pub peer_router_ip: Option<String>,
+}
+
+/// A partner network connect attachment.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PartnerAttachment {
+    pub id: String,
+    pub name: String,
+    pub state: PartnerAttachmentState,
+    #[serde(default)]
+    pub connection_bandwidth_in_mbps: u64,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub naas_provider: String,
+    #[serde(default)]
+    pub vpc_ids: Vec<String>,
+    #[serde(default)]
+    pub bgp: Option<BgpStatus>,
+    #[serde(default)]
+    pub parent_uuid: Option<String>,
+    #[serde(default)]
+    pub children: Vec<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+/// Request body for [`Client::create_partner_attachment`].
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatePartnerAttachment {
+    pub name: String,
+    pub connection_bandwidth_in_mbps: u64,
+    /// Partner region slug, e.g. `nyc` or `sfo`.
+    pub region: String,
+    /// Network-as-a-service provider, e.g. `MEGAPORT`.
+    pub naas_provider: String,
+    pub vpc_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bgp: Option<BgpConfig>,
+    /// Parent attachment when creating a redundant (HA) connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_uuid: Option<String>,
+}
+
+/// A VPC attached to a partner attachment.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AttachedVpc {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub ip_range: String,
+    #[serde(default)]
+    pub urn: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Deserialize)]
+struct AttachmentEnvelope {
+    partner_attachment: PartnerAttachment,
+}
+
+#[derive(Deserialize)]
+struct BgpAuthKeyEnvelope {
+    bgp_auth_key: BgpAuthKey,
+}
+
+#[derive(Deserialize)]
+struct BgpAuthKey {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct VpcEnvelope {
+    vpc: AttachedVpc,
+}
+
+impl Client {
+    /// Create a partner attachment. The returned attachment is usually still `PENDING`;
+    /// use [`Client::wait_for_partner_attachment_active`] to block until it is usable.
+    pub async fn create_partner_attachment(
+        &self,
+        request: &CreatePartnerAttachment,
+    ) -> Result<PartnerAttachment, Error> {
+        let envelope: AttachmentEnvelope = self
+            .send_json(
+                ApiRequest::post("partnerAttachments_create", ATTACHMENTS_PATH)
+                    .json(serde_json::to_value(request)?),
+            )
+            .await?;
+        Ok(envelope.partner_attachment)
+    }
+
+    /// Fetch a single partner attachment.
+    pub async fn partner_attachment(&self, id: &str) -> Result<PartnerAttachment, Error> {
+        let envelope: AttachmentEnvelope = self
+            .send_json(ApiRequest::get(
+                "partnerAttachments_get",
+                format!("{}/{}", ATTACHMENTS_PATH, id),
+            ))
+            .await?;
+        Ok(envelope.partner_attachment)
+    }
+
+    /// List all partner attachments on the account, following every page.
+    pub async fn partner_attachments(&self) -> Result<Vec<PartnerAttachment>, Error> {
+        self.collect_pages("partnerAttachments_list", "partner_attachments")
+            .await
+    }
+
+    /// Poll a partner attachment until it reports `ACTIVE`.
+    ///
+    /// Fails early if the attachment enters a state it cannot recover from (`DELETING`,
+    /// `FAILED`), and with [`Error::Timeout`] once `timeout` has elapsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Partner attachment ID
+    /// * `interval` - Delay between polls
+    /// * `timeout` - Upper bound on the total wait
+    pub async fn wait_for_partner_attachment_active(
+        &self,
+        id: &str,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<PartnerAttachment, Error> {
+        let workflow = Workflow::new(
+            self.inner(),
+            "wait_for_partner_attachment_active",
+            format!("partner attachment {}", id),
+        );
+        let waiter = self
+            .waiter(
+                format!("partner attachment {} to become ACTIVE", id),
+                || self.partner_attachment(id),
+            )
+            .until(|attachment| attachment.state == PartnerAttachmentState::Active)
+            .fail_if(|attachment| {
+                attachment.state.is_terminal_failure().then(|| {
+                    Error::Other(format!(
+                        "partner attachment {} entered state {} while provisioning",
+                        id, attachment.state
+                    ))
+                })
+            })
+            .state(|attachment| attachment.state.to_string())
+            .options(WaitOptions::default().interval(interval).timeout(timeout));
+        workflow.step("wait_active", waiter.wait()).await
+    }
+
+    /// Replace the BGP settings of a partner attachment.
+    pub async fn update_partner_attachment_bgp(
+        &self,
+        id: &str,
+        bgp: &BgpConfig,
+    ) -> Result<PartnerAttachment, Error> {
+        let envelope: AttachmentEnvelope = self
+            .send_json(
+                ApiRequest::patch(
+                    "partnerAttachments_patch",
+                    format!("{}/{}", ATTACHMENTS_PATH, id),
+                )
+                .json(serde_json::json!({ "bgp": bgp })),
+            )
+            .await?;
+        Ok(envelope.partner_attachment)
+    }
+
+    /// Retrieve the BGP MD5 authentication key of a partner attachment.
+    pub async fn partner_attachment_bgp_auth_key(&self, id: &str) -> Result<String, Error> {
+        let envelope: BgpAuthKeyEnvelope = self
+            .send_json(ApiRequest::get(
+                "partnerAttachments_get_bgp_auth_key",
+                format!("{}/{}/bgp_auth_key", ATTACHMENTS_PATH, id),
+            ))
+            .await?;
+        Ok(envelope.bgp_auth_key.value)
+    }
+
+    /// Replace the set of VPCs attached to a partner attachment.
+    pub async fn set_partner_attachment_vpcs(
+        &self,
+        id: &str,
+        vpc_ids: &[String],
+    ) -> Result<PartnerAttachment, Error> {
+        let envelope: AttachmentEnvelope = self
+            .send_json(
+                ApiRequest::patch(
+                    "partnerAttachments_patch",
+                    format!("{}/{}", ATTACHMENTS_PATH, id),
+                )
+                .json(serde_json::json!({ "vpc_ids": vpc_ids })),
+            )
+            .await?;
+        Ok(envelope.partner_attachment)
+    }
+
+    /// Fetch the full VPC records for every VPC attached to a partner attachment.
+    pub async fn partner_attachment_vpcs(&self, id: &str) -> Result<Vec<AttachedVpc>, Error> {
+        let attachment = self.partner_attachment(id).await?;
+        let mut vpcs = Vec::with_capacity(attachment.vpc_ids.len());
+        for vpc_id in &attachment.vpc_ids {
+            let envelope: VpcEnvelope = self
+                .send_json(ApiRequest::get("vpcs_get", format!("/v2/vpcs/{}", vpc_id)))
+                .await?;
+            vpcs.push(envelope.vpc);
+        }
+        Ok(vpcs)
+    }
+
+    /// Delete a partner attachment.
+    pub async fn delete_partner_attachment(&self, id: &str) -> Result<(), Error> {
+        self.send_empty(ApiRequest::delete(
+            "partnerAttachments_delete",
+            format!("{}/{}", ATTACHMENTS_PATH, id),
+        ))
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_round_trip() {
+        let state: PartnerAttachmentState = serde_json::from_str("\"ACTIVE\"").unwrap();
+        assert_eq!(state, PartnerAttachmentState::Active);
+        assert_eq!(serde_json::to_string(&state).unwrap(), "\"ACTIVE\"");
+
+        let state: PartnerAttachmentState = serde_json::from_str("\"REWIRING\"").unwrap();
+        assert_eq!(
+            state,
+            PartnerAttachmentState::Unknown("REWIRING".to_string())
+        );
+        assert!(!state.is_terminal_failure());
+    }
+
+    #[tokio::test]
+    async fn test_attachments_are_listed_across_pages() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let pages = [
+            format!(
+                r#"{{"partner_attachments":[{{"id":"a","name":"one","state":"ACTIVE"}}],"links":{{"pages":{{"next":"{base_url}{ATTACHMENTS_PATH}?page=2"}}}},"meta":{{"total":2}}}}"#
+            ),
+            r#"{"partner_attachments":[{"id":"b","name":"two","state":"PENDING"}],"links":{},"meta":{"total":2}}"#
+                .to_string(),
+        ];
+        let server = tokio::spawn(async move {
+            let mut paths = Vec::new();
+            for page in pages {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                while !request.ends_with(b"\r\n\r\n") {
+                    let mut buf = [0; 1024];
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8(request).unwrap();
+                paths.push(request.split(' ').nth(1).unwrap().to_string());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{page}",
+                    page.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            paths
+        });
+
+        let client = Client::builder("test-token")
+            .base_url(base_url)
+            .build()
+            .unwrap();
+        let attachments = client.partner_attachments().await.unwrap();
+        let ids: Vec<_> = attachments.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+        assert_eq!(attachments[1].state, PartnerAttachmentState::Pending);
+
+        let paths = server.await.unwrap();
+        assert!(paths[0].starts_with(ATTACHMENTS_PATH));
+        assert!(paths[1].starts_with(&format!("{ATTACHMENTS_PATH}?page=2")));
+    }
+
+    #[test]
+    fn test_bgp_config_skips_unset_fields() {
+        let bgp = BgpConfig {
+            peer_router_asn: Some(64532),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&bgp).unwrap(),
+            serde_json::json!({ "peer_router_asn": 64532 })
+        );
+    }
+}
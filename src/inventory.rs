@@ -0,0 +1,409 @@
+//! Account inventories and drift detection.
+//!
+//! [`Client::inventory`] records the droplets, volumes, Kubernetes clusters, database
+//! clusters, load balancers, firewalls, domains and snapshots of an account, keeping
+//! the key attributes of each. Inventories serialize to JSON, so a scheduled job can
+//! store one per run and compare it with the previous run using [`Inventory::diff`].
+//! The diff lists added and removed resources and the changed attributes of modified
+//! ones.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::inventory::Inventory;
+//!
+//! # async fn run(client: rsdo::Client, yesterday: Inventory) -> Result<(), rsdo::error::Error> {
+//! let today = client.inventory().await?;
+//! let diff = today.diff(&yesterday);
+//! for resource in &diff.added {
+//!     println!("+ {} {} ({})", resource.kind, resource.name, resource.id);
+//! }
+//! for change in &diff.modified {
+//!     for field in &change.fields {
+//!         println!("~ {} {}: {}", change.kind, change.name, field);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Kind of resource tracked by an [`Inventory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    Droplet,
+    Volume,
+    KubernetesCluster,
+    DatabaseCluster,
+    LoadBalancer,
+    Firewall,
+    Domain,
+    Snapshot,
+}
+
+impl ResourceKind {
+    /// Attributes compared by [`Inventory::diff`], with the JSON pointer to each in
+    /// the API representation.
+    fn attributes(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Droplet => &[
+                ("status", "/status"),
+                ("size", "/size_slug"),
+                ("region", "/region/slug"),
+                ("image", "/image/slug"),
+                ("vpc", "/vpc_uuid"),
+                ("volumes", "/volume_ids"),
+                ("tags", "/tags"),
+            ],
+            Self::Volume => &[
+                ("size_gigabytes", "/size_gigabytes"),
+                ("region", "/region/slug"),
+                ("droplets", "/droplet_ids"),
+                ("tags", "/tags"),
+            ],
+            Self::KubernetesCluster => &[
+                ("status", "/status/state"),
+                ("version", "/version"),
+                ("region", "/region"),
+                ("auto_upgrade", "/auto_upgrade"),
+                ("tags", "/tags"),
+            ],
+            Self::DatabaseCluster => &[
+                ("status", "/status"),
+                ("engine", "/engine"),
+                ("version", "/version"),
+                ("size", "/size"),
+                ("nodes", "/num_nodes"),
+                ("region", "/region"),
+                ("tags", "/tags"),
+            ],
+            Self::LoadBalancer => &[
+                ("status", "/status"),
+                ("size", "/size_unit"),
+                ("region", "/region/slug"),
+                ("droplets", "/droplet_ids"),
+                ("tag", "/tag"),
+            ],
+            Self::Firewall => &[
+                ("status", "/status"),
+                ("droplets", "/droplet_ids"),
+                ("tags", "/tags"),
+            ],
+            Self::Domain => &[("ttl", "/ttl")],
+            Self::Snapshot => &[
+                ("resource", "/resource_id"),
+                ("size_gigabytes", "/size_gigabytes"),
+                ("regions", "/regions"),
+                ("tags", "/tags"),
+            ],
+        }
+    }
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Droplet => "droplet",
+            Self::Volume => "volume",
+            Self::KubernetesCluster => "kubernetes cluster",
+            Self::DatabaseCluster => "database cluster",
+            Self::LoadBalancer => "load balancer",
+            Self::Firewall => "firewall",
+            Self::Domain => "domain",
+            Self::Snapshot => "snapshot",
+        };
+        f.write_str(s)
+    }
+}
+
+/// A resource as recorded in an [`Inventory`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resource {
+    pub kind: ResourceKind,
+    /// The resource's ID; the name for domains.
+    pub id: String,
+    pub name: String,
+    /// Key attributes, rendered as text. List values are sorted and comma-separated.
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Resource {
+    /// Record a resource of `kind` from its API representation.
+    ///
+    /// Returns `None` if `value` has no `id` (or `name`, for domains).
+    pub fn from_api(kind: ResourceKind, value: &Value) -> Option<Self> {
+        let name = value.get("name").and_then(render)?;
+        let id = match kind {
+            ResourceKind::Domain => name.clone(),
+            _ => value.get("id").and_then(render)?,
+        };
+        let attributes = kind
+            .attributes()
+            .iter()
+            .filter_map(|(attribute, pointer)| {
+                let rendered = value.pointer(pointer).and_then(render)?;
+                Some((attribute.to_string(), rendered))
+            })
+            .collect();
+        Some(Self {
+            kind,
+            id,
+            name,
+            attributes,
+        })
+    }
+}
+
+/// Render a JSON value for comparison, or `None` for null.
+fn render(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => {
+            let mut items: Vec<String> = items.iter().filter_map(render).collect();
+            items.sort();
+            Some(items.join(","))
+        }
+        other => Some(other.to_string()),
+    }
+}
+
+/// The resources of an account at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inventory {
+    pub taken_at: DateTime<Utc>,
+    pub resources: Vec<Resource>,
+}
+
+/// A resource present in both inventories whose attributes differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceChange {
+    pub kind: ResourceKind,
+    pub id: String,
+    /// The resource's current name.
+    pub name: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/// One attribute that changed between inventories. `None` means the attribute was
+/// absent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        write!(
+            f,
+            "{} {} -> {}",
+            self.field,
+            show(&self.old),
+            show(&self.new)
+        )
+    }
+}
+
+/// Changes from an older inventory to a newer one, each list ordered by kind and ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InventoryDiff {
+    pub added: Vec<Resource>,
+    pub removed: Vec<Resource>,
+    pub modified: Vec<ResourceChange>,
+}
+
+impl InventoryDiff {
+    /// Whether nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl Inventory {
+    /// Compare with an `older` inventory of the same account.
+    ///
+    /// Resources are matched by kind and ID, so a renamed resource is reported as
+    /// modified, with a `name` field change.
+    pub fn diff(&self, older: &Inventory) -> InventoryDiff {
+        let index = |inventory: &'_ Inventory| -> BTreeMap<(ResourceKind, String), Resource> {
+            inventory
+                .resources
+                .iter()
+                .map(|resource| ((resource.kind, resource.id.clone()), resource.clone()))
+                .collect()
+        };
+        let mut old = index(older);
+        let mut diff = InventoryDiff::default();
+
+        for (key, new) in index(self) {
+            let Some(old) = old.remove(&key) else {
+                diff.added.push(new);
+                continue;
+            };
+            let fields = field_changes(&old, &new);
+            if !fields.is_empty() {
+                diff.modified.push(ResourceChange {
+                    kind: new.kind,
+                    id: new.id,
+                    name: new.name,
+                    fields,
+                });
+            }
+        }
+        diff.removed = old.into_values().collect();
+        diff
+    }
+}
+
+fn field_changes(old: &Resource, new: &Resource) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    if old.name != new.name {
+        changes.push(FieldChange {
+            field: "name".to_string(),
+            old: Some(old.name.clone()),
+            new: Some(new.name.clone()),
+        });
+    }
+    let mut fields: Vec<&String> = old.attributes.keys().chain(new.attributes.keys()).collect();
+    fields.sort();
+    fields.dedup();
+    for field in fields {
+        let (before, after) = (old.attributes.get(field), new.attributes.get(field));
+        if before != after {
+            changes.push(FieldChange {
+                field: field.clone(),
+                old: before.cloned(),
+                new: after.cloned(),
+            });
+        }
+    }
+    changes
+}
+
+impl Client {
+    /// Record the account's resources.
+    ///
+    /// The listings are fetched concurrently, following pagination.
+    pub async fn inventory(&self) -> Result<Inventory, Error> {
+        let list = |operation, key| self.collect_pages::<Value>(operation, key);
+        let (droplets, volumes, clusters, load_balancers, firewalls, domains, snapshots) = futures::try_join!(
+            list("droplets_list", "droplets"),
+            list("volumes_list", "volumes"),
+            list("kubernetes_list_clusters", "kubernetes_clusters"),
+            list("loadBalancers_list", "load_balancers"),
+            list("firewalls_list", "firewalls"),
+            list("domains_list", "domains"),
+            list("snapshots_list", "snapshots"),
+        )?;
+        // The database cluster listing is not paginated.
+        let mut databases: Value = self
+            .send_json(ApiRequest::get("databases_list_clusters", "/v2/databases"))
+            .await?;
+        let databases = match databases["databases"].take() {
+            Value::Array(items) => items,
+            _ => Vec::new(),
+        };
+
+        let resources = [
+            (ResourceKind::Droplet, droplets),
+            (ResourceKind::Volume, volumes),
+            (ResourceKind::KubernetesCluster, clusters),
+            (ResourceKind::DatabaseCluster, databases),
+            (ResourceKind::LoadBalancer, load_balancers),
+            (ResourceKind::Firewall, firewalls),
+            (ResourceKind::Domain, domains),
+            (ResourceKind::Snapshot, snapshots),
+        ]
+        .into_iter()
+        .flat_map(|(kind, items)| {
+            items
+                .into_iter()
+                .filter_map(move |item| Resource::from_api(kind, &item))
+        })
+        .collect();
+        Ok(Inventory {
+            taken_at: Utc::now(),
+            resources,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn inventory(droplets: Value) -> Inventory {
+        let resources = droplets
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|droplet| Resource::from_api(ResourceKind::Droplet, droplet))
+            .collect();
+        Inventory {
+            taken_at: Utc::now(),
+            resources,
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_added_removed_and_modified() {
+        let older = inventory(json!([
+            {"id": 1, "name": "web-1", "status": "active", "size_slug": "s-1vcpu-1gb",
+             "region": {"slug": "nyc3"}, "tags": ["web", "prod"]},
+            {"id": 2, "name": "old", "status": "off", "region": {"slug": "nyc3"}}
+        ]));
+        let newer = inventory(json!([
+            {"id": 1, "name": "web-1", "status": "active", "size_slug": "s-2vcpu-2gb",
+             "region": {"slug": "nyc3"}, "tags": ["prod", "web"], "vpc_uuid": "vpc-1"},
+            {"id": 3, "name": "web-2", "status": "new", "region": {"slug": "nyc3"}}
+        ]));
+
+        let diff = newer.diff(&older);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].id, "3");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].name, "old");
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(
+            diff.modified[0].fields,
+            [
+                FieldChange {
+                    field: "size".to_string(),
+                    old: Some("s-1vcpu-1gb".to_string()),
+                    new: Some("s-2vcpu-2gb".to_string()),
+                },
+                FieldChange {
+                    field: "vpc".to_string(),
+                    old: None,
+                    new: Some("vpc-1".to_string()),
+                },
+            ]
+        );
+        assert_eq!(diff.modified[0].fields[1].to_string(), "vpc - -> vpc-1");
+        assert!(newer.diff(&newer).is_empty());
+    }
+
+    #[test]
+    fn test_domains_are_keyed_by_name() {
+        let domain = Resource::from_api(
+            ResourceKind::Domain,
+            &json!({"name": "example.com", "ttl": 1800}),
+        )
+        .unwrap();
+        assert_eq!(domain.id, "example.com");
+        assert_eq!(domain.attributes["ttl"], "1800");
+    }
+}
@@ -0,0 +1,209 @@
+//! Node pool autoscaler status.
+//!
+//! [`Client::autoscaling_report`] puts each pool's autoscaler bounds next to its desired
+//! node count and the nodes actually running, so capacity tooling can see at a glance
+//! which pools are scaling and which are pinned at a bound. The API keeps no history
+//! of scaling events; what a pool is doing right now is read from its nodes: nodes
+//! still provisioning mean it is growing, nodes draining or deleting that it shrinks.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let report = client
+//!     .autoscaling_report("bd5f5959-5e1e-4205-a714-a914373942af")
+//!     .await?;
+//! for pool in &report.pools {
+//!     println!(
+//!         "{}: {}/{} running, {:?}{}",
+//!         pool.name,
+//!         pool.running,
+//!         pool.desired,
+//!         pool.activity(),
+//!         if pool.at_max() { ", at max" } else { "" }
+//!     );
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{KubernetesCluster, KubernetesNodePool};
+use crate::error::Error;
+use crate::Client;
+
+/// What a pool's node count is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScalingActivity {
+    /// Every desired node is running and none is leaving.
+    Steady,
+    /// Nodes are being added.
+    ScalingUp,
+    /// Nodes are draining or being deleted.
+    ScalingDown,
+}
+
+/// Autoscaler configuration and node counts of one node pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolScaling {
+    pub id: String,
+    pub name: String,
+    /// Droplet size slug of the nodes.
+    pub size: String,
+    pub auto_scale: bool,
+    /// Autoscaler bounds; zero when autoscaling is off.
+    pub min_nodes: u32,
+    pub max_nodes: u32,
+    /// Node count the pool is converging on.
+    pub desired: u32,
+    /// Nodes that are running.
+    pub running: u32,
+    /// Nodes still provisioning.
+    pub provisioning: u32,
+    /// Nodes draining or being deleted.
+    pub leaving: u32,
+}
+
+impl PoolScaling {
+    fn from_pool(pool: &KubernetesNodePool) -> Self {
+        let count = |states: &[&str]| {
+            pool.nodes
+                .iter()
+                .filter(|node| states.contains(&node.status.state.as_str()))
+                .count() as u32
+        };
+        Self {
+            id: pool.id.clone(),
+            name: pool.name.clone(),
+            size: pool.size.clone(),
+            auto_scale: pool.auto_scale,
+            min_nodes: pool.min_nodes,
+            max_nodes: pool.max_nodes,
+            desired: pool.count,
+            running: count(&["running"]),
+            provisioning: count(&["provisioning"]),
+            leaving: count(&["draining", "deleting"]),
+        }
+    }
+
+    /// What the pool's node count is doing.
+    pub fn activity(&self) -> ScalingActivity {
+        if self.leaving > 0 || self.running > self.desired {
+            ScalingActivity::ScalingDown
+        } else if self.provisioning > 0 || self.running < self.desired {
+            ScalingActivity::ScalingUp
+        } else {
+            ScalingActivity::Steady
+        }
+    }
+
+    /// Whether the autoscaler wants as many nodes as it may have.
+    pub fn at_max(&self) -> bool {
+        self.auto_scale && self.desired >= self.max_nodes
+    }
+
+    /// Whether the autoscaler wants as few nodes as it may have.
+    pub fn at_min(&self) -> bool {
+        self.auto_scale && self.desired <= self.min_nodes
+    }
+}
+
+/// Autoscaler status of every node pool of a cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoscalingReport {
+    pub cluster_id: String,
+    pub cluster_name: String,
+    pub pools: Vec<PoolScaling>,
+}
+
+impl AutoscalingReport {
+    /// The report for `cluster` as fetched.
+    pub fn from_cluster(cluster: &KubernetesCluster) -> Self {
+        Self {
+            cluster_id: cluster.id.clone(),
+            cluster_name: cluster.name.clone(),
+            pools: cluster
+                .node_pools
+                .iter()
+                .map(PoolScaling::from_pool)
+                .collect(),
+        }
+    }
+
+    /// Desired nodes across all pools.
+    pub fn desired(&self) -> u32 {
+        self.pools.iter().map(|pool| pool.desired).sum()
+    }
+
+    /// Running nodes across all pools.
+    pub fn running(&self) -> u32 {
+        self.pools.iter().map(|pool| pool.running).sum()
+    }
+}
+
+impl Client {
+    /// Autoscaler bounds and desired and running node counts of each node pool of
+    /// Kubernetes cluster `cluster_id`.
+    pub async fn autoscaling_report(&self, cluster_id: &str) -> Result<AutoscalingReport, Error> {
+        let cluster = self.kubernetes_cluster(cluster_id).await?;
+        Ok(AutoscalingReport::from_cluster(&cluster))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_report_from_cluster() {
+        let node =
+            |id: &str, state: &str| json!({ "id": id, "name": id, "status": { "state": state } });
+        let cluster: KubernetesCluster = serde_json::from_value(json!({
+            "id": "bd5f5959-5e1e-4205-a714-a914373942af",
+            "name": "prod",
+            "region": "nyc1",
+            "version": "1.29.1-do.0",
+            "status": { "state": "running" },
+            "created_at": "2024-01-01T00:00:00Z",
+            "node_pools": [
+                {
+                    "id": "p1", "name": "web", "size": "s-2vcpu-4gb", "count": 4,
+                    "auto_scale": true, "min_nodes": 2, "max_nodes": 4,
+                    "nodes": [
+                        node("n1", "running"), node("n2", "running"),
+                        node("n3", "running"), node("n4", "provisioning"),
+                    ],
+                },
+                {
+                    "id": "p2", "name": "batch", "size": "c-4", "count": 1,
+                    "nodes": [node("n5", "running"), node("n6", "draining")],
+                },
+            ],
+        }))
+        .unwrap();
+
+        let report = AutoscalingReport::from_cluster(&cluster);
+        let web = &report.pools[0];
+        assert_eq!(
+            (
+                web.auto_scale,
+                web.min_nodes,
+                web.max_nodes,
+                web.desired,
+                web.running
+            ),
+            (true, 2, 4, 4, 3)
+        );
+        assert_eq!(web.activity(), ScalingActivity::ScalingUp);
+        assert!(web.at_max() && !web.at_min());
+
+        let batch = &report.pools[1];
+        assert_eq!(
+            (batch.auto_scale, batch.desired, batch.running),
+            (false, 1, 1)
+        );
+        assert_eq!(batch.activity(), ScalingActivity::ScalingDown);
+        assert!(!batch.at_max());
+        assert_eq!((report.desired(), report.running()), (5, 4));
+    }
+}
@@ -0,0 +1,437 @@
+//! Building a Kubernetes cluster create request.
+//!
+//! [`KubernetesClusterBuilder`] takes the cluster settings and its [`NodePool`]s
+//! through fluent setters and checks them locally. The Kubernetes version can be
+//! pinned to a slug or follow the latest patch of a minor release with
+//! [`KubernetesVersion::latest_patch_of`], resolved against the versions the API
+//! offers when the cluster is created.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::kubernetes::{KubernetesClusterBuilder, KubernetesVersion, NodePool, Taint, TaintEffect};
+//! use rsdo::wait::WaitOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster = KubernetesClusterBuilder::new("prod")
+//!     .region("nyc1")
+//!     .version(KubernetesVersion::latest_patch_of("1.29"))
+//!     .node_pool(NodePool::new("web", "s-2vcpu-4gb").count(3))
+//!     .node_pool(
+//!         NodePool::new("batch", "c-4")
+//!             .autoscale(0, 10)
+//!             .label("workload", "batch")
+//!             .taint(Taint::new("workload", "batch", TaintEffect::NoSchedule)),
+//!     )
+//!     .create_and_wait(&client, WaitOptions::default())
+//!     .await?;
+//! println!("{} is {}", cluster.name, cluster.status.state);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{ClusterEnvelope, ClusterState, KubernetesCluster};
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Kubernetes version of a new cluster.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum KubernetesVersion {
+    /// The newest version the API offers.
+    #[default]
+    Latest,
+    /// The newest patch release of a minor version such as `1.29`.
+    LatestPatch(String),
+    /// An exact version slug, e.g. `1.29.1-do.0`.
+    Slug(String),
+}
+
+impl KubernetesVersion {
+    pub fn latest() -> Self {
+        Self::Latest
+    }
+
+    pub fn latest_patch_of(minor: impl Into<String>) -> Self {
+        Self::LatestPatch(minor.into())
+    }
+}
+
+impl From<&str> for KubernetesVersion {
+    fn from(slug: &str) -> Self {
+        Self::Slug(slug.to_string())
+    }
+}
+
+/// Effect of a node taint on pods that do not tolerate it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TaintEffect {
+    NoSchedule,
+    PreferNoSchedule,
+    NoExecute,
+}
+
+/// A taint applied to every node of a pool.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Taint {
+    pub key: String,
+    #[serde(default)]
+    pub value: String,
+    pub effect: TaintEffect,
+}
+
+impl Taint {
+    pub fn new(key: impl Into<String>, value: impl Into<String>, effect: TaintEffect) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            effect,
+        }
+    }
+}
+
+/// A node pool of a cluster to create.
+#[derive(Debug, Clone, Serialize)]
+pub struct NodePool {
+    name: String,
+    size: String,
+    count: u32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    auto_scale: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_nodes: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_nodes: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    taints: Vec<Taint>,
+}
+
+impl NodePool {
+    /// A pool named `name` of one `size` node, e.g. `s-2vcpu-4gb`.
+    pub fn new(name: impl Into<String>, size: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            size: size.into(),
+            count: 1,
+            auto_scale: false,
+            min_nodes: None,
+            max_nodes: None,
+            tags: Vec::new(),
+            labels: BTreeMap::new(),
+            taints: Vec::new(),
+        }
+    }
+
+    /// Number of nodes, or the initial number when autoscaling.
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Scale between `min` and `max` nodes. The initial count is raised to `min`, or
+    /// to one node if `min` is zero, when it is lower.
+    pub fn autoscale(mut self, min: u32, max: u32) -> Self {
+        self.auto_scale = true;
+        self.min_nodes = Some(min);
+        self.max_nodes = Some(max);
+        self.count = self.count.max(min).max(1);
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Kubernetes label applied to every node.
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn taint(mut self, taint: Taint) -> Self {
+        self.taints.push(taint);
+        self
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.name.is_empty() {
+            problems.push("a node pool has no name".to_string());
+        }
+        if self.size.is_empty() {
+            problems.push(format!("node pool {:?} has no size", self.name));
+        }
+        match (self.min_nodes, self.max_nodes) {
+            (Some(min), Some(max)) if min > max => problems.push(format!(
+                "node pool {:?} autoscales from {min} to fewer nodes ({max})",
+                self.name
+            )),
+            (_, Some(max)) if self.count > max => problems.push(format!(
+                "node pool {:?} starts with {} nodes, above its maximum of {max}",
+                self.name, self.count
+            )),
+            _ if self.count == 0 => {
+                problems.push(format!("node pool {:?} has no nodes", self.name))
+            }
+            _ => {}
+        }
+        problems
+    }
+}
+
+/// Builder for creating a Kubernetes cluster.
+#[derive(Debug, Clone, Default)]
+#[must_use = "call `.create()` or `.create_and_wait()` to create the cluster"]
+pub struct KubernetesClusterBuilder {
+    name: String,
+    region: Option<String>,
+    version: KubernetesVersion,
+    vpc_uuid: Option<String>,
+    tags: Vec<String>,
+    node_pools: Vec<NodePool>,
+    ha: bool,
+    auto_upgrade: bool,
+    surge_upgrade: bool,
+}
+
+impl KubernetesClusterBuilder {
+    /// Start a cluster named `name`, on the latest Kubernetes version.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Region slug, e.g. `nyc1`. Required.
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<KubernetesVersion>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// VPC to place the cluster in, instead of the region's default.
+    pub fn vpc(mut self, vpc_uuid: impl Into<String>) -> Self {
+        self.vpc_uuid = Some(vpc_uuid.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Add a node pool. At least one is required.
+    pub fn node_pool(mut self, pool: NodePool) -> Self {
+        self.node_pools.push(pool);
+        self
+    }
+
+    /// Run a highly available control plane.
+    pub fn ha(mut self, enabled: bool) -> Self {
+        self.ha = enabled;
+        self
+    }
+
+    /// Upgrade to new patch releases in the maintenance window.
+    pub fn auto_upgrade(mut self, enabled: bool) -> Self {
+        self.auto_upgrade = enabled;
+        self
+    }
+
+    /// Create extra nodes during upgrades so workloads keep their capacity.
+    pub fn surge_upgrade(mut self, enabled: bool) -> Self {
+        self.surge_upgrade = enabled;
+        self
+    }
+
+    /// Check the request without sending it.
+    ///
+    /// Fails with [`Error::InvalidInput`] listing every problem: a missing name or
+    /// region, no node pools, pools with the same name, or a pool whose node counts
+    /// do not fit together.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut problems = Vec::new();
+        if self.name.is_empty() {
+            problems.push("name is empty".to_string());
+        }
+        if self.region.is_none() {
+            problems.push("region is required".to_string());
+        }
+        if self.node_pools.is_empty() {
+            problems.push("at least one node pool is required".to_string());
+        }
+        for (i, pool) in self.node_pools.iter().enumerate() {
+            if self.node_pools[..i].iter().any(|p| p.name == pool.name) {
+                problems.push(format!("node pool name {:?} is used twice", pool.name));
+            }
+            problems.extend(pool.problems());
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(format!(
+                "invalid Kubernetes cluster {:?}: {}",
+                self.name,
+                problems.join("; ")
+            )))
+        }
+    }
+
+    /// The version slug to send, looking up the latest patch of a minor version.
+    async fn version_slug(&self, client: &Client) -> Result<String, Error> {
+        let minor = match &self.version {
+            KubernetesVersion::Latest => return Ok("latest".to_string()),
+            KubernetesVersion::Slug(slug) => return Ok(slug.clone()),
+            KubernetesVersion::LatestPatch(minor) => minor,
+        };
+        let options = client.kubernetes_options().await?;
+        options
+            .versions_for_minor(minor)
+            .first()
+            .map(|release| release.slug.clone())
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("no Kubernetes {minor} release is available"))
+            })
+    }
+
+    /// The validated `kubernetes_create_cluster` body, with the version resolved.
+    pub async fn body(&self, client: &Client) -> Result<Value, Error> {
+        self.validate()?;
+        let mut body = json!({
+            "name": self.name,
+            "region": self.region,
+            "version": self.version_slug(client).await?,
+            "node_pools": self.node_pools,
+        });
+        if let Some(vpc_uuid) = &self.vpc_uuid {
+            body["vpc_uuid"] = json!(vpc_uuid);
+        }
+        if !self.tags.is_empty() {
+            body["tags"] = json!(self.tags);
+        }
+        for (key, enabled) in [
+            ("ha", self.ha),
+            ("auto_upgrade", self.auto_upgrade),
+            ("surge_upgrade", self.surge_upgrade),
+        ] {
+            if enabled {
+                body[key] = json!(true);
+            }
+        }
+        Ok(body)
+    }
+
+    /// Create the cluster and return it as the API first reports it, provisioning.
+    pub async fn create(&self, client: &Client) -> Result<KubernetesCluster, Error> {
+        let body = self.body(client).await?;
+        let envelope: ClusterEnvelope = client
+            .send_json(
+                ApiRequest::post("kubernetes_create_cluster", "/v2/kubernetes/clusters").json(body),
+            )
+            .await?;
+        Ok(envelope.kubernetes_cluster)
+    }
+
+    /// Create the cluster and poll it until it and every node of every pool are
+    /// running.
+    ///
+    /// Fails if the cluster turns `error` or `invalid`, or with [`Error::Timeout`].
+    pub async fn create_and_wait(
+        &self,
+        client: &Client,
+        options: WaitOptions,
+    ) -> Result<KubernetesCluster, Error> {
+        let workflow = Workflow::new(
+            client.inner(),
+            "create_kubernetes_cluster_and_wait",
+            format!("Kubernetes cluster {}", self.name),
+        );
+        let created = workflow.step("create", self.create(client)).await?;
+        let id = created.id;
+        let waiter = client
+            .waiter(format!("Kubernetes cluster {id} to be running"), || {
+                client.kubernetes_cluster(&id)
+            })
+            .until(KubernetesCluster::is_fully_running)
+            .fail_if(|cluster| {
+                matches!(
+                    cluster.status.state,
+                    ClusterState::Error | ClusterState::Invalid
+                )
+                .then(|| {
+                    Error::Other(format!(
+                        "Kubernetes cluster {id} is {}: {}",
+                        cluster.status.state,
+                        cluster.status.message.as_deref().unwrap_or("no message")
+                    ))
+                })
+            })
+            .state(|cluster| {
+                let running = cluster
+                    .node_pools
+                    .iter()
+                    .flat_map(|pool| &pool.nodes)
+                    .filter(|node| node.status.state == "running")
+                    .count();
+                let wanted: u32 = cluster.node_pools.iter().map(|pool| pool.count).sum();
+                format!("{}, {running}/{wanted} nodes", cluster.status.state)
+            })
+            .typical_duration(|_| Some(Duration::from_secs(360)))
+            .options(options);
+        workflow.step("wait", waiter.wait()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_pool_validation_and_body() {
+        let pool = NodePool::new("batch", "c-4")
+            .autoscale(2, 10)
+            .label("workload", "batch")
+            .taint(Taint::new("workload", "batch", TaintEffect::NoSchedule));
+        assert_eq!(
+            serde_json::to_value(&pool).unwrap(),
+            json!({
+                "name": "batch",
+                "size": "c-4",
+                "count": 2,
+                "auto_scale": true,
+                "min_nodes": 2,
+                "max_nodes": 10,
+                "labels": { "workload": "batch" },
+                "taints": [{ "key": "workload", "value": "batch", "effect": "NoSchedule" }],
+            })
+        );
+
+        let err = KubernetesClusterBuilder::new("prod")
+            .node_pool(NodePool::new("web", "s-2vcpu-4gb").count(5).autoscale(1, 3))
+            .node_pool(NodePool::new("web", "s-2vcpu-4gb"))
+            .validate()
+            .unwrap_err();
+        let Error::InvalidInput(message) = err else {
+            panic!("expected invalid input");
+        };
+        assert!(message.contains("region is required"));
+        assert!(message.contains("\"web\" is used twice"));
+        assert!(message.contains("above its maximum of 3"));
+    }
+}
@@ -0,0 +1,283 @@
+//! Clusterlint runs and their diagnostics.
+//!
+//! Clusterlint checks a cluster's workloads for problems that break upgrades or
+//! violate best practices. A run happens in the background: [`Client::run_clusterlint`]
+//! starts one and [`Client::clusterlint_report`] fetches the result, typed as
+//! [`Diagnostic`]s. [`Client::run_clusterlint_and_wait`] does both, polling until the
+//! run completes.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::kubernetes::{ClusterlintChecks, Severity};
+//! use rsdo::wait::WaitOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let report = client
+//!     .run_clusterlint_and_wait(
+//!         "bd5f5959-5e1e-4205-a714-a914373942af",
+//!         &ClusterlintChecks::default().exclude_group("basic"),
+//!         WaitOptions::default(),
+//!     )
+//!     .await?;
+//! for diagnostic in report.with_severity(Severity::Error) {
+//!     println!("{}: {} ({})", diagnostic.check_name, diagnostic.message, diagnostic.object.name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Severity {
+    Error,
+    Warning,
+    Suggestion,
+    /// A severity this version of rsdo does not know about yet.
+    Unknown(String),
+}
+
+impl From<String> for Severity {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "error" => Self::Error,
+            "warning" => Self::Warning,
+            "suggestion" => Self::Suggestion,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<Severity> for String {
+    fn from(severity: Severity) -> Self {
+        severity.to_string()
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Suggestion => "suggestion",
+            Self::Unknown(severity) => severity,
+        })
+    }
+}
+
+/// Which checks a clusterlint run performs. Empty lists mean the API's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ClusterlintChecks {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    include_groups: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    include_checks: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exclude_groups: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exclude_checks: Vec<String>,
+}
+
+impl ClusterlintChecks {
+    /// Run the checks of `group`, e.g. `doks`.
+    pub fn include_group(mut self, group: impl Into<String>) -> Self {
+        self.include_groups.push(group.into());
+        self
+    }
+
+    /// Run the check named `check`, e.g. `unused-config-map`.
+    pub fn include_check(mut self, check: impl Into<String>) -> Self {
+        self.include_checks.push(check.into());
+        self
+    }
+
+    pub fn exclude_group(mut self, group: impl Into<String>) -> Self {
+        self.exclude_groups.push(group.into());
+        self
+    }
+
+    pub fn exclude_check(mut self, check: impl Into<String>) -> Self {
+        self.exclude_checks.push(check.into());
+        self
+    }
+}
+
+/// The Kubernetes object a diagnostic is about.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LintedObject {
+    pub name: String,
+    /// e.g. `Pod` or `Deployment`.
+    pub kind: String,
+    #[serde(default)]
+    pub namespace: String,
+    /// Objects owning this one, such as the deployment of a pod.
+    #[serde(default)]
+    pub owners: Vec<ObjectOwner>,
+}
+
+/// An owner reference of a [`LintedObject`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ObjectOwner {
+    pub kind: String,
+    pub name: String,
+}
+
+/// One problem clusterlint found.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Diagnostic {
+    pub check_name: String,
+    pub severity: Severity,
+    pub message: String,
+    pub object: LintedObject,
+}
+
+/// Result of a clusterlint run.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ClusterlintReport {
+    pub run_id: String,
+    pub requested_at: DateTime<Utc>,
+    /// `None` while the run is in progress.
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ClusterlintReport {
+    /// The diagnostics of `severity`.
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(move |diagnostic| diagnostic.severity == severity)
+    }
+}
+
+#[derive(Deserialize)]
+struct RunStarted {
+    run_id: String,
+}
+
+impl Client {
+    /// Start a clusterlint run of `checks` on Kubernetes cluster `cluster_id` and
+    /// return its run ID.
+    pub async fn run_clusterlint(
+        &self,
+        cluster_id: &str,
+        checks: &ClusterlintChecks,
+    ) -> Result<String, Error> {
+        let started: RunStarted = self
+            .send_json(
+                ApiRequest::post(
+                    "kubernetes_run_clusterlint",
+                    format!("/v2/kubernetes/clusters/{cluster_id}/clusterlint"),
+                )
+                .json(serde_json::to_value(checks)?),
+            )
+            .await?;
+        Ok(started.run_id)
+    }
+
+    /// Fetch the report of clusterlint run `run_id`, or of the latest run.
+    pub async fn clusterlint_report(
+        &self,
+        cluster_id: &str,
+        run_id: Option<&str>,
+    ) -> Result<ClusterlintReport, Error> {
+        let mut request = ApiRequest::get(
+            "kubernetes_get_clusterlint",
+            format!("/v2/kubernetes/clusters/{cluster_id}/clusterlint"),
+        );
+        if let Some(run_id) = run_id {
+            request = request.query("run_id", run_id);
+        }
+        self.send_json(request).await
+    }
+
+    /// Run clusterlint on Kubernetes cluster `cluster_id` and poll until the report
+    /// is complete.
+    ///
+    /// The report is not found until the run has finished, which is waited out.
+    /// Fails with [`Error::Timeout`] if it is not complete after the options' timeout.
+    pub async fn run_clusterlint_and_wait(
+        &self,
+        cluster_id: &str,
+        checks: &ClusterlintChecks,
+        options: WaitOptions,
+    ) -> Result<ClusterlintReport, Error> {
+        let workflow = Workflow::new(
+            self.inner(),
+            "run_clusterlint_and_wait",
+            format!("Kubernetes cluster {cluster_id}"),
+        );
+        let run_id = workflow
+            .step("run", self.run_clusterlint(cluster_id, checks))
+            .await?;
+        let waiter = self
+            .waiter(format!("clusterlint run {run_id}"), || async {
+                match self.clusterlint_report(cluster_id, Some(&run_id)).await {
+                    Ok(report) => Ok(Some(report)),
+                    Err(err) if err.is_not_found() => Ok(None),
+                    Err(err) => Err(err),
+                }
+            })
+            .until(|report| {
+                report
+                    .as_ref()
+                    .is_some_and(|report| report.completed_at.is_some())
+            })
+            .state(|report| match report {
+                Some(_) => "completed".to_string(),
+                None => "running".to_string(),
+            })
+            .typical_duration(|_| Some(Duration::from_secs(30)))
+            .options(options);
+        let report = workflow.step("wait", waiter.wait()).await?;
+        Ok(report.expect("the waiter only stops on a report"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_diagnostics_are_typed() {
+        let report: ClusterlintReport = serde_json::from_str(
+            r#"{"run_id": "50c2f44c-011d-493e-aee5-361a4a0d1844",
+                "requested_at": "2019-10-30T05:34:07Z",
+                "completed_at": "2019-10-30T05:34:11Z",
+                "diagnostics": [{
+                    "check_name": "unused-config-map",
+                    "severity": "warning",
+                    "message": "Unused config map",
+                    "object": {"name": "foo", "kind": "config map", "namespace": "kube-system"}
+                }, {
+                    "check_name": "bare-pods",
+                    "severity": "error",
+                    "message": "Pod has no owner",
+                    "object": {"name": "nginx", "kind": "pod", "namespace": "default",
+                               "owners": [{"kind": "ReplicaSet", "name": "nginx-5d4f"}]}
+                }]}"#,
+        )
+        .unwrap();
+        let errors: Vec<&Diagnostic> = report.with_severity(Severity::Error).collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].check_name, "bare-pods");
+        assert_eq!(errors[0].object.owners[0].kind, "ReplicaSet");
+        assert_eq!(
+            serde_json::to_value(ClusterlintChecks::default().exclude_group("basic")).unwrap(),
+            serde_json::json!({ "exclude_groups": ["basic"] })
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Rotating a cluster's access credentials.
+//!
+//! Every call to the `credentials` and `kubeconfig` endpoints issues a new token.
+//! [`Client::rotate_cluster_credentials`] fetches both with the same lifetime, so the
+//! raw credentials and the kubeconfig handed to tools carry the same new token
+//! generation, and reports when they expire.
+//!
+//! The API has no call that revokes earlier tokens: they keep working until their
+//! own expiry. Keep lifetimes short if rotation is meant to cut off old credentials.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let rotated = client
+//!     .rotate_cluster_credentials("bd5f5959-5e1e-4205-a714-a914373942af")
+//!     .await?;
+//! std::fs::write("kubeconfig.yaml", &rotated.kubeconfig.yaml).unwrap();
+//! println!("valid until {}", rotated.expires_at);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{ClusterKubeconfig, DEFAULT_KUBECONFIG_EXPIRY};
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Credentials for a cluster's API server.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ClusterCredentials {
+    /// URL of the API server.
+    pub server: String,
+    /// Base64 of the PEM CA certificate of the API server.
+    pub certificate_authority_data: String,
+    /// Bearer token; `None` on clusters still using client certificates.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Base64 of the PEM client certificate, on older clusters.
+    #[serde(default)]
+    pub client_certificate_data: Option<String>,
+    #[serde(default)]
+    pub client_key_data: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Outcome of [`Client::rotate_cluster_credentials`].
+#[derive(Debug, Clone)]
+pub struct RotatedCredentials {
+    pub credentials: ClusterCredentials,
+    pub kubeconfig: ClusterKubeconfig,
+    /// When the first of the new credentials stops working.
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Client {
+    /// Fetch credentials for Kubernetes cluster `cluster_id`, valid for `expiry`
+    /// rounded down to whole seconds, or for [`DEFAULT_KUBECONFIG_EXPIRY`].
+    pub async fn cluster_credentials(
+        &self,
+        cluster_id: &str,
+        expiry: Option<Duration>,
+    ) -> Result<ClusterCredentials, Error> {
+        let mut request = ApiRequest::get(
+            "kubernetes_get_credentials",
+            format!("/v2/kubernetes/clusters/{cluster_id}/credentials"),
+        );
+        if let Some(expiry) = expiry {
+            request = request.query("expiry_seconds", expiry.as_secs());
+        }
+        self.send_json(request).await
+    }
+
+    /// Issue new credentials and a new kubeconfig for Kubernetes cluster `cluster_id`,
+    /// valid for [`DEFAULT_KUBECONFIG_EXPIRY`].
+    pub async fn rotate_cluster_credentials(
+        &self,
+        cluster_id: &str,
+    ) -> Result<RotatedCredentials, Error> {
+        self.rotate_cluster_credentials_with(cluster_id, DEFAULT_KUBECONFIG_EXPIRY)
+            .await
+    }
+
+    /// [`rotate_cluster_credentials`](Self::rotate_cluster_credentials) with
+    /// credentials valid for `expiry`.
+    pub async fn rotate_cluster_credentials_with(
+        &self,
+        cluster_id: &str,
+        expiry: Duration,
+    ) -> Result<RotatedCredentials, Error> {
+        if expiry.as_secs() == 0 {
+            return Err(Error::InvalidInput(
+                "credentials must be valid for at least a second".to_string(),
+            ));
+        }
+        let workflow = Workflow::new(
+            self.inner(),
+            "rotate_cluster_credentials",
+            format!("Kubernetes cluster {cluster_id}"),
+        );
+        let credentials = workflow
+            .step(
+                "credentials",
+                self.cluster_credentials(cluster_id, Some(expiry)),
+            )
+            .await?;
+        let kubeconfig = workflow
+            .step(
+                "kubeconfig",
+                self.kubernetes_kubeconfig_with(cluster_id, expiry),
+            )
+            .await?;
+        let expires_at = credentials.expires_at.min(kubeconfig.expires_at);
+        Ok(RotatedCredentials {
+            credentials,
+            kubeconfig,
+            expires_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials_deserialize_without_client_certificates() {
+        let credentials: ClusterCredentials = serde_json::from_str(
+            r#"{"server": "https://bd5f5959-5e1e-4205-a714-a914373942af.k8s.ondigitalocean.com",
+                "certificate_authority_data": "LS0tLS1CRUdJTiBDRVJUSUZJQ0FURS0tLS0tCg==",
+                "client_certificate_data": null,
+                "client_key_data": null,
+                "token": "$DIGITALOCEAN_TOKEN",
+                "expires_at": "2019-11-09T11:50:28.889080521Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(credentials.token.as_deref(), Some("$DIGITALOCEAN_TOKEN"));
+        assert!(credentials.client_key_data.is_none());
+        assert_eq!(
+            credentials.expires_at.to_rfc3339(),
+            "2019-11-09T11:50:28.889080521+00:00"
+        );
+    }
+}
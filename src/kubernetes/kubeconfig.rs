@@ -0,0 +1,577 @@
+//! DOKS kubeconfigs: fetching them and saving them into a local kubeconfig.
+//!
+//! [`Client::kubernetes_kubeconfig`] downloads a cluster's kubeconfig together with
+//! when its credentials expire, so long-running tools can
+//! [`renew`](ClusterKubeconfig::renew_if_expiring) them in time. With the `kube`
+//! feature, [`ClusterKubeconfig::kube_config`] turns it into a `kube::Config`.
+//!
+//! [`Client::merge_kubeconfig`] does what `doctl kubernetes cluster kubeconfig save`
+//! does: download the cluster's kubeconfig and merge its cluster, user and context
+//! entries into an existing file, leaving every other entry untouched.
+//!
+//! Entries are matched by name. Re-saving a cluster replaces its entries, which is how
+//! expired credentials get refreshed. If a cluster entry of the same name points at a
+//! different API server (two accounts with identically named clusters in one region,
+//! say), the downloaded entries are renamed with a numeric suffix instead of
+//! overwriting the other cluster.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster_id = "bd5f5959-5e1e-4205-a714-a914373942af";
+//! let mut kubeconfig = client
+//!     .kubernetes_kubeconfig_with(cluster_id, Duration::from_secs(3600))
+//!     .await?;
+//! // Later, before the credentials run out:
+//! kubeconfig
+//!     .renew_if_expiring(&client, Duration::from_secs(300))
+//!     .await?;
+//!
+//! let home = std::env::var("HOME").unwrap();
+//! let merged = client
+//!     .merge_kubeconfig("bd5f5959-5e1e-4205-a714-a914373942af", format!("{home}/.kube/config"))
+//!     .await?;
+//! println!("saved context {}", merged.context);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long kubeconfig credentials stay valid when no expiry is requested.
+pub const DEFAULT_KUBECONFIG_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A cluster's kubeconfig and the lifetime of the credentials in it.
+#[derive(Debug, Clone)]
+pub struct ClusterKubeconfig {
+    pub cluster_id: String,
+    /// The kubeconfig as YAML.
+    pub yaml: String,
+    /// When the credentials stop working.
+    pub expires_at: DateTime<Utc>,
+    /// Requested lifetime, reused on renewal; `None` for the API default.
+    expiry: Option<Duration>,
+}
+
+impl ClusterKubeconfig {
+    fn new(
+        cluster_id: &str,
+        yaml: String,
+        expiry: Option<Duration>,
+        fetched_at: DateTime<Utc>,
+    ) -> Self {
+        let lifetime = expiry.unwrap_or(DEFAULT_KUBECONFIG_EXPIRY);
+        Self {
+            cluster_id: cluster_id.to_string(),
+            yaml,
+            expires_at: TimeDelta::from_std(lifetime)
+                .ok()
+                .and_then(|lifetime| fetched_at.checked_add_signed(lifetime))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            expiry,
+        }
+    }
+
+    /// Whether the credentials expire within `margin` from now.
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        let margin = TimeDelta::from_std(margin).unwrap_or(TimeDelta::MAX);
+        self.expires_at - Utc::now() <= margin
+    }
+
+    /// Fetch fresh credentials, with the same lifetime as before, if the current ones
+    /// expire within `margin`. Returns whether they were renewed.
+    pub async fn renew_if_expiring(
+        &mut self,
+        client: &Client,
+        margin: Duration,
+    ) -> Result<bool, Error> {
+        if !self.expires_within(margin) {
+            return Ok(false);
+        }
+        let fetched_at = Utc::now();
+        let yaml = client
+            .fetch_kubeconfig(&self.cluster_id, self.expiry)
+            .await?;
+        *self = Self::new(&self.cluster_id, yaml, self.expiry, fetched_at);
+        Ok(true)
+    }
+
+    /// The kubeconfig as a `kube::Config`, using its current context.
+    #[cfg(feature = "kube")]
+    pub async fn kube_config(&self) -> Result<kube::Config, Error> {
+        let invalid = |err: kube::config::KubeconfigError| {
+            Error::InvalidInput(format!(
+                "kubeconfig of Kubernetes cluster {} is not usable: {err}",
+                self.cluster_id
+            ))
+        };
+        let kubeconfig = kube::config::Kubeconfig::from_yaml(&self.yaml).map_err(invalid)?;
+        kube::Config::from_custom_kubeconfig(
+            kubeconfig,
+            &kube::config::KubeConfigOptions::default(),
+        )
+        .await
+        .map_err(invalid)
+    }
+}
+
+/// How [`Client::merge_kubeconfig_with`] updates the kubeconfig.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KubeconfigOptions {
+    /// Make the cluster's context the current context. Defaults to `true`, as in
+    /// `doctl`.
+    pub set_current_context: bool,
+}
+
+impl Default for KubeconfigOptions {
+    fn default() -> Self {
+        Self {
+            set_current_context: true,
+        }
+    }
+}
+
+/// Outcome of merging a downloaded kubeconfig.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedKubeconfig {
+    /// Name of the context for the cluster, after any renaming.
+    pub context: String,
+    /// Whether the downloaded entries were renamed to avoid overwriting entries of a
+    /// different cluster.
+    pub renamed: bool,
+}
+
+/// The parts of a kubeconfig the merge needs; everything else is kept as is.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Kubeconfig {
+    #[serde(default, deserialize_with = "null_as_empty")]
+    clusters: Vec<NamedEntry>,
+    #[serde(default, deserialize_with = "null_as_empty")]
+    contexts: Vec<NamedEntry>,
+    #[serde(default, deserialize_with = "null_as_empty")]
+    users: Vec<NamedEntry>,
+    #[serde(
+        rename = "current-context",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    current_context: Option<String>,
+    #[serde(flatten)]
+    rest: Mapping,
+}
+
+/// A `clusters`, `contexts` or `users` list entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NamedEntry {
+    name: String,
+    #[serde(flatten)]
+    rest: Mapping,
+}
+
+impl NamedEntry {
+    /// A string field of the nested `cluster`/`context`/`user` object.
+    fn field(&self, object: &str, key: &str) -> Option<&str> {
+        self.rest.get(object)?.get(key)?.as_str()
+    }
+
+    fn set_field(&mut self, object: &str, key: &str, value: &str) {
+        if let Some(Value::Mapping(object)) = self.rest.get_mut(object) {
+            object.insert(Value::from(key), Value::from(value));
+        }
+    }
+}
+
+fn null_as_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<NamedEntry>, D::Error> {
+    Ok(Option::deserialize(deserializer)?.unwrap_or_default())
+}
+
+impl Kubeconfig {
+    fn parse(yaml: &str, what: &str) -> Result<Self, Error> {
+        if yaml.trim().is_empty() {
+            return Ok(Self::empty());
+        }
+        serde_yaml::from_str(yaml)
+            .map_err(|err| Error::InvalidInput(format!("{what} is not a valid kubeconfig: {err}")))
+    }
+
+    fn empty() -> Self {
+        let mut rest = Mapping::new();
+        rest.insert("apiVersion".into(), "v1".into());
+        rest.insert("kind".into(), "Config".into());
+        rest.insert("preferences".into(), Value::Mapping(Mapping::new()));
+        Self {
+            rest,
+            ..Self::default()
+        }
+    }
+
+    fn names(&self) -> HashSet<&str> {
+        self.clusters
+            .iter()
+            .chain(&self.contexts)
+            .chain(&self.users)
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    /// Whether a cluster entry named `name` exists and points at a server other than
+    /// `server`.
+    fn has_other_cluster(&self, name: &str, server: Option<&str>) -> bool {
+        self.clusters
+            .iter()
+            .any(|c| c.name == name && c.field("cluster", "server") != server)
+    }
+
+    /// Append `suffix` to every entry name and to the references between them.
+    fn rename(&mut self, suffix: &str) {
+        for entry in self.clusters.iter_mut().chain(&mut self.users) {
+            entry.name.push_str(suffix);
+        }
+        for context in &mut self.contexts {
+            context.name.push_str(suffix);
+            for key in ["cluster", "user"] {
+                if let Some(target) = context.field("context", key) {
+                    let renamed = format!("{target}{suffix}");
+                    context.set_field("context", key, &renamed);
+                }
+            }
+        }
+        if let Some(current) = &mut self.current_context {
+            current.push_str(suffix);
+        }
+    }
+}
+
+/// Replace entries named like those in `new`, appending the rest.
+fn upsert(entries: &mut Vec<NamedEntry>, new: Vec<NamedEntry>) {
+    for entry in new {
+        match entries.iter_mut().find(|e| e.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => entries.push(entry),
+        }
+    }
+}
+
+/// Merge the `downloaded` kubeconfig of a cluster into `existing`, returning the updated
+/// document.
+///
+/// `existing` may be empty. This is the file-free core of [`Client::merge_kubeconfig`].
+pub fn merge_kubeconfig_yaml(
+    existing: &str,
+    downloaded: &str,
+    options: KubeconfigOptions,
+) -> Result<(String, MergedKubeconfig), Error> {
+    let mut config = Kubeconfig::parse(existing, "the existing file")?;
+    let mut new = Kubeconfig::parse(downloaded, "the downloaded kubeconfig")?;
+
+    let renamed = new
+        .clusters
+        .iter()
+        .any(|c| config.has_other_cluster(&c.name, c.field("cluster", "server")));
+    if renamed {
+        let taken = config.names();
+        let suffix = (2..)
+            .map(|n| format!("-{n}"))
+            .find(|suffix| {
+                new.names()
+                    .iter()
+                    .all(|name| !taken.contains(format!("{name}{suffix}").as_str()))
+            })
+            .expect("an unused suffix exists");
+        new.rename(&suffix);
+    }
+    let context = new
+        .current_context
+        .clone()
+        .or_else(|| new.contexts.first().map(|c| c.name.clone()))
+        .ok_or_else(|| Error::Other("the downloaded kubeconfig has no context".to_string()))?;
+
+    upsert(&mut config.clusters, new.clusters);
+    upsert(&mut config.users, new.users);
+    upsert(&mut config.contexts, new.contexts);
+    if options.set_current_context || config.current_context.is_none() {
+        config.current_context = Some(context.clone());
+    }
+
+    let yaml = serde_yaml::to_string(&config)
+        .map_err(|err| Error::Other(format!("failed to serialize kubeconfig: {err}")))?;
+    Ok((yaml, MergedKubeconfig { context, renamed }))
+}
+
+impl Client {
+    /// Download the kubeconfig of a Kubernetes cluster as YAML.
+    pub async fn kubeconfig(&self, cluster_id: &str) -> Result<String, Error> {
+        self.fetch_kubeconfig(cluster_id, None).await
+    }
+
+    /// Download the kubeconfig of a Kubernetes cluster, with credentials valid for
+    /// [`DEFAULT_KUBECONFIG_EXPIRY`].
+    pub async fn kubernetes_kubeconfig(
+        &self,
+        cluster_id: &str,
+    ) -> Result<ClusterKubeconfig, Error> {
+        let fetched_at = Utc::now();
+        let yaml = self.fetch_kubeconfig(cluster_id, None).await?;
+        Ok(ClusterKubeconfig::new(cluster_id, yaml, None, fetched_at))
+    }
+
+    /// Download the kubeconfig of a Kubernetes cluster, with credentials valid for
+    /// `expiry`, rounded down to whole seconds.
+    pub async fn kubernetes_kubeconfig_with(
+        &self,
+        cluster_id: &str,
+        expiry: Duration,
+    ) -> Result<ClusterKubeconfig, Error> {
+        let fetched_at = Utc::now();
+        let yaml = self.fetch_kubeconfig(cluster_id, Some(expiry)).await?;
+        Ok(ClusterKubeconfig::new(
+            cluster_id,
+            yaml,
+            Some(expiry),
+            fetched_at,
+        ))
+    }
+
+    async fn fetch_kubeconfig(
+        &self,
+        cluster_id: &str,
+        expiry: Option<Duration>,
+    ) -> Result<String, Error> {
+        let mut request = ApiRequest::get(
+            "kubernetes_get_kubeconfig",
+            format!("/v2/kubernetes/clusters/{cluster_id}/kubeconfig"),
+        );
+        if let Some(expiry) = expiry {
+            request = request.query("expiry_seconds", expiry.as_secs());
+        }
+        let context = request.context(self.inner().redact_error_paths);
+        let response = self.send(request).await?;
+        let context = context.with_response(response.headers());
+        response
+            .text()
+            .await
+            .map_err(|source| Error::Request { context, source })
+    }
+
+    /// Download the kubeconfig of a cluster and merge it into the kubeconfig at `path`,
+    /// making it the current context.
+    ///
+    /// The file and its parent directories are created if missing.
+    pub async fn merge_kubeconfig(
+        &self,
+        cluster_id: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<MergedKubeconfig, Error> {
+        self.merge_kubeconfig_with(cluster_id, path, KubeconfigOptions::default())
+            .await
+    }
+
+    /// [`merge_kubeconfig`](Self::merge_kubeconfig) with explicit options.
+    pub async fn merge_kubeconfig_with(
+        &self,
+        cluster_id: &str,
+        path: impl AsRef<Path>,
+        options: KubeconfigOptions,
+    ) -> Result<MergedKubeconfig, Error> {
+        let path = path.as_ref();
+        let workflow = Workflow::new(
+            self.inner(),
+            "merge_kubeconfig",
+            format!("Kubernetes cluster {cluster_id}"),
+        );
+        let downloaded = workflow
+            .step("download", self.kubeconfig(cluster_id))
+            .await?;
+        workflow
+            .step("merge", async {
+                let existing = match tokio::fs::read_to_string(path).await {
+                    Ok(existing) => existing,
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+                    Err(source) => return Err(io_error(path, source)),
+                };
+                let (yaml, merged) = merge_kubeconfig_yaml(&existing, &downloaded, options)?;
+                write_private(path, &yaml).await?;
+                Ok(merged)
+            })
+            .await
+    }
+}
+
+fn io_error(path: &Path, source: std::io::Error) -> Error {
+    Error::Io {
+        path: PathBuf::from(path),
+        source,
+    }
+}
+
+/// Replace `path` with `contents` via a temporary file, readable only by the owner as
+/// the file holds credentials.
+async fn write_private(path: &Path, contents: &str) -> Result<(), Error> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|source| io_error(parent, source))?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".rsdo-tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = tokio::fs::OpenOptions::new();
+    file.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    file.mode(0o600);
+    let mut file = file
+        .open(&tmp)
+        .await
+        .map_err(|source| io_error(&tmp, source))?;
+    tokio::io::AsyncWriteExt::write_all(&mut file, contents.as_bytes())
+        .await
+        .map_err(|source| io_error(&tmp, source))?;
+    file.sync_all()
+        .await
+        .map_err(|source| io_error(&tmp, source))?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .map_err(|source| io_error(path, source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn downloaded(server: &str) -> String {
+        format!(
+            r#"apiVersion: v1
+kind: Config
+clusters:
+- cluster:
+    certificate-authority-data: Q0E=
+    server: {server}
+  name: do-nyc1-prod
+contexts:
+- context:
+    cluster: do-nyc1-prod
+    user: do-nyc1-prod-admin
+  name: do-nyc1-prod
+current-context: do-nyc1-prod
+users:
+- name: do-nyc1-prod-admin
+  user:
+    token: new-token
+"#
+        )
+    }
+
+    fn existing() -> String {
+        r#"apiVersion: v1
+kind: Config
+clusters:
+- cluster:
+    server: https://kind.local
+  name: kind
+- cluster:
+    server: https://a.k8s.ondigitalocean.com
+  name: do-nyc1-prod
+contexts:
+- context: {cluster: kind, user: kind}
+  name: kind
+- context: {cluster: do-nyc1-prod, user: do-nyc1-prod-admin}
+  name: do-nyc1-prod
+current-context: kind
+preferences: {}
+users:
+- name: kind
+  user: {token: kind-token}
+- name: do-nyc1-prod-admin
+  user: {token: old-token}
+"#
+        .to_string()
+    }
+
+    #[test]
+    fn test_same_cluster_is_replaced() {
+        let (yaml, merged) = merge_kubeconfig_yaml(
+            &existing(),
+            &downloaded("https://a.k8s.ondigitalocean.com"),
+            KubeconfigOptions::default(),
+        )
+        .unwrap();
+        assert!(!merged.renamed);
+        assert_eq!(merged.context, "do-nyc1-prod");
+
+        let config: Kubeconfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(config.clusters.len(), 2);
+        assert_eq!(config.users.len(), 2);
+        assert_eq!(config.users[1].field("user", "token"), Some("new-token"));
+        assert_eq!(config.current_context.as_deref(), Some("do-nyc1-prod"));
+        assert_eq!(
+            config.rest.get("preferences"),
+            Some(&Value::Mapping(Mapping::new()))
+        );
+    }
+
+    #[test]
+    fn test_different_cluster_with_same_name_is_renamed() {
+        let (yaml, merged) = merge_kubeconfig_yaml(
+            &existing(),
+            &downloaded("https://b.k8s.ondigitalocean.com"),
+            KubeconfigOptions {
+                set_current_context: false,
+            },
+        )
+        .unwrap();
+        assert!(merged.renamed);
+        assert_eq!(merged.context, "do-nyc1-prod-2");
+
+        let config: Kubeconfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(config.clusters.len(), 3);
+        assert_eq!(config.users[1].field("user", "token"), Some("old-token"));
+        let context = &config.contexts[2];
+        assert_eq!(context.name, "do-nyc1-prod-2");
+        assert_eq!(context.field("context", "cluster"), Some("do-nyc1-prod-2"));
+        assert_eq!(
+            context.field("context", "user"),
+            Some("do-nyc1-prod-admin-2")
+        );
+        assert_eq!(config.current_context.as_deref(), Some("kind"));
+    }
+
+    #[test]
+    fn test_requested_expiry_sets_expires_at() {
+        let fetched_at = Utc::now();
+        let short = ClusterKubeconfig::new(
+            "bd5f5959",
+            downloaded("https://a"),
+            Some(Duration::from_secs(600)),
+            fetched_at,
+        );
+        assert_eq!(short.expires_at, fetched_at + TimeDelta::seconds(600));
+        assert!(short.expires_within(Duration::from_secs(900)));
+        assert!(!short.expires_within(Duration::from_secs(60)));
+
+        let default = ClusterKubeconfig::new("bd5f5959", downloaded("https://a"), None, fetched_at);
+        assert_eq!(default.expires_at, fetched_at + TimeDelta::days(7));
+    }
+
+    #[test]
+    fn test_merge_into_empty_file() {
+        let (yaml, merged) =
+            merge_kubeconfig_yaml("", &downloaded("https://a"), KubeconfigOptions::default())
+                .unwrap();
+        assert_eq!(merged.context, "do-nyc1-prod");
+        assert!(yaml.contains("kind: Config"));
+        assert!(yaml.contains("current-context: do-nyc1-prod"));
+    }
+}
@@ -0,0 +1,148 @@
+//! Installing 1-Click apps into a cluster.
+//!
+//! [`Client::kubernetes_marketplace_apps`] lists the 1-Click apps available for
+//! Kubernetes. [`Client::install_marketplace_app`] checks the requested slugs against
+//! that catalog, waits for the cluster to be running, since installs sent to a cluster
+//! that is still provisioning are lost, and starts the install.
+//!
+//! The API only reports that the install jobs were started, not when they finish; the
+//! apps come up inside the cluster over the following minutes.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! client
+//!     .install_marketplace_app("bd5f5959-5e1e-4205-a714-a914373942af", &["monitoring", "ingress-nginx"])
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// A 1-Click app in the catalog.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MarketplaceApp {
+    /// Slug to install the app by, e.g. `ingress-nginx`.
+    pub slug: String,
+    /// `kubernetes` or `droplet`.
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Deserialize)]
+struct Catalog {
+    #[serde(rename = "1_clicks", default)]
+    apps: Vec<MarketplaceApp>,
+}
+
+/// The slugs among `requested` that `catalog` does not offer.
+fn unknown_slugs<'a>(catalog: &[MarketplaceApp], requested: &[&'a str]) -> Vec<&'a str> {
+    requested
+        .iter()
+        .copied()
+        .filter(|slug| !catalog.iter().any(|app| app.slug == *slug))
+        .collect()
+}
+
+impl Client {
+    /// List the 1-Click apps that can be installed into a Kubernetes cluster.
+    pub async fn kubernetes_marketplace_apps(&self) -> Result<Vec<MarketplaceApp>, Error> {
+        let catalog: Catalog = self
+            .send_json(
+                ApiRequest::get("oneClicks_list", "/v2/1-clicks").query("type", "kubernetes"),
+            )
+            .await?;
+        Ok(catalog.apps)
+    }
+
+    /// Install the 1-Click `apps` into Kubernetes cluster `cluster_id` once it is
+    /// running, waiting with the default [`WaitOptions`].
+    pub async fn install_marketplace_app(
+        &self,
+        cluster_id: &str,
+        apps: &[&str],
+    ) -> Result<(), Error> {
+        self.install_marketplace_app_with(cluster_id, apps, WaitOptions::default())
+            .await
+    }
+
+    /// [`install_marketplace_app`](Self::install_marketplace_app), waiting for the
+    /// cluster with `options`.
+    ///
+    /// Fails with [`Error::InvalidInput`] naming every slug the catalog does not
+    /// offer, or with [`Error::Timeout`] if the cluster is not running in time.
+    pub async fn install_marketplace_app_with(
+        &self,
+        cluster_id: &str,
+        apps: &[&str],
+        options: WaitOptions,
+    ) -> Result<(), Error> {
+        if apps.is_empty() {
+            return Err(Error::InvalidInput(
+                "no 1-Click apps to install".to_string(),
+            ));
+        }
+        let workflow = Workflow::new(
+            self.inner(),
+            "install_marketplace_app",
+            format!("Kubernetes cluster {cluster_id}"),
+        );
+        let catalog = workflow
+            .step("catalog", self.kubernetes_marketplace_apps())
+            .await?;
+        let unknown = unknown_slugs(&catalog, apps);
+        if !unknown.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "no Kubernetes 1-Click app is named {}",
+                unknown.join(", ")
+            )));
+        }
+        let waiter = self
+            .waiter(
+                format!("Kubernetes cluster {cluster_id} to be running"),
+                || self.kubernetes_cluster(cluster_id),
+            )
+            .until(|cluster| cluster.is_fully_running())
+            .state(|cluster| cluster.status.state.to_string())
+            .typical_duration(|_| Some(Duration::from_secs(360)))
+            .options(options);
+        workflow.step("wait", waiter.wait()).await?;
+        workflow
+            .step(
+                "install",
+                self.send_empty(
+                    ApiRequest::post("oneClicks_install_kubernetes", "/v2/1-clicks/kubernetes")
+                        .json(json!({ "addon_slugs": apps, "cluster_uuid": cluster_id })),
+                ),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_and_unknown_slugs() {
+        let catalog: Catalog = serde_json::from_str(
+            r#"{"1_clicks": [{"slug": "monitoring", "type": "kubernetes"},
+                             {"slug": "ingress-nginx", "type": "kubernetes"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            unknown_slugs(&catalog.apps, &["monitoring", "nginx", "loki"]),
+            ["nginx", "loki"]
+        );
+        assert!(unknown_slugs(&catalog.apps, &["ingress-nginx"]).is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+//! Kubernetes (DOKS) helpers layered on top of the generated cluster operations.
+//!
+//! Like the droplet helpers, these use the smaller [`KubernetesCluster`] model rather
+//! than the generated cluster types.
+
+mod autoscaling;
+mod builder;
+mod clusterlint;
+mod credentials;
+mod kubeconfig;
+mod marketplace;
+mod options;
+mod registry;
+mod upgrade;
+
+pub use autoscaling::{AutoscalingReport, PoolScaling, ScalingActivity};
+pub use builder::{KubernetesClusterBuilder, KubernetesVersion, NodePool, Taint, TaintEffect};
+pub use clusterlint::{
+    ClusterlintChecks, ClusterlintReport, Diagnostic, LintedObject, ObjectOwner, Severity,
+};
+pub use credentials::{ClusterCredentials, RotatedCredentials};
+pub use kubeconfig::{
+    merge_kubeconfig_yaml, ClusterKubeconfig, KubeconfigOptions, MergedKubeconfig,
+    DEFAULT_KUBECONFIG_EXPIRY,
+};
+pub use marketplace::MarketplaceApp;
+pub use options::{KubernetesOption, KubernetesOptions, KubernetesRelease, Semver};
+pub use registry::{DockerAuth, DockerCredentials, RegistryLink, REGISTRY_SERVER};
+pub use upgrade::UpgradePolicy;
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::Client;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// State of a Kubernetes cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ClusterState {
+    Provisioning,
+    Running,
+    Degraded,
+    Upgrading,
+    Error,
+    Deleting,
+    Deleted,
+    Invalid,
+    /// A state this version of rsdo does not know about yet.
+    Unknown(String),
+}
+
+impl From<String> for ClusterState {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "provisioning" => Self::Provisioning,
+            "running" => Self::Running,
+            "degraded" => Self::Degraded,
+            "upgrading" => Self::Upgrading,
+            "error" => Self::Error,
+            "deleting" => Self::Deleting,
+            "deleted" => Self::Deleted,
+            "invalid" => Self::Invalid,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<ClusterState> for String {
+    fn from(state: ClusterState) -> Self {
+        state.to_string()
+    }
+}
+
+impl fmt::Display for ClusterState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Provisioning => "provisioning",
+            Self::Running => "running",
+            Self::Degraded => "degraded",
+            Self::Upgrading => "upgrading",
+            Self::Error => "error",
+            Self::Deleting => "deleting",
+            Self::Deleted => "deleted",
+            Self::Invalid => "invalid",
+            Self::Unknown(state) => state,
+        })
+    }
+}
+
+/// Status of a cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ClusterStatus {
+    pub state: ClusterState,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// A Kubernetes cluster.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KubernetesCluster {
+    pub id: String,
+    pub name: String,
+    /// Region slug, e.g. `nyc1`.
+    pub region: String,
+    /// Version slug, e.g. `1.29.1-do.0`.
+    pub version: String,
+    pub status: ClusterStatus,
+    #[serde(default)]
+    pub node_pools: Vec<KubernetesNodePool>,
+    /// URL of the Kubernetes API server, empty while provisioning.
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(default)]
+    pub vpc_uuid: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub auto_upgrade: bool,
+    #[serde(default)]
+    pub surge_upgrade: bool,
+    /// Whether the control plane is highly available.
+    #[serde(default)]
+    pub ha: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl KubernetesCluster {
+    /// Whether the cluster is running and every node of every pool is too.
+    pub fn is_fully_running(&self) -> bool {
+        self.status.state == ClusterState::Running
+            && self.node_pools.iter().all(KubernetesNodePool::is_running)
+    }
+}
+
+/// A node pool of a cluster.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KubernetesNodePool {
+    pub id: String,
+    pub name: String,
+    /// Droplet size slug of the nodes.
+    pub size: String,
+    /// Desired number of nodes.
+    pub count: u32,
+    #[serde(default)]
+    pub auto_scale: bool,
+    #[serde(default)]
+    pub min_nodes: u32,
+    #[serde(default)]
+    pub max_nodes: u32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub labels: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    pub taints: Vec<Taint>,
+    #[serde(default)]
+    pub nodes: Vec<KubernetesNode>,
+}
+
+impl KubernetesNodePool {
+    /// Whether the pool has its desired number of nodes, all running.
+    pub fn is_running(&self) -> bool {
+        self.nodes.len() == self.count as usize
+            && self.nodes.iter().all(|node| node.status.state == "running")
+    }
+}
+
+/// A node of a node pool.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KubernetesNode {
+    pub id: String,
+    pub name: String,
+    pub status: NodeStatus,
+    /// ID of the droplet backing the node, once it exists.
+    #[serde(default)]
+    pub droplet_id: Option<String>,
+}
+
+/// Status of a node.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NodeStatus {
+    /// `provisioning`, `running`, `draining` or `deleting`.
+    pub state: String,
+}
+
+#[derive(Deserialize)]
+struct ClusterEnvelope {
+    kubernetes_cluster: KubernetesCluster,
+}
+
+impl Client {
+    /// Fetch Kubernetes cluster `id`.
+    pub async fn kubernetes_cluster(&self, id: &str) -> Result<KubernetesCluster, Error> {
+        let envelope: ClusterEnvelope = self
+            .send_json(ApiRequest::get(
+                "kubernetes_get_cluster",
+                format!("/v2/kubernetes/clusters/{id}"),
+            ))
+            .await?;
+        Ok(envelope.kubernetes_cluster)
+    }
+
+    /// Delete a Kubernetes cluster and wait until the API no longer returns it.
+    ///
+    /// Only the cluster itself is deleted; use the generated
+    /// `kubernetes_destroy_associated_resources_dangerous` to remove its load balancers
+    /// and volumes too. Fails with [`Error::Timeout`] if the cluster is still present
+    /// after `timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Cluster UUID
+    /// * `interval` - Delay between polls
+    /// * `timeout` - Upper bound on the total wait
+    pub async fn delete_kubernetes_cluster_and_wait_gone(
+        &self,
+        id: &str,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let path = format!("/v2/kubernetes/clusters/{id}");
+        self.delete_and_wait_gone(
+            ApiRequest::delete("kubernetes_delete_cluster", path.clone()),
+            ApiRequest::get("kubernetes_get_cluster", path),
+            &format!("Kubernetes cluster {id}"),
+            interval,
+            timeout,
+        )
+        .await
+    }
+
+    /// Poll Kubernetes cluster `id` until the API no longer returns it.
+    ///
+    /// Fails with [`Error::Timeout`] if the cluster is still present after the
+    /// options' timeout.
+    pub async fn wait_until_kubernetes_cluster_deleted(
+        &self,
+        id: &str,
+        options: WaitOptions,
+    ) -> Result<(), Error> {
+        self.wait_until_deleted_at(
+            ApiRequest::get(
+                "kubernetes_get_cluster",
+                format!("/v2/kubernetes/clusters/{id}"),
+            ),
+            &format!("Kubernetes cluster {id}"),
+            options,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A client for a local server answering one request per connection with
+    /// `statuses`, in order, and the task returning the request lines it saw.
+    async fn client_for(
+        statuses: &'static [&'static str],
+    ) -> (Client, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                while !request.ends_with(b"\r\n\r\n") {
+                    let mut buf = [0; 1024];
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8(request).unwrap();
+                requests.push(request.lines().next().unwrap().to_string());
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-length: 2\r\nconnection: close\r\n\r\n{{}}"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        let client = crate::ClientBuilder::new("test-token")
+            .base_url(base_url)
+            .build()
+            .unwrap();
+        (client, server)
+    }
+
+    #[tokio::test]
+    async fn test_not_found_means_gone() {
+        let id = "bd5f5959-5e1e-4205-a714-a914373942af";
+        let path = format!("/v2/kubernetes/clusters/{id}");
+
+        // A cluster that is already gone is not waited for.
+        let (client, server) = client_for(&["404 Not Found"]).await;
+        client
+            .delete_kubernetes_cluster_and_wait_gone(id, Duration::ZERO, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(server.await.unwrap(), [format!("DELETE {path} HTTP/1.1")]);
+
+        let (client, server) = client_for(&["200 OK", "404 Not Found"]).await;
+        client
+            .wait_until_kubernetes_cluster_deleted(
+                id,
+                WaitOptions::default().interval(Duration::ZERO),
+            )
+            .await
+            .unwrap();
+        let get = format!("GET {path} HTTP/1.1");
+        assert_eq!(server.await.unwrap(), [get.clone(), get]);
+
+        // Any other failure is not mistaken for deletion.
+        let (client, server) = client_for(&["403 Forbidden"]).await;
+        let err = client
+            .wait_until_kubernetes_cluster_deleted(id, WaitOptions::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(reqwest::StatusCode::FORBIDDEN));
+        server.await.unwrap();
+    }
+}
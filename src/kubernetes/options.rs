@@ -0,0 +1,214 @@
+//! The versions, regions and sizes DOKS offers.
+//!
+//! `kubernetes_list_options` returns versions as slugs like `1.29.1-do.0`, which sort
+//! wrongly as strings (`1.29.10` before `1.29.9`). [`Client::kubernetes_options`]
+//! parses them into [`Semver`] so they compare as versions, and
+//! [`KubernetesOptions::latest_version`] and [`KubernetesOptions::versions_for_minor`]
+//! answer the usual questions.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let options = client.kubernetes_options().await?;
+//! if let Some(version) = options.versions_for_minor("1.29").first() {
+//!     println!("newest 1.29 release: {}", version.slug);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use serde::{Deserialize, Deserializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A `major.minor.patch` version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Semver {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Semver {
+    /// Parse the version part of a slug like `1.29.1-do.0`.
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        slug.split('-').next()?.parse().ok()
+    }
+
+    /// Whether this is a release of minor version `major.minor`, e.g. `1.29`.
+    pub fn is_minor(&self, minor: &str) -> bool {
+        match minor.split_once('.') {
+            Some((major, minor)) => {
+                major.parse() == Ok(self.major) && minor.parse() == Ok(self.minor)
+            }
+            None => false,
+        }
+    }
+}
+
+impl FromStr for Semver {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidInput(format!("{s:?} is not a major.minor.patch version"));
+        let mut parts = s.split('.').map(|part| part.parse().map_err(|_| invalid()));
+        let mut next = || parts.next().unwrap_or_else(|| Err(invalid()));
+        let version = Self {
+            major: next()?,
+            minor: next()?,
+            patch: next()?,
+        };
+        match parts.next() {
+            None => Ok(version),
+            Some(_) => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Semver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl<'de> Deserialize<'de> for Semver {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A Kubernetes release DOKS offers.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KubernetesRelease {
+    /// Slug to create or upgrade clusters with, e.g. `1.29.1-do.0`.
+    pub slug: String,
+    #[serde(rename = "kubernetes_version")]
+    pub version: Semver,
+    #[serde(default)]
+    pub supported_features: Vec<String>,
+}
+
+impl KubernetesRelease {
+    /// DigitalOcean's revision of the release, the `N` in `-do.N`.
+    pub fn do_revision(&self) -> u64 {
+        self.slug
+            .rsplit_once("-do.")
+            .and_then(|(_, revision)| revision.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Order by version, then by DigitalOcean revision.
+    pub(crate) fn cmp_newer(&self, other: &Self) -> Ordering {
+        (self.version, self.do_revision()).cmp(&(other.version, other.do_revision()))
+    }
+}
+
+/// A region or node size DOKS offers.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KubernetesOption {
+    pub slug: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Everything a new cluster can be created with.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct KubernetesOptions {
+    #[serde(default)]
+    pub versions: Vec<KubernetesRelease>,
+    #[serde(default)]
+    pub regions: Vec<KubernetesOption>,
+    #[serde(default)]
+    pub sizes: Vec<KubernetesOption>,
+}
+
+impl KubernetesOptions {
+    /// The newest release.
+    pub fn latest_version(&self) -> Option<&KubernetesRelease> {
+        self.versions.iter().max_by(|a, b| a.cmp_newer(b))
+    }
+
+    /// The releases of minor version `minor`, e.g. `1.29`, newest first.
+    pub fn versions_for_minor(&self, minor: &str) -> Vec<&KubernetesRelease> {
+        let mut versions: Vec<_> = self
+            .versions
+            .iter()
+            .filter(|release| release.version.is_minor(minor))
+            .collect();
+        versions.sort_by(|a, b| b.cmp_newer(a));
+        versions
+    }
+
+    pub fn supports_region(&self, slug: &str) -> bool {
+        self.regions.iter().any(|region| region.slug == slug)
+    }
+
+    pub fn supports_size(&self, slug: &str) -> bool {
+        self.sizes.iter().any(|size| size.slug == slug)
+    }
+}
+
+#[derive(Deserialize)]
+struct OptionsEnvelope {
+    options: KubernetesOptions,
+}
+
+impl Client {
+    /// Fetch the versions, regions and node sizes clusters can be created with.
+    pub async fn kubernetes_options(&self) -> Result<KubernetesOptions, Error> {
+        let envelope: OptionsEnvelope = self
+            .send_json(ApiRequest::get(
+                "kubernetes_list_options",
+                "/v2/kubernetes/options",
+            ))
+            .await?;
+        Ok(envelope.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_compare_numerically() {
+        let options: KubernetesOptions = serde_json::from_str(
+            r#"{"versions": [
+                    {"slug": "1.29.9-do.0", "kubernetes_version": "1.29.9"},
+                    {"slug": "1.29.10-do.0", "kubernetes_version": "1.29.10"},
+                    {"slug": "1.29.10-do.1", "kubernetes_version": "1.29.10"},
+                    {"slug": "1.30.2-do.0", "kubernetes_version": "1.30.2"}],
+                "regions": [{"name": "New York 1", "slug": "nyc1"}],
+                "sizes": [{"name": "s-2vcpu-4gb", "slug": "s-2vcpu-4gb"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(options.latest_version().unwrap().slug, "1.30.2-do.0");
+        let slugs: Vec<&str> = options
+            .versions_for_minor("1.29")
+            .iter()
+            .map(|release| release.slug.as_str())
+            .collect();
+        assert_eq!(slugs, ["1.29.10-do.1", "1.29.10-do.0", "1.29.9-do.0"]);
+        assert!(options.versions_for_minor("1.2").is_empty());
+        assert!(options.supports_region("nyc1"));
+        assert!(!options.supports_size("c-4"));
+
+        assert_eq!(
+            Semver::from_slug("1.29.1-do.0"),
+            Some(Semver {
+                major: 1,
+                minor: 29,
+                patch: 1
+            })
+        );
+        assert!("1.29".parse::<Semver>().is_err());
+        assert!("1.29.1.2".parse::<Semver>().is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Linking the container registry to clusters.
+//!
+//! Adding the account's DigitalOcean Container Registry (DOCR) to a cluster makes
+//! DigitalOcean create a `registry-<name>` image pull secret in it, holding read-only
+//! Docker credentials. [`Client::link_registry_to_clusters`] adds the registry to the
+//! given clusters and then checks that read-only credentials like those in the secret
+//! are accepted by the registry, so a broken link shows up now rather than as
+//! `ImagePullBackOff` later.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let link = client
+//!     .link_registry_to_clusters(&["bd5f5959-5e1e-4205-a714-a914373942af"])
+//!     .await?;
+//! println!("pull from {}/{}/<image>", link.server, link.registry);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Error, OperationContext};
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::{Client, ClientInfo};
+use reqwest::{header, Method, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Host name of the DigitalOcean Container Registry.
+pub const REGISTRY_SERVER: &str = "registry.digitalocean.com";
+
+/// Outcome of [`Client::link_registry_to_clusters`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryLink {
+    /// Name of the account's registry.
+    pub registry: String,
+    /// Registry host, e.g. `registry.digitalocean.com`.
+    pub server: String,
+    pub clusters: Vec<String>,
+}
+
+/// A Docker `config.json` granting access to the registry.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DockerCredentials {
+    #[serde(default)]
+    pub auths: HashMap<String, DockerAuth>,
+}
+
+/// Credentials for one registry host.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DockerAuth {
+    /// Base64 of `user:password`.
+    pub auth: String,
+}
+
+impl DockerCredentials {
+    /// The `auth` value for `server`.
+    pub fn auth_for(&self, server: &str) -> Option<&str> {
+        self.auths.get(server).map(|auth| auth.auth.as_str())
+    }
+}
+
+#[derive(Deserialize)]
+struct RegistryEnvelope {
+    registry: Registry,
+}
+
+#[derive(Deserialize)]
+struct Registry {
+    name: String,
+}
+
+/// The `key="value"` parameters of a `WWW-Authenticate: Bearer ...` challenge.
+fn bearer_challenge(value: &str) -> Option<HashMap<String, String>> {
+    let params = value.strip_prefix("Bearer ")?;
+    let mut parsed = HashMap::new();
+    for param in params.split(',') {
+        let (key, value) = param.trim().split_once('=')?;
+        parsed.insert(key.to_string(), value.trim_matches('"').to_string());
+    }
+    Some(parsed)
+}
+
+impl Client {
+    /// Fetch Docker credentials for the account's registry, read-only unless
+    /// `read_write`, expiring after `expiry` or never.
+    pub async fn registry_docker_credentials(
+        &self,
+        read_write: bool,
+        expiry: Option<Duration>,
+    ) -> Result<DockerCredentials, Error> {
+        let mut request = ApiRequest::get(
+            "registry_get_dockerCredentials",
+            "/v2/registry/docker-credentials",
+        )
+        .query("read_write", read_write);
+        if let Some(expiry) = expiry {
+            request = request.query("expiry_seconds", expiry.as_secs());
+        }
+        self.send_json(request).await
+    }
+
+    /// Add the account's registry to the Kubernetes clusters `cluster_ids` and check
+    /// that the registry accepts read-only credentials.
+    ///
+    /// Fails if the account has no registry, or with [`Error::Response`] if the
+    /// registry rejects the credentials.
+    pub async fn link_registry_to_clusters(
+        &self,
+        cluster_ids: &[&str],
+    ) -> Result<RegistryLink, Error> {
+        let workflow = Workflow::new(
+            self.inner(),
+            "link_registry_to_clusters",
+            format!("Kubernetes clusters {}", cluster_ids.join(", ")),
+        );
+        let registry: RegistryEnvelope = workflow
+            .step(
+                "registry",
+                self.send_json(ApiRequest::get("registry_get", "/v2/registry")),
+            )
+            .await?;
+        workflow
+            .step(
+                "link",
+                self.send_empty(
+                    ApiRequest::post("kubernetes_add_registry", "/v2/kubernetes/registry")
+                        .json(json!({ "cluster_uuids": cluster_ids })),
+                ),
+            )
+            .await?;
+        let credentials = workflow
+            .step(
+                "credentials",
+                self.registry_docker_credentials(false, Some(Duration::from_secs(300))),
+            )
+            .await?;
+        let auth = credentials.auth_for(REGISTRY_SERVER).ok_or_else(|| {
+            Error::Other(format!(
+                "the Docker credentials have no entry for {REGISTRY_SERVER}"
+            ))
+        })?;
+        workflow
+            .step("verify", self.verify_registry_auth(REGISTRY_SERVER, auth))
+            .await?;
+        Ok(RegistryLink {
+            registry: registry.registry.name,
+            server: REGISTRY_SERVER.to_string(),
+            clusters: cluster_ids.iter().map(|id| id.to_string()).collect(),
+        })
+    }
+
+    /// Remove the account's registry from the Kubernetes clusters `cluster_ids`.
+    pub async fn unlink_registry_from_clusters(&self, cluster_ids: &[&str]) -> Result<(), Error> {
+        self.send_empty(
+            ApiRequest::delete("kubernetes_remove_registry", "/v2/kubernetes/registry")
+                .json(json!({ "cluster_uuids": cluster_ids })),
+        )
+        .await
+    }
+
+    /// Log in to the registry at `server` with `auth` the way Docker does: follow the
+    /// registry's bearer challenge to its token service and request a token.
+    async fn verify_registry_auth(&self, server: &str, auth: &str) -> Result<(), Error> {
+        let send = |url: String| async move {
+            let context = OperationContext::new("registry_login", Method::GET, &url, false);
+            let response = self
+                .client()
+                .get(&url)
+                .header(header::AUTHORIZATION, format!("Basic {auth}"))
+                .send()
+                .await
+                .map_err(|source| Error::Request {
+                    context: context.clone(),
+                    source,
+                })?;
+            Ok::<_, Error>((context, response))
+        };
+
+        let (context, response) = send(format!("https://{server}/v2/")).await?;
+        let challenge = response
+            .headers()
+            .get(header::WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(bearer_challenge);
+        let (context, response) = match (response.status(), challenge) {
+            (StatusCode::UNAUTHORIZED, Some(challenge)) => {
+                let realm = challenge.get("realm").ok_or_else(|| {
+                    Error::Other(format!("{server} sent a bearer challenge without a realm"))
+                })?;
+                let service = challenge.get("service").map_or(server, String::as_str);
+                send(format!("{realm}?service={service}")).await?
+            }
+            _ => (context, response),
+        };
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(Error::Response {
+            context,
+            status,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bearer_challenge() {
+        let challenge = bearer_challenge(
+            r#"Bearer realm="https://api.digitalocean.com/v2/registry/auth",service="registry.digitalocean.com""#,
+        )
+        .unwrap();
+        assert_eq!(
+            challenge["realm"],
+            "https://api.digitalocean.com/v2/registry/auth"
+        );
+        assert_eq!(challenge["service"], "registry.digitalocean.com");
+        assert!(bearer_challenge(r#"Basic realm="registry""#).is_none());
+    }
+}
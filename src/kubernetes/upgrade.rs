@@ -0,0 +1,226 @@
+//! Upgrading a cluster to the newest version it can take.
+//!
+//! [`Client::upgrade_cluster_to_latest`] picks the target from the cluster's available
+//! upgrades, checks it against the [`UpgradePolicy`], starts the upgrade and polls
+//! until the control plane and every node run the new version. Nodes are replaced one
+//! at a time, or up to ten at a time with surge upgrades, which temporarily adds
+//! nodes to each pool; progress reports show those extra nodes and the time estimate
+//! accounts for them.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::kubernetes::UpgradePolicy;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let cluster = client
+//!     .upgrade_cluster_to_latest("bd5f5959-5e1e-4205-a714-a914373942af", UpgradePolicy::patch_only())
+//!     .await?;
+//! println!("{} runs {}", cluster.name, cluster.version);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{ClusterState, KubernetesCluster, KubernetesRelease, Semver};
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// Nodes replaced at once during a surge upgrade.
+const SURGE_BATCH: usize = 10;
+
+/// Which upgrade [`Client::upgrade_cluster_to_latest`] may apply.
+#[derive(Debug, Clone, Default)]
+pub struct UpgradePolicy {
+    patch_only: bool,
+    options: WaitOptions,
+}
+
+impl UpgradePolicy {
+    /// Upgrade to the newest available version, across minor releases.
+    pub fn latest() -> Self {
+        Self::default()
+    }
+
+    /// Only upgrade to the newest patch release of the cluster's minor version.
+    pub fn patch_only() -> Self {
+        Self {
+            patch_only: true,
+            ..Self::default()
+        }
+    }
+
+    /// How to poll the upgrade. Defaults to every 5 seconds for 10 minutes, which
+    /// large clusters without surge upgrades can exceed.
+    pub fn wait_options(mut self, options: WaitOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct AvailableUpgrades {
+    #[serde(default)]
+    available_upgrade_versions: Option<Vec<KubernetesRelease>>,
+}
+
+/// The upgrade `policy` picks from `available` for a cluster on `current`, if any.
+fn pick_target<'a>(
+    current: &str,
+    available: &'a [KubernetesRelease],
+    policy: &UpgradePolicy,
+) -> Result<Option<&'a KubernetesRelease>, Error> {
+    let current = KubernetesRelease {
+        slug: current.to_string(),
+        version: Semver::from_slug(current)
+            .ok_or_else(|| Error::Other(format!("cannot parse cluster version {current:?}")))?,
+        supported_features: Vec::new(),
+    };
+    let same_minor = |release: &KubernetesRelease| {
+        (release.version.major, release.version.minor)
+            == (current.version.major, current.version.minor)
+    };
+    Ok(available
+        .iter()
+        .filter(|release| release.cmp_newer(&current).is_gt())
+        .filter(|release| !policy.patch_only || same_minor(release))
+        .max_by(|a, b| a.cmp_newer(b)))
+}
+
+/// Rough time for `cluster` to finish upgrading from its current state.
+fn upgrade_duration(cluster: &KubernetesCluster) -> Duration {
+    let nodes: usize = cluster
+        .node_pools
+        .iter()
+        .map(|pool| pool.count as usize)
+        .sum();
+    let batches = if cluster.surge_upgrade {
+        nodes.div_ceil(SURGE_BATCH)
+    } else {
+        nodes
+    };
+    Duration::from_secs(300 + 240 * batches as u64)
+}
+
+/// Progress of an upgrade, e.g. `upgrading, 2 surge nodes`.
+fn upgrade_state(cluster: &KubernetesCluster) -> String {
+    let extra: usize = cluster
+        .node_pools
+        .iter()
+        .map(|pool| pool.nodes.len().saturating_sub(pool.count as usize))
+        .sum();
+    if extra > 0 {
+        format!("{}, {extra} surge nodes", cluster.status.state)
+    } else {
+        cluster.status.state.to_string()
+    }
+}
+
+impl Client {
+    /// Upgrade Kubernetes cluster `id` to the newest version `policy` allows and wait
+    /// until the control plane and all nodes run it.
+    ///
+    /// Returns the cluster unchanged when no such upgrade is available. Fails with
+    /// [`Error::InvalidInput`] if the cluster is not running, as soon as the upgrade
+    /// turns the cluster `error`, or with [`Error::Timeout`]. Nodes may be briefly
+    /// `degraded` while they are replaced, which is waited out.
+    pub async fn upgrade_cluster_to_latest(
+        &self,
+        id: &str,
+        policy: UpgradePolicy,
+    ) -> Result<KubernetesCluster, Error> {
+        let workflow = Workflow::new(
+            self.inner(),
+            "upgrade_cluster_to_latest",
+            format!("Kubernetes cluster {id}"),
+        );
+        let base = format!("/v2/kubernetes/clusters/{id}");
+        let (cluster, upgrades) = workflow
+            .step("check", async {
+                let cluster = self.kubernetes_cluster(id).await?;
+                let upgrades: AvailableUpgrades = self
+                    .send_json(ApiRequest::get(
+                        "kubernetes_get_available_upgrades",
+                        format!("{base}/upgrades"),
+                    ))
+                    .await?;
+                Ok((cluster, upgrades))
+            })
+            .await?;
+        if cluster.status.state != ClusterState::Running {
+            return Err(Error::InvalidInput(format!(
+                "Kubernetes cluster {id} is {} and cannot be upgraded",
+                cluster.status.state
+            )));
+        }
+        let available = upgrades.available_upgrade_versions.unwrap_or_default();
+        let Some(target) = pick_target(&cluster.version, &available, &policy)? else {
+            return Ok(cluster);
+        };
+        let target = target.slug.clone();
+
+        workflow
+            .step(
+                "upgrade",
+                self.send_empty(
+                    ApiRequest::post("kubernetes_upgrade_cluster", format!("{base}/upgrade"))
+                        .json(json!({ "version": target })),
+                ),
+            )
+            .await?;
+        let waiter = self
+            .waiter(format!("Kubernetes cluster {id} to run {target}"), || {
+                self.kubernetes_cluster(id)
+            })
+            .until(|cluster| cluster.version == target && cluster.is_fully_running())
+            .fail_if(|cluster| {
+                (cluster.status.state == ClusterState::Error).then(|| {
+                    Error::Other(format!(
+                        "upgrade of Kubernetes cluster {id} to {target} left it {}: {}",
+                        cluster.status.state,
+                        cluster.status.message.as_deref().unwrap_or("no message")
+                    ))
+                })
+            })
+            .state(upgrade_state)
+            .typical_duration(|cluster| Some(upgrade_duration(cluster)))
+            .options(policy.options);
+        workflow.step("wait", waiter.wait()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_target_respects_policy() {
+        let available: Vec<KubernetesRelease> = serde_json::from_value(json!([
+            { "slug": "1.29.10-do.0", "kubernetes_version": "1.29.10" },
+            { "slug": "1.30.4-do.0", "kubernetes_version": "1.30.4" },
+            { "slug": "1.29.9-do.0", "kubernetes_version": "1.29.9" },
+        ]))
+        .unwrap();
+        let pick = |current, policy| {
+            pick_target(current, &available, &policy)
+                .unwrap()
+                .map(|version| version.slug.as_str())
+        };
+        assert_eq!(
+            pick("1.29.1-do.0", UpgradePolicy::latest()),
+            Some("1.30.4-do.0")
+        );
+        assert_eq!(
+            pick("1.29.1-do.0", UpgradePolicy::patch_only()),
+            Some("1.29.10-do.0")
+        );
+        assert_eq!(pick("1.30.4-do.0", UpgradePolicy::latest()), None);
+        assert_eq!(pick("1.29.10-do.0", UpgradePolicy::patch_only()), None);
+        assert!(pick_target("latest", &available, &UpgradePolicy::latest()).is_err());
+    }
+}
@@ -33,9 +33,6 @@
 //! - Automatic serialization/deserialization with serde
 //! - Comprehensive error handling
 
-use reqwest::header::{self, HeaderMap, HeaderValue};
-use std::time::Duration;
-
 // Include the generated code from build.rs
 // Disable doctests for generated code since OpenAPI examples aren't meant to be Rust tests
 #[cfg(doctest)]
@@ -49,6 +46,96 @@ mod generated {
 #[cfg(not(doctest))]
 pub use generated::*;
 
+#[cfg(not(doctest))]
+pub mod account;
+#[cfg(not(doctest))]
+pub mod actions;
+#[cfg(not(doctest))]
+pub mod allowlist;
+#[cfg(not(doctest))]
+pub mod apps;
+#[cfg(not(doctest))]
+pub mod auth;
+#[cfg(not(doctest))]
+pub mod billing;
+#[cfg(not(doctest))]
+mod builder;
+#[cfg(not(doctest))]
+pub mod cancellation;
+#[cfg(not(doctest))]
+pub mod circuit_breaker;
+#[cfg(not(doctest))]
+pub mod databases;
+#[cfg(not(doctest))]
+pub mod dns;
+#[cfg(not(doctest))]
+pub mod droplets;
+#[cfg(not(doctest))]
+pub mod error;
+#[cfg(not(doctest))]
+pub mod events;
+#[cfg(not(doctest))]
+pub mod hedging;
+#[cfg(not(doctest))]
+pub mod idempotency;
+#[cfg(not(doctest))]
+pub mod interceptor;
+#[cfg(not(doctest))]
+pub mod interconnect;
+#[cfg(not(doctest))]
+pub mod inventory;
+#[cfg(not(doctest))]
+pub mod kubernetes;
+#[cfg(not(doctest))]
+pub mod lint;
+#[cfg(not(doctest))]
+pub mod listing;
+#[cfg(not(doctest))]
+pub mod oauth;
+#[cfg(not(doctest))]
+pub mod offline;
+#[cfg(not(doctest))]
+pub mod operations;
+#[cfg(not(doctest))]
+pub mod pagination;
+#[cfg(not(doctest))]
+pub mod projects;
+#[cfg(not(doctest))]
+pub mod rate_limit;
+#[cfg(not(doctest))]
+pub mod raw;
+#[cfg(not(doctest))]
+mod request;
+#[cfg(not(doctest))]
+pub mod request_id;
+#[cfg(not(doctest))]
+pub mod response_meta;
+#[cfg(not(doctest))]
+pub mod retry;
+#[cfg(not(doctest))]
+pub mod snapshots;
+#[cfg(all(feature = "spaces", not(doctest)))]
+pub mod spaces;
+#[cfg(not(doctest))]
+pub mod tags;
+#[cfg(not(doctest))]
+pub mod transfers;
+#[cfg(not(doctest))]
+mod transport;
+#[cfg(not(doctest))]
+pub mod uptime;
+#[cfg(not(doctest))]
+pub mod wait;
+#[cfg(not(doctest))]
+pub mod workflows;
+
+#[cfg(not(doctest))]
+pub use builder::ClientBuilder;
+#[cfg(not(doctest))]
+pub use error::{ApiError, Error, Result};
+#[cfg(not(doctest))]
+pub use transport::ClientState;
+
 // For doctests, provide a minimal stub
 #[cfg(doctest)]
 pub struct Client;
@@ -70,7 +157,9 @@ impl Client {
 impl Client {
     /// Create a new DigitalOcean client with a personal access token.
     ///
-    /// This is the most common way to create a client for the DigitalOcean API.
+    /// This is the most common way to create a client for the DigitalOcean API. It uses
+    /// a 15 second connect timeout and a 30 second request timeout; use
+    /// [`ClientBuilder`] to change those or the base URL.
     ///
     /// # Arguments
     ///
@@ -84,20 +173,9 @@ impl Client {
     /// let client = Client::from_token("your-digitalocean-token");
     /// ```
     pub fn from_token(token: &str) -> Self {
-        let mut headers = HeaderMap::new();
-        let auth_value = HeaderValue::from_str(&format!("Bearer {}", token))
-            .expect("Failed to create authorization header");
-        headers.insert(header::AUTHORIZATION, auth_value);
-
-        let http_client = reqwest::ClientBuilder::new()
-            .connect_timeout(Duration::from_secs(15))
-            .timeout(Duration::from_secs(30))
-            .default_headers(headers)
-            .user_agent("rsdo/0.1.0")
+        ClientBuilder::new(token)
             .build()
-            .expect("Failed to build HTTP client");
-
-        Self::new_with_client("https://api.digitalocean.com", http_client)
+            .expect("Failed to build HTTP client")
     }
 
     /// Create a new DigitalOcean client with a custom reqwest client.
@@ -107,7 +185,9 @@ impl Client {
     ///
     /// # Arguments
     ///
-    /// * `token` - Your DigitalOcean personal access token
+    /// * `token` - Your DigitalOcean personal access token, sent with every request
+    ///   in place of any `Authorization` header `http_client` sets; a token that is
+    ///   not a valid header value fails each request with [`Error::InvalidInput`]
     /// * `http_client` - A configured reqwest::Client
     ///
     /// # Example
@@ -131,10 +211,12 @@ impl Client {
     ///
     /// let client = Client::with_client("your-token", http_client);
     /// ```
-    pub fn with_client(_token: &str, http_client: reqwest::Client) -> Self {
-        // Note: This assumes the client doesn't already have auth headers
-        // In a real implementation, you might want to check and update headers
-        Self::new_with_client("https://api.digitalocean.com", http_client)
+    pub fn with_client(token: &str, http_client: reqwest::Client) -> Self {
+        let state = ClientState {
+            token_provider: Some(std::sync::Arc::new(auth::StaticToken::new(token))),
+            ..ClientState::default()
+        };
+        Self::new_with_client_and_state(builder::DEFAULT_BASE_URL, http_client, state)
     }
 }
 
@@ -161,4 +243,23 @@ mod tests {
         // but we can at least verify the client was created successfully
         assert_eq!(client.baseurl(), "https://api.digitalocean.com");
     }
+
+    #[tokio::test]
+    async fn test_with_client_applies_token() {
+        let client = Client::with_client("team-a", reqwest::Client::new());
+        let mut request = reqwest::Request::new(
+            reqwest::Method::GET,
+            "https://api.digitalocean.com/v2/account".parse().unwrap(),
+        );
+        transport::prepare(&mut request, client.inner(), "account_get")
+            .await
+            .unwrap();
+        assert_eq!(
+            request.headers()[reqwest::header::AUTHORIZATION],
+            "Bearer team-a"
+        );
+
+        let plain = Client::new_with_client(builder::DEFAULT_BASE_URL, reqwest::Client::new());
+        assert_eq!(plain.baseurl(), "https://api.digitalocean.com");
+    }
 }
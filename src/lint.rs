@@ -0,0 +1,514 @@
+//! Static checks for firewall and load balancer configurations.
+//!
+//! The API accepts plenty of configurations that are almost certainly mistakes: SSH open
+//! to the whole internet, a health check probing a port nothing forwards to, rules that
+//! shadow each other. [`lint_firewall`] and [`lint_load_balancer`] inspect a
+//! configuration locally, without calling the API, and return typed warnings so CI
+//! pipelines can flag problems before applying a change.
+//!
+//! The models deserialize from the same JSON the API uses for request and response
+//! bodies, so an existing request body can be linted directly.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rsdo::lint::{lint_firewall, FirewallConfig};
+//!
+//! let firewall: FirewallConfig = serde_json::from_str(r#"{
+//!     "inbound_rules": [
+//!         {"protocol": "tcp", "ports": "22", "sources": {"addresses": ["0.0.0.0/0"]}}
+//!     ]
+//! }"#)?;
+//!
+//! for warning in lint_firewall(&firewall) {
+//!     eprintln!("warning: {warning}");
+//! }
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+
+/// Ports that should never be reachable from the whole internet, with the service
+/// usually listening on them.
+const SENSITIVE_PORTS: &[(u16, &str)] = &[
+    (22, "SSH"),
+    (3306, "MySQL"),
+    (3389, "RDP"),
+    (5432, "PostgreSQL"),
+    (6379, "Redis"),
+    (9200, "Elasticsearch"),
+    (27017, "MongoDB"),
+];
+
+/// Firewall rules, as sent to `POST /v2/firewalls` or returned by the API.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirewallConfig {
+    #[serde(default)]
+    pub inbound_rules: Vec<FirewallRule>,
+    #[serde(default)]
+    pub outbound_rules: Vec<FirewallRule>,
+}
+
+/// A single inbound or outbound firewall rule.
+///
+/// Inbound rules use `sources`, outbound rules use `destinations`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirewallRule {
+    /// `tcp`, `udp` or `icmp`.
+    pub protocol: String,
+    /// A single port, a range such as `8000-9000`, or `all`/`0` for every port. Empty
+    /// for ICMP.
+    #[serde(default)]
+    pub ports: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sources: Option<RuleTargets>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destinations: Option<RuleTargets>,
+}
+
+impl FirewallRule {
+    /// The sources of an inbound rule or the destinations of an outbound one.
+    pub fn targets(&self) -> &RuleTargets {
+        static EMPTY: RuleTargets = RuleTargets {
+            addresses: Vec::new(),
+            droplet_ids: Vec::new(),
+            load_balancer_uids: Vec::new(),
+            kubernetes_ids: Vec::new(),
+            tags: Vec::new(),
+        };
+        self.sources
+            .as_ref()
+            .or(self.destinations.as_ref())
+            .unwrap_or(&EMPTY)
+    }
+}
+
+/// The addresses and resources a firewall rule applies to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleTargets {
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub droplet_ids: Vec<u64>,
+    #[serde(default)]
+    pub load_balancer_uids: Vec<String>,
+    #[serde(default)]
+    pub kubernetes_ids: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Load balancer forwarding and health check settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadBalancerConfig {
+    #[serde(default)]
+    pub forwarding_rules: Vec<ForwardingRule>,
+    #[serde(default)]
+    pub health_check: Option<HealthCheck>,
+}
+
+/// How traffic entering the load balancer is forwarded to its targets.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardingRule {
+    pub entry_protocol: String,
+    pub entry_port: u16,
+    pub target_protocol: String,
+    pub target_port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate_id: Option<String>,
+    #[serde(default)]
+    pub tls_passthrough: bool,
+}
+
+/// Load balancer health check.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub protocol: String,
+    pub port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// Direction of a firewall rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Inbound => "inbound",
+            Self::Outbound => "outbound",
+        })
+    }
+}
+
+/// A likely mistake found in a firewall or load balancer configuration.
+///
+/// Rule indices refer to positions in the corresponding list of the linted
+/// configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LintWarning {
+    /// An inbound rule exposes a sensitive port, such as SSH, to every address.
+    SensitivePortOpenToWorld {
+        rule: usize,
+        port: u16,
+        service: &'static str,
+        source: String,
+    },
+    /// Two firewall rules cover the same protocol, ports and targets, so one of them
+    /// is at least partly redundant.
+    OverlappingRules {
+        direction: Direction,
+        first: usize,
+        second: usize,
+    },
+    /// A firewall rule has a port specification the API will reject.
+    InvalidPorts { direction: Direction, rule: usize },
+    /// The health check probes a port that no forwarding rule targets, so it checks a
+    /// service that never receives traffic.
+    HealthCheckPortNotForwarded { port: u16 },
+    /// Two forwarding rules listen on the same entry port.
+    DuplicateEntryPort {
+        port: u16,
+        first: usize,
+        second: usize,
+    },
+    /// An HTTPS or HTTP/2 forwarding rule has neither a certificate nor TLS
+    /// passthrough.
+    MissingCertificate { rule: usize },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SensitivePortOpenToWorld {
+                rule,
+                port,
+                service,
+                source,
+            } => write!(
+                f,
+                "inbound rule {rule} opens {service} (port {port}) to {source}"
+            ),
+            Self::OverlappingRules {
+                direction,
+                first,
+                second,
+            } => write!(f, "{direction} rules {first} and {second} overlap"),
+            Self::InvalidPorts { direction, rule } => {
+                write!(
+                    f,
+                    "{direction} rule {rule} has an invalid port specification"
+                )
+            }
+            Self::HealthCheckPortNotForwarded { port } => write!(
+                f,
+                "health check port {port} is not the target port of any forwarding rule"
+            ),
+            Self::DuplicateEntryPort {
+                port,
+                first,
+                second,
+            } => write!(
+                f,
+                "forwarding rules {first} and {second} both listen on port {port}"
+            ),
+            Self::MissingCertificate { rule } => write!(
+                f,
+                "forwarding rule {rule} terminates TLS but has no certificate"
+            ),
+        }
+    }
+}
+
+/// Check a firewall configuration for common mistakes.
+pub fn lint_firewall(firewall: &FirewallConfig) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+
+    for (index, rule) in firewall.inbound_rules.iter().enumerate() {
+        let Some(ports) = PortRange::parse(rule) else {
+            continue;
+        };
+        let Some(source) = rule.targets().addresses.iter().find(|a| is_world(a)) else {
+            continue;
+        };
+        for &(port, service) in SENSITIVE_PORTS {
+            if ports.contains(port) {
+                warnings.push(LintWarning::SensitivePortOpenToWorld {
+                    rule: index,
+                    port,
+                    service,
+                    source: source.clone(),
+                });
+            }
+        }
+    }
+
+    for (direction, rules) in [
+        (Direction::Inbound, &firewall.inbound_rules),
+        (Direction::Outbound, &firewall.outbound_rules),
+    ] {
+        lint_rule_list(direction, rules, &mut warnings);
+    }
+
+    warnings
+}
+
+fn lint_rule_list(direction: Direction, rules: &[FirewallRule], warnings: &mut Vec<LintWarning>) {
+    let parsed: Vec<Option<PortRange>> = rules.iter().map(PortRange::parse).collect();
+    for (index, ports) in parsed.iter().enumerate() {
+        if ports.is_none() {
+            warnings.push(LintWarning::InvalidPorts {
+                direction,
+                rule: index,
+            });
+        }
+    }
+
+    for (first, a) in rules.iter().enumerate() {
+        for (offset, b) in rules[first + 1..].iter().enumerate() {
+            let second = first + 1 + offset;
+            let (Some(a_ports), Some(b_ports)) = (parsed[first], parsed[second]) else {
+                continue;
+            };
+            if a.protocol.eq_ignore_ascii_case(&b.protocol)
+                && a_ports.overlaps(b_ports)
+                && targets_overlap(a.targets(), b.targets())
+            {
+                warnings.push(LintWarning::OverlappingRules {
+                    direction,
+                    first,
+                    second,
+                });
+            }
+        }
+    }
+}
+
+/// Check a load balancer configuration for common mistakes.
+pub fn lint_load_balancer(load_balancer: &LoadBalancerConfig) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let rules = &load_balancer.forwarding_rules;
+
+    if let Some(health_check) = &load_balancer.health_check {
+        if !rules.is_empty() && !rules.iter().any(|r| r.target_port == health_check.port) {
+            warnings.push(LintWarning::HealthCheckPortNotForwarded {
+                port: health_check.port,
+            });
+        }
+    }
+
+    for (first, a) in rules.iter().enumerate() {
+        if let Some(offset) = rules[first + 1..]
+            .iter()
+            .position(|b| b.entry_port == a.entry_port)
+        {
+            warnings.push(LintWarning::DuplicateEntryPort {
+                port: a.entry_port,
+                first,
+                second: first + 1 + offset,
+            });
+        }
+
+        let terminates_tls = matches!(
+            a.entry_protocol.to_ascii_lowercase().as_str(),
+            "https" | "http2" | "http3"
+        );
+        if terminates_tls
+            && !a.tls_passthrough
+            && a.certificate_id.as_deref().unwrap_or("").is_empty()
+        {
+            warnings.push(LintWarning::MissingCertificate { rule: first });
+        }
+    }
+
+    warnings
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PortRange {
+    start: u16,
+    end: u16,
+}
+
+impl PortRange {
+    const ALL: Self = Self {
+        start: 1,
+        end: u16::MAX,
+    };
+
+    /// Ports covered by `rule`; `None` if the specification is malformed.
+    fn parse(rule: &FirewallRule) -> Option<Self> {
+        let ports = rule.ports.trim();
+        if rule.protocol.eq_ignore_ascii_case("icmp")
+            || ports.is_empty()
+            || ports == "0"
+            || ports.eq_ignore_ascii_case("all")
+        {
+            return Some(Self::ALL);
+        }
+        let (start, end) = match ports.split_once('-') {
+            Some((start, end)) => (start.trim().parse().ok()?, end.trim().parse().ok()?),
+            None => {
+                let port = ports.parse().ok()?;
+                (port, port)
+            }
+        };
+        (start >= 1 && start <= end).then_some(Self { start, end })
+    }
+
+    fn contains(self, port: u16) -> bool {
+        self.start <= port && port <= self.end
+    }
+
+    fn overlaps(self, other: Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+fn is_world(address: &str) -> bool {
+    matches!(address.trim(), "0.0.0.0/0" | "::/0")
+}
+
+fn targets_overlap(a: &RuleTargets, b: &RuleTargets) -> bool {
+    fn shares<T: PartialEq>(a: &[T], b: &[T]) -> bool {
+        a.iter().any(|x| b.contains(x))
+    }
+
+    shares(&a.droplet_ids, &b.droplet_ids)
+        || shares(&a.load_balancer_uids, &b.load_balancer_uids)
+        || shares(&a.kubernetes_ids, &b.kubernetes_ids)
+        || shares(&a.tags, &b.tags)
+        || a.addresses.iter().any(|x| {
+            b.addresses.iter().any(
+                |y| matches!((Cidr::parse(x), Cidr::parse(y)), (Some(x), Some(y)) if x.overlaps(y)),
+            )
+        })
+}
+
+/// An IPv4 or IPv6 network in CIDR notation; a bare address is a single-host network.
+#[derive(Debug, Clone, Copy)]
+struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    fn parse(s: &str) -> Option<Self> {
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, prefix.parse().ok()?),
+            None => {
+                let addr = s.trim().parse::<IpAddr>().ok()?;
+                (addr, if addr.is_ipv4() { 32 } else { 128 })
+            }
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        (prefix <= max).then_some(Self { addr, prefix })
+    }
+
+    /// Two networks overlap exactly when one contains the other, which is decided by
+    /// comparing both under the shorter prefix.
+    fn overlaps(self, other: Self) -> bool {
+        let prefix = self.prefix.min(other.prefix) as u32;
+        match (self.addr, other.addr) {
+            (IpAddr::V4(a), IpAddr::V4(b)) => {
+                let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                u32::from(a) & mask == u32::from(b) & mask
+            }
+            (IpAddr::V6(a), IpAddr::V6(b)) => {
+                let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+                u128::from(a) & mask == u128::from(b) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_ssh_open_to_world() {
+        let firewall: FirewallConfig = serde_json::from_value(json!({
+            "inbound_rules": [
+                {"protocol": "tcp", "ports": "20-25", "sources": {"addresses": ["::/0"]}},
+                {"protocol": "tcp", "ports": "22", "sources": {"addresses": ["10.0.0.0/8"]}}
+            ]
+        }))
+        .unwrap();
+        let warnings = lint_firewall(&firewall);
+        assert_eq!(
+            warnings,
+            vec![LintWarning::SensitivePortOpenToWorld {
+                rule: 0,
+                port: 22,
+                service: "SSH",
+                source: "::/0".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_rules() {
+        let firewall: FirewallConfig = serde_json::from_value(json!({
+            "inbound_rules": [
+                {"protocol": "tcp", "ports": "8000-9000", "sources": {"addresses": ["10.0.0.0/8"]}},
+                {"protocol": "tcp", "ports": "8080", "sources": {"addresses": ["10.1.2.3"]}},
+                {"protocol": "udp", "ports": "8080", "sources": {"addresses": ["10.1.2.3"]}}
+            ],
+            "outbound_rules": [
+                {"protocol": "tcp", "ports": "all", "destinations": {"tags": ["db"]}},
+                {"protocol": "tcp", "ports": "bogus", "destinations": {"tags": ["db"]}}
+            ]
+        }))
+        .unwrap();
+        let warnings = lint_firewall(&firewall);
+        assert_eq!(
+            warnings,
+            vec![
+                LintWarning::OverlappingRules {
+                    direction: Direction::Inbound,
+                    first: 0,
+                    second: 1,
+                },
+                LintWarning::InvalidPorts {
+                    direction: Direction::Outbound,
+                    rule: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_balancer_checks() {
+        let load_balancer: LoadBalancerConfig = serde_json::from_value(json!({
+            "forwarding_rules": [
+                {"entry_protocol": "http", "entry_port": 80, "target_protocol": "http", "target_port": 8080},
+                {"entry_protocol": "https", "entry_port": 80, "target_protocol": "http", "target_port": 8080}
+            ],
+            "health_check": {"protocol": "http", "port": 80, "path": "/"}
+        }))
+        .unwrap();
+        let warnings = lint_load_balancer(&load_balancer);
+        assert_eq!(
+            warnings,
+            vec![
+                LintWarning::HealthCheckPortNotForwarded { port: 80 },
+                LintWarning::DuplicateEntryPort {
+                    port: 80,
+                    first: 0,
+                    second: 1,
+                },
+                LintWarning::MissingCertificate { rule: 1 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Full listings that report concurrent modification.
+//!
+//! Paging through a list while resources are being created and deleted can skip
+//! items or return one twice, since each page is cut from the list as it is when
+//! that page is requested. [`Client::snapshot_listing`] fetches every page of a
+//! resource listing, drops repeated IDs and compares what it saw against `meta.total`
+//! from the first and last page. When they disagree the snapshot carries a
+//! [`ListingDrift`], telling inventory tools their copy may be inconsistent and worth
+//! retrying.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::listing::Droplets;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let snapshot = client.snapshot_listing::<Droplets>().await?;
+//! if let Some(drift) = &snapshot.drift {
+//!     eprintln!("droplets changed while listing: {drift}");
+//! }
+//! println!("{} droplets", snapshot.items.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::pagination::{Page, PageCursor};
+use crate::Client;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A resource listing [`Client::snapshot_listing`] can record.
+pub trait Listing {
+    /// The paginated list operation.
+    const OPERATION: &'static str;
+    /// The response field holding the items.
+    const ITEMS_KEY: &'static str;
+    /// JSON pointer to the field identifying an item.
+    const ID_POINTER: &'static str = "/id";
+}
+
+/// Every droplet of the account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Droplets;
+
+impl Listing for Droplets {
+    const OPERATION: &'static str = "droplets_list";
+    const ITEMS_KEY: &'static str = "droplets";
+}
+
+/// Every block storage volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Volumes;
+
+impl Listing for Volumes {
+    const OPERATION: &'static str = "volumes_list";
+    const ITEMS_KEY: &'static str = "volumes";
+}
+
+/// Every Kubernetes cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KubernetesClusters;
+
+impl Listing for KubernetesClusters {
+    const OPERATION: &'static str = "kubernetes_list_clusters";
+    const ITEMS_KEY: &'static str = "kubernetes_clusters";
+}
+
+/// Every load balancer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadBalancers;
+
+impl Listing for LoadBalancers {
+    const OPERATION: &'static str = "loadBalancers_list";
+    const ITEMS_KEY: &'static str = "load_balancers";
+}
+
+/// Every cloud firewall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Firewalls;
+
+impl Listing for Firewalls {
+    const OPERATION: &'static str = "firewalls_list";
+    const ITEMS_KEY: &'static str = "firewalls";
+}
+
+/// Every domain, identified by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Domains;
+
+impl Listing for Domains {
+    const OPERATION: &'static str = "domains_list";
+    const ITEMS_KEY: &'static str = "domains";
+    const ID_POINTER: &'static str = "/name";
+}
+
+/// Every droplet and volume snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshots;
+
+impl Listing for Snapshots {
+    const OPERATION: &'static str = "snapshots_list";
+    const ITEMS_KEY: &'static str = "snapshots";
+}
+
+/// Signs that a listing changed while [`Client::snapshot_listing`] paged through it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingDrift {
+    /// `meta.total` on the first page.
+    pub total_at_start: Option<u64>,
+    /// `meta.total` on the last page.
+    pub total_at_end: Option<u64>,
+    /// Whether any page reported a different total than the first.
+    pub total_changed: bool,
+    /// IDs returned on more than one page.
+    pub duplicate_ids: Vec<String>,
+    /// Distinct items received.
+    pub received: usize,
+}
+
+impl fmt::Display for ListingDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = |total: Option<u64>| total.map_or("unknown".to_string(), |t| t.to_string());
+        write!(
+            f,
+            "received {} items; total {} at start, {} at end",
+            self.received,
+            total(self.total_at_start),
+            total(self.total_at_end)
+        )?;
+        if !self.duplicate_ids.is_empty() {
+            write!(f, "; repeated: {}", self.duplicate_ids.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Every item of a listing, with a warning if it changed while being fetched.
+#[derive(Debug, Clone)]
+pub struct ListingSnapshot {
+    /// The items in list order, each ID once.
+    pub items: Vec<Value>,
+    /// Set when the items may not match the listing at any single point in time.
+    pub drift: Option<ListingDrift>,
+}
+
+/// Accumulates pages and spots drift between them.
+#[derive(Debug, Default)]
+struct SnapshotBuilder {
+    items: Vec<Value>,
+    seen: HashSet<String>,
+    duplicate_ids: Vec<String>,
+    total_at_start: Option<u64>,
+    total_at_end: Option<u64>,
+    total_changed: bool,
+    pages: usize,
+}
+
+impl SnapshotBuilder {
+    fn add_page(&mut self, items: Vec<Value>, total: Option<u64>, id_pointer: &str) {
+        if self.pages == 0 {
+            self.total_at_start = total;
+        } else if total != self.total_at_start {
+            self.total_changed = true;
+        }
+        self.total_at_end = total;
+        self.pages += 1;
+
+        for item in items {
+            let id = match item.pointer(id_pointer) {
+                Some(Value::String(id)) => Some(id.clone()),
+                Some(Value::Number(id)) => Some(id.to_string()),
+                _ => None,
+            };
+            match id {
+                Some(id) if !self.seen.insert(id.clone()) => self.duplicate_ids.push(id),
+                _ => self.items.push(item),
+            }
+        }
+    }
+
+    fn finish(self) -> ListingSnapshot {
+        let received = self.items.len();
+        let count_differs = matches!(self.total_at_end, Some(total) if total != received as u64);
+        let drift = if self.total_changed || !self.duplicate_ids.is_empty() || count_differs {
+            Some(ListingDrift {
+                total_at_start: self.total_at_start,
+                total_at_end: self.total_at_end,
+                total_changed: self.total_changed,
+                duplicate_ids: self.duplicate_ids,
+                received,
+            })
+        } else {
+            None
+        };
+        ListingSnapshot {
+            items: self.items,
+            drift,
+        }
+    }
+}
+
+impl Client {
+    /// Fetch every item of the listing `L`, noting whether it changed meanwhile.
+    ///
+    /// Drift is reported in the snapshot and logged, not returned as an error: the
+    /// items are still the best available copy.
+    pub async fn snapshot_listing<L: Listing>(&self) -> Result<ListingSnapshot, Error> {
+        let mut builder = SnapshotBuilder::default();
+        let mut page: Page<Value> = self
+            .paginate(L::OPERATION)
+            .items_key(L::ITEMS_KEY)
+            .per_page(200)
+            .page()
+            .await?;
+        loop {
+            let next: Option<PageCursor> = page.next_page().cloned();
+            builder.add_page(page.items, page.total, L::ID_POINTER);
+            match next {
+                Some(next) => page = self.fetch_page(&next).await?,
+                None => break,
+            }
+        }
+
+        let snapshot = builder.finish();
+        if let Some(drift) = &snapshot.drift {
+            tracing::warn!(operation = L::OPERATION, %drift, "listing changed while paging");
+        }
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_snapshot_detects_drift() {
+        let mut builder = SnapshotBuilder::default();
+        builder.add_page(vec![json!({"id": 1}), json!({"id": 2})], Some(3), "/id");
+        builder.add_page(vec![json!({"id": 3})], Some(3), "/id");
+        let snapshot = builder.finish();
+        assert_eq!(snapshot.items.len(), 3);
+        assert_eq!(snapshot.drift, None);
+
+        // A droplet created mid-listing pushes droplet 2 onto the second page.
+        let mut builder = SnapshotBuilder::default();
+        builder.add_page(vec![json!({"id": 1}), json!({"id": 2})], Some(3), "/id");
+        builder.add_page(vec![json!({"id": 2}), json!({"id": 3})], Some(4), "/id");
+        let drift = builder.finish().drift.unwrap();
+        assert!(drift.total_changed);
+        assert_eq!(drift.duplicate_ids, ["2"]);
+        assert_eq!(drift.received, 3);
+        assert_eq!(
+            drift.to_string(),
+            "received 3 items; total 3 at start, 4 at end; repeated: 2"
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! OAuth 2.0 for applications acting on behalf of DigitalOcean users.
+//!
+//! Third-party applications authenticate users with the authorization code flow
+//! instead of personal access tokens:
+//!
+//! 1. Send the user to [`OAuthConfig::authorize_url`].
+//! 2. DigitalOcean redirects back to the application's callback with a `code`, which
+//!    [`OAuthConfig::exchange_code`] trades for an [`OAuthToken`].
+//! 3. Build a client with [`Client::from_oauth`]. It refreshes the access token
+//!    shortly before it expires, and once more if the API answers `401 Unauthorized`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::oauth::{OAuthConfig, OAuthTokenSource};
+//! use rsdo::Client;
+//! use std::sync::Arc;
+//!
+//! # async fn run(code: &str) -> Result<(), rsdo::error::Error> {
+//! let config = OAuthConfig::new(
+//!     "client-id",
+//!     "client-secret",
+//!     "https://app.example.com/oauth/callback",
+//! );
+//!
+//! // Redirect the user here, then receive `code` on the callback URL.
+//! let url = config.authorize_url(&["read", "write"], "csrf-state-value")?;
+//!
+//! let token = config.exchange_code(code).await?;
+//! let client = Client::from_oauth(Arc::new(OAuthTokenSource::new(config, token)));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::auth::TokenProvider;
+use crate::error::{Error, InvalidResponse, OperationContext};
+use crate::{Client, ClientBuilder};
+use chrono::{DateTime, Duration, Utc};
+use futures::future::BoxFuture;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Host serving the OAuth endpoints.
+pub const DEFAULT_OAUTH_URL: &str = "https://cloud.digitalocean.com";
+
+/// Refresh tokens this long before they expire.
+const REFRESH_MARGIN: Duration = Duration::minutes(5);
+
+/// Do not refresh again after a `401` if the token was refreshed this recently.
+const MIN_REFRESH_INTERVAL: Duration = Duration::seconds(10);
+
+/// Registration details of an OAuth application.
+#[derive(Clone)]
+pub struct OAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// Callback URL registered for the application.
+    pub redirect_uri: String,
+    /// Host serving `/v1/oauth/*`; defaults to [`DEFAULT_OAUTH_URL`].
+    pub oauth_url: String,
+    http: reqwest::Client,
+}
+
+impl fmt::Debug for OAuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuthConfig")
+            .field("client_id", &self.client_id)
+            .field("redirect_uri", &self.redirect_uri)
+            .field("oauth_url", &self.oauth_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OAuthConfig {
+    pub fn new(
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        redirect_uri: impl Into<String>,
+    ) -> Self {
+        Self {
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            redirect_uri: redirect_uri.into(),
+            oauth_url: DEFAULT_OAUTH_URL.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Use `oauth_url` instead of `https://cloud.digitalocean.com`.
+    pub fn with_oauth_url(mut self, oauth_url: impl Into<String>) -> Self {
+        self.oauth_url = oauth_url.into();
+        self
+    }
+
+    /// Use `http` for token requests.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// URL to send the user to for authorization.
+    ///
+    /// `scopes` are OAuth scopes such as `read`, `write` or `droplet:read`; `state` is
+    /// an unguessable value the callback must check to prevent CSRF.
+    ///
+    /// Fails with [`Error::InvalidInput`] if the configured OAuth URL is not a URL.
+    pub fn authorize_url(&self, scopes: &[&str], state: &str) -> Result<Url, Error> {
+        let mut url = self.endpoint("authorize")?;
+        url.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_uri)
+            .append_pair("response_type", "code")
+            .append_pair("scope", &scopes.join(" "))
+            .append_pair("state", state);
+        Ok(url)
+    }
+
+    /// Exchange the `code` received on the callback URL for a token.
+    ///
+    /// Fails with [`Error::InvalidInput`] if the configured OAuth URL is not a URL.
+    pub async fn exchange_code(&self, code: &str) -> Result<OAuthToken, Error> {
+        self.token_request(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.redirect_uri),
+        ])
+        .await
+    }
+
+    /// Obtain a new access token with a refresh token.
+    ///
+    /// Refresh tokens are single-use: the returned token carries the refresh token to
+    /// use next time. Fails with [`Error::InvalidInput`] if the configured OAuth URL is
+    /// not a URL.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<OAuthToken, Error> {
+        self.token_request(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ])
+        .await
+    }
+
+    async fn token_request(&self, params: &[(&str, &str)]) -> Result<OAuthToken, Error> {
+        let url = self.endpoint("token")?;
+        let context = OperationContext::new("oauth_token", Method::POST, url.path(), false);
+        let response = self
+            .http
+            .post(url)
+            .query(params)
+            .query(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|source| Error::Request {
+                context: context.clone(),
+                source,
+            })?;
+
+        let context = context.with_response(response.headers());
+        let status = response.status();
+        let body = response.bytes().await.map_err(|source| Error::Request {
+            context: context.clone(),
+            source,
+        })?;
+        if !status.is_success() {
+            return Err(Error::Response {
+                context,
+                status,
+                body: String::from_utf8_lossy(&body).into_owned(),
+            });
+        }
+
+        let mut token: OAuthToken = serde_json::from_slice(&body)
+            .map_err(|source| InvalidResponse::error(context, status, &body, source))?;
+        token.obtained_at = Utc::now();
+        Ok(token)
+    }
+
+    fn endpoint(&self, name: &str) -> Result<Url, Error> {
+        let base = self.oauth_url.trim_end_matches('/');
+        Url::parse(&format!("{base}/v1/oauth/{name}"))
+            .map_err(|err| Error::InvalidInput(format!("invalid OAuth URL {base:?}: {err}")))
+    }
+}
+
+/// An access token issued by the OAuth token endpoint.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OAuthToken {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub token_type: String,
+    /// Lifetime of the access token in seconds, counted from `obtained_at`.
+    #[serde(default)]
+    pub expires_in: Option<i64>,
+    #[serde(default)]
+    pub scope: String,
+    /// The user who authorized the application.
+    #[serde(default)]
+    pub info: Option<OAuthUserInfo>,
+    /// When the token was issued; set locally. Persist it alongside the token.
+    #[serde(default = "Utc::now")]
+    pub obtained_at: DateTime<Utc>,
+}
+
+impl fmt::Debug for OAuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuthToken")
+            .field("token_type", &self.token_type)
+            .field("expires_in", &self.expires_in)
+            .field("scope", &self.scope)
+            .field("info", &self.info)
+            .field("obtained_at", &self.obtained_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OAuthToken {
+    /// When the access token expires, if the server said.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_in
+            .map(|seconds| self.obtained_at + Duration::seconds(seconds))
+    }
+
+    /// Whether the access token expires within `margin` of `now`.
+    pub fn expires_within(&self, margin: Duration, now: DateTime<Utc>) -> bool {
+        self.expires_at()
+            .is_some_and(|expires_at| expires_at - margin <= now)
+    }
+}
+
+/// The user behind an [`OAuthToken`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OAuthUserInfo {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub uuid: String,
+}
+
+/// A [`TokenProvider`] that keeps an OAuth access token fresh.
+///
+/// The token is refreshed five minutes before it expires, and when the API rejects it
+/// with `401 Unauthorized`. Register a callback with [`OAuthTokenSource::on_refresh`]
+/// to persist rotated refresh tokens, since each can only be used once.
+pub struct OAuthTokenSource {
+    config: OAuthConfig,
+    state: Mutex<OAuthToken>,
+    on_refresh: Option<RefreshCallback>,
+}
+
+type RefreshCallback = Box<dyn Fn(&OAuthToken) + Send + Sync>;
+
+impl fmt::Debug for OAuthTokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuthTokenSource")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OAuthTokenSource {
+    pub fn new(config: OAuthConfig, token: OAuthToken) -> Self {
+        Self {
+            config,
+            state: Mutex::new(token),
+            on_refresh: None,
+        }
+    }
+
+    /// Call `callback` with every newly obtained token.
+    pub fn on_refresh(mut self, callback: impl Fn(&OAuthToken) + Send + Sync + 'static) -> Self {
+        self.on_refresh = Some(Box::new(callback));
+        self
+    }
+
+    /// The current token.
+    pub async fn current(&self) -> OAuthToken {
+        self.state.lock().await.clone()
+    }
+
+    async fn refresh_locked(&self, token: &mut OAuthToken) -> Result<(), Error> {
+        let refresh_token = token.refresh_token.clone().ok_or_else(|| {
+            Error::Other("OAuth token expired and has no refresh token".to_string())
+        })?;
+        let mut refreshed = self.config.refresh(&refresh_token).await?;
+        if refreshed.refresh_token.is_none() {
+            refreshed.refresh_token = Some(refresh_token);
+        }
+        tracing::debug!(expires_at = ?refreshed.expires_at(), "refreshed OAuth token");
+        if let Some(callback) = &self.on_refresh {
+            callback(&refreshed);
+        }
+        *token = refreshed;
+        Ok(())
+    }
+}
+
+impl TokenProvider for OAuthTokenSource {
+    fn token(&self) -> BoxFuture<'_, Result<String, Error>> {
+        Box::pin(async move {
+            let mut token = self.state.lock().await;
+            if token.expires_within(REFRESH_MARGIN, Utc::now()) && token.refresh_token.is_some() {
+                self.refresh_locked(&mut token).await?;
+            }
+            Ok(token.access_token.clone())
+        })
+    }
+
+    fn on_unauthorized(&self) -> BoxFuture<'_, Result<bool, Error>> {
+        Box::pin(async move {
+            let mut token = self.state.lock().await;
+            // Another request already refreshed the token; retry with that one.
+            if Utc::now() - token.obtained_at < MIN_REFRESH_INTERVAL {
+                return Ok(true);
+            }
+            if token.refresh_token.is_none() {
+                return Ok(false);
+            }
+            self.refresh_locked(&mut token).await?;
+            Ok(true)
+        })
+    }
+}
+
+impl Client {
+    /// Create a client that authenticates with an OAuth token, refreshing it as
+    /// needed. Use [`ClientBuilder::token_provider`] to combine OAuth with other
+    /// settings.
+    pub fn from_oauth(source: Arc<OAuthTokenSource>) -> Self {
+        ClientBuilder::default()
+            .token_provider(source)
+            .build()
+            .expect("Failed to build HTTP client")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_authorize_url() {
+        let config = OAuthConfig::new("abc", "secret", "https://app.example.com/cb");
+        let url = config.authorize_url(&["read", "write"], "xyz").unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://cloud.digitalocean.com/v1/oauth/authorize?client_id=abc\
+             &redirect_uri=https%3A%2F%2Fapp.example.com%2Fcb&response_type=code\
+             &scope=read+write&state=xyz"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_oauth_url_is_invalid_input() {
+        let config = OAuthConfig::new("abc", "secret", "https://app.example.com/cb")
+            .with_oauth_url("not a url");
+        assert!(matches!(
+            config.authorize_url(&["read"], "xyz"),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            config.exchange_code("code").await,
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            config.refresh("refresh").await,
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        let mut token: OAuthToken = serde_json::from_value(json!({
+            "access_token": "access",
+            "token_type": "bearer",
+            "expires_in": 2592000,
+            "refresh_token": "refresh",
+            "scope": "read write",
+            "info": {"name": "Sammy", "email": "sammy@example.com", "uuid": "e028b1b9"}
+        }))
+        .unwrap();
+        token.obtained_at = "2026-01-01T00:00:00Z".parse().unwrap();
+
+        let day_before = "2026-01-30T00:00:00Z".parse().unwrap();
+        let minute_before = "2026-01-30T23:59:00Z".parse().unwrap();
+        assert!(!token.expires_within(REFRESH_MARGIN, day_before));
+        assert!(token.expires_within(REFRESH_MARGIN, minute_before));
+        assert!(!format!("{token:?}").contains("access"));
+    }
+}
@@ -0,0 +1,337 @@
+//! Store-and-forward for agents on unreliable links.
+//!
+//! An agent managing droplets from the edge may lose its network for minutes at a
+//! time. [`RawRequest::send_or_queue`] sends a mutating call when the API can be
+//! reached and otherwise appends it to an [`OfflineQueue`], a JSON file that survives
+//! restarts. Once the queue holds anything, later calls are queued behind it so they
+//! still reach the API in order.
+//!
+//! [`OfflineQueue::replay`] sends the queued calls oldest first and stops at the first
+//! one that cannot be delivered yet. A call the API rejects with a `4xx` status, for
+//! example because the droplet it resizes was deleted meanwhile, is a conflict: the
+//! callback decides whether to drop it or stop replaying. [`OfflineQueue::drain`]
+//! replays periodically until the queue is empty.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::offline::{Conflict, Delivery, OfflineQueue};
+//! use std::time::Duration;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let queue = OfflineQueue::open("/var/lib/edge-agent/queue.json").await?;
+//! let delivery = client
+//!     .execute_raw("dropletActions_post")
+//!     .path_param("droplet_id", 3164494)
+//!     .json(&serde_json::json!({ "type": "reboot" }))
+//!     .send_or_queue(&queue)
+//!     .await?;
+//! if let Delivery::Queued(id) = delivery {
+//!     println!("offline; queued as #{id}");
+//! }
+//!
+//! // Later, e.g. in a background task:
+//! queue
+//!     .drain(&client, Duration::from_secs(30), |queued, response| {
+//!         eprintln!("dropping #{}: {}", queued.id, response.status);
+//!         Conflict::Drop
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cancellation;
+use crate::error::Error;
+use crate::raw::{RawRequest, RawResponse};
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// A call waiting in an [`OfflineQueue`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedRequest {
+    /// Position in the queue, increasing with every call queued.
+    pub id: u64,
+    pub operation: String,
+    pub path_params: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+    pub body: Option<serde_json::Value>,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// What [`RawRequest::send_or_queue`] did with a call.
+#[derive(Debug)]
+pub enum Delivery {
+    /// The API answered, with any status.
+    Sent(Box<RawResponse>),
+    /// The API could not be reached, or earlier calls are still queued; holds the
+    /// queued call's ID.
+    Queued(u64),
+}
+
+/// How [`OfflineQueue::replay`] handles a queued call the API rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conflict {
+    /// Remove the call and continue with the next.
+    Drop,
+    /// Keep the call at the head of the queue and stop replaying.
+    Stop,
+}
+
+/// Outcome of one [`OfflineQueue::replay`].
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// Calls the API accepted, with their responses.
+    pub delivered: Vec<(QueuedRequest, RawResponse)>,
+    /// Calls dropped after a conflict.
+    pub dropped: Vec<QueuedRequest>,
+    /// Calls still queued.
+    pub remaining: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueFile {
+    next_id: u64,
+    requests: VecDeque<QueuedRequest>,
+}
+
+/// Calls waiting to be sent, persisted to a JSON file after every change.
+#[derive(Debug)]
+pub struct OfflineQueue {
+    path: PathBuf,
+    state: Mutex<QueueFile>,
+}
+
+impl OfflineQueue {
+    /// Open the queue stored at `path`, starting empty if the file does not exist.
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let state = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|err| {
+                Error::InvalidInput(format!(
+                    "{} is not an offline queue file: {err}",
+                    path.display()
+                ))
+            })?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => QueueFile::default(),
+            Err(source) => return Err(Error::Io { path, source }),
+        };
+        Ok(Self {
+            path,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Number of queued calls.
+    pub async fn len(&self) -> usize {
+        self.state.lock().await.requests.len()
+    }
+
+    /// Whether no calls are queued.
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// The queued calls, oldest first.
+    pub async fn pending(&self) -> Vec<QueuedRequest> {
+        self.state.lock().await.requests.iter().cloned().collect()
+    }
+
+    async fn push(&self, request: &RawRequest) -> Result<u64, Error> {
+        let body = match &request.body {
+            Some(Ok(body)) => Some(body.clone()),
+            Some(Err(err)) => {
+                return Err(Error::InvalidInput(format!(
+                    "request body for {} is not valid JSON: {err}",
+                    request.operation
+                )));
+            }
+            None => None,
+        };
+        let mut state = self.state.lock().await;
+        let id = state.next_id;
+        state.next_id += 1;
+        state.requests.push_back(QueuedRequest {
+            id,
+            operation: request.operation.clone(),
+            path_params: request.path_params.clone(),
+            query: request.query.clone(),
+            body,
+            queued_at: Utc::now(),
+        });
+        save(&self.path, &state).await?;
+        Ok(id)
+    }
+
+    /// Send queued calls in order until the queue is empty, the API cannot be reached
+    /// or `on_conflict` returns [`Conflict::Stop`].
+    ///
+    /// Calls answered with `429` or a `5xx` status stay queued and end the replay, as
+    /// the API is not ready for them yet.
+    pub async fn replay(
+        &self,
+        client: &Client,
+        mut on_conflict: impl FnMut(&QueuedRequest, &RawResponse) -> Conflict,
+    ) -> Result<ReplayReport, Error> {
+        let mut report = ReplayReport::default();
+        let mut state = self.state.lock().await;
+        while let Some(queued) = state.requests.front().cloned() {
+            let mut request = client.execute_raw(queued.operation.clone());
+            request.path_params = queued.path_params.clone();
+            request.query = queued.query.clone();
+            request.body = queued.body.clone().map(Ok);
+
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(err) if is_unreachable(&err) => break,
+                Err(err) => return Err(err),
+            };
+            let status = response.status;
+            if status.is_success() {
+                report.delivered.push((queued, response));
+            } else if status.is_server_error() || status.as_u16() == 429 {
+                break;
+            } else if on_conflict(&queued, &response) == Conflict::Drop {
+                report.dropped.push(queued);
+            } else {
+                break;
+            }
+            state.requests.pop_front();
+            save(&self.path, &state).await?;
+        }
+        report.remaining = state.requests.len();
+        Ok(report)
+    }
+
+    /// [`replay`](Self::replay) every `interval` until the queue is empty.
+    ///
+    /// Returns the calls delivered and dropped along the way. Stops early, with the
+    /// calls still queued, when `on_conflict` returns [`Conflict::Stop`] or the client
+    /// is cancelled.
+    pub async fn drain(
+        &self,
+        client: &Client,
+        interval: Duration,
+        mut on_conflict: impl FnMut(&QueuedRequest, &RawResponse) -> Conflict,
+    ) -> Result<ReplayReport, Error> {
+        let mut total = ReplayReport::default();
+        loop {
+            let mut stopped = false;
+            let report = self
+                .replay(client, |queued, response| {
+                    let conflict = on_conflict(queued, response);
+                    stopped |= conflict == Conflict::Stop;
+                    conflict
+                })
+                .await?;
+            total.delivered.extend(report.delivered);
+            total.dropped.extend(report.dropped);
+            total.remaining = report.remaining;
+            if total.remaining == 0 || stopped {
+                return Ok(total);
+            }
+            if !cancellation::sleep(client.inner(), interval).await {
+                return Ok(total);
+            }
+        }
+    }
+}
+
+async fn save(path: &Path, state: &QueueFile) -> Result<(), Error> {
+    let io_error = |path: &Path, source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".rsdo-tmp");
+    let tmp = PathBuf::from(tmp);
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(state)?)
+        .await
+        .map_err(|source| io_error(&tmp, source))?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .map_err(|source| io_error(path, source))
+}
+
+/// Whether `err` means the API could not be reached, rather than that it refused the
+/// call.
+fn is_unreachable(err: &Error) -> bool {
+    match err {
+        Error::Request { .. } => err.is_transient(),
+        Error::CircuitOpen { .. } => true,
+        _ => false,
+    }
+}
+
+impl RawRequest {
+    /// Send the call, or add it to `queue` if the API cannot be reached or earlier
+    /// calls are still queued.
+    ///
+    /// Reads are never queued; a `GET` is sent as with [`send`](Self::send).
+    pub async fn send_or_queue(self, queue: &OfflineQueue) -> Result<Delivery, Error> {
+        let read = self.is_read()?;
+        if !read && !queue.is_empty().await {
+            return queue.push(&self).await.map(Delivery::Queued);
+        }
+        match self.clone().send().await {
+            Ok(response) => Ok(Delivery::Sent(Box::new(response))),
+            Err(err) if !read && is_unreachable(&err) => {
+                tracing::info!(operation = %self.operation, error = %err, "API unreachable; queueing call");
+                queue.push(&self).await.map(Delivery::Queued)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unreachable_calls_queue_in_order_and_persist() {
+        let dir = std::env::temp_dir().join(format!("rsdo-offline-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("queue.json");
+
+        // Nothing listens on port 9 of localhost, so every call fails to connect.
+        let client = crate::ClientBuilder::new("test-token")
+            .base_url("http://127.0.0.1:9")
+            .retry_policy(crate::retry::RetryPolicy::none())
+            .build()
+            .unwrap();
+        let queue = OfflineQueue::open(&path).await.unwrap();
+        for droplet_id in [1, 2] {
+            let delivery = client
+                .execute_raw("dropletActions_post")
+                .path_param("droplet_id", droplet_id)
+                .json(&serde_json::json!({ "type": "reboot" }))
+                .send_or_queue(&queue)
+                .await
+                .unwrap();
+            assert!(matches!(delivery, Delivery::Queued(_)));
+        }
+
+        let reopened = OfflineQueue::open(&path).await.unwrap();
+        let pending = reopened.pending().await;
+        assert_eq!(pending.len(), 2);
+        assert_eq!(
+            pending[0].path_params,
+            [("droplet_id".to_string(), "1".to_string())]
+        );
+        assert!(pending[0].id < pending[1].id);
+
+        let report = reopened
+            .replay(&client, |_, _| Conflict::Drop)
+            .await
+            .unwrap();
+        assert!(report.delivered.is_empty());
+        assert_eq!(report.remaining, 2);
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
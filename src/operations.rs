@@ -0,0 +1,63 @@
+//! Per-operation metadata extracted from the OpenAPI specification at build time.
+//!
+//! `build.rs` records every operation's method, path template and pagination limits in
+//! a static registry so behaviour that depends on the operation (such as clamping
+//! `per_page`) does not need to be hand-maintained for 500+ endpoints.
+
+/// Static metadata describing one API operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OperationMeta {
+    /// Operation ID as written in the specification, e.g. `floatingIPs_list`.
+    pub operation_id: &'static str,
+    /// Name of the generated client method, e.g. `floating_i_ps_list`.
+    pub method_name: &'static str,
+    /// Upper-case HTTP method.
+    pub method: &'static str,
+    /// Path template, e.g. `/v2/droplets/{droplet_id}`.
+    pub path: &'static str,
+    /// Whether the operation accepts the `page` query parameter.
+    pub paginated: bool,
+    /// Documented maximum for the `per_page` query parameter.
+    pub max_per_page: Option<u64>,
+    /// The operation's entry in the API reference on docs.digitalocean.com.
+    pub docs_url: &'static str,
+}
+
+include!(concat!(env!("OUT_DIR"), "/operations.rs"));
+
+/// Look up an operation by its spec operation ID or generated method name.
+pub fn find(name: &str) -> Option<&'static OperationMeta> {
+    OPERATIONS
+        .iter()
+        .find(|op| op.method_name == name || op.operation_id == name)
+}
+
+/// All operations known to this build of the client.
+pub fn all() -> &'static [OperationMeta] {
+    OPERATIONS
+}
+
+/// Validate a requested `per_page` against the operation's documented limits.
+///
+/// Values of zero are raised to one and values above the operation's maximum are
+/// lowered to it; both cases log a warning. Operations without a documented maximum
+/// pass the value through unchanged.
+pub fn clamp_per_page(operation: &str, requested: u64) -> u64 {
+    if requested == 0 {
+        tracing::warn!(operation, "per_page must be at least 1; using 1");
+        return 1;
+    }
+    match find(operation).and_then(|op| op.max_per_page) {
+        Some(max) if requested > max => {
+            tracing::warn!(
+                operation,
+                requested,
+                max,
+                "per_page exceeds the operation's maximum; clamping"
+            );
+            max
+        }
+        _ => requested,
+    }
+}
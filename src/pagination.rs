@@ -0,0 +1,1063 @@
+//! Automatic pagination for list operations.
+//!
+//! Every paginated operation in the API returns one page of items together with
+//! `links.pages.next`, the URL of the following page. [`Client::paginate`] turns any
+//! such operation into a [`Stream`] of items that follows those links until the last
+//! page, so callers no longer need to track page numbers themselves.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use futures::TryStreamExt;
+//! use rsdo::types::DropletsListResponseDropletsItem;
+//! use rsdo::Client;
+//!
+//! # async fn run() -> Result<(), rsdo::error::Error> {
+//! let client = Client::from_token("your-digitalocean-token");
+//!
+//! let droplets: Vec<DropletsListResponseDropletsItem> = client
+//!     .paginate("droplets_list")
+//!     .per_page(200)
+//!     .query("tag_name", "web")
+//!     .stream()
+//!     .try_collect()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To gather a whole list into a `Vec`, every paginated operation also has a
+//! `*_all` method, such as `droplets_list_all`, built on [`Paginate::collect_all`].
+//! It stops with an error rather than paging forever once the list grows past a
+//! safety cap ([`DEFAULT_MAX_ITEMS`] unless changed with
+//! [`Client::with_max_list_items`] or [`Paginate::max_items`]):
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let droplets = client.droplets_list_all().await?;
+//! println!("{} droplets", droplets.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Operations that accept a `tag_name` filter also have a `*_by_tag_stream` method,
+//! such as `droplets_by_tag_stream("web")`, streaming every tagged item.
+//!
+//! Web services that page through a list on behalf of their own clients can fetch
+//! one [`Page`] at a time instead. Its [`PageCursor`]s serialize, so the position
+//! can be handed out and resumed with [`Client::fetch_page`] on a later request:
+//!
+//! ```rust,no_run
+//! use rsdo::pagination::PageCursor;
+//!
+//! # async fn run(client: rsdo::Client, token: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+//! let page = match token {
+//!     Some(token) => {
+//!         let cursor: PageCursor = serde_json::from_str(token)?;
+//!         client.fetch_page::<serde_json::Value>(&cursor).await?
+//!     }
+//!     None => client.paginate("droplets_list").per_page(50).page().await?,
+//! };
+//! let next_token = page.next_page().map(serde_json::to_string).transpose()?;
+//! println!("{} of {:?} droplets, next: {next_token:?}", page.items.len(), page.total);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every paginated operation also has a `*_with` method taking [`ListOptions`], so
+//! filters are named instead of passed as a row of positional `None`s:
+//!
+//! ```rust,no_run
+//! use rsdo::pagination::ListOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let options = ListOptions::new().tag_name("web").per_page(50);
+//! let page = client.droplets_list_with(&options).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every generated list response implements [`Paginated`], so code can be written
+//! once over any listable resource:
+//!
+//! ```rust,no_run
+//! use rsdo::pagination::Paginated;
+//!
+//! fn summary<R: Paginated>(response: &R) -> String {
+//!     let more = if response.links().next.is_some() { ", more pages" } else { "" };
+//!     format!("{} of {:?}{more}", response.items().len(), response.total())
+//! }
+//! ```
+//!
+//! Pipelines that do real work per item can hide the request latency by letting the
+//! stream fetch ahead while they process the current page:
+//!
+//! ```rust,no_run
+//! use futures::TryStreamExt;
+//! use rsdo::pagination::PaginateOptions;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let mut volumes = std::pin::pin!(client
+//!     .paginate::<serde_json::Value>("volumes_list")
+//!     .with_options(PaginateOptions { prefetch: 2, ..Default::default() })
+//!     .stream());
+//! while let Some(volume) = volumes.try_next().await? {
+//!     // Up to two further pages are fetched in the background meanwhile.
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Long-running sync jobs can checkpoint the `links.pages.next` URL of the last page
+//! they finished and pick the listing up there with [`Client::resume_list`].
+//!
+//! Operations nested under another resource take their path parameters by name:
+//!
+//! ```rust,no_run
+//! # use futures::TryStreamExt;
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let records: Vec<serde_json::Value> = client
+//!     .paginate("domains_list_records")
+//!     .path_param("domain_name", "example.com")
+//!     .stream()
+//!     .try_collect()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::operations::{self, OperationMeta};
+use crate::request::ApiRequest;
+use crate::{Client, ClientInfo};
+use futures::future;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::marker::PhantomData;
+
+/// Most items [`Paginate::collect_all`] gathers unless the client or call sets
+/// another cap.
+pub const DEFAULT_MAX_ITEMS: usize = 10_000;
+
+/// Top-level response keys that never hold the listed items.
+const ENVELOPE_KEYS: &[&str] = &["links", "meta"];
+
+/// How a [`Paginate`] stream fetches its pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PaginateOptions {
+    /// Pages to fetch ahead of the consumer in a background task, so the next page
+    /// is usually ready when the current one is used up. `0`, the default, fetches
+    /// a page only once the previous one has been consumed.
+    ///
+    /// The task runs on the current Tokio runtime and stops when the stream is
+    /// dropped.
+    pub prefetch: usize,
+    /// Pages fetched at once; see [`Paginate::concurrency`]. `0` and `1` fetch one
+    /// page after another.
+    pub concurrency: usize,
+}
+
+/// Builder for a stream over every item of a paginated list operation.
+///
+/// Created by [`Client::paginate`].
+#[derive(Debug, Clone)]
+#[must_use = "call `.stream()` to start fetching pages"]
+pub struct Paginate<T> {
+    client: Client,
+    operation: String,
+    path_params: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    items_key: Option<String>,
+    max_items: Option<usize>,
+    options: PaginateOptions,
+    _item: PhantomData<fn() -> T>,
+}
+
+/// A response to a list operation: one page of items plus `links` and `meta`.
+///
+/// Implemented by every generated list response, whatever the field holding its
+/// items is called.
+pub trait Paginated {
+    type Item;
+
+    /// The items on this page.
+    fn items(&self) -> &[Self::Item];
+
+    /// The items on this page, by value.
+    fn into_items(self) -> Vec<Self::Item>;
+
+    /// Items in the whole list, from `meta.total`, if the operation reports it.
+    fn total(&self) -> Option<u64>;
+
+    /// The `links.pages` URLs.
+    fn links(&self) -> PageLinks;
+}
+
+/// The `links.pages` URLs of a list response; each is missing where there is no such
+/// page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[non_exhaustive]
+pub struct PageLinks {
+    #[serde(default)]
+    pub first: Option<String>,
+    #[serde(default)]
+    pub prev: Option<String>,
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub last: Option<String>,
+}
+
+impl PageLinks {
+    /// Read the `pages` of a generated `links` value, whatever its type.
+    pub(crate) fn from_links(links: &impl Serialize) -> Self {
+        serde_json::to_value(links)
+            .ok()
+            .and_then(|mut links| links.get_mut("pages").map(Value::take))
+            .and_then(|pages| serde_json::from_value(pages).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// `total` of a generated `meta` value, whatever its type.
+pub(crate) fn meta_total(meta: &impl Serialize) -> Option<u64> {
+    serde_json::to_value(meta).ok()?.get("total")?.as_u64()
+}
+
+/// Named query parameters for a list operation.
+///
+/// Only the parameters that are set are sent; whether an operation supports a filter
+/// such as `tag_name` is up to the operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    page: Option<u64>,
+    per_page: Option<u64>,
+    tag_name: Option<String>,
+    name: Option<String>,
+    params: Vec<(String, String)>,
+}
+
+impl ListOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 1-based page to fetch.
+    pub fn page(mut self, page: u64) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    /// Items per page. Clamped to the operation's documented maximum.
+    pub fn per_page(mut self, per_page: u64) -> Self {
+        self.per_page = Some(per_page);
+        self
+    }
+
+    /// Only resources with this tag.
+    pub fn tag_name(mut self, tag_name: impl Into<String>) -> Self {
+        self.tag_name = Some(tag_name.into());
+        self
+    }
+
+    /// Only resources with this exact name.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Any other query parameter, such as `type` for `droplets_list` or `region`.
+    pub fn param(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.params.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// The query parameters to send, in a fixed order.
+    fn query_pairs(&self) -> Vec<(String, String)> {
+        let named = [
+            ("page", self.page.map(|page| page.to_string())),
+            (
+                "per_page",
+                self.per_page.map(|per_page| per_page.to_string()),
+            ),
+            ("tag_name", self.tag_name.clone()),
+            ("name", self.name.clone()),
+        ];
+        named
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), value)))
+            .chain(self.params.iter().cloned())
+            .collect()
+    }
+}
+
+/// One page of a list operation, fetched with [`Paginate::page`] or
+/// [`Client::fetch_page`].
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Items in the whole list, from `meta.total`, if the operation reports it.
+    pub total: Option<u64>,
+    next: Option<PageCursor>,
+    prev: Option<PageCursor>,
+}
+
+impl<T> Page<T> {
+    /// Position of the following page, from `links.pages.next`; `None` on the last
+    /// page.
+    pub fn next_page(&self) -> Option<&PageCursor> {
+        self.next.as_ref()
+    }
+
+    /// Position of the preceding page, from `links.pages.prev`; `None` on the first
+    /// page.
+    pub fn prev_page(&self) -> Option<&PageCursor> {
+        self.prev.as_ref()
+    }
+}
+
+/// Position in a list operation, resumed with [`Client::fetch_page`].
+///
+/// Serializes to a small JSON object. A cursor coming back from an untrusted client
+/// can only fetch a page of the operation it names: [`Client::fetch_page`] rejects
+/// paths that do not belong to that operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    operation: String,
+    path: String,
+    query: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    items_key: Option<String>,
+}
+
+impl PageCursor {
+    /// The operation ID the cursor pages through, e.g. `droplets_list`.
+    pub fn operation(&self) -> &str {
+        &self.operation
+    }
+
+    /// The 1-based page number, if the cursor names one.
+    pub fn page(&self) -> Option<u64> {
+        page_number(&self.query)
+    }
+
+    fn from_request(request: &ApiRequest, items_key: Option<&str>) -> Self {
+        Self {
+            operation: request.operation_id.to_string(),
+            path: request.path.clone(),
+            query: request.query.clone(),
+            items_key: items_key.map(str::to_string),
+        }
+    }
+
+    fn to_request(&self) -> Result<ApiRequest, Error> {
+        let op = paginated_operation(&self.operation)?;
+        if !path_matches(op.path, &self.path) {
+            return Err(Error::InvalidInput(format!(
+                "page cursor path {} does not belong to {}",
+                self.path, op.operation_id
+            )));
+        }
+        Ok(self.query.iter().fold(
+            ApiRequest::get(op.operation_id, self.path.clone()),
+            |request, (key, value)| request.query(key, value),
+        ))
+    }
+}
+
+impl Client {
+    /// Fetch the page of a list operation that `cursor` points at.
+    pub async fn fetch_page<T: DeserializeOwned>(
+        &self,
+        cursor: &PageCursor,
+    ) -> Result<Page<T>, Error> {
+        fetch_page(self, cursor.to_request()?, cursor.items_key.as_deref()).await
+    }
+
+    /// Iterate over every item returned by the list operation `operation`.
+    ///
+    /// `operation` is either the spec operation ID (`droplets_list`) or the generated
+    /// method name; see [`operations::find`]. Items are deserialized into `T`, which can
+    /// be the generated item type or [`serde_json::Value`]. The generated `*_all` and
+    /// `*_with` methods wrap this with the operation's item type already chosen.
+    pub fn paginate<T: DeserializeOwned>(&self, operation: impl Into<String>) -> Paginate<T> {
+        Paginate {
+            client: self.clone(),
+            operation: operation.into(),
+            path_params: Vec::new(),
+            query: Vec::new(),
+            items_key: None,
+            max_items: None,
+            options: PaginateOptions::default(),
+            _item: PhantomData,
+        }
+    }
+
+    /// Continue a listing from a `links.pages.next` URL saved earlier.
+    ///
+    /// The URL must point at this client's base URL; a link from another host or
+    /// another base path is rejected rather than followed. The operation, path
+    /// parameters and filters are taken from the URL, and the returned builder works
+    /// like one from [`paginate`](Self::paginate).
+    pub fn resume_list<T: DeserializeOwned>(&self, next_url: &str) -> Result<Paginate<T>, Error> {
+        let url = reqwest::Url::parse(next_url)
+            .map_err(|err| Error::InvalidInput(format!("invalid page link {next_url:?}: {err}")))?;
+        let path = api_path(self.baseurl(), &url).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "page link {next_url} does not belong to {}",
+                self.baseurl()
+            ))
+        })?;
+        // Prefer literal segments, so `/v2/droplets/actions` is not read as a droplet ID.
+        let op = operations::OPERATIONS
+            .iter()
+            .filter(|op| op.paginated && op.method == "GET" && path_matches(op.path, path))
+            .min_by_key(|op| op.path.matches('{').count())
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("page link {next_url} is not a list operation"))
+            })?;
+
+        let paginate = op
+            .path
+            .split('/')
+            .zip(path.split('/'))
+            .filter_map(|(expected, segment)| {
+                let name = expected.strip_prefix('{')?.strip_suffix('}')?;
+                Some((name, segment))
+            })
+            .fold(self.paginate(op.operation_id), |paginate, (name, value)| {
+                paginate.path_param(name, value)
+            });
+        Ok(url.query_pairs().fold(paginate, |paginate, (key, value)| {
+            paginate.query(key, value)
+        }))
+    }
+
+    /// Collect every item of `operation` found under `items_key`, at the client's
+    /// [`default_per_page`](crate::ClientBuilder::default_per_page).
+    pub(crate) async fn collect_pages<T>(
+        &self,
+        operation: &str,
+        items_key: &str,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.paginate(operation)
+            .items_key(items_key)
+            .stream()
+            .try_collect()
+            .await
+    }
+}
+
+impl<T: DeserializeOwned> Paginate<T> {
+    /// Fill the `{name}` placeholder in the operation's path.
+    pub fn path_param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.path_params.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Add a query parameter, such as a `tag_name` filter, to the first request.
+    ///
+    /// Later pages reuse whatever the API puts in `links.pages.next`, which carries the
+    /// original filters forward.
+    pub fn query(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.query.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Number of items to request per page. Clamped to the operation's documented
+    /// maximum.
+    pub fn per_page(self, per_page: u64) -> Self {
+        self.query("per_page", per_page)
+    }
+
+    /// Add every parameter set in `options`.
+    pub fn options(mut self, options: &ListOptions) -> Self {
+        self.query.extend(options.query_pairs());
+        self
+    }
+
+    /// Name of the response field holding the items, e.g. `droplets`.
+    ///
+    /// Only needed when a response contains more than one array; otherwise the field is
+    /// detected automatically.
+    pub fn items_key(mut self, key: impl Into<String>) -> Self {
+        self.items_key = Some(key.into());
+        self
+    }
+
+    /// Most items [`collect_all`](Self::collect_all) gathers before failing. Defaults
+    /// to the client's cap; see [`Client::with_max_list_items`].
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Fetch up to `limit` pages at once instead of one after another.
+    ///
+    /// The page count comes from the first page's `meta.total`, and items are still
+    /// yielded in list order. Operations that do not report a total fall back to
+    /// following `links.pages.next`. Items added or removed while the pages are being
+    /// fetched can shift between pages, so an item may be missed or seen twice.
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.options.concurrency = limit;
+        self
+    }
+
+    /// Replace every fetching option at once.
+    pub fn with_options(mut self, options: PaginateOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Fetch every page and return all items.
+    ///
+    /// Fails with [`Error::Other`] as soon as more items than the cap arrive, rather
+    /// than returning a silently truncated list or paging without end.
+    pub async fn collect_all(self) -> Result<Vec<T>, Error>
+    where
+        T: Send + 'static,
+    {
+        let max_items = self
+            .max_items
+            .or(self.client.inner().max_list_items)
+            .unwrap_or(DEFAULT_MAX_ITEMS);
+        let operation = self.operation.clone();
+        let mut items = Vec::new();
+        let mut stream = std::pin::pin!(self.stream());
+        while let Some(item) = stream.try_next().await? {
+            if items.len() == max_items {
+                return Err(Error::Other(format!(
+                    "{operation} returned more than {max_items} items; raise the cap with \
+                     max_items or narrow the list with filters"
+                )));
+            }
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    /// Fetch only the first page.
+    pub async fn page(self) -> Result<Page<T>, Error> {
+        let request = self.first_request()?;
+        fetch_page(&self.client, request, self.items_key.as_deref()).await
+    }
+
+    /// Start fetching pages, yielding items in the order the API returns them.
+    ///
+    /// Pages are requested lazily as the stream is polled. The stream ends after the
+    /// last page, or after the first error.
+    pub fn stream(self) -> impl Stream<Item = Result<T, Error>> + Send + 'static
+    where
+        T: Send + 'static,
+    {
+        let prefetch = self.options.prefetch;
+        let pages = if self.options.concurrency > 1 {
+            self.concurrent_pages().left_stream()
+        } else {
+            self.sequential_pages().right_stream()
+        };
+        let pages = if prefetch > 0 {
+            prefetched(pages, prefetch).left_stream()
+        } else {
+            pages.right_stream()
+        };
+        pages
+            .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// Pages one after another, following `links.pages.next`.
+    fn sequential_pages(self) -> impl Stream<Item = Result<Vec<T>, Error>> + Send + 'static
+    where
+        T: Send + 'static,
+    {
+        let state = self.first_request().map(|request| PageState {
+            client: self.client,
+            items_key: self.items_key,
+            next: Some(request),
+        });
+
+        stream::try_unfold(state, |state| async move {
+            let mut state = state?;
+            match state.next.take() {
+                Some(request) => {
+                    let items: Vec<T> = state.fetch(request).await?;
+                    Ok(Some((items, Ok(state))))
+                }
+                None => Ok::<_, Error>(None),
+            }
+        })
+    }
+
+    /// The first page, then every following page by number, `concurrency` at a time.
+    fn concurrent_pages(self) -> impl Stream<Item = Result<Vec<T>, Error>> + Send + 'static
+    where
+        T: Send + 'static,
+    {
+        let first_request = self.first_request();
+        let concurrency = self.options.concurrency;
+        let Paginate {
+            client, items_key, ..
+        } = self;
+
+        stream::once(async move {
+            let request = first_request?;
+            let first = fetch_page::<T>(&client, request.clone(), items_key.as_deref()).await?;
+            let first_number = page_number(&request.query).unwrap_or(1);
+            let last_number = match first.total {
+                Some(total) if first.next.is_some() => {
+                    last_page_number(first_number, first.items.len(), total)
+                }
+                _ => first_number,
+            };
+
+            let rest = stream::iter(first_number + 1..=last_number)
+                .map(move |number| {
+                    let client = client.clone();
+                    let items_key = items_key.clone();
+                    let request = with_page_number(&request, number);
+                    async move {
+                        fetch_page::<T>(&client, request, items_key.as_deref())
+                            .await
+                            .map(|page| page.items)
+                    }
+                })
+                .buffered(concurrency);
+            Ok::<_, Error>(stream::once(future::ready(Ok(first.items))).chain(rest))
+        })
+        .try_flatten()
+    }
+
+    fn first_request(&self) -> Result<ApiRequest, Error> {
+        let op = paginated_operation(&self.operation)?;
+        let mut path = op.path.to_string();
+        for (name, value) in &self.path_params {
+            path = path.replace(&format!("{{{name}}}"), value);
+        }
+        if path.contains('{') {
+            return Err(Error::InvalidInput(format!(
+                "missing path parameters for {}: {}",
+                op.operation_id, path
+            )));
+        }
+
+        let mut request = ApiRequest::get(op.operation_id, path);
+        for (key, value) in &self.query {
+            request = request.query(key, value);
+        }
+        Ok(request)
+    }
+}
+
+/// Drive `pages` in a background task that runs up to `prefetch` pages ahead of the
+/// returned stream.
+///
+/// The task is spawned on first poll, so building the stream needs no runtime, and
+/// aborted when the stream is dropped.
+fn prefetched<S>(pages: S, prefetch: usize) -> impl Stream<Item = S::Item> + Send + 'static
+where
+    S: Stream + Send + 'static,
+    S::Item: Send + 'static,
+{
+    stream::once(async move {
+        let (sender, receiver) = tokio::sync::mpsc::channel(prefetch);
+        let task = tokio::spawn(async move {
+            let mut pages = std::pin::pin!(pages);
+            while let Some(page) = pages.next().await {
+                if sender.send(page).await.is_err() {
+                    break;
+                }
+            }
+        });
+        stream::unfold(
+            (receiver, AbortOnDrop(task)),
+            |(mut receiver, task)| async move {
+                let page = receiver.recv().await?;
+                Some((page, (receiver, task)))
+            },
+        )
+    })
+    .flatten()
+}
+
+/// Aborts a background task when dropped.
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+struct PageState {
+    client: Client,
+    items_key: Option<String>,
+    next: Option<ApiRequest>,
+}
+
+impl PageState {
+    /// Fetch one page, remember the request for the following page and return the
+    /// page's items.
+    async fn fetch<T: DeserializeOwned>(&mut self, request: ApiRequest) -> Result<Vec<T>, Error> {
+        let current_query = request.query.clone();
+        let page = fetch_page(&self.client, request, self.items_key.as_deref()).await?;
+        self.next = page
+            .next
+            .map(|next| next.to_request())
+            .transpose()?
+            .filter(|next| next.query != current_query);
+        Ok(page.items)
+    }
+}
+
+/// Fetch the page `request` asks for, decoding its items and links.
+async fn fetch_page<T: DeserializeOwned>(
+    client: &Client,
+    request: ApiRequest,
+    items_key: Option<&str>,
+) -> Result<Page<T>, Error> {
+    let context = request.context(client.inner().redact_error_paths);
+    let operation_id = request.operation_id;
+    let mut page: Value = client.send_json(request).await?;
+
+    let cursor = |link: &str| -> Result<Option<PageCursor>, Error> {
+        page_link(&page, link)
+            .map(|url| url_to_request(operation_id, &url))
+            .transpose()
+            .map(|request| request.map(|request| PageCursor::from_request(&request, items_key)))
+    };
+    let next = cursor("next")?;
+    let prev = cursor("prev")?;
+    let total = page.pointer("/meta/total").and_then(Value::as_u64);
+
+    let items = take_items(&mut page, items_key).ok_or_else(|| {
+        Error::Other(format!(
+            "could not find the list of items in the response to {context}; \
+             set `items_key` to the field holding them"
+        ))
+    })?;
+    let items = items
+        .into_iter()
+        .map(|item| {
+            serde_json::from_value(item).map_err(|source| Error::Decode {
+                context: context.clone(),
+                source,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Page {
+        items,
+        total,
+        next,
+        prev,
+    })
+}
+
+fn paginated_operation(name: &str) -> Result<&'static OperationMeta, Error> {
+    match operations::find(name) {
+        Some(op) if op.paginated && op.method == "GET" => Ok(op),
+        Some(op) => Err(Error::InvalidInput(format!(
+            "{} is not a paginated list operation",
+            op.operation_id
+        ))),
+        None => Err(Error::InvalidInput(format!("unknown operation: {name}"))),
+    }
+}
+
+/// The `links.pages.{link}` URL of a page, such as `next`, if there is one.
+fn page_link(page: &Value, link: &str) -> Option<String> {
+    page.pointer(&format!("/links/pages/{link}"))
+        .and_then(Value::as_str)
+        .filter(|next| !next.is_empty())
+        .map(str::to_string)
+}
+
+/// The `page` query parameter, if set.
+fn page_number(query: &[(String, String)]) -> Option<u64> {
+    query
+        .iter()
+        .find(|(key, _)| key == "page")
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+/// `request` asking for page `number` instead.
+fn with_page_number(request: &ApiRequest, number: u64) -> ApiRequest {
+    let mut request = request.clone();
+    request.query.retain(|(key, _)| key != "page");
+    request.query("page", number)
+}
+
+/// Number of the last page of a list of `total` items, given that page `first` came
+/// back full with `page_len` items.
+fn last_page_number(first: u64, page_len: usize, total: u64) -> u64 {
+    match page_len as u64 {
+        0 => first,
+        page_len => total.div_ceil(page_len).max(first),
+    }
+}
+
+/// Whether `path` is the operation path `template` with its placeholders filled.
+///
+/// The path must also come out of URL parsing unchanged, and filled placeholders may
+/// not contain an encoded slash, so they cannot add a query or fragment (`?`, `#`) or
+/// step out of the template (`..`, `%2F`).
+fn path_matches(template: &str, path: &str) -> bool {
+    let sent = reqwest::Url::parse(&format!("http://api.invalid{path}"));
+    if !sent
+        .is_ok_and(|url| url.path() == path && url.query().is_none() && url.fragment().is_none())
+    {
+        return false;
+    }
+    let mut segments = path.split('/');
+    template.split('/').all(|expected| match segments.next() {
+        Some(segment) if expected.starts_with('{') => {
+            let lower = segment.to_ascii_lowercase();
+            !segment.is_empty() && !lower.contains("%2f") && !lower.contains("%5c")
+        }
+        Some(segment) => segment == expected,
+        None => false,
+    }) && segments.next().is_none()
+}
+
+/// The API path of `url` (from `/v2/` on) if it lies under the base URL `base`.
+fn api_path<'a>(base: &str, url: &'a reqwest::Url) -> Option<&'a str> {
+    let base = reqwest::Url::parse(base).ok()?;
+    if url.origin() != base.origin() {
+        return None;
+    }
+    url.path()
+        .strip_prefix(base.path().trim_end_matches('/'))
+        .filter(|path| path.starts_with("/v2/"))
+}
+
+/// Turn a `links.pages.next` URL into a request against the client's own base URL.
+///
+/// Only the API path (from `/v2/` on) and query are kept, so pagination keeps working
+/// through proxies and custom base URLs, whether their links point at the public API
+/// host or at the proxy with its own path prefix.
+fn url_to_request(operation_id: &'static str, next: &str) -> Result<ApiRequest, Error> {
+    let url = reqwest::Url::parse(next)
+        .map_err(|err| Error::Other(format!("invalid next page link {next:?}: {err}")))?;
+    let path = url.path();
+    let path = path.find("/v2/").map_or(path, |start| &path[start..]);
+    Ok(url.query_pairs().fold(
+        ApiRequest::get(operation_id, path),
+        |request, (key, value)| request.query(&key, value),
+    ))
+}
+
+/// Remove and return the array of items from a list response.
+///
+/// With no explicit key the single array-valued field outside `links`/`meta` is used;
+/// responses with zero or several candidates return `None`.
+fn take_items(page: &mut Value, items_key: Option<&str>) -> Option<Vec<Value>> {
+    let object = page.as_object_mut()?;
+    let key = match items_key {
+        Some(key) => key.to_string(),
+        None => {
+            let mut candidates = object
+                .iter()
+                .filter(|(key, value)| value.is_array() && !ENVELOPE_KEYS.contains(&key.as_str()))
+                .map(|(key, _)| key);
+            match (candidates.next(), candidates.next()) {
+                (Some(key), None) => key.clone(),
+                _ => return None,
+            }
+        }
+    };
+    match object.remove(&key)? {
+        Value::Array(items) => Some(items),
+        Value::Null => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/list_all.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_items_detected_without_key() {
+        let mut page = json!({
+            "droplets": [{"id": 1}, {"id": 2}],
+            "links": {"pages": {"next": "https://api.digitalocean.com/v2/droplets?page=2"}},
+            "meta": {"total": 3}
+        });
+        assert_eq!(
+            take_items(&mut page, None).map(|items| items.len()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_items_require_key() {
+        let mut page = json!({"droplets": [], "kernels": []});
+        assert!(take_items(&mut page.clone(), None).is_none());
+        assert_eq!(take_items(&mut page, Some("kernels")), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_next_link_keeps_path_and_query() {
+        let page = json!({
+            "links": {"pages": {"next": "https://api.digitalocean.com/v2/droplets?page=2&per_page=200&tag_name=web"}}
+        });
+        let request = url_to_request("droplets_list", &page_link(&page, "next").unwrap()).unwrap();
+        assert_eq!(request.path, "/v2/droplets");
+        assert_eq!(
+            request.query,
+            [
+                ("page".to_string(), "2".to_string()),
+                ("per_page".to_string(), "200".to_string()),
+                ("tag_name".to_string(), "web".to_string()),
+            ]
+        );
+
+        let proxied = url_to_request("droplets_list", "http://proxy/do-api/v2/droplets?page=2");
+        assert_eq!(proxied.unwrap().path, "/v2/droplets");
+    }
+
+    #[test]
+    fn test_cursor_roundtrip_and_path_check() {
+        let request = url_to_request(
+            "domains_list_records",
+            "https://api.digitalocean.com/v2/domains/example.com/records?page=3",
+        )
+        .unwrap();
+        let cursor = PageCursor::from_request(&request, Some("domain_records"));
+        assert_eq!(cursor.page(), Some(3));
+        let token = serde_json::to_string(&cursor).unwrap();
+        assert_eq!(serde_json::from_str::<PageCursor>(&token).unwrap(), cursor);
+
+        let template = "/v2/domains/{domain_name}/records";
+        assert!(path_matches(template, "/v2/domains/example.com/records"));
+        assert!(!path_matches(template, "/v2/domains//records"));
+        assert!(!path_matches(template, "/v2/domains/example.com/records/1"));
+        assert!(!path_matches(template, "/v2/account/keys"));
+        assert!(!path_matches(template, "/v2/domains/..%2F../records"));
+        assert!(!path_matches(template, "/v2/domains/../records"));
+        assert!(!path_matches(template, "/v2/domains/%2e%2e/records"));
+        assert!(!path_matches(template, "/v2/domains/x?page=1/records"));
+        assert!(!path_matches(template, "/v2/domains/x#/records"));
+
+        let forged = PageCursor {
+            path: "/v2/domains/../../v2/account/records".to_string(),
+            ..cursor
+        };
+        assert!(matches!(forged.to_request(), Err(Error::InvalidInput(_))));
+        let forged = PageCursor {
+            path: "/v2/domains/x?name=y/records".to_string(),
+            ..forged
+        };
+        assert!(matches!(forged.to_request(), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_concurrent_page_numbers() {
+        assert_eq!(last_page_number(1, 200, 1000), 5);
+        assert_eq!(last_page_number(1, 200, 1001), 6);
+        assert_eq!(last_page_number(3, 20, 41), 3);
+        assert_eq!(last_page_number(1, 0, 10), 1);
+
+        let request = ApiRequest::get("droplets_list", "/v2/droplets")
+            .query("page", 1)
+            .query("per_page", 200);
+        let request = with_page_number(&request, 4);
+        assert_eq!(page_number(&request.query), Some(4));
+        assert_eq!(request.query.len(), 2);
+    }
+
+    #[test]
+    fn test_list_options_query() {
+        let options = ListOptions::new()
+            .param("type", "gpus")
+            .tag_name("web")
+            .per_page(50);
+        assert_eq!(
+            options.query_pairs(),
+            [
+                ("per_page".to_string(), "50".to_string()),
+                ("tag_name".to_string(), "web".to_string()),
+                ("type".to_string(), "gpus".to_string()),
+            ]
+        );
+        assert!(ListOptions::new().query_pairs().is_empty());
+    }
+
+    #[test]
+    fn test_links_and_total_from_generated_values() {
+        let links = json!({"pages": {"next": "https://api.digitalocean.com/v2/droplets?page=2"}});
+        let links = PageLinks::from_links(&links);
+        assert_eq!(
+            links.next.as_deref(),
+            Some("https://api.digitalocean.com/v2/droplets?page=2")
+        );
+        assert_eq!(links.prev, None);
+        assert_eq!(PageLinks::from_links(&None::<Value>), PageLinks::default());
+
+        assert_eq!(meta_total(&json!({"total": 42})), Some(42));
+        assert_eq!(meta_total(&None::<Value>), None);
+    }
+
+    #[test]
+    fn test_resume_link_must_match_base_url() {
+        let url = |link: &str| reqwest::Url::parse(link).unwrap();
+        let link = url("https://api.digitalocean.com/v2/droplets?page=3");
+        assert_eq!(
+            api_path("https://api.digitalocean.com", &link),
+            Some("/v2/droplets")
+        );
+        assert_eq!(api_path("https://do-proxy.internal", &link), None);
+        assert_eq!(api_path("http://api.digitalocean.com", &link), None);
+
+        let proxied = url("http://do-proxy/api/v2/droplets?page=2");
+        assert_eq!(
+            api_path("http://do-proxy/api/", &proxied),
+            Some("/v2/droplets")
+        );
+        assert_eq!(api_path("http://do-proxy/other", &proxied), None);
+
+        let client = Client::from_token("token");
+        let resumed = client
+            .resume_list::<Value>("https://api.digitalocean.com/v2/droplets?page=3&per_page=200")
+            .unwrap();
+        assert_eq!(resumed.operation, "droplets_list");
+        assert_eq!(page_number(&resumed.query), Some(3));
+        assert!(client
+            .resume_list::<Value>("https://evil.example/v2/droplets?page=3")
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_keeps_order_and_runs_ahead() {
+        let fetched = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = fetched.clone();
+        let pages = stream::iter(0..5).map(move |page| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            page
+        });
+        let mut pages = std::pin::pin!(prefetched(pages, 2));
+
+        assert_eq!(pages.next().await, Some(0));
+        tokio::task::yield_now().await;
+        // The consumer holds one page while the task has fetched further ones.
+        assert!(fetched.load(std::sync::atomic::Ordering::SeqCst) >= 3);
+        assert_eq!(pages.collect::<Vec<_>>().await, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_last_page_has_no_next_link() {
+        assert_eq!(page_link(&json!({"links": {}}), "next"), None);
+        assert_eq!(
+            page_link(&json!({"links": {"pages": {"first": "x"}}}), "next"),
+            None
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Moving resources between projects.
+//!
+//! Projects group resources by URN (`do:droplet:13457723`, `do:domain:example.com`,
+//! ...), and a resource belongs to exactly one project at a time: assigning it to
+//! another project moves it. [`Client::move_project_resources`] lists a project's
+//! resources, keeps the ones a [`ResourceSelector`] matches and assigns them to the
+//! target project in batches, reporting the outcome per resource.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::projects::ResourceSelector;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let selector = ResourceSelector::all().kind("droplet").kind("volume");
+//! let report = client
+//!     .move_project_resources(
+//!         "4e1bfbc3-dc3e-41f2-a18f-1b4d7ba71679",
+//!         "b4e1ce07-6bd2-4a6e-9a4e-4f0e1d6c2f8a",
+//!         &selector,
+//!     )
+//!     .await?;
+//! println!("moved {} resources", report.moved().count());
+//! for (urn, err) in report.failed() {
+//!     eprintln!("{urn}: {err}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::{Client, ClientInfo};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Most URNs sent in one `projects_assign_resources` request.
+const ASSIGN_BATCH_SIZE: usize = 50;
+
+/// A resource assigned to a project.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProjectResource {
+    pub urn: String,
+    #[serde(default)]
+    pub assigned_at: Option<String>,
+    /// `ok` once assigned; anything else means the assignment did not take.
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
+impl ProjectResource {
+    /// The resource type from the URN, e.g. `droplet` for `do:droplet:13457723`.
+    pub fn kind(&self) -> Option<&str> {
+        urn_kind(&self.urn)
+    }
+}
+
+fn urn_kind(urn: &str) -> Option<&str> {
+    let mut parts = urn.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("do"), Some(kind), Some(_)) => Some(kind),
+        _ => None,
+    }
+}
+
+/// Which resources of a project to move.
+///
+/// Starts out matching everything; [`kind`](Self::kind) and [`urn`](Self::urn) narrow
+/// it down (a resource must match one of the kinds and one of the URNs, where given),
+/// and [`exclude`](Self::exclude) removes single resources.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceSelector {
+    kinds: HashSet<String>,
+    urns: HashSet<String>,
+    excluded: HashSet<String>,
+}
+
+impl ResourceSelector {
+    /// Every resource of the project.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Only resources of type `kind`, the second URN segment (`droplet`, `volume`,
+    /// `domain`, `dbaas`, `kubernetes`, `loadbalancer`, `floatingip`, `space`, `app`).
+    pub fn kind(mut self, kind: impl Into<String>) -> Self {
+        self.kinds.insert(kind.into());
+        self
+    }
+
+    /// Only the resource with this URN.
+    pub fn urn(mut self, urn: impl Into<String>) -> Self {
+        self.urns.insert(urn.into());
+        self
+    }
+
+    /// Never the resource with this URN.
+    pub fn exclude(mut self, urn: impl Into<String>) -> Self {
+        self.excluded.insert(urn.into());
+        self
+    }
+
+    /// Whether the resource with `urn` is selected.
+    pub fn matches(&self, urn: &str) -> bool {
+        let kind_matches =
+            self.kinds.is_empty() || urn_kind(urn).is_some_and(|kind| self.kinds.contains(kind));
+        let urn_matches = self.urns.is_empty() || self.urns.contains(urn);
+        kind_matches && urn_matches && !self.excluded.contains(urn)
+    }
+}
+
+/// Outcome for one resource of [`Client::move_project_resources`].
+#[derive(Debug)]
+pub struct MovedResource {
+    pub urn: String,
+    /// Resources sent in the same rejected request share its error.
+    pub result: Result<(), Arc<Error>>,
+}
+
+/// Outcome of [`Client::move_project_resources`], in the order the source project
+/// listed the resources.
+#[derive(Debug)]
+pub struct ProjectMove {
+    pub from_project: String,
+    pub to_project: String,
+    pub resources: Vec<MovedResource>,
+    /// Resources of the source project the selector did not match.
+    pub skipped: usize,
+}
+
+impl ProjectMove {
+    /// URNs of the resources now in the target project.
+    pub fn moved(&self) -> impl Iterator<Item = &str> {
+        self.resources
+            .iter()
+            .filter(|r| r.result.is_ok())
+            .map(|r| r.urn.as_str())
+    }
+
+    /// URNs of the resources that stayed behind, with the reason.
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &Error)> {
+        self.resources
+            .iter()
+            .filter_map(|r| r.result.as_ref().err().map(|err| (r.urn.as_str(), &**err)))
+    }
+
+    /// Whether every selected resource was moved.
+    pub fn all_moved(&self) -> bool {
+        self.resources.iter().all(|r| r.result.is_ok())
+    }
+}
+
+#[derive(Deserialize)]
+struct AssignedEnvelope {
+    #[serde(default)]
+    resources: Vec<ProjectResource>,
+}
+
+impl Client {
+    /// List every resource assigned to `project_id`.
+    pub async fn project_resources(&self, project_id: &str) -> Result<Vec<ProjectResource>, Error> {
+        self.paginate("projects_list_resources")
+            .path_param("project_id", project_id)
+            .items_key("resources")
+            .per_page(200)
+            .stream()
+            .try_collect()
+            .await
+    }
+
+    /// Move the resources of `from_project` matched by `selector` into `to_project`.
+    ///
+    /// Resources are assigned in batches of 50. A rejected batch fails every resource
+    /// in it without stopping the remaining batches; only failing to list the source
+    /// project is an error of the call itself.
+    pub async fn move_project_resources(
+        &self,
+        from_project: &str,
+        to_project: &str,
+        selector: &ResourceSelector,
+    ) -> Result<ProjectMove, Error> {
+        if from_project == to_project {
+            return Err(Error::InvalidInput(format!(
+                "cannot move resources of project {from_project} into itself"
+            )));
+        }
+
+        let listed = self.project_resources(from_project).await?;
+        let total = listed.len();
+        let selected: Vec<String> = listed
+            .into_iter()
+            .map(|resource| resource.urn)
+            .filter(|urn| selector.matches(urn))
+            .collect();
+
+        let workflow = Workflow::new(
+            self.inner(),
+            "move_project_resources",
+            format!("project {to_project}"),
+        );
+        let mut resources = Vec::with_capacity(selected.len());
+        for batch in selected.chunks(ASSIGN_BATCH_SIZE) {
+            let request = ApiRequest::post(
+                "projects_assign_resources",
+                format!("/v2/projects/{to_project}/resources"),
+            )
+            .json(json!({ "resources": batch }));
+            match workflow
+                .step("assign", self.send_json::<AssignedEnvelope>(request))
+                .await
+            {
+                Ok(assigned) => resources.extend(batch.iter().map(|urn| {
+                    let status = assigned
+                        .resources
+                        .iter()
+                        .find(|resource| &resource.urn == urn)
+                        .and_then(|resource| resource.status.as_deref());
+                    let result = match status {
+                        None | Some("ok") => Ok(()),
+                        Some(status) => Err(Arc::new(Error::Other(format!(
+                            "{urn} was not assigned to project {to_project}: {status}"
+                        )))),
+                    };
+                    MovedResource {
+                        urn: urn.clone(),
+                        result,
+                    }
+                })),
+                Err(err) => {
+                    let err = Arc::new(err);
+                    resources.extend(batch.iter().map(|urn| MovedResource {
+                        urn: urn.clone(),
+                        result: Err(err.clone()),
+                    }));
+                }
+            }
+        }
+
+        Ok(ProjectMove {
+            from_project: from_project.to_string(),
+            to_project: to_project.to_string(),
+            skipped: total - resources.len(),
+            resources,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selector_matches_kind_urn_and_exclusions() {
+        let all = ResourceSelector::all();
+        assert!(all.matches("do:droplet:13457723"));
+
+        let droplets = ResourceSelector::all()
+            .kind("droplet")
+            .kind("volume")
+            .exclude("do:droplet:1");
+        assert!(droplets.matches("do:droplet:13457723"));
+        assert!(droplets.matches("do:volume:506f78a4-e098-11e5-ad9f-000f53306ae1"));
+        assert!(!droplets.matches("do:domain:example.com"));
+        assert!(!droplets.matches("do:droplet:1"));
+        assert!(!droplets.matches("not-a-urn"));
+
+        let one = ResourceSelector::all().urn("do:domain:example.com");
+        assert!(one.matches("do:domain:example.com"));
+        assert!(!one.matches("do:domain:example.org"));
+
+        let resource: ProjectResource =
+            serde_json::from_value(json!({"urn": "do:floatingip:192.0.2.1", "status": "ok"}))
+                .unwrap();
+        assert_eq!(resource.kind(), Some("floatingip"));
+    }
+}
@@ -0,0 +1,287 @@
+//! Rate-limit state reported by the API.
+//!
+//! Every response carries `RateLimit-Limit`, `RateLimit-Remaining` and
+//! `RateLimit-Reset` headers describing the token's hourly request budget. The client
+//! records the most recent values, available from [`Client::rate_limit`], so batch jobs
+//! can slow down before they hit `429 Too Many Requests`. Responses of generated
+//! operations expose their own values through [`ResponseRateLimit`].
+//!
+//! Alternatively, [`ClientBuilder::rate_limit`](crate::ClientBuilder::rate_limit) makes
+//! the client pace itself: a token bucket shared by all clones of the client delays
+//! requests so they stay under a budget such as DigitalOcean's 5,000 per hour.
+//! [`ClientBuilder::adaptive_rate_limit`](crate::ClientBuilder::adaptive_rate_limit)
+//! instead follows the API's own count: as `RateLimit-Remaining` runs low, requests
+//! are spread over the time left until `RateLimit-Reset`, and once it reaches zero
+//! they pause until the reset.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let _ = client.account().await?;
+//! if let Some(limit) = client.rate_limit() {
+//!     if limit.remaining < 100 {
+//!         println!("only {} requests left until {}", limit.remaining, limit.reset);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Request budget of the token, as of one response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    /// Requests allowed per window.
+    pub limit: u64,
+    /// Requests left in the current window.
+    pub remaining: u64,
+    /// When the oldest request in the window stops counting against the limit.
+    pub reset: DateTime<Utc>,
+}
+
+impl RateLimitInfo {
+    /// Parse the `RateLimit-*` headers of a response; `None` if any is missing or
+    /// malformed.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let number = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok();
+        let reset = i64::try_from(number("ratelimit-reset")?).ok()?;
+        Some(Self {
+            limit: number("ratelimit-limit")?,
+            remaining: number("ratelimit-remaining")?,
+            reset: DateTime::from_timestamp(reset, 0)?,
+        })
+    }
+
+    /// Whether no requests are left before [`reset`](Self::reset).
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// Rate-limit headers of a generated operation's response.
+pub trait ResponseRateLimit {
+    fn rate_limit(&self) -> Option<RateLimitInfo>;
+}
+
+impl<T> ResponseRateLimit for progenitor_client::ResponseValue<T> {
+    fn rate_limit(&self) -> Option<RateLimitInfo> {
+        RateLimitInfo::from_headers(self.headers())
+    }
+}
+
+/// The most recent [`RateLimitInfo`] seen by a client and its clones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RateLimitTracker(Arc<Mutex<Option<RateLimitInfo>>>);
+
+impl RateLimitTracker {
+    pub(crate) fn record(&self, headers: &HeaderMap) {
+        if let Some(info) = RateLimitInfo::from_headers(headers) {
+            *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(info);
+        }
+    }
+
+    pub(crate) fn latest(&self) -> Option<RateLimitInfo> {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Client-side token bucket that spaces out requests.
+///
+/// Holds up to `burst` tokens and regains `requests_per_hour` of them per hour. Each
+/// request takes a token; when none is left the request waits until one is due.
+/// Waiting requests reserve their tokens in arrival order, so they are released one
+/// by one rather than all at once.
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimiter {
+    requests_per_hour: u32,
+    burst: u32,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Negative while requests are waiting for tokens they have reserved.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(requests_per_hour: u32, burst: u32) -> Self {
+        let burst = burst.max(1);
+        Self {
+            requests_per_hour: requests_per_hour.max(1),
+            burst,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: f64::from(burst),
+                updated: Instant::now(),
+            })),
+        }
+    }
+
+    /// A full bucket with the same settings, for a client with another token.
+    pub(crate) fn fresh(&self) -> Self {
+        Self::new(self.requests_per_hour, self.burst)
+    }
+
+    /// Wait until the next request may be sent.
+    pub(crate) async fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            tracing::debug!(?wait, "client-side rate limit reached; waiting");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take a token at `now` and return how long to wait until it is due.
+    fn reserve(&self, now: Instant) -> Duration {
+        let per_second = f64::from(self.requests_per_hour) / 3600.0;
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(f64::from(self.burst));
+        bucket.updated = now;
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / per_second)
+        }
+    }
+}
+
+/// Slows requests down as the API reports the token's budget running out.
+#[derive(Debug, Clone)]
+pub(crate) struct AdaptiveThrottle {
+    slow_below: u64,
+    /// Whether the last request was delayed, so engaging is logged once.
+    engaged: Arc<AtomicBool>,
+}
+
+impl AdaptiveThrottle {
+    pub(crate) fn new(slow_below: u64) -> Self {
+        Self {
+            slow_below,
+            engaged: Arc::default(),
+        }
+    }
+
+    /// Wait as long as the most recent rate-limit headers call for.
+    pub(crate) async fn wait(&self, latest: Option<RateLimitInfo>) {
+        let wait = latest.map_or(Duration::ZERO, |info| self.delay(&info, Utc::now()));
+        let was_engaged = self.engaged.swap(!wait.is_zero(), Ordering::Relaxed);
+        match latest {
+            Some(info) if !wait.is_zero() => {
+                if !was_engaged {
+                    tracing::warn!(
+                        remaining = info.remaining,
+                        reset = %info.reset,
+                        ?wait,
+                        "rate limit running low; throttling requests"
+                    );
+                }
+                tokio::time::sleep(wait).await;
+            }
+            _ if was_engaged => tracing::info!("rate limit recovered; throttling released"),
+            _ => {}
+        }
+    }
+
+    /// Delay before the next request given `info` at `now`: none while plenty of the
+    /// budget is left, the time left spread over the remaining requests when it runs
+    /// low, and the whole wait until the reset once it is used up.
+    fn delay(&self, info: &RateLimitInfo, now: DateTime<Utc>) -> Duration {
+        let until_reset = (info.reset - now).to_std().unwrap_or_default();
+        if info.remaining >= self.slow_below || until_reset.is_zero() {
+            Duration::ZERO
+        } else if info.is_exhausted() {
+            until_reset
+        } else {
+            until_reset / u32::try_from(info.remaining + 1).unwrap_or(u32::MAX)
+        }
+    }
+}
+
+impl Client {
+    /// Rate-limit state from the most recent response, or `None` before the first one.
+    ///
+    /// Shared with clones of this client; clients switched to another token with
+    /// [`Client::with_token`] start over, as limits are per token.
+    pub fn rate_limit(&self) -> Option<RateLimitInfo> {
+        self.inner().rate_limit.latest()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headers_are_parsed() {
+        let mut headers = HeaderMap::new();
+        headers.insert("ratelimit-limit", "5000".parse().unwrap());
+        headers.insert("ratelimit-remaining", "4816".parse().unwrap());
+        headers.insert("ratelimit-reset", "1444931833".parse().unwrap());
+        let info = RateLimitInfo::from_headers(&headers).unwrap();
+        assert_eq!(info.limit, 5000);
+        assert_eq!(info.remaining, 4816);
+        assert_eq!(info.reset.to_rfc3339(), "2015-10-15T17:57:13+00:00");
+
+        headers.remove("ratelimit-reset");
+        assert_eq!(RateLimitInfo::from_headers(&headers), None);
+    }
+
+    #[test]
+    fn test_limiter_allows_burst_then_spaces_requests() {
+        let limiter = RateLimiter::new(3600, 2);
+        let start = Instant::now();
+        assert_eq!(limiter.reserve(start), Duration::ZERO);
+        assert_eq!(limiter.reserve(start), Duration::ZERO);
+        // One token per second: the third request waits a second, the fourth two.
+        assert_eq!(limiter.reserve(start).as_secs_f64().round(), 1.0);
+        assert_eq!(limiter.reserve(start).as_secs_f64().round(), 2.0);
+
+        let clone = limiter.clone();
+        let later = start + Duration::from_secs(10);
+        assert_eq!(clone.reserve(later), Duration::ZERO);
+        assert_eq!(limiter.reserve(later), Duration::ZERO);
+        assert!(limiter.reserve(later) > Duration::ZERO);
+        assert_eq!(limiter.fresh().reserve(later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_throttle_spreads_remaining_budget() {
+        let throttle = AdaptiveThrottle::new(100);
+        let now = Utc::now();
+        let info = |remaining| RateLimitInfo {
+            limit: 5000,
+            remaining,
+            reset: now + chrono::TimeDelta::seconds(100),
+        };
+        assert_eq!(throttle.delay(&info(4000), now), Duration::ZERO);
+        assert_eq!(throttle.delay(&info(99), now), Duration::from_secs(1));
+        assert_eq!(throttle.delay(&info(0), now), Duration::from_secs(100));
+        let later = now + chrono::TimeDelta::seconds(200);
+        assert_eq!(throttle.delay(&info(0), later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_tracker_keeps_last_values() {
+        let tracker = RateLimitTracker::default();
+        let clone = tracker.clone();
+        tracker.record(&HeaderMap::new());
+        assert_eq!(clone.latest(), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("RateLimit-Limit", "5000".parse().unwrap());
+        headers.insert("RateLimit-Remaining", "0".parse().unwrap());
+        headers.insert("RateLimit-Reset", "1444931833".parse().unwrap());
+        tracker.record(&headers);
+        assert!(clone.latest().unwrap().is_exhausted());
+    }
+}
@@ -0,0 +1,241 @@
+//! Calling any operation without its generated types.
+//!
+//! Generated methods deserialize straight into the types built from the spec, so a
+//! response that drifted from the spec fails the call and hides the body, and fields
+//! the spec does not model yet are dropped. [`Client::execute_raw`] sends any
+//! operation from the [`operations`](crate::operations) registry through the same
+//! transport (authentication, retries, hooks) and hands back the status, headers and
+//! body untouched, for any status code.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let response = client
+//!     .execute_raw("droplets_get")
+//!     .path_param("droplet_id", 3164494)
+//!     .send()
+//!     .await?;
+//! println!("{} (request {:?})", response.status, response.meta().request_id);
+//! let droplet: serde_json::Value = response.json()?;
+//! println!("{}", droplet["droplet"]["status"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Error, InvalidResponse, OperationContext};
+use crate::operations::{self, OperationMeta};
+use crate::request::ApiRequest;
+use crate::response_meta::ResponseMeta;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// Builder for a call to any operation, returning the response as-is.
+///
+/// Created by [`Client::execute_raw`].
+#[derive(Debug, Clone)]
+#[must_use = "call `.send()` to perform the request"]
+pub struct RawRequest {
+    client: Client,
+    pub(crate) operation: String,
+    pub(crate) path_params: Vec<(String, String)>,
+    pub(crate) query: Vec<(String, String)>,
+    pub(crate) body: Option<Result<serde_json::Value, String>>,
+}
+
+impl Client {
+    /// Call the operation `operation` and return the undecoded response.
+    ///
+    /// `operation` is either the spec operation ID (`droplets_get`) or the generated
+    /// method name; see [`operations::find`].
+    pub fn execute_raw(&self, operation: impl Into<String>) -> RawRequest {
+        RawRequest {
+            client: self.clone(),
+            operation: operation.into(),
+            path_params: Vec::new(),
+            query: Vec::new(),
+            body: None,
+        }
+    }
+}
+
+impl RawRequest {
+    /// Fill the `{name}` placeholder in the operation's path.
+    pub fn path_param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.path_params.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Add a query parameter.
+    pub fn query(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.query.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Send `body` as the JSON request body.
+    pub fn json(mut self, body: &impl Serialize) -> Self {
+        self.body = Some(serde_json::to_value(body).map_err(|err| err.to_string()));
+        self
+    }
+
+    /// Perform the request.
+    ///
+    /// Only failing to send the request is an error; error statuses, including
+    /// `429 Too Many Requests`, are returned as responses.
+    pub async fn send(self) -> Result<RawResponse, Error> {
+        let request = self.build()?;
+        let context = request.context(self.client.inner().redact_error_paths);
+        let response = self.client.send_raw(request).await?;
+        let context = context.with_response(response.headers());
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = match response.bytes().await {
+            Ok(body) => body.to_vec(),
+            Err(source) => return Err(Error::Request { context, source }),
+        };
+        Ok(RawResponse {
+            status,
+            headers,
+            body,
+            context,
+        })
+    }
+
+    /// Poll this `GET` until it answers `404 Not Found`, for resources without a
+    /// dedicated `wait_until_*_deleted` helper.
+    ///
+    /// Fails with [`Error::InvalidInput`] for an operation that is not a read, or
+    /// with [`Error::Timeout`] if the resource is still present after the options'
+    /// timeout.
+    pub async fn wait_until_deleted(self, options: WaitOptions) -> Result<(), Error> {
+        if !self.is_read()? {
+            return Err(Error::InvalidInput(format!(
+                "{} does not read a resource; pass its GET operation",
+                self.operation
+            )));
+        }
+        let request = self.build()?;
+        let what = request.path.clone();
+        self.client
+            .wait_until_deleted_at(request, &what, options)
+            .await
+    }
+
+    fn operation(&self) -> Result<&'static OperationMeta, Error> {
+        operations::find(&self.operation)
+            .ok_or_else(|| Error::InvalidInput(format!("unknown operation: {}", self.operation)))
+    }
+
+    /// Whether the operation only reads (`GET` or `HEAD`).
+    pub(crate) fn is_read(&self) -> Result<bool, Error> {
+        Ok(matches!(self.operation()?.method, "GET" | "HEAD"))
+    }
+
+    fn build(&self) -> Result<ApiRequest, Error> {
+        let op = self.operation()?;
+        let path = fill_path(op, &self.path_params)?;
+        let method = Method::from_bytes(op.method.as_bytes()).map_err(|_| {
+            Error::Other(format!(
+                "invalid method for {}: {}",
+                op.operation_id, op.method
+            ))
+        })?;
+
+        let mut request = ApiRequest::new(op.operation_id, method, path);
+        for (key, value) in &self.query {
+            request = request.query(key, value);
+        }
+        match &self.body {
+            Some(Ok(body)) => request = request.json(body.clone()),
+            Some(Err(err)) => {
+                return Err(Error::InvalidInput(format!(
+                    "request body for {} is not valid JSON: {err}",
+                    op.operation_id
+                )));
+            }
+            None => {}
+        }
+        Ok(request)
+    }
+}
+
+/// Substitute `params` into the operation's path template.
+fn fill_path(op: &OperationMeta, params: &[(String, String)]) -> Result<String, Error> {
+    let mut path = op.path.to_string();
+    for (name, value) in params {
+        let placeholder = format!("{{{name}}}");
+        if !path.contains(&placeholder) {
+            return Err(Error::InvalidInput(format!(
+                "{} has no path parameter {name:?}: {}",
+                op.operation_id, op.path
+            )));
+        }
+        path = path.replace(&placeholder, value);
+    }
+    if path.contains('{') {
+        return Err(Error::InvalidInput(format!(
+            "missing path parameters for {}: {}",
+            op.operation_id, path
+        )));
+    }
+    Ok(path)
+}
+
+/// An undecoded response from [`RawRequest::send`].
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    context: OperationContext,
+}
+
+impl RawResponse {
+    /// Parsed common headers: request ID, rate limit, content type and date.
+    pub fn meta(&self) -> ResponseMeta {
+        ResponseMeta::from_headers(self.status, &self.headers)
+    }
+
+    /// The body as text, replacing invalid UTF-8.
+    pub fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+
+    /// Decode the body as JSON into `T`, e.g. [`serde_json::Value`] or a generated
+    /// type. A mismatch is reported as [`Error::InvalidResponse`] with the start of
+    /// the body.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_slice(&self.body).map_err(|source| {
+            InvalidResponse::error(self.context.clone(), self.status, &self.body, source)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_path() {
+        let op = OperationMeta {
+            operation_id: "droplets_get",
+            method_name: "droplets_get",
+            method: "GET",
+            path: "/v2/droplets/{droplet_id}",
+            paginated: false,
+            max_per_page: None,
+            docs_url: "",
+        };
+        assert_eq!(
+            fill_path(&op, &[("droplet_id".to_string(), "42".to_string())]).unwrap(),
+            "/v2/droplets/42"
+        );
+        assert!(fill_path(&op, &[]).is_err());
+        assert!(fill_path(&op, &[("id".to_string(), "42".to_string())]).is_err());
+    }
+}
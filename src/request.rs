@@ -0,0 +1,155 @@
+//! Request plumbing used by the hand-written helpers.
+//!
+//! The helpers talk to a handful of endpoints whose generated types are either awkward
+//! (deeply nested inline structs, `Variant0` enums) or drift frequently between spec
+//! revisions. Rather than going through the generated methods they build an [`ApiRequest`]
+//! and deserialize into small, purpose-built models, reusing the generated client's base
+//! URL and `reqwest::Client` so authentication and timeouts stay identical.
+
+use crate::error::{Error, InvalidResponse, OperationContext};
+use crate::transport;
+use crate::{Client, ClientInfo};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+
+/// A single API call described by method, path, query and optional JSON body.
+#[derive(Debug, Clone)]
+pub(crate) struct ApiRequest {
+    pub(crate) operation_id: &'static str,
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) query: Vec<(String, String)>,
+    pub(crate) headers: Vec<(&'static str, String)>,
+    pub(crate) body: Option<serde_json::Value>,
+}
+
+impl ApiRequest {
+    pub(crate) fn new(operation_id: &'static str, method: Method, path: impl Into<String>) -> Self {
+        Self {
+            operation_id,
+            method,
+            path: path.into(),
+            query: Vec::new(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub(crate) fn get(operation_id: &'static str, path: impl Into<String>) -> Self {
+        Self::new(operation_id, Method::GET, path)
+    }
+
+    pub(crate) fn post(operation_id: &'static str, path: impl Into<String>) -> Self {
+        Self::new(operation_id, Method::POST, path)
+    }
+
+    pub(crate) fn put(operation_id: &'static str, path: impl Into<String>) -> Self {
+        Self::new(operation_id, Method::PUT, path)
+    }
+
+    pub(crate) fn patch(operation_id: &'static str, path: impl Into<String>) -> Self {
+        Self::new(operation_id, Method::PATCH, path)
+    }
+
+    pub(crate) fn delete(operation_id: &'static str, path: impl Into<String>) -> Self {
+        Self::new(operation_id, Method::DELETE, path)
+    }
+
+    /// Appends a query parameter.
+    pub(crate) fn query(mut self, key: &str, value: impl ToString) -> Self {
+        self.query.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Adds a request header.
+    pub(crate) fn header(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+
+    /// Sets the JSON request body.
+    pub(crate) fn json(mut self, body: serde_json::Value) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// Describes this request for error reporting.
+    pub(crate) fn context(&self, redact: bool) -> OperationContext {
+        OperationContext::new(self.operation_id, self.method.clone(), &self.path, redact)
+    }
+}
+
+impl Client {
+    /// Sends `request` through the shared transport hooks and returns the raw response,
+    /// mapping non-success statuses to [`Error::Response`].
+    pub(crate) async fn send(&self, request: ApiRequest) -> Result<reqwest::Response, Error> {
+        let context = request.context(self.inner().redact_error_paths);
+        let response = self.send_raw(request).await?;
+        let context = context.with_response(response.headers());
+        let status = response.status();
+        if status.is_success() {
+            Ok(response)
+        } else if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            Err(Error::rate_limited(context, response.headers()))
+        } else {
+            let body = response.text().await.unwrap_or_default();
+            Err(Error::Response {
+                context,
+                status,
+                body,
+            })
+        }
+    }
+
+    /// Sends `request` through the shared transport hooks and returns the response
+    /// whatever its status.
+    pub(crate) async fn send_raw(&self, request: ApiRequest) -> Result<reqwest::Response, Error> {
+        let context = request.context(self.inner().redact_error_paths);
+        let url = format!("{}{}", self.baseurl().trim_end_matches('/'), request.path);
+        let mut builder = self.client().request(request.method, url);
+        if !request.query.is_empty() {
+            builder = builder.query(&request.query);
+        }
+        for (name, value) in &request.headers {
+            builder = builder.header(*name, value);
+        }
+        if let Some(body) = &request.body {
+            builder = builder.json(body);
+        }
+
+        let mut http_request = match builder.build() {
+            Ok(http_request) => http_request,
+            Err(source) => return Err(Error::Request { context, source }),
+        };
+        transport::prepare(&mut http_request, self.inner(), request.operation_id).await?;
+        transport::execute(
+            self.client(),
+            self.inner(),
+            http_request,
+            request.operation_id,
+        )
+        .await
+    }
+
+    /// Sends `request` and deserializes the JSON response body into `T`.
+    pub(crate) async fn send_json<T: DeserializeOwned>(
+        &self,
+        request: ApiRequest,
+    ) -> Result<T, Error> {
+        let context = request.context(self.inner().redact_error_paths);
+        let response = self.send(request).await?;
+        let context = context.with_response(response.headers());
+        let status = response.status();
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(source) => return Err(Error::Request { context, source }),
+        };
+        serde_json::from_slice(&bytes)
+            .map_err(|source| InvalidResponse::error(context, status, &bytes, source))
+    }
+
+    /// Sends `request` and discards the response body.
+    pub(crate) async fn send_empty(&self, request: ApiRequest) -> Result<(), Error> {
+        self.send(request).await.map(|_| ())
+    }
+}
@@ -0,0 +1,54 @@
+//! Request IDs for support tickets.
+//!
+//! DigitalOcean tags every response with an `x-request-id` header, and support asks
+//! for it when investigating a failed call. Errors from the helper APIs carry it in
+//! their [`OperationContext`](crate::error::OperationContext) and print it, and
+//! [`Error::request_id`](crate::error::Error::request_id) returns it. Responses and
+//! errors of generated operations expose it through [`ResponseRequestId`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::request_id::ResponseRequestId;
+//!
+//! # async fn run(client: rsdo::Client) {
+//! match client.droplets_get(3164494).await {
+//!     Ok(response) => println!("request {:?}", response.request_id()),
+//!     Err(err) => eprintln!("failed (request {:?}): {err}", err.request_id()),
+//! }
+//! # }
+//! ```
+
+use reqwest::header::HeaderMap;
+
+/// Name of the response header holding the request ID.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The request ID in `headers`, if present and valid text.
+pub fn from_headers(headers: &HeaderMap) -> Option<&str> {
+    headers.get(REQUEST_ID_HEADER)?.to_str().ok()
+}
+
+/// Request ID of a generated operation's response or error.
+pub trait ResponseRequestId {
+    fn request_id(&self) -> Option<&str>;
+}
+
+impl<T> ResponseRequestId for progenitor_client::ResponseValue<T> {
+    fn request_id(&self) -> Option<&str> {
+        from_headers(self.headers())
+    }
+}
+
+/// Only errors that include the API's response carry a request ID.
+impl<E> ResponseRequestId for progenitor_client::Error<E> {
+    fn request_id(&self) -> Option<&str> {
+        match self {
+            progenitor_client::Error::ErrorResponse(response) => response.request_id(),
+            progenitor_client::Error::UnexpectedResponse(response) => {
+                from_headers(response.headers())
+            }
+            _ => None,
+        }
+    }
+}
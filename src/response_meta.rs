@@ -0,0 +1,105 @@
+//! Typed access to the response headers DigitalOcean documents.
+//!
+//! Generated operations return a `ResponseValue` whose headers are a plain
+//! `HeaderMap`. [`ResponseMetadata::meta`] parses the handful that matter to callers
+//! (request ID, rate limit, content type and date) into a [`ResponseMeta`], so nobody
+//! has to look them up by string key.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::response_meta::ResponseMetadata;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), Box<dyn std::error::Error>> {
+//! let response = client.droplets_get(3164494).await?;
+//! let meta = response.meta();
+//! println!("request {:?} at {:?}", meta.request_id, meta.date);
+//! if let Some(limit) = meta.rate_limit {
+//!     println!("{} of {} requests left", limit.remaining, limit.limit);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::rate_limit::RateLimitInfo;
+use crate::request_id;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, CONTENT_TYPE, DATE};
+use reqwest::StatusCode;
+
+/// Parsed common headers of one response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseMeta {
+    pub status: StatusCode,
+    /// The `x-request-id` to quote in support tickets.
+    pub request_id: Option<String>,
+    /// The `RateLimit-*` headers.
+    pub rate_limit: Option<RateLimitInfo>,
+    /// The `Content-Type`, e.g. `application/json; charset=utf-8`.
+    pub content_type: Option<String>,
+    /// When the API produced the response, from the `Date` header.
+    pub date: Option<DateTime<Utc>>,
+}
+
+impl ResponseMeta {
+    /// Parse the headers of a response with `status`. Missing or malformed headers are
+    /// `None`.
+    pub fn from_headers(status: StatusCode, headers: &HeaderMap) -> Self {
+        let text = |name| headers.get(name)?.to_str().ok();
+        Self {
+            status,
+            request_id: request_id::from_headers(headers).map(str::to_string),
+            rate_limit: RateLimitInfo::from_headers(headers),
+            content_type: text(CONTENT_TYPE).map(str::to_string),
+            date: text(DATE)
+                .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+                .map(|date| date.with_timezone(&Utc)),
+        }
+    }
+}
+
+/// [`ResponseMeta`] of a generated operation's response.
+pub trait ResponseMetadata {
+    fn meta(&self) -> ResponseMeta;
+}
+
+impl<T> ResponseMetadata for progenitor_client::ResponseValue<T> {
+    fn meta(&self) -> ResponseMeta {
+        ResponseMeta::from_headers(self.status(), self.headers())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "7e1b8a52".parse().unwrap());
+        headers.insert("ratelimit-limit", "5000".parse().unwrap());
+        headers.insert("ratelimit-remaining", "4816".parse().unwrap());
+        headers.insert("ratelimit-reset", "1444931833".parse().unwrap());
+        headers.insert(
+            CONTENT_TYPE,
+            "application/json; charset=utf-8".parse().unwrap(),
+        );
+        headers.insert(DATE, "Fri, 16 Oct 2015 17:57:13 GMT".parse().unwrap());
+
+        let meta = ResponseMeta::from_headers(StatusCode::OK, &headers);
+        assert_eq!(meta.request_id.as_deref(), Some("7e1b8a52"));
+        assert_eq!(meta.rate_limit.map(|limit| limit.remaining), Some(4816));
+        assert_eq!(
+            meta.content_type.as_deref(),
+            Some("application/json; charset=utf-8")
+        );
+        assert_eq!(
+            meta.date,
+            Some("2015-10-16T17:57:13Z".parse::<DateTime<Utc>>().unwrap())
+        );
+
+        let empty = ResponseMeta::from_headers(StatusCode::NO_CONTENT, &HeaderMap::new());
+        assert_eq!(empty.request_id, None);
+        assert_eq!(empty.date, None);
+    }
+}
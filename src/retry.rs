@@ -0,0 +1,417 @@
+//! Automatic retries for transport failures and temporary error responses.
+//!
+//! Connection resets, timeouts and `429`/`502`/`503`/`504` responses are retried
+//! transparently, but only for methods that are safe to repeat. `POST` (and `PATCH`)
+//! requests are never retried unless the operation has been explicitly opted in, so a
+//! flaky connection cannot double-create droplets, volumes or other billable resources.
+//!
+//! Attempts back off exponentially from [`RetryPolicy::delay`] with random jitter, so
+//! clients that failed together do not retry in lockstep. A `Retry-After` header
+//! takes precedence over the computed delay; when it asks for longer than
+//! [`RetryPolicy::max_delay`] the response is returned instead of waiting.
+//!
+//! During an incident, hundreds of tasks sharing a client would multiply the load on
+//! the API with their retries. A [`RetryBudget`] caps retries at a fraction of recent
+//! requests across every clone of the client, and counts the retries it refused.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::retry::RetryPolicy;
+//! use rsdo::Client;
+//! use std::time::Duration;
+//!
+//! let client = Client::from_token("your-digitalocean-token").with_retry_policy(
+//!     RetryPolicy::default()
+//!         .max_retries(3)
+//!         .max_delay(Duration::from_secs(10))
+//!         // Tagging is idempotent even though it is a POST.
+//!         .allow_post("tags_assign_resources"),
+//! );
+//! ```
+
+use crate::operations;
+use crate::{Client, ClientInfo, ClientState};
+use reqwest::{Method, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashSet, VecDeque};
+use std::error::Error as _;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Which requests are retried, how often and how long to wait in between.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    statuses: Arc<HashSet<StatusCode>>,
+    safe_posts: Arc<HashSet<String>>,
+    budget: Option<RetryBudget>,
+}
+
+impl Default for RetryPolicy {
+    /// Two retries for idempotent methods only, backing off from 250ms with jitter, on
+    /// transport failures and `429`, `502`, `503` and `504` responses.
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            statuses: Arc::new(HashSet::from([
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ])),
+            safe_posts: Arc::new(HashSet::new()),
+            budget: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Maximum number of retries after the initial attempt.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Delay before the first retry; each further retry waits twice as long.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Longest wait between attempts. A `Retry-After` asking for more is not retried.
+    /// Defaults to 30 seconds.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Whether to randomize each wait between half and all of the computed delay.
+    /// Defaults to `true`.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Response statuses that are retried, replacing the default `429`, `502`, `503`
+    /// and `504`. Pass an empty list to retry transport failures only.
+    pub fn retry_statuses(mut self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.statuses = Arc::new(statuses.into_iter().collect());
+        self
+    }
+
+    /// Opt a `POST` operation into retries, by operation ID or generated method name.
+    ///
+    /// Only do this for operations that are known not to create duplicates when
+    /// repeated, such as tagging or action endpoints guarded by server-side checks.
+    pub fn allow_post(mut self, operation: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.safe_posts).insert(operation.into());
+        self
+    }
+
+    /// Limit retries to `budget`, shared by every client using this policy.
+    pub fn budget(mut self, budget: RetryBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Whether a request with `method` for `operation_id` may be retried.
+    pub fn allows(&self, method: &Method, operation_id: &str) -> bool {
+        if self.max_retries == 0 {
+            return false;
+        }
+        match *method {
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS => true,
+            Method::POST => self.is_safe_post(operation_id),
+            _ => false,
+        }
+    }
+
+    fn is_safe_post(&self, operation_id: &str) -> bool {
+        self.safe_posts.contains(operation_id)
+            || operations::find(operation_id).is_some_and(|op| {
+                self.safe_posts.contains(op.operation_id)
+                    || self.safe_posts.contains(op.method_name)
+            })
+    }
+
+    pub(crate) fn max_retry_count(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Whether a response with `status` is worth another attempt.
+    pub(crate) fn retries_status(&self, status: StatusCode) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    /// Count a request against the budget, if any.
+    pub(crate) fn note_request(&self) {
+        if let Some(budget) = &self.budget {
+            budget.note(Instant::now(), false);
+        }
+    }
+
+    /// Take a retry from the budget; `false` if it is used up.
+    pub(crate) fn spend_retry(&self) -> bool {
+        match &self.budget {
+            Some(budget) => budget.try_retry(Instant::now()),
+            None => true,
+        }
+    }
+
+    /// Wait before retry number `attempt` (starting at 1).
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.delay.saturating_mul(factor).min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        let random = RandomState::new().build_hasher().finish();
+        let half = delay / 2;
+        half + half.mul_f64((random % 1_000) as f64 / 1_000.0)
+    }
+
+    /// Wait before retry number `attempt` of a retryable response, or `None` when its
+    /// `Retry-After` asks for longer than [`max_delay`](Self::max_delay).
+    pub(crate) fn response_backoff(
+        &self,
+        attempt: u32,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        match retry_after {
+            Some(retry_after) if retry_after > self.max_delay => None,
+            Some(retry_after) => Some(retry_after),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+}
+
+/// Caps retries at a share of recent requests.
+///
+/// Within any `window` (10 seconds by default), retries may number at most `ratio` of
+/// the requests sent, not counting the retries themselves, plus `min_retries` so that
+/// a quiet client can still retry.
+/// Clones share their counts.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    ratio: f64,
+    min_retries: u32,
+    window: Duration,
+    counts: Arc<Mutex<BudgetCounts>>,
+}
+
+#[derive(Debug, Default)]
+struct BudgetCounts {
+    /// When each request in the window was sent, and whether it was a retry.
+    sent: VecDeque<(Instant, bool)>,
+    /// First attempts in the window; retries are counted separately.
+    requests: u64,
+    retries: u64,
+    rejected: u64,
+}
+
+impl RetryBudget {
+    /// Allow retries up to `ratio` of requests, e.g. `0.2` for 20%.
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            ratio: ratio.max(0.0),
+            min_retries: 10,
+            window: Duration::from_secs(10),
+            counts: Arc::default(),
+        }
+    }
+
+    /// Retries allowed per window regardless of the ratio. Defaults to 10.
+    pub fn min_retries(mut self, min_retries: u32) -> Self {
+        self.min_retries = min_retries;
+        self
+    }
+
+    /// Period over which requests and retries are counted. Defaults to 10 seconds.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Retries refused because the budget was used up.
+    pub fn rejected(&self) -> u64 {
+        self.lock().rejected
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BudgetCounts> {
+        self.counts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn note(&self, now: Instant, retry: bool) {
+        let mut counts = self.lock();
+        self.expire(&mut counts, now);
+        counts.sent.push_back((now, retry));
+        if retry {
+            counts.retries += 1;
+        } else {
+            counts.requests += 1;
+        }
+    }
+
+    fn expire(&self, counts: &mut BudgetCounts, now: Instant) {
+        while let Some(&(sent, retry)) = counts.sent.front() {
+            if now.saturating_duration_since(sent) < self.window {
+                break;
+            }
+            counts.sent.pop_front();
+            if retry {
+                counts.retries -= 1;
+            } else {
+                counts.requests -= 1;
+            }
+        }
+    }
+
+    fn try_retry(&self, now: Instant) -> bool {
+        let mut counts = self.lock();
+        self.expire(&mut counts, now);
+        let allowed = counts.requests as f64 * self.ratio + f64::from(self.min_retries);
+        if counts.retries as f64 + 1.0 > allowed {
+            counts.rejected += 1;
+            return false;
+        }
+        drop(counts);
+        self.note(now, true);
+        true
+    }
+}
+
+/// Whether `err` is a connection-level failure worth retrying.
+///
+/// Covers connect failures, timeouts and connections reset or closed by the peer
+/// before a response arrived. Errors produced after a response was received (such as
+/// body decoding failures) are not retried.
+pub(crate) fn is_transient(err: &reqwest::Error) -> bool {
+    if err.is_connect() || err.is_timeout() {
+        return true;
+    }
+
+    let mut source = err.source();
+    while let Some(cause) = source {
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            );
+        }
+        source = cause.source();
+    }
+    false
+}
+
+impl Client {
+    /// Return a copy of this client that uses `policy` for retries.
+    ///
+    /// The copy shares the underlying connection pool with `self`.
+    pub fn with_retry_policy(&self, policy: RetryPolicy) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.retry = policy;
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_only_retries_idempotent_methods() {
+        let policy = RetryPolicy::default();
+        assert!(policy.allows(&Method::GET, "droplets_list"));
+        assert!(policy.allows(&Method::DELETE, "droplets_destroy"));
+        assert!(policy.allows(&Method::PUT, "domains_update_record"));
+        assert!(!policy.allows(&Method::POST, "droplets_create"));
+        assert!(!policy.allows(&Method::PATCH, "apps_update"));
+    }
+
+    #[test]
+    fn test_opted_in_post_is_retried() {
+        let policy = RetryPolicy::default().allow_post("tags_assign_resources");
+        assert!(policy.allows(&Method::POST, "tags_assign_resources"));
+        assert!(!policy.allows(&Method::POST, "droplets_create"));
+    }
+
+    #[test]
+    fn test_backoff_grows_and_honors_retry_after() {
+        let policy = RetryPolicy::default()
+            .delay(Duration::from_millis(100))
+            .max_delay(Duration::from_secs(1))
+            .jitter(false);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(10), Duration::from_secs(1));
+        assert_eq!(
+            policy.response_backoff(1, Some(Duration::from_millis(700))),
+            Some(Duration::from_millis(700))
+        );
+        assert_eq!(
+            policy.response_backoff(1, Some(Duration::from_secs(5))),
+            None
+        );
+
+        let jittered = RetryPolicy::default().delay(Duration::from_millis(100));
+        let wait = jittered.backoff(2);
+        assert!(wait >= Duration::from_millis(100) && wait <= Duration::from_millis(200));
+
+        assert!(jittered.retries_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!jittered.retries_status(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+
+    #[test]
+    fn test_budget_limits_retry_share() {
+        let budget = RetryBudget::new(0.2).min_retries(1);
+        let start = Instant::now();
+        for _ in 0..10 {
+            budget.note(start, false);
+        }
+        // 10 requests allow 2 retries, plus the minimum of 1.
+        assert!(budget.try_retry(start));
+        assert!(budget.try_retry(start));
+        assert!(budget.try_retry(start));
+        assert!(!budget.try_retry(start));
+        assert_eq!(budget.rejected(), 1);
+
+        // Once the window has passed only the minimum is left.
+        let later = start + Duration::from_secs(10);
+        assert!(budget.try_retry(later));
+        assert!(!budget.try_retry(later));
+
+        // Retries do not raise their own allowance.
+        let budget = RetryBudget::new(0.5).min_retries(0);
+        for _ in 0..10 {
+            budget.note(start, false);
+        }
+        for _ in 0..5 {
+            assert!(budget.try_retry(start));
+        }
+        assert!(!budget.try_retry(start));
+    }
+
+    #[test]
+    fn test_none_disables_retries() {
+        assert!(!RetryPolicy::none().allows(&Method::GET, "droplets_list"));
+    }
+}
@@ -0,0 +1,307 @@
+//! Snapshot storage and cost reporting.
+//!
+//! Droplet and volume snapshots are billed per GiB per month for as long as they exist,
+//! and they are easy to forget about once the resource they were taken from is gone.
+//! [`Client::snapshot_report`] lists every snapshot in the account and aggregates size
+//! and estimated monthly cost per source resource and per age bucket, giving cleanup and
+//! cost tooling something concrete to act on.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let report = client.snapshot_report().await?;
+//! println!(
+//!     "{} snapshots, {:.1} GiB, ~${:.2}/month",
+//!     report.total.count, report.total.size_gigabytes, report.total.estimated_monthly_cost
+//! );
+//! for (bucket, usage) in &report.by_age {
+//!     println!("{bucket}: {} snapshots, ~${:.2}/month", usage.count, usage.estimated_monthly_cost);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::Client;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Published snapshot storage price, in USD per GiB per month.
+pub const SNAPSHOT_PRICE_PER_GIB_MONTH: f64 = 0.06;
+
+/// Kind of resource a snapshot was taken from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum SnapshotResourceType {
+    Droplet,
+    Volume,
+    /// A resource type this version of rsdo does not know about yet.
+    Unknown(String),
+}
+
+impl From<String> for SnapshotResourceType {
+    fn from(value: String) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "droplet" => Self::Droplet,
+            "volume" => Self::Volume,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<SnapshotResourceType> for String {
+    fn from(value: SnapshotResourceType) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for SnapshotResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Droplet => "droplet",
+            Self::Volume => "volume",
+            Self::Unknown(s) => s,
+        };
+        f.write_str(s)
+    }
+}
+
+/// A droplet or volume snapshot.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Snapshot {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// ID of the droplet or volume the snapshot was taken from. The resource may no
+    /// longer exist.
+    pub resource_id: String,
+    pub resource_type: SnapshotResourceType,
+    /// Billable size of the snapshot.
+    pub size_gigabytes: f64,
+    #[serde(default)]
+    pub regions: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Age of a snapshot relative to when the report was built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AgeBucket {
+    /// Less than 7 days old.
+    UnderWeek,
+    /// 7 to 30 days old.
+    UnderMonth,
+    /// 30 to 90 days old.
+    UnderQuarter,
+    /// 90 to 365 days old.
+    UnderYear,
+    /// At least a year old.
+    OverYear,
+}
+
+impl AgeBucket {
+    /// Bucket for a snapshot created at `created_at`, as seen at `now`.
+    pub fn of(created_at: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        match (now - created_at).num_days() {
+            ..=6 => Self::UnderWeek,
+            7..=29 => Self::UnderMonth,
+            30..=89 => Self::UnderQuarter,
+            90..=364 => Self::UnderYear,
+            _ => Self::OverYear,
+        }
+    }
+}
+
+impl fmt::Display for AgeBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::UnderWeek => "< 7 days",
+            Self::UnderMonth => "7-30 days",
+            Self::UnderQuarter => "30-90 days",
+            Self::UnderYear => "90-365 days",
+            Self::OverYear => "> 1 year",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Count, size and estimated cost of a group of snapshots.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SnapshotUsage {
+    pub count: usize,
+    pub size_gigabytes: f64,
+    /// Estimated storage cost in USD per month.
+    pub estimated_monthly_cost: f64,
+}
+
+impl SnapshotUsage {
+    fn add(&mut self, snapshot: &Snapshot, price_per_gib_month: f64) {
+        self.count += 1;
+        self.size_gigabytes += snapshot.size_gigabytes;
+        self.estimated_monthly_cost += snapshot.size_gigabytes * price_per_gib_month;
+    }
+}
+
+/// Snapshot usage for a single source droplet or volume.
+#[derive(Debug, Clone)]
+pub struct ResourceSnapshots {
+    pub resource_type: SnapshotResourceType,
+    pub resource_id: String,
+    pub usage: SnapshotUsage,
+    /// Creation time of the most recent snapshot of this resource.
+    pub newest: DateTime<Utc>,
+    /// Creation time of the oldest snapshot of this resource.
+    pub oldest: DateTime<Utc>,
+}
+
+/// Aggregated snapshot storage for an account.
+#[derive(Debug, Clone)]
+pub struct SnapshotReport {
+    /// When the report was built; ages are relative to this.
+    pub generated_at: DateTime<Utc>,
+    /// Price the estimates were computed with, in USD per GiB per month.
+    pub price_per_gib_month: f64,
+    pub total: SnapshotUsage,
+    pub by_type: BTreeMap<SnapshotResourceType, SnapshotUsage>,
+    pub by_age: BTreeMap<AgeBucket, SnapshotUsage>,
+    /// Per source resource, most expensive first.
+    pub by_resource: Vec<ResourceSnapshots>,
+}
+
+impl SnapshotReport {
+    /// Aggregate `snapshots` as of `now`, pricing storage at `price_per_gib_month`.
+    pub fn build(snapshots: &[Snapshot], now: DateTime<Utc>, price_per_gib_month: f64) -> Self {
+        let mut report = Self {
+            generated_at: now,
+            price_per_gib_month,
+            total: SnapshotUsage::default(),
+            by_type: BTreeMap::new(),
+            by_age: BTreeMap::new(),
+            by_resource: Vec::new(),
+        };
+        let mut by_resource: BTreeMap<(SnapshotResourceType, &str), ResourceSnapshots> =
+            BTreeMap::new();
+
+        for snapshot in snapshots {
+            report.total.add(snapshot, price_per_gib_month);
+            report
+                .by_type
+                .entry(snapshot.resource_type.clone())
+                .or_default()
+                .add(snapshot, price_per_gib_month);
+            report
+                .by_age
+                .entry(AgeBucket::of(snapshot.created_at, now))
+                .or_default()
+                .add(snapshot, price_per_gib_month);
+
+            let resource = by_resource
+                .entry((snapshot.resource_type.clone(), &snapshot.resource_id))
+                .or_insert_with(|| ResourceSnapshots {
+                    resource_type: snapshot.resource_type.clone(),
+                    resource_id: snapshot.resource_id.clone(),
+                    usage: SnapshotUsage::default(),
+                    newest: snapshot.created_at,
+                    oldest: snapshot.created_at,
+                });
+            resource.usage.add(snapshot, price_per_gib_month);
+            resource.newest = resource.newest.max(snapshot.created_at);
+            resource.oldest = resource.oldest.min(snapshot.created_at);
+        }
+
+        report.by_resource = by_resource.into_values().collect();
+        report.by_resource.sort_by(|a, b| {
+            b.usage
+                .estimated_monthly_cost
+                .total_cmp(&a.usage.estimated_monthly_cost)
+        });
+        report
+    }
+}
+
+impl Client {
+    /// List every droplet and volume snapshot in the account.
+    pub async fn all_snapshots(&self) -> Result<Vec<Snapshot>, Error> {
+        self.collect_pages("snapshots_list", "snapshots").await
+    }
+
+    /// Build a [`SnapshotReport`] for the account at the published snapshot price.
+    ///
+    /// Use [`SnapshotReport::build`] with [`Client::all_snapshots`] to price storage
+    /// differently, e.g. for negotiated rates.
+    pub async fn snapshot_report(&self) -> Result<SnapshotReport, Error> {
+        let snapshots = self.all_snapshots().await?;
+        Ok(SnapshotReport::build(
+            &snapshots,
+            Utc::now(),
+            SNAPSHOT_PRICE_PER_GIB_MONTH,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    fn snapshot(resource_type: &str, resource_id: &str, size: f64, age_days: i64) -> Snapshot {
+        let now = "2026-01-31T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        serde_json::from_value(json!({
+            "id": format!("{resource_id}-{age_days}"),
+            "name": "nightly",
+            "created_at": (now - Duration::days(age_days)).to_rfc3339(),
+            "resource_id": resource_id,
+            "resource_type": resource_type,
+            "size_gigabytes": size,
+            "min_disk_size": 25
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_report_groups_by_resource_and_age() {
+        let now = "2026-01-31T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let snapshots = vec![
+            snapshot("droplet", "111", 10.0, 1),
+            snapshot("droplet", "111", 10.0, 40),
+            snapshot("volume", "vol-a", 100.0, 400),
+        ];
+        let report = SnapshotReport::build(&snapshots, now, 0.05);
+
+        assert_eq!(report.total.count, 3);
+        assert!((report.total.estimated_monthly_cost - 6.0).abs() < 1e-9);
+        assert_eq!(report.by_age[&AgeBucket::UnderWeek].count, 1);
+        assert_eq!(report.by_age[&AgeBucket::UnderQuarter].count, 1);
+        assert_eq!(report.by_age[&AgeBucket::OverYear].count, 1);
+        assert_eq!(report.by_type[&SnapshotResourceType::Droplet].count, 2);
+
+        assert_eq!(report.by_resource.len(), 2);
+        assert_eq!(report.by_resource[0].resource_id, "vol-a");
+        assert_eq!(report.by_resource[1].usage.count, 2);
+        assert_eq!(
+            (report.by_resource[1].newest - report.by_resource[1].oldest).num_days(),
+            39
+        );
+    }
+
+    #[test]
+    fn test_age_bucket_boundaries() {
+        let now = Utc::now();
+        assert_eq!(
+            AgeBucket::of(now - Duration::days(6), now),
+            AgeBucket::UnderWeek
+        );
+        assert_eq!(
+            AgeBucket::of(now - Duration::days(7), now),
+            AgeBucket::UnderMonth
+        );
+        assert_eq!(
+            AgeBucket::of(now - Duration::days(365), now),
+            AgeBucket::OverYear
+        );
+    }
+}
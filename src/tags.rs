@@ -0,0 +1,141 @@
+//! Tag helpers.
+//!
+//! Tags must exist before resources can be tagged, and creating a tag that already
+//! exists is an error. [`Client::ensure_tags`] creates whatever is missing from a list
+//! of names and reports which tags were new, so bulk tagging jobs can run it
+//! unconditionally beforehand.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let tags = client.ensure_tags(&["env:prod", "team:platform"]).await?;
+//! println!("created {:?}, already present {:?}", tags.created, tags.existing);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+
+/// Maximum length of a tag name.
+const MAX_TAG_LENGTH: usize = 255;
+
+/// Outcome of [`Client::ensure_tags`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnsuredTags {
+    /// Tags created by this call, in request order.
+    pub created: Vec<String>,
+    /// Tags that already existed, in request order.
+    pub existing: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TagName {
+    name: String,
+}
+
+impl Client {
+    /// Create every tag in `names` that does not exist yet.
+    ///
+    /// Duplicate names are ignored. A tag created concurrently by someone else between
+    /// the existence check and the create call is reported as existing rather than
+    /// failing the whole batch.
+    pub async fn ensure_tags(&self, names: &[&str]) -> Result<EnsuredTags, Error> {
+        for name in names {
+            validate_tag_name(name)?;
+        }
+
+        let existing: HashSet<String> = self
+            .collect_pages::<TagName>("tags_list", "tags")
+            .await?
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+
+        let mut result = EnsuredTags::default();
+        let mut seen = HashSet::new();
+        for &name in names {
+            if !seen.insert(name) {
+                continue;
+            }
+            if existing.contains(name) {
+                result.existing.push(name.to_string());
+                continue;
+            }
+
+            let request = ApiRequest::post("tags_create", "/v2/tags").json(json!({ "name": name }));
+            match self.send_empty(request).await {
+                Ok(()) => result.created.push(name.to_string()),
+                Err(err) if is_already_exists(&err) => result.existing.push(name.to_string()),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Check a tag name against the API's rules: 1-255 letters, digits, `:`, `-` or `_`.
+fn validate_tag_name(name: &str) -> Result<(), Error> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_TAG_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '-' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!(
+            "invalid tag name {name:?}: use 1-{MAX_TAG_LENGTH} letters, digits, ':', '-' or '_'"
+        )))
+    }
+}
+
+/// Whether a create failure means the tag exists already.
+fn is_already_exists(err: &Error) -> bool {
+    match err {
+        Error::Response { status, body, .. } => {
+            (*status == StatusCode::CONFLICT || *status == StatusCode::UNPROCESSABLE_ENTITY)
+                && body.to_ascii_lowercase().contains("already exists")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::OperationContext;
+    use reqwest::Method;
+
+    #[test]
+    fn test_tag_name_validation() {
+        assert!(validate_tag_name("env:prod").is_ok());
+        assert!(validate_tag_name("team_platform-1").is_ok());
+        assert!(validate_tag_name("").is_err());
+        assert!(validate_tag_name("has space").is_err());
+        assert!(validate_tag_name(&"a".repeat(256)).is_err());
+    }
+
+    #[test]
+    fn test_conflict_is_recognized() {
+        let response = |status, body: &str| Error::Response {
+            context: OperationContext::new("tags_create", Method::POST, "/v2/tags", false),
+            status,
+            body: body.to_string(),
+        };
+        assert!(is_already_exists(&response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            r#"{"id":"unprocessable_entity","message":"Tag already exists"}"#,
+        )));
+        assert!(!is_already_exists(&response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            r#"{"id":"unprocessable_entity","message":"name is invalid"}"#,
+        )));
+    }
+}
@@ -0,0 +1,521 @@
+//! Scheduled image transfers and snapshots.
+//!
+//! Copying dozens of images to another region or snapshotting a fleet of droplets is
+//! slow and eats into the API rate limit. [`TransferScheduler`] works through a batch of
+//! [`TransferJob`]s over time:
+//!
+//! - new jobs start only inside an optional daily [`TimeWindow`] (e.g. nightly);
+//! - at most `max_concurrent` jobs run at once;
+//! - starting and polling pause while the client's remaining rate limit is low, and
+//!   after `429 Too Many Requests`;
+//! - progress is saved to a JSON file after every change, so a restarted run skips
+//!   finished jobs and resumes polling the actions that were still in flight.
+//!
+//! Failed jobs are recorded in the progress rather than aborting the batch. Each job is
+//! reported as a `transfer` step through [`events`](crate::events).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use chrono::NaiveTime;
+//! use rsdo::transfers::{TimeWindow, TransferJob, TransferScheduler};
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let jobs = vec![
+//!     TransferJob::ImageTransfer { image_id: 7555620, region: "ams3".to_string() },
+//!     TransferJob::DropletSnapshot { droplet_id: 3164494, name: "nightly".to_string() },
+//! ];
+//! let progress = TransferScheduler::new()
+//!     .window(TimeWindow::new(
+//!         NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+//!         NaiveTime::from_hms_opt(5, 0, 0).unwrap(),
+//!     ))
+//!     .max_concurrent(2)
+//!     .state_path("/var/lib/backup-job/transfers.json")
+//!     .run(&client, jobs)
+//!     .await?;
+//! println!("{} done, {} failed", progress.completed(), progress.failed());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cancellation;
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, NaiveTime, Utc};
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// A transfer or snapshot to perform.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransferJob {
+    /// Copy an image or snapshot to another region.
+    ImageTransfer { image_id: u64, region: String },
+    /// Snapshot a droplet.
+    DropletSnapshot { droplet_id: u64, name: String },
+    /// Snapshot a block storage volume.
+    VolumeSnapshot { volume_id: String, name: String },
+}
+
+impl fmt::Display for TransferJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ImageTransfer { image_id, region } => {
+                write!(f, "transfer of image {image_id} to {region}")
+            }
+            Self::DropletSnapshot { droplet_id, name } => {
+                write!(f, "snapshot {name:?} of droplet {droplet_id}")
+            }
+            Self::VolumeSnapshot { volume_id, name } => {
+                write!(f, "snapshot {name:?} of volume {volume_id}")
+            }
+        }
+    }
+}
+
+/// Where a job stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    /// Started; the API is working on action `action_id`.
+    Running {
+        action_id: u64,
+    },
+    Completed,
+    Failed {
+        error: String,
+    },
+}
+
+impl JobStatus {
+    fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed { .. })
+    }
+}
+
+/// A job and its status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub job: TransferJob,
+    pub status: JobStatus,
+}
+
+/// Progress of a batch, as persisted between runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferProgress {
+    pub jobs: Vec<JobProgress>,
+}
+
+impl TransferProgress {
+    /// Read progress saved by an earlier run; `None` if the file does not exist.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Option<Self>, Error> {
+        let path = path.as_ref();
+        match tokio::fs::read(path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|err| {
+                Error::InvalidInput(format!(
+                    "{} is not a transfer progress file: {err}",
+                    path.display()
+                ))
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(Error::Io {
+                path: path.to_path_buf(),
+                source,
+            }),
+        }
+    }
+
+    async fn save(&self, path: &Path) -> Result<(), Error> {
+        let io_error = |path: &Path, source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".rsdo-tmp");
+        let tmp = PathBuf::from(tmp);
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .await
+            .map_err(|source| io_error(&tmp, source))?;
+        tokio::fs::rename(&tmp, path)
+            .await
+            .map_err(|source| io_error(path, source))
+    }
+
+    /// Add `jobs` that are not part of the batch yet as pending.
+    fn add(&mut self, jobs: Vec<TransferJob>) {
+        for job in jobs {
+            if !self.jobs.iter().any(|progress| progress.job == job) {
+                self.jobs.push(JobProgress {
+                    job,
+                    status: JobStatus::Pending,
+                });
+            }
+        }
+    }
+
+    fn count(&self, matches: impl Fn(&JobStatus) -> bool) -> usize {
+        self.jobs.iter().filter(|p| matches(&p.status)).count()
+    }
+
+    /// Jobs not finished yet.
+    pub fn remaining(&self) -> usize {
+        self.count(|status| !status.is_finished())
+    }
+
+    pub fn completed(&self) -> usize {
+        self.count(|status| *status == JobStatus::Completed)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|status| matches!(status, JobStatus::Failed { .. }))
+    }
+}
+
+/// A daily time range, in UTC, during which new jobs may start.
+///
+/// A window whose end is before its start wraps around midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self { start, end }
+    }
+
+    /// Whether `time` falls inside the window.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// How long from `now` until the window is open; zero inside the window.
+    pub fn until_open(&self, now: DateTime<Utc>) -> Duration {
+        if self.contains(now.time()) {
+            return Duration::ZERO;
+        }
+        let mut opens = now.date_naive().and_time(self.start).and_utc();
+        if opens <= now {
+            opens += chrono::TimeDelta::days(1);
+        }
+        (opens - now).to_std().unwrap_or_default()
+    }
+}
+
+/// Runs batches of [`TransferJob`]s within time, concurrency and rate-limit bounds.
+#[derive(Debug, Clone)]
+#[must_use = "call `.run()` to process the jobs"]
+pub struct TransferScheduler {
+    window: Option<TimeWindow>,
+    max_concurrent: usize,
+    min_rate_limit_remaining: u64,
+    poll_interval: Duration,
+    state_path: Option<PathBuf>,
+}
+
+impl Default for TransferScheduler {
+    fn default() -> Self {
+        Self {
+            window: None,
+            max_concurrent: 1,
+            min_rate_limit_remaining: 100,
+            poll_interval: Duration::from_secs(30),
+            state_path: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ActionEnvelope {
+    action: Action,
+}
+
+#[derive(Deserialize)]
+struct Action {
+    id: u64,
+    status: String,
+}
+
+impl TransferScheduler {
+    /// One job at a time, at any time of day, pausing below 100 remaining requests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only start jobs within `window`. Running jobs are not interrupted when it
+    /// closes.
+    pub fn window(mut self, window: TimeWindow) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Run at most `max_concurrent` jobs at once. Defaults to 1.
+    pub fn max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent.max(1);
+        self
+    }
+
+    /// Pause until the rate limit resets when fewer than `remaining` requests are left.
+    /// Defaults to 100.
+    pub fn min_rate_limit_remaining(mut self, remaining: u64) -> Self {
+        self.min_rate_limit_remaining = remaining;
+        self
+    }
+
+    /// Delay between polls of a running action. Defaults to 30 seconds.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Save progress to `path`, and resume from it if it exists.
+    pub fn state_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.state_path = Some(path.into());
+        self
+    }
+
+    /// Run every unfinished job of the batch and return the final progress.
+    ///
+    /// `jobs` missing from saved progress are added to it, so the same list can be
+    /// passed on every run. Only failures to read or save the progress file abort the
+    /// run.
+    pub async fn run(
+        &self,
+        client: &Client,
+        jobs: Vec<TransferJob>,
+    ) -> Result<TransferProgress, Error> {
+        let mut progress = match &self.state_path {
+            Some(path) => TransferProgress::load(path).await?.unwrap_or_default(),
+            None => TransferProgress::default(),
+        };
+        progress.add(jobs);
+        self.save(&progress).await?;
+
+        let unfinished: Vec<usize> = (0..progress.jobs.len())
+            .filter(|&i| !progress.jobs[i].status.is_finished())
+            .collect();
+        let progress = Mutex::new(progress);
+        futures::stream::iter(unfinished)
+            .map(|index| self.run_job(client, &progress, index))
+            .buffer_unordered(self.max_concurrent)
+            .try_collect::<()>()
+            .await?;
+        Ok(progress.into_inner())
+    }
+
+    /// Run one job to completion, recording its status changes.
+    async fn run_job(
+        &self,
+        client: &Client,
+        progress: &Mutex<TransferProgress>,
+        index: usize,
+    ) -> Result<(), Error> {
+        let JobProgress { job, status } = progress.lock().await.jobs[index].clone();
+        let workflow = Workflow::new(client.inner(), "transfer_scheduler", job.to_string());
+        let outcome = workflow
+            .step("transfer", async {
+                let action_id = match status {
+                    JobStatus::Running { action_id } => action_id,
+                    _ => match self.start(client, &job).await? {
+                        Some(action_id) => {
+                            let running = JobStatus::Running { action_id };
+                            self.update(progress, index, running).await?;
+                            action_id
+                        }
+                        None => return Ok(()),
+                    },
+                };
+                self.wait_for_action(client, action_id).await
+            })
+            .await;
+
+        let status = match outcome {
+            Ok(()) => JobStatus::Completed,
+            // Persistence failures abort the batch rather than being recorded.
+            Err(err @ Error::Io { .. }) => return Err(err),
+            Err(err) => JobStatus::Failed {
+                error: err.to_string(),
+            },
+        };
+        self.update(progress, index, status).await
+    }
+
+    /// Start `job` once the window is open, returning the action to wait for, if any.
+    async fn start(&self, client: &Client, job: &TransferJob) -> Result<Option<u64>, Error> {
+        if let Some(window) = &self.window {
+            cancellation::sleep(client.inner(), window.until_open(Utc::now())).await;
+        }
+        loop {
+            self.pause_for_rate_limit(client).await;
+            let result = match job {
+                TransferJob::ImageTransfer { image_id, region } => {
+                    let request = ApiRequest::post(
+                        "imageActions_post",
+                        format!("/v2/images/{image_id}/actions"),
+                    )
+                    .json(json!({ "type": "transfer", "region": region }));
+                    client
+                        .send_json::<ActionEnvelope>(request)
+                        .await
+                        .map(|envelope| Some(envelope.action.id))
+                }
+                TransferJob::DropletSnapshot { droplet_id, name } => {
+                    let request = ApiRequest::post(
+                        "dropletActions_post",
+                        format!("/v2/droplets/{droplet_id}/actions"),
+                    )
+                    .json(json!({ "type": "snapshot", "name": name }));
+                    client
+                        .send_json::<ActionEnvelope>(request)
+                        .await
+                        .map(|envelope| Some(envelope.action.id))
+                }
+                // Volume snapshots are taken synchronously.
+                TransferJob::VolumeSnapshot { volume_id, name } => {
+                    let request = ApiRequest::post(
+                        "volumeSnapshots_create",
+                        format!("/v2/volumes/{volume_id}/snapshots"),
+                    )
+                    .json(json!({ "name": name }));
+                    client.send_empty(request).await.map(|()| None)
+                }
+            };
+            match result {
+                Err(Error::RateLimited { retry_after, .. }) => {
+                    cancellation::sleep(client.inner(), retry_after).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Poll action `action_id` until it completes or errors.
+    async fn wait_for_action(&self, client: &Client, action_id: u64) -> Result<(), Error> {
+        loop {
+            self.pause_for_rate_limit(client).await;
+            let request = ApiRequest::get("actions_get", format!("/v2/actions/{action_id}"));
+            match client.send_json::<ActionEnvelope>(request).await {
+                Ok(envelope) if envelope.action.status == "completed" => return Ok(()),
+                Ok(envelope) if envelope.action.status == "errored" => {
+                    return Err(Error::Other(format!("action {action_id} errored")));
+                }
+                Ok(_) => {}
+                Err(err) if err.is_retryable() => {
+                    tracing::debug!(action_id, error = %err, "retrying action poll");
+                }
+                Err(err) => return Err(err),
+            }
+            cancellation::sleep(client.inner(), self.poll_interval).await;
+        }
+    }
+
+    /// How long to wait for the rate limit to reset, if too few requests are left.
+    fn rate_limit_pause(&self, client: &Client) -> Option<Duration> {
+        let info = client.rate_limit()?;
+        if info.remaining >= self.min_rate_limit_remaining {
+            return None;
+        }
+        Some((info.reset - Utc::now()).to_std().unwrap_or_default())
+    }
+
+    async fn pause_for_rate_limit(&self, client: &Client) {
+        if let Some(pause) = self.rate_limit_pause(client) {
+            tracing::info!(?pause, "pausing transfers until the rate limit resets");
+            cancellation::sleep(client.inner(), pause).await;
+        }
+    }
+
+    async fn update(
+        &self,
+        progress: &Mutex<TransferProgress>,
+        index: usize,
+        status: JobStatus,
+    ) -> Result<(), Error> {
+        let mut progress = progress.lock().await;
+        progress.jobs[index].status = status;
+        self.save(&progress).await
+    }
+
+    async fn save(&self, progress: &TransferProgress) -> Result<(), Error> {
+        match &self.state_path {
+            Some(path) => progress.save(path).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_window_wraps_midnight() {
+        let window = TimeWindow::new(time(22, 0), time(4, 0));
+        assert!(window.contains(time(23, 30)));
+        assert!(window.contains(time(1, 0)));
+        assert!(!window.contains(time(12, 0)));
+
+        let noon = "2026-03-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(window.until_open(noon), Duration::from_secs(10 * 3600));
+        let early = "2026-03-01T05:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let nightly = TimeWindow::new(time(1, 0), time(4, 0));
+        assert_eq!(nightly.until_open(early), Duration::from_secs(20 * 3600));
+    }
+
+    #[test]
+    fn test_progress_round_trip_and_resume() {
+        let mut progress = TransferProgress::default();
+        let transfer = TransferJob::ImageTransfer {
+            image_id: 7555620,
+            region: "ams3".to_string(),
+        };
+        progress.add(vec![transfer.clone()]);
+        progress.jobs[0].status = JobStatus::Running {
+            action_id: 36805022,
+        };
+
+        let saved = serde_json::to_value(&progress).unwrap();
+        assert_eq!(
+            saved["jobs"][0],
+            json!({
+                "job": {"type": "image_transfer", "image_id": 7555620, "region": "ams3"},
+                "status": {"status": "running", "action_id": 36805022}
+            })
+        );
+
+        let mut resumed: TransferProgress = serde_json::from_value(saved).unwrap();
+        resumed.add(vec![
+            transfer,
+            TransferJob::VolumeSnapshot {
+                volume_id: "vol-1".to_string(),
+                name: "nightly".to_string(),
+            },
+        ]);
+        assert_eq!(resumed.jobs.len(), 2);
+        assert_eq!(resumed.remaining(), 2);
+        assert_eq!(
+            resumed.jobs[0].status,
+            JobStatus::Running {
+                action_id: 36805022
+            }
+        );
+    }
+}
@@ -0,0 +1,555 @@
+//! Behaviour applied to every outgoing request, generated or hand-written.
+//!
+//! `build.rs` installs a `ClientHooks` implementation on the generated client that calls
+//! into this module, and [`Client::send`](crate::Client) uses the same entry points, so
+//! both paths behave identically.
+
+use crate::auth::{self, TokenProvider};
+use crate::cancellation::{self, CancellationToken};
+use crate::circuit_breaker::{self, CircuitBreaker};
+use crate::droplets::SshKeyCache;
+use crate::error::{self, Error, InvalidResponse, OperationContext};
+use crate::events::EventHandler;
+use crate::hedging::HedgePolicy;
+use crate::idempotency::{self, IdempotencyKey, IdempotencyStore, MemoryIdempotencyStore};
+use crate::interceptor::Interceptor;
+use crate::operations;
+use crate::rate_limit::{AdaptiveThrottle, RateLimitTracker, RateLimiter};
+use crate::retry::{self, RetryPolicy};
+use crate::wait::ProgressHandler;
+use progenitor_client::ResponseValue;
+use reqwest::{header, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Per-client configuration consulted by the transport hooks.
+///
+/// This is the generated client's inner type: every `Client` carries one, and clones of
+/// a client share it. Construct clients with [`Client::from_token`](crate::Client) or
+/// pass one to `Client::new_with_client_and_state`.
+#[derive(Debug, Clone, Default)]
+pub struct ClientState {
+    pub(crate) retry: RetryPolicy,
+    pub(crate) redact_error_paths: bool,
+    /// Source of the bearer token, overriding the one built into the `reqwest::Client`.
+    pub(crate) token_provider: Option<Arc<dyn TokenProvider>>,
+    /// Hooks run around every request, in registration order.
+    pub(crate) interceptors: Vec<Arc<dyn Interceptor>>,
+    /// Reject everything but `GET` and `HEAD` before it is sent.
+    pub(crate) read_only: bool,
+    /// Rate-limit headers of the most recent response.
+    pub(crate) rate_limit: RateLimitTracker,
+    /// SSH key IDs by name, shared with clones of the client.
+    pub(crate) ssh_keys: SshKeyCache,
+    /// Client-side request budget, shared with clones of the client.
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    /// Slows requests down as `RateLimit-Remaining` approaches zero.
+    pub(crate) throttle: Option<AdaptiveThrottle>,
+    /// Sends a second copy of slow reads.
+    pub(crate) hedge: Option<HedgePolicy>,
+    /// Fails requests fast after repeated upstream failures.
+    pub(crate) circuit_breaker: Option<CircuitBreaker>,
+    /// Records responses of keyed requests; `None` uses `idempotency_memory`.
+    pub(crate) idempotency_store: Option<Arc<dyn IdempotencyStore>>,
+    /// Default store of keyed responses, shared with clones of the client but not with
+    /// other clients or clones switched to another token.
+    pub(crate) idempotency_memory: Arc<MemoryIdempotencyStore>,
+    /// Key sent with every request but `GET` and `HEAD`.
+    pub(crate) idempotency_key: Option<IdempotencyKey>,
+    /// Receives progress events from workflow helpers.
+    pub(crate) events: Option<EventHandler>,
+    /// Receives the progress of every waiter.
+    pub(crate) wait_progress: Option<ProgressHandler>,
+    /// Cap on the items gathered by `collect_all` and the `*_all` methods; `None`
+    /// means [`DEFAULT_MAX_ITEMS`](crate::pagination::DEFAULT_MAX_ITEMS).
+    pub(crate) max_list_items: Option<usize>,
+    /// `per_page` added to list requests that do not set one.
+    pub(crate) default_per_page: Option<u64>,
+    /// Timeout for every request, overriding `operation_timeouts`.
+    pub(crate) timeout: Option<Duration>,
+    /// Timeouts by operation ID or method name, overriding the `reqwest::Client`'s.
+    pub(crate) operation_timeouts: Arc<HashMap<String, Duration>>,
+    /// The `reqwest::Client`'s timeout, when [`ClientBuilder`](crate::ClientBuilder)
+    /// configured it.
+    pub(crate) client_timeout: Option<Duration>,
+    /// Stops requests and waits once cancelled.
+    pub(crate) cancellation: Option<CancellationToken>,
+    /// Stops requests and waits once passed.
+    pub(crate) deadline: Option<Instant>,
+}
+
+/// Adjusts a request before it is sent.
+///
+/// Rejects mutating requests on read-only clients and all requests once the client is
+/// cancelled, past its deadline or while its circuit breaker is open, applies the
+/// client's token provider, if any, sets the timeout configured for the call or
+/// operation, shortened to end at the deadline, adds the client's
+/// default `per_page` to list requests without one, clamps
+/// an oversized or zero `per_page` query parameter to the limits documented for
+/// `operation_id`, and then runs the client's interceptors.
+pub(crate) async fn prepare(
+    request: &mut reqwest::Request,
+    state: &ClientState,
+    operation_id: &str,
+) -> Result<(), Error> {
+    let context = || {
+        OperationContext::new(
+            operation_id,
+            request.method().clone(),
+            request.url().path(),
+            state.redact_error_paths,
+        )
+    };
+    if state.read_only && !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return Err(Error::ReadOnlyViolation { context: context() });
+    }
+    cancellation::check(state, context)?;
+    if let Some(breaker) = &state.circuit_breaker {
+        if let Err(retry_after) = breaker.admit(Instant::now()) {
+            return Err(Error::CircuitOpen {
+                context: context(),
+                retry_after,
+            });
+        }
+    }
+    if let Some(authorization) = auth::authorization(state).await? {
+        request
+            .headers_mut()
+            .insert(header::AUTHORIZATION, authorization);
+    }
+    let timeout = request_timeout(state, operation_id).or(request.timeout().copied());
+    *request.timeout_mut() = cancellation::cap_timeout(state, timeout);
+    if let Some(per_page) = state.default_per_page {
+        add_default_per_page(request.url_mut(), operation_id, per_page);
+    }
+    clamp_per_page(request.url_mut(), operation_id);
+    for interceptor in &state.interceptors {
+        interceptor.before_send(request, operation_id).await?;
+    }
+    Ok(())
+}
+
+/// Sends a prepared request, retrying transport failures and temporary error
+/// responses as allowed by the client's [`RetryPolicy`].
+///
+/// A request carrying an idempotency key that already succeeded is answered with the
+/// recorded response instead, and a first success is recorded.
+///
+/// If the response is `401 Unauthorized` and the client's [`TokenProvider`] reports a
+/// refreshed token, the request is sent once more with the new token. The final
+/// response's rate-limit headers are recorded, the outcome is reported to the circuit
+/// breaker, if any, and passed to the client's interceptors. Every response is tagged
+/// with the call's [`OperationContext`] (see [`error::OPERATION_HEADER`]).
+///
+/// Once the client's token is cancelled or its deadline arrives, waiting for the rate
+/// limiter, the throttle or the response ends with [`Error::Cancelled`] or
+/// [`Error::DeadlineExceeded`], and a pause between retries ends with the last outcome.
+pub(crate) async fn execute(
+    http: &reqwest::Client,
+    state: &ClientState,
+    request: reqwest::Request,
+    operation_id: &str,
+) -> Result<reqwest::Response, Error> {
+    let context = OperationContext::new(
+        operation_id,
+        request.method().clone(),
+        request.url().path(),
+        state.redact_error_paths,
+    );
+    let key = idempotency::key_for(state, &request);
+    // Held until the response is recorded, so a repeat sent meanwhile is replayed.
+    let _reservation = match &key {
+        Some(key) => Some(idempotency::reserve(operation_id, key).await),
+        None => None,
+    };
+    if let Some(key) = &key {
+        if let Some(mut response) = idempotency::replay(state, operation_id, key).await {
+            context.tag(response.headers_mut());
+            return Ok(response);
+        }
+    }
+    let send = send_authorized(http, state, request, operation_id);
+    let result = cancellation::guard(state, || context.clone(), send).await?;
+    let result = result.map(|mut response| {
+        context.tag(response.headers_mut());
+        response
+    });
+    if let Ok(response) = &result {
+        state.rate_limit.record(response.headers());
+    }
+    if let Some(breaker) = &state.circuit_breaker {
+        breaker.record(circuit_breaker::is_failure(result.as_ref()), Instant::now());
+    }
+    let result = match (key, result) {
+        (Some(key), Ok(response)) => {
+            idempotency::remember(state, operation_id, &key, response).await
+        }
+        (_, result) => result,
+    };
+    for interceptor in &state.interceptors {
+        interceptor
+            .after_receive(result.as_ref(), operation_id)
+            .await;
+    }
+    result.map_err(|source| Error::Request { context, source })
+}
+
+/// [`prepare`] and [`execute`] a request of a generated operation.
+///
+/// This is the generated client's `exec` hook, which can only fail with a
+/// `reqwest::Error`. Any other error is [carried](error::carry) on a stand-in response
+/// tagged with the call, which the generated code returns as `UnexpectedResponse` and
+/// which converts back into the original [`Error`].
+pub(crate) async fn execute_generated(
+    http: &reqwest::Client,
+    state: &ClientState,
+    mut request: reqwest::Request,
+    operation_id: &str,
+) -> reqwest::Result<reqwest::Response> {
+    let context = OperationContext::new(
+        operation_id,
+        request.method().clone(),
+        request.url().path(),
+        state.redact_error_paths,
+    );
+    let result = match prepare(&mut request, state, operation_id).await {
+        Ok(()) => execute(http, state, request, operation_id).await,
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(response) => Ok(response),
+        Err(Error::Request { source, .. }) => Err(source),
+        Err(err) => {
+            let mut headers = header::HeaderMap::new();
+            context.tag(&mut headers);
+            Ok(error::carry(err, CARRIED_STATUS, headers))
+        }
+    }
+}
+
+/// Status of the stand-in response [`execute_generated`] returns for an error raised
+/// without a response, chosen outside the range the API answers with so that
+/// generated operations treat it as unexpected.
+const CARRIED_STATUS: StatusCode = match StatusCode::from_u16(599) {
+    Ok(status) => status,
+    Err(_) => panic!("599 is a valid status"),
+};
+
+/// Decode the JSON body of a generated operation's response.
+///
+/// `build.rs` makes the generated code call this instead of
+/// `ResponseValue::from_response`. A response [`execute_generated`] made to carry an
+/// error is returned as `UnexpectedResponse` without decoding. A body that does not
+/// decode carries [`Error::InvalidResponse`] naming the call and status the same way,
+/// so converting the failure yields that rather than an opaque [`Error::Generated`];
+/// without a tagged call it fails with `InvalidResponsePayload`, as progenitor would.
+pub(crate) async fn decode_response<T: DeserializeOwned, E>(
+    response: reqwest::Response,
+) -> Result<ResponseValue<T>, progenitor_client::Error<E>> {
+    if response.extensions().get::<error::Carried>().is_some() {
+        return Err(progenitor_client::Error::UnexpectedResponse(response));
+    }
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response
+        .bytes()
+        .await
+        .map_err(progenitor_client::Error::ResponseBodyError)?;
+    match serde_json::from_slice(&body) {
+        Ok(inner) => Ok(ResponseValue::new(inner, status, headers)),
+        Err(source) => match OperationContext::from_headers(&headers) {
+            Some(context) => {
+                let err = InvalidResponse::error(context, status, &body, source);
+                Err(progenitor_client::Error::UnexpectedResponse(error::carry(
+                    err, status, headers,
+                )))
+            }
+            None => Err(progenitor_client::Error::InvalidResponsePayload(
+                body, source,
+            )),
+        },
+    }
+}
+
+async fn send_authorized(
+    http: &reqwest::Client,
+    state: &ClientState,
+    request: reqwest::Request,
+    operation_id: &str,
+) -> reqwest::Result<reqwest::Response> {
+    let replay = match state.token_provider {
+        Some(_) => request.try_clone(),
+        None => None,
+    };
+    let response = send_with_retries(http, state, request, operation_id).await?;
+
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return Ok(response);
+    }
+    let (Some(provider), Some(mut replay)) = (&state.token_provider, replay) else {
+        return Ok(response);
+    };
+    match provider.on_unauthorized().await {
+        Ok(true) => {}
+        Ok(false) => return Ok(response),
+        Err(err) => {
+            tracing::warn!(operation = operation_id, error = %err, "token refresh failed");
+            return Ok(response);
+        }
+    }
+    match auth::authorization(state).await {
+        Ok(Some(authorization)) => {
+            tracing::debug!(operation = operation_id, "retrying with refreshed token");
+            replay
+                .headers_mut()
+                .insert(header::AUTHORIZATION, authorization);
+            send_with_retries(http, state, replay, operation_id).await
+        }
+        Ok(None) => Ok(response),
+        Err(err) => {
+            tracing::warn!(operation = operation_id, error = %err, "token refresh failed");
+            Ok(response)
+        }
+    }
+}
+
+async fn send_with_retries(
+    http: &reqwest::Client,
+    state: &ClientState,
+    mut request: reqwest::Request,
+    operation_id: &str,
+) -> reqwest::Result<reqwest::Response> {
+    let policy = &state.retry;
+    let retryable = policy.allows(request.method(), operation_id);
+    let mut attempt = 0;
+    policy.note_request();
+
+    loop {
+        let next = if retryable && attempt < policy.max_retry_count() {
+            request.try_clone()
+        } else {
+            None
+        };
+
+        if let Some(limiter) = &state.rate_limiter {
+            limiter.acquire().await;
+        }
+        if let Some(throttle) = &state.throttle {
+            throttle.wait(state.rate_limit.latest()).await;
+        }
+        let result = match &state.hedge {
+            Some(hedge) => {
+                hedge
+                    .execute(http, request, state.rate_limiter.as_ref())
+                    .await
+            }
+            None => http.execute(request).await,
+        };
+        match (result, next) {
+            (Err(err), Some(next)) if retry::is_transient(&err) => {
+                if !policy.spend_retry() {
+                    tracing::debug!(operation = operation_id, "retry budget exhausted");
+                    return Err(err);
+                }
+                attempt += 1;
+                tracing::debug!(
+                    operation = operation_id,
+                    attempt,
+                    error = %err,
+                    "retrying after transport failure"
+                );
+                if !cancellation::sleep(state, policy.backoff(attempt)).await {
+                    return Err(err);
+                }
+                request = next;
+            }
+            (Ok(response), Some(next)) if policy.retries_status(response.status()) => {
+                let retry_after = response
+                    .headers()
+                    .get(header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| error::parse_retry_after(value, chrono::Utc::now()));
+                let Some(wait) = policy.response_backoff(attempt + 1, retry_after) else {
+                    return Ok(response);
+                };
+                if !policy.spend_retry() {
+                    tracing::debug!(operation = operation_id, "retry budget exhausted");
+                    return Ok(response);
+                }
+                attempt += 1;
+                tracing::debug!(
+                    operation = operation_id,
+                    attempt,
+                    status = %response.status(),
+                    ?wait,
+                    "retrying after error response"
+                );
+                if !cancellation::sleep(state, wait).await {
+                    return Ok(response);
+                }
+                drop(response);
+                request = next;
+            }
+            (result, _) => return result,
+        }
+    }
+}
+
+fn request_timeout(state: &ClientState, operation_id: &str) -> Option<Duration> {
+    if state.timeout.is_some() || state.operation_timeouts.is_empty() {
+        return state.timeout;
+    }
+    let by_name = |name: &str| state.operation_timeouts.get(name).copied();
+    by_name(operation_id).or_else(|| {
+        let op = operations::find(operation_id)?;
+        by_name(op.operation_id).or_else(|| by_name(op.method_name))
+    })
+}
+
+fn add_default_per_page(url: &mut reqwest::Url, operation_id: &str, per_page: u64) {
+    let paginated = operations::find(operation_id).is_some_and(|op| op.paginated);
+    if paginated && !url.query_pairs().any(|(key, _)| key == "per_page") {
+        url.query_pairs_mut()
+            .append_pair("per_page", &per_page.to_string());
+    }
+}
+
+fn clamp_per_page(url: &mut reqwest::Url, operation_id: &str) {
+    let Some(requested) = url
+        .query_pairs()
+        .find(|(key, _)| key == "per_page")
+        .and_then(|(_, value)| value.parse::<u64>().ok())
+    else {
+        return;
+    };
+
+    let clamped = operations::clamp_per_page(operation_id, requested);
+    if clamped == requested {
+        return;
+    }
+
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| {
+            if key == "per_page" {
+                (key.into_owned(), clamped.to_string())
+            } else {
+                (key.into_owned(), value.into_owned())
+            }
+        })
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_per_page_is_raised() {
+        let mut url =
+            reqwest::Url::parse("https://api.digitalocean.com/v2/x?page=2&per_page=0").unwrap();
+        clamp_per_page(&mut url, "not_a_real_operation");
+        assert_eq!(url.query(), Some("page=2&per_page=1"));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_rejects_mutations() {
+        let state = ClientState {
+            read_only: true,
+            ..ClientState::default()
+        };
+        let url: reqwest::Url = "https://api.digitalocean.com/v2/droplets/123"
+            .parse()
+            .unwrap();
+
+        let mut get = reqwest::Request::new(Method::GET, url.clone());
+        assert!(prepare(&mut get, &state, "droplets_get").await.is_ok());
+
+        let mut delete = reqwest::Request::new(Method::DELETE, url);
+        let err = prepare(&mut delete, &state, "droplets_destroy")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ReadOnlyViolation { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Read-only client refused droplets_destroy DELETE /v2/droplets/123"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generated_call_reports_read_only_violation() {
+        let client = crate::Client::from_token("test-token").with_read_only(true);
+        let err = Error::from(client.droplets_destroy(123).await.unwrap_err());
+        assert!(matches!(err, Error::ReadOnlyViolation { .. }), "{err:?}");
+        assert_eq!(err.operation().unwrap().operation_id, "droplets_destroy");
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_body_is_invalid_response() {
+        let mut response = http::Response::new(r#"{"droplets": "#.to_string());
+        OperationContext::new("droplets_list", Method::GET, "/v2/droplets", false)
+            .tag(response.headers_mut());
+        let err = decode_response::<Vec<u64>, ()>(response.into())
+            .await
+            .unwrap_err();
+
+        // The call travels with the error, so converting it elsewhere still names it.
+        let err = tokio::spawn(async move { Error::from(err) }).await.unwrap();
+        let Error::InvalidResponse(invalid) = &err else {
+            panic!("expected InvalidResponse, got {err:?}");
+        };
+        assert_eq!(invalid.status, StatusCode::OK);
+        assert_eq!(invalid.body_snippet, r#"{"droplets": "#);
+        assert_eq!(err.operation().unwrap().operation_id, "droplets_list");
+    }
+
+    #[test]
+    fn test_default_per_page_only_fills_gaps() {
+        let mut url =
+            reqwest::Url::parse("https://api.digitalocean.com/v2/droplets?page=2").unwrap();
+        add_default_per_page(&mut url, "droplets_list", 200);
+        assert_eq!(url.query(), Some("page=2&per_page=200"));
+
+        let mut url =
+            reqwest::Url::parse("https://api.digitalocean.com/v2/droplets?per_page=5").unwrap();
+        add_default_per_page(&mut url, "droplets_list", 200);
+        assert_eq!(url.query(), Some("per_page=5"));
+
+        let mut url = reqwest::Url::parse("https://api.digitalocean.com/v2/droplets/1").unwrap();
+        add_default_per_page(&mut url, "droplets_get", 200);
+        assert_eq!(url.query(), None);
+    }
+
+    #[test]
+    fn test_call_timeout_overrides_operation_timeout() {
+        let mut state = ClientState {
+            operation_timeouts: Arc::new(HashMap::from([(
+                "account_get".to_string(),
+                Duration::from_secs(5),
+            )])),
+            ..ClientState::default()
+        };
+        assert_eq!(
+            request_timeout(&state, "account_get"),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(request_timeout(&state, "droplets_create"), None);
+
+        state.timeout = Some(Duration::from_secs(120));
+        assert_eq!(
+            request_timeout(&state, "account_get"),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn test_unknown_operation_is_left_alone() {
+        let mut url =
+            reqwest::Url::parse("https://api.digitalocean.com/v2/x?per_page=5000").unwrap();
+        clamp_per_page(&mut url, "not_a_real_operation");
+        assert_eq!(url.query(), Some("per_page=5000"));
+    }
+}
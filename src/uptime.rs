@@ -0,0 +1,533 @@
+//! Typed uptime checks and alerts.
+//!
+//! The uptime endpoints take region codes, alert types, comparison operators and
+//! periods as plain strings, and a typo or an operator the alert type does not
+//! support is only rejected once the request reaches the API. These helpers model
+//! each of them as an enum and validate a check or alert before it is sent, so
+//! mistakes surface as [`Error::InvalidInput`] with a message naming the valid
+//! choices.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::uptime::{AlertPeriod, CheckType, CreateUptimeAlert, CreateUptimeCheck, UptimeRegion};
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let check = CreateUptimeCheck::new("landing page", CheckType::Https, "https://example.com")
+//!     .regions([UptimeRegion::UsEast, "eu_west".parse()?]);
+//! let check = client.create_uptime_check(&check).await?;
+//!
+//! let alert = CreateUptimeAlert::latency("slow landing page", 500, AlertPeriod::FiveMinutes)
+//!     .email("oncall@example.com");
+//! client.create_uptime_alert(&check.id, &alert).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::request::ApiRequest;
+use crate::Client;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+const CHECKS_PATH: &str = "/v2/uptime/checks";
+
+/// A region uptime checks can run from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum UptimeRegion {
+    UsEast,
+    UsWest,
+    EuWest,
+    SeAsia,
+    /// A region this version of rsdo does not know about yet. Only produced when
+    /// decoding responses; requests using it fail validation.
+    Unknown(String),
+}
+
+impl UptimeRegion {
+    /// Every region checks can currently be created in.
+    pub const SUPPORTED: [UptimeRegion; 4] =
+        [Self::UsEast, Self::UsWest, Self::EuWest, Self::SeAsia];
+
+    /// The API's code for the region, e.g. `us_east`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::UsEast => "us_east",
+            Self::UsWest => "us_west",
+            Self::EuWest => "eu_west",
+            Self::SeAsia => "se_asia",
+            Self::Unknown(code) => code,
+        }
+    }
+}
+
+impl FromStr for UptimeRegion {
+    type Err = Error;
+
+    /// Parse a region code, rejecting codes not in [`UptimeRegion::SUPPORTED`].
+    fn from_str(code: &str) -> Result<Self, Error> {
+        match Self::from(code.to_string()) {
+            Self::Unknown(code) => Err(Error::InvalidInput(format!(
+                "unknown uptime check region {code:?}; expected one of {}",
+                supported_regions()
+            ))),
+            region => Ok(region),
+        }
+    }
+}
+
+impl From<String> for UptimeRegion {
+    fn from(code: String) -> Self {
+        match code.as_str() {
+            "us_east" => Self::UsEast,
+            "us_west" => Self::UsWest,
+            "eu_west" => Self::EuWest,
+            "se_asia" => Self::SeAsia,
+            _ => Self::Unknown(code),
+        }
+    }
+}
+
+impl From<UptimeRegion> for String {
+    fn from(region: UptimeRegion) -> Self {
+        region.to_string()
+    }
+}
+
+impl fmt::Display for UptimeRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn supported_regions() -> String {
+    UptimeRegion::SUPPORTED
+        .iter()
+        .map(UptimeRegion::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// How an uptime check probes its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckType {
+    /// ICMP ping of a hostname or IP address.
+    Ping,
+    /// GET of an `http://` URL.
+    Http,
+    /// GET of an `https://` URL, which also tracks certificate expiry.
+    Https,
+}
+
+/// Request body for [`Client::create_uptime_check`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CreateUptimeCheck {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub check_type: CheckType,
+    /// Hostname or IP for [`CheckType::Ping`], otherwise a URL with the matching
+    /// scheme.
+    pub target: String,
+    pub regions: Vec<UptimeRegion>,
+    pub enabled: bool,
+}
+
+impl CreateUptimeCheck {
+    /// An enabled check running from every supported region.
+    pub fn new(name: impl Into<String>, check_type: CheckType, target: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            check_type,
+            target: target.into(),
+            regions: UptimeRegion::SUPPORTED.to_vec(),
+            enabled: true,
+        }
+    }
+
+    /// Run the check from `regions` only.
+    pub fn regions(mut self, regions: impl IntoIterator<Item = UptimeRegion>) -> Self {
+        self.regions = regions.into_iter().collect();
+        self
+    }
+
+    /// Create the check disabled.
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+
+    /// Check the request against the rules the API enforces.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.name.trim().is_empty() {
+            return Err(Error::InvalidInput(
+                "uptime check name must not be empty".to_string(),
+            ));
+        }
+        if self.regions.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "uptime check {:?} needs at least one region out of {}",
+                self.name,
+                supported_regions()
+            )));
+        }
+        for region in &self.regions {
+            if let UptimeRegion::Unknown(code) = region {
+                return Err(Error::InvalidInput(format!(
+                    "unknown uptime check region {code:?}; expected one of {}",
+                    supported_regions()
+                )));
+            }
+        }
+
+        let scheme = match self.check_type {
+            CheckType::Ping => None,
+            CheckType::Http => Some("http://"),
+            CheckType::Https => Some("https://"),
+        };
+        let valid_target = match scheme {
+            Some(scheme) => self.target.len() > scheme.len() && self.target.starts_with(scheme),
+            None => !self.target.is_empty() && !self.target.contains("://"),
+        };
+        if !valid_target {
+            let expected = scheme.map_or("a hostname or IP address".to_string(), |scheme| {
+                format!("a URL starting with {scheme}")
+            });
+            return Err(Error::InvalidInput(format!(
+                "{:?} check target {:?} must be {expected}",
+                self.check_type, self.target
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// An uptime check.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UptimeCheck {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub check_type: CheckType,
+    pub target: String,
+    #[serde(default)]
+    pub regions: Vec<UptimeRegion>,
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// What an uptime alert fires on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertType {
+    /// Response time in milliseconds.
+    Latency,
+    /// The target is down in any region.
+    Down,
+    /// The target is down in every region.
+    DownGlobal,
+    /// Days until the TLS certificate expires (HTTPS checks).
+    SslExpiry,
+}
+
+impl AlertType {
+    /// Comparison operators the alert type accepts. Types without a threshold accept
+    /// none.
+    pub fn comparisons(self) -> &'static [Comparison] {
+        match self {
+            Self::Latency => &[Comparison::GreaterThan],
+            Self::SslExpiry => &[Comparison::LessThan],
+            Self::Down | Self::DownGlobal => &[],
+        }
+    }
+}
+
+/// How an alert's value is compared to its threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    GreaterThan,
+    LessThan,
+}
+
+/// How long a condition must hold before the alert fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AlertPeriod {
+    #[serde(rename = "2m")]
+    TwoMinutes,
+    #[serde(rename = "3m")]
+    ThreeMinutes,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "10m")]
+    TenMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    ThirtyMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+}
+
+/// A Slack channel to notify.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlackChannel {
+    pub channel: String,
+    /// Incoming webhook URL.
+    pub url: String,
+}
+
+/// Where an alert sends notifications.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertNotifications {
+    #[serde(default)]
+    pub email: Vec<String>,
+    #[serde(default)]
+    pub slack: Vec<SlackChannel>,
+}
+
+/// Request body for [`Client::create_uptime_alert`]. Build it with the constructor
+/// for its [`AlertType`], which fills in the only valid comparison.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CreateUptimeAlert {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub alert_type: AlertType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<Comparison>,
+    pub period: AlertPeriod,
+    pub notifications: AlertNotifications,
+}
+
+impl CreateUptimeAlert {
+    fn new(
+        name: impl Into<String>,
+        alert_type: AlertType,
+        threshold: Option<u32>,
+        period: AlertPeriod,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            alert_type,
+            threshold,
+            comparison: threshold.and(alert_type.comparisons().first().copied()),
+            period,
+            notifications: AlertNotifications::default(),
+        }
+    }
+
+    /// Alert when responses take longer than `threshold_ms` milliseconds.
+    pub fn latency(name: impl Into<String>, threshold_ms: u32, period: AlertPeriod) -> Self {
+        Self::new(name, AlertType::Latency, Some(threshold_ms), period)
+    }
+
+    /// Alert when the target is down in any region.
+    pub fn down(name: impl Into<String>, period: AlertPeriod) -> Self {
+        Self::new(name, AlertType::Down, None, period)
+    }
+
+    /// Alert when the target is down in every region.
+    pub fn down_global(name: impl Into<String>, period: AlertPeriod) -> Self {
+        Self::new(name, AlertType::DownGlobal, None, period)
+    }
+
+    /// Alert when the TLS certificate expires in less than `days` days.
+    pub fn ssl_expiry(name: impl Into<String>, days: u32) -> Self {
+        Self::new(
+            name,
+            AlertType::SslExpiry,
+            Some(days),
+            AlertPeriod::TwoMinutes,
+        )
+    }
+
+    /// Also notify `address` by email.
+    pub fn email(mut self, address: impl Into<String>) -> Self {
+        self.notifications.email.push(address.into());
+        self
+    }
+
+    /// Also notify a Slack channel through an incoming webhook.
+    pub fn slack(mut self, channel: impl Into<String>, url: impl Into<String>) -> Self {
+        self.notifications.slack.push(SlackChannel {
+            channel: channel.into(),
+            url: url.into(),
+        });
+        self
+    }
+
+    /// Check the request against the rules the API enforces: a threshold and a
+    /// supported comparison for latency and SSL expiry alerts, neither for down
+    /// alerts, and at least one notification target.
+    pub fn validate(&self) -> Result<(), Error> {
+        let allowed = self.alert_type.comparisons();
+        match (allowed.is_empty(), self.threshold, self.comparison) {
+            (true, None, None) => {}
+            (true, _, _) => {
+                return Err(Error::InvalidInput(format!(
+                    "{:?} alerts take no threshold or comparison",
+                    self.alert_type
+                )));
+            }
+            (false, Some(_), Some(comparison)) if allowed.contains(&comparison) => {}
+            (false, Some(_), Some(comparison)) => {
+                return Err(Error::InvalidInput(format!(
+                    "{:?} alerts do not support {comparison:?}; expected {allowed:?}",
+                    self.alert_type
+                )));
+            }
+            (false, _, _) => {
+                return Err(Error::InvalidInput(format!(
+                    "{:?} alerts need a threshold and one of {allowed:?}",
+                    self.alert_type
+                )));
+            }
+        }
+        if self.notifications.email.is_empty() && self.notifications.slack.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "uptime alert {:?} has no email or Slack notification",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// An alert on an uptime check.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UptimeAlert {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub alert_type: AlertType,
+    #[serde(default)]
+    pub threshold: Option<u32>,
+    #[serde(default)]
+    pub comparison: Option<Comparison>,
+    pub period: AlertPeriod,
+    #[serde(default)]
+    pub notifications: AlertNotifications,
+}
+
+#[derive(Deserialize)]
+struct CheckEnvelope {
+    check: UptimeCheck,
+}
+
+#[derive(Deserialize)]
+struct AlertEnvelope {
+    alert: UptimeAlert,
+}
+
+impl Client {
+    /// Create an uptime check after validating it with
+    /// [`CreateUptimeCheck::validate`].
+    pub async fn create_uptime_check(
+        &self,
+        request: &CreateUptimeCheck,
+    ) -> Result<UptimeCheck, Error> {
+        request.validate()?;
+        let envelope: CheckEnvelope = self
+            .send_json(
+                ApiRequest::post("uptime_create_check", CHECKS_PATH)
+                    .json(serde_json::to_value(request)?),
+            )
+            .await?;
+        Ok(envelope.check)
+    }
+
+    /// Add an alert to the uptime check `check_id` after validating it with
+    /// [`CreateUptimeAlert::validate`].
+    pub async fn create_uptime_alert(
+        &self,
+        check_id: &str,
+        request: &CreateUptimeAlert,
+    ) -> Result<UptimeAlert, Error> {
+        request.validate()?;
+        let envelope: AlertEnvelope = self
+            .send_json(
+                ApiRequest::post(
+                    "uptime_create_alert",
+                    format!("{CHECKS_PATH}/{check_id}/alerts"),
+                )
+                .json(serde_json::to_value(request)?),
+            )
+            .await?;
+        Ok(envelope.alert)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_regions_and_target() {
+        assert_eq!(
+            "se_asia".parse::<UptimeRegion>().unwrap(),
+            UptimeRegion::SeAsia
+        );
+        let err = "ap_south".parse::<UptimeRegion>().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("us_east, us_west, eu_west, se_asia"));
+        let decoded: UptimeRegion = serde_json::from_value(json!("ap_south")).unwrap();
+        assert_eq!(decoded, UptimeRegion::Unknown("ap_south".to_string()));
+
+        let check = CreateUptimeCheck::new("site", CheckType::Https, "https://example.com")
+            .regions([UptimeRegion::UsEast]);
+        check.validate().unwrap();
+        assert_eq!(
+            serde_json::to_value(&check).unwrap(),
+            json!({
+                "name": "site",
+                "type": "https",
+                "target": "https://example.com",
+                "regions": ["us_east"],
+                "enabled": true
+            })
+        );
+        assert!(check.clone().regions([decoded]).validate().is_err());
+        assert!(
+            CreateUptimeCheck::new("site", CheckType::Https, "http://example.com")
+                .validate()
+                .is_err()
+        );
+        assert!(
+            CreateUptimeCheck::new("ping", CheckType::Ping, "203.0.113.7")
+                .validate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_alert_comparison_per_type() {
+        let latency = CreateUptimeAlert::latency("slow", 500, AlertPeriod::FiveMinutes)
+            .slack("#ops", "https://hooks.slack.com/services/T/B/X");
+        latency.validate().unwrap();
+        assert_eq!(
+            serde_json::to_value(&latency).unwrap()["comparison"],
+            json!("greater_than")
+        );
+
+        let mut wrong = latency.clone();
+        wrong.comparison = Some(Comparison::LessThan);
+        assert!(wrong.validate().is_err());
+
+        let down = CreateUptimeAlert::down("down", AlertPeriod::TwoMinutes).email("a@example.com");
+        down.validate().unwrap();
+        assert_eq!(serde_json::to_value(&down).unwrap().get("threshold"), None);
+        let mut with_threshold = down.clone();
+        with_threshold.threshold = Some(1);
+        assert!(with_threshold.validate().is_err());
+
+        assert!(CreateUptimeAlert::ssl_expiry("cert", 14)
+            .validate()
+            .is_err());
+    }
+}
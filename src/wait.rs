@@ -0,0 +1,562 @@
+//! Polling until a condition holds.
+//!
+//! A [`Waiter`] fetches a value, checks it and pauses between polls as its
+//! [`WaitOptions`] say, until the value is ready, a failure condition matches or the
+//! timeout passes. The resource-specific waiters, such as
+//! [`Client::wait_for_action`] and [`Client::wait_for_droplet_status`], are built on
+//! it; [`Client::waiter`] builds one for any other condition.
+//!
+//! The delay between polls is fixed by default. [`WaitOptions::backoff`] grows it
+//! exponentially, and [`WaitOptions::strategy`] plugs in any [`PollStrategy`],
+//! including a closure from the poll number to the delay.
+//!
+//! Every poll that does not end the wait is reported as a [`WaitProgress`], to the
+//! options' [`on_progress`](WaitOptions::on_progress) callback and to the client's
+//! (see [`Client::with_wait_progress`]), which also covers the helpers that take a
+//! plain interval and timeout. Waiters for resources with a known typical duration,
+//! such as a droplet boot, include an estimate of the time left, enough to drive a
+//! progress bar.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::wait::WaitOptions;
+//! use std::time::Duration;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let droplet = client
+//!     .waiter("droplet 3164494 to be unlocked", || client.droplet(3164494))
+//!     .until(|droplet| !droplet.locked)
+//!     .state(|droplet| droplet.status.to_string())
+//!     .options(WaitOptions::default().strategy(|attempt: u32| {
+//!         Duration::from_secs(u64::from(attempt.min(6)) * 5)
+//!     }))
+//!     .wait()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cancellation;
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::{Client, ClientInfo, ClientState};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// One poll of a waiter, as passed to [`WaitOptions::on_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitProgress {
+    /// What is being waited for, e.g. `droplet 42 to become active`.
+    pub waiting_for: String,
+    /// Polls so far, starting at 1.
+    pub attempt: u32,
+    pub elapsed: Duration,
+    /// The state observed, e.g. `in-progress` or `new`.
+    pub state: String,
+    /// Estimated time left, from how long such a wait typically takes. `None` when
+    /// there is no estimate or the wait is already taking longer than usual.
+    pub eta: Option<Duration>,
+}
+
+impl WaitProgress {
+    /// Estimated share of the wait done so far, between 0 and 1, if there is an
+    /// estimate.
+    pub fn fraction(&self) -> Option<f64> {
+        let eta = self.eta?;
+        let total = self.elapsed + eta;
+        Some(if total.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f64() / total.as_secs_f64()
+        })
+    }
+}
+
+/// How long to pause between polls.
+///
+/// Implemented for closures taking the poll number, so
+/// `|attempt: u32| Duration::from_secs(attempt.into())` is a strategy.
+pub trait PollStrategy: Send + Sync {
+    /// Delay after poll number `attempt` (starting at 1) before the next poll.
+    fn delay(&self, attempt: u32) -> Duration;
+}
+
+impl<F> PollStrategy for F
+where
+    F: Fn(u32) -> Duration + Send + Sync,
+{
+    fn delay(&self, attempt: u32) -> Duration {
+        self(attempt)
+    }
+}
+
+type ProgressCallback = dyn Fn(&WaitProgress) + Send + Sync;
+
+/// A client-wide progress callback.
+#[derive(Clone)]
+pub(crate) struct ProgressHandler(pub(crate) Arc<ProgressCallback>);
+
+impl fmt::Debug for ProgressHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressHandler(..)")
+    }
+}
+
+/// How often to poll and when to give up.
+#[derive(Clone)]
+pub struct WaitOptions {
+    interval: Duration,
+    timeout: Duration,
+    backoff: f64,
+    max_interval: Duration,
+    strategy: Option<Arc<dyn PollStrategy>>,
+    on_progress: Option<Arc<ProgressCallback>>,
+}
+
+impl fmt::Debug for WaitOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WaitOptions")
+            .field("interval", &self.interval)
+            .field("timeout", &self.timeout)
+            .field("backoff", &self.backoff)
+            .field("max_interval", &self.max_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for WaitOptions {
+    /// Poll every 5 seconds for up to 10 minutes.
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(600),
+            backoff: 1.0,
+            max_interval: Duration::from_secs(5),
+            strategy: None,
+            on_progress: None,
+        }
+    }
+}
+
+impl WaitOptions {
+    /// Delay between polls, or before the second poll when backing off.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self.max_interval = self.max_interval.max(interval);
+        self
+    }
+
+    /// Upper bound on the total wait.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Multiply the delay by `factor` after every poll, up to `max_interval`.
+    pub fn backoff(mut self, factor: f64, max_interval: Duration) -> Self {
+        self.backoff = factor.max(1.0);
+        self.max_interval = max_interval.max(self.interval);
+        self
+    }
+
+    /// Take the delay between polls from `strategy`, overriding
+    /// [`interval`](Self::interval) and [`backoff`](Self::backoff).
+    pub fn strategy(mut self, strategy: impl PollStrategy + 'static) -> Self {
+        self.strategy = Some(Arc::new(strategy));
+        self
+    }
+
+    /// Call `callback` after every poll that did not finish the wait.
+    pub fn on_progress(mut self, callback: impl Fn(&WaitProgress) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        match &self.strategy {
+            Some(strategy) => strategy.delay(attempt),
+            None => self
+                .interval
+                .mul_f64(self.backoff.powi(attempt.saturating_sub(1).min(64) as i32))
+                .min(self.max_interval),
+        }
+    }
+
+    /// Report `progress`, then wait before the next poll. Fails with
+    /// [`Error::Timeout`] instead if the next poll would come after the timeout.
+    async fn pause(&self, client: &Client, progress: WaitProgress) -> Result<(), Error> {
+        if let Some(callback) = &self.on_progress {
+            callback(&progress);
+        }
+        if let Some(ProgressHandler(callback)) = &client.inner().wait_progress {
+            callback(&progress);
+        }
+        let delay = self.delay(progress.attempt);
+        if progress.elapsed + delay > self.timeout {
+            return Err(Error::Timeout {
+                waiting_for: progress.waiting_for,
+                elapsed: progress.elapsed,
+            });
+        }
+        cancellation::sleep(client.inner(), delay).await;
+        Ok(())
+    }
+}
+
+type Fetch<'a, T> = Box<dyn FnMut() -> BoxFuture<'a, Result<T, Error>> + Send + 'a>;
+type Check<'a, T, R> = Box<dyn Fn(&T) -> R + Send + Sync + 'a>;
+
+/// Polls a value until it is ready, built by [`Client::waiter`].
+pub struct Waiter<'a, T> {
+    client: &'a Client,
+    waiting_for: String,
+    fetch: Fetch<'a, T>,
+    ready: Check<'a, T, bool>,
+    fail: Option<Check<'a, T, Option<Error>>>,
+    state: Option<Check<'a, T, String>>,
+    typical: Option<Check<'a, T, Option<Duration>>>,
+    options: WaitOptions,
+}
+
+impl<T> fmt::Debug for Waiter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Waiter")
+            .field("waiting_for", &self.waiting_for)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T> Waiter<'a, T> {
+    /// Stop once `ready` returns `true` for the fetched value. Without it, the first
+    /// value fetched is returned.
+    pub fn until(mut self, ready: impl Fn(&T) -> bool + Send + Sync + 'a) -> Self {
+        self.ready = Box::new(ready);
+        self
+    }
+
+    /// Give up with the error `fail` returns for a value that is not ready, e.g.
+    /// because the resource errored and will never become ready.
+    pub fn fail_if(mut self, fail: impl Fn(&T) -> Option<Error> + Send + Sync + 'a) -> Self {
+        self.fail = Some(Box::new(fail));
+        self
+    }
+
+    /// Describe a value that is not ready for [`WaitOptions::on_progress`]. Defaults
+    /// to `pending`.
+    pub fn state(mut self, state: impl Fn(&T) -> String + Send + Sync + 'a) -> Self {
+        self.state = Some(Box::new(state));
+        self
+    }
+
+    /// Estimate how long the whole wait typically takes, given the latest value, for
+    /// [`WaitProgress::eta`]. Return `None` where there is no estimate.
+    pub fn typical_duration(
+        mut self,
+        typical: impl Fn(&T) -> Option<Duration> + Send + Sync + 'a,
+    ) -> Self {
+        self.typical = Some(Box::new(typical));
+        self
+    }
+
+    /// Poll as `options` say, instead of the defaults.
+    pub fn options(mut self, options: WaitOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Poll until the value is ready, and return it.
+    ///
+    /// Fails with the first fetch error, the error from [`fail_if`](Self::fail_if),
+    /// or [`Error::Timeout`] if the value is still not ready after the options'
+    /// timeout.
+    pub async fn wait(mut self) -> Result<T, Error> {
+        let started = Instant::now();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let value = (self.fetch)().await?;
+            if (self.ready)(&value) {
+                return Ok(value);
+            }
+            if let Some(err) = self.fail.as_ref().and_then(|fail| fail(&value)) {
+                return Err(err);
+            }
+            let elapsed = started.elapsed();
+            let progress = WaitProgress {
+                waiting_for: self.waiting_for.clone(),
+                attempt,
+                elapsed,
+                state: match &self.state {
+                    Some(state) => state(&value),
+                    None => "pending".to_string(),
+                },
+                eta: self
+                    .typical
+                    .as_ref()
+                    .and_then(|typical| typical(&value))
+                    .and_then(|typical| typical.checked_sub(elapsed)),
+            };
+            drop(value);
+            self.options.pause(self.client, progress).await?;
+        }
+    }
+}
+
+impl Client {
+    /// Return a copy of this client that calls `callback` after every poll of every
+    /// waiter that did not finish the wait, replacing any previous callback.
+    ///
+    /// The copy shares the underlying connection pool with `self`.
+    pub fn with_wait_progress(
+        &self,
+        callback: impl Fn(&WaitProgress) + Send + Sync + 'static,
+    ) -> Self {
+        let mut state: ClientState = self.inner().clone();
+        state.wait_progress = Some(ProgressHandler(Arc::new(callback)));
+        Client::new_with_client_and_state(self.baseurl(), self.client().clone(), state)
+    }
+
+    /// Build a [`Waiter`] that polls `fetch`. `waiting_for` describes the condition in
+    /// timeout errors, e.g. `droplet 42 to be unlocked`.
+    pub fn waiter<'a, T, F, Fut>(
+        &'a self,
+        waiting_for: impl Into<String>,
+        mut fetch: F,
+    ) -> Waiter<'a, T>
+    where
+        F: FnMut() -> Fut + Send + 'a,
+        Fut: Future<Output = Result<T, Error>> + Send + 'a,
+    {
+        Waiter {
+            client: self,
+            waiting_for: waiting_for.into(),
+            fetch: Box::new(move || fetch().boxed()),
+            ready: Box::new(|_| true),
+            fail: None,
+            state: None,
+            typical: None,
+            options: WaitOptions::default(),
+        }
+    }
+
+    /// Send `delete`, then poll `get` until it answers `404 Not Found`.
+    ///
+    /// A `404` from the delete itself counts as already gone, so teardown can be
+    /// re-run safely. `what` describes the resource in timeout errors.
+    pub(crate) async fn delete_and_wait_gone(
+        &self,
+        delete: ApiRequest,
+        get: ApiRequest,
+        what: &str,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let workflow = Workflow::new(self.inner(), "delete_and_wait_gone", what.to_string());
+        let deleted = workflow
+            .step("delete", async {
+                match self.send_empty(delete).await {
+                    Err(err) if err.is_not_found() => Ok(false),
+                    result => result.map(|()| true),
+                }
+            })
+            .await?;
+        if !deleted {
+            return Ok(());
+        }
+
+        let options = WaitOptions::default().interval(interval).timeout(timeout);
+        workflow
+            .step("wait_gone", self.wait_gone(get, what, options))
+            .await
+    }
+
+    /// Poll `get` until it answers `404 Not Found`, for a resource deleted elsewhere.
+    pub(crate) async fn wait_until_deleted_at(
+        &self,
+        get: ApiRequest,
+        what: &str,
+        options: WaitOptions,
+    ) -> Result<(), Error> {
+        let workflow = Workflow::new(self.inner(), "wait_until_deleted", what.to_string());
+        workflow
+            .step("wait_gone", self.wait_gone(get, what, options))
+            .await
+    }
+
+    async fn wait_gone(
+        &self,
+        get: ApiRequest,
+        what: &str,
+        options: WaitOptions,
+    ) -> Result<(), Error> {
+        self.waiter(format!("{what} to be deleted"), move || {
+            let get = get.clone();
+            async move {
+                match self.send_empty(get).await {
+                    Err(err) if err.is_not_found() => Ok(true),
+                    result => result.map(|()| false),
+                }
+            }
+        })
+        .until(|gone| *gone)
+        .state(|_| "present".to_string())
+        .typical_duration(|_| Some(Duration::from_secs(30)))
+        .options(options)
+        .wait()
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answers one request per connection with the given statuses, in order, and
+    /// returns the request lines it saw.
+    async fn serve(listener: TcpListener, statuses: &[&str]) -> Vec<String> {
+        let mut requests = Vec::new();
+        for status in statuses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8(request).unwrap();
+            requests.push(request.lines().next().unwrap().to_string());
+            let response =
+                format!("HTTP/1.1 {status}\r\ncontent-length: 2\r\nconnection: close\r\n\r\n{{}}");
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+        requests
+    }
+
+    #[tokio::test]
+    async fn test_pause_backs_off_and_times_out() {
+        let client = crate::Client::from_token("test-token");
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let options = WaitOptions::default()
+            .interval(Duration::from_millis(1))
+            .backoff(2.0, Duration::from_millis(4))
+            .timeout(Duration::from_millis(500))
+            .on_progress(move |progress| log.lock().unwrap().push(progress.attempt));
+        assert_eq!(options.delay(3), Duration::from_millis(4));
+
+        let progress = |attempt, elapsed| WaitProgress {
+            waiting_for: "droplet 1".to_string(),
+            attempt,
+            elapsed,
+            state: "new".to_string(),
+            eta: None,
+        };
+        for attempt in 1..=3 {
+            options
+                .pause(&client, progress(attempt, Duration::ZERO))
+                .await
+                .unwrap();
+        }
+        assert_eq!(*seen.lock().unwrap(), [1, 2, 3]);
+
+        let err = options
+            .pause(&client, progress(4, Duration::from_secs(1)))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::Timeout { ref waiting_for, .. } if waiting_for == "droplet 1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_waiter_polls_until_ready_or_failed() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let client = crate::Client::from_token("test-token")
+            .with_wait_progress(move |progress| log.lock().unwrap().push(progress.clone()));
+        let polls = std::sync::atomic::AtomicU32::new(0);
+        let fetch = || async { Ok(polls.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1) };
+        let options = WaitOptions::default().strategy(|_| Duration::ZERO);
+
+        let value = client
+            .waiter("three polls", fetch)
+            .until(|polls| *polls == 3)
+            .state(|polls| format!("poll {polls}"))
+            .typical_duration(|_| Some(Duration::from_secs(3600)))
+            .options(options.clone())
+            .wait()
+            .await
+            .unwrap();
+        assert_eq!(value, 3);
+        let progress = seen.lock().unwrap().clone();
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[1].state, "poll 2");
+        assert!(progress[1].eta.unwrap() > Duration::from_secs(3500));
+        assert!(progress[1].fraction().unwrap() < 0.01);
+
+        let err = client
+            .waiter("never", fetch)
+            .until(|_| false)
+            .fail_if(|polls| (*polls == 5).then(|| Error::Other("gave up".into())))
+            .options(options)
+            .wait()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Other(ref message) if message == "gave up"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_deleted_treats_not_found_as_done() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve(listener, &["200 OK", "404 Not Found"]));
+
+        let client = crate::ClientBuilder::new("test-token")
+            .base_url(base_url)
+            .build()
+            .unwrap();
+        client
+            .wait_until_droplet_deleted(42, WaitOptions::default().interval(Duration::ZERO))
+            .await
+            .unwrap();
+        assert_eq!(server.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_polls_until_not_found() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve(
+            listener,
+            &["204 No Content", "200 OK", "404 Not Found"],
+        ));
+
+        let client = crate::ClientBuilder::new("test-token")
+            .base_url(base_url)
+            .build()
+            .unwrap();
+        client
+            .delete_droplet_and_wait_gone(42, Duration::ZERO, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            server.await.unwrap(),
+            [
+                "DELETE /v2/droplets/42 HTTP/1.1",
+                "GET /v2/droplets/42 HTTP/1.1",
+                "GET /v2/droplets/42 HTTP/1.1",
+            ]
+        );
+    }
+}
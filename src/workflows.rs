@@ -0,0 +1,239 @@
+//! Create a resource and wait until it is ready.
+//!
+//! Creating a droplet returns as soon as DigitalOcean has accepted the request; the
+//! droplet boots in the background and the returned object has no IP addresses yet.
+//! The helpers here chain the create call, waiting for the resource to finish
+//! provisioning and a fresh `GET`, so one `await` hands back the resource as it is
+//! once usable:
+//!
+//! - [`Client::create_droplet_and_wait`] waits for the droplet's create action;
+//! - [`Client::create_volume_and_wait`] for a volume, which is ready once created;
+//! - [`Client::create_load_balancer_and_wait`] polls until the load balancer is
+//!   `active`.
+//!
+//! Each step is reported through [`events`](crate::events).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rsdo::wait::WaitOptions;
+//! use serde_json::json;
+//!
+//! # async fn run(client: rsdo::Client) -> Result<(), rsdo::error::Error> {
+//! let droplet = client
+//!     .create_droplet_and_wait(
+//!         &json!({ "name": "web-1", "region": "nyc3", "size": "s-1vcpu-1gb", "image": "ubuntu-22-04-x64" }),
+//!         WaitOptions::default(),
+//!     )
+//!     .await?;
+//! println!("{} is {}", droplet.name, droplet.status);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::droplets::{Droplet, DropletRegion, DropletStatus};
+use crate::error::Error;
+use crate::events::Workflow;
+use crate::request::ApiRequest;
+use crate::wait::WaitOptions;
+use crate::{Client, ClientInfo};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A block storage volume.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Volume {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub size_gigabytes: u64,
+    #[serde(default)]
+    pub region: Option<DropletRegion>,
+    /// Droplets the volume is attached to.
+    #[serde(default)]
+    pub droplet_ids: Vec<u64>,
+    #[serde(default)]
+    pub filesystem_type: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A load balancer.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoadBalancer {
+    pub id: String,
+    pub name: String,
+    /// Public IPv4 address, empty until the load balancer is active.
+    #[serde(default)]
+    pub ip: String,
+    /// `new`, `active` or `errored`.
+    pub status: String,
+    #[serde(default)]
+    pub region: Option<DropletRegion>,
+    #[serde(default)]
+    pub droplet_ids: Vec<u64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct CreatedDroplet {
+    droplet: Droplet,
+    #[serde(default)]
+    links: CreatedLinks,
+}
+
+#[derive(Default, Deserialize)]
+struct CreatedLinks {
+    #[serde(default)]
+    actions: Vec<ActionLink>,
+}
+
+#[derive(Deserialize)]
+struct ActionLink {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct VolumeEnvelope {
+    volume: Volume,
+}
+
+#[derive(Deserialize)]
+struct LoadBalancerEnvelope {
+    load_balancer: LoadBalancer,
+}
+
+impl Client {
+    /// Create a droplet from `body` (as for `droplets_create`), wait for its create
+    /// action and return it as fetched afterwards.
+    ///
+    /// Without a linked action, waits for the droplet to become active instead.
+    pub async fn create_droplet_and_wait(
+        &self,
+        body: &impl Serialize,
+        options: WaitOptions,
+    ) -> Result<Droplet, Error> {
+        let body = serde_json::to_value(body)?;
+        let name = body["name"].as_str().unwrap_or("droplet").to_string();
+        let workflow = Workflow::new(
+            self.inner(),
+            "create_droplet_and_wait",
+            format!("droplet {name}"),
+        );
+        let created: CreatedDroplet = workflow
+            .step(
+                "create",
+                self.send_json(ApiRequest::post("droplets_create", "/v2/droplets").json(body)),
+            )
+            .await?;
+        let id = created.droplet.id;
+        match created.links.actions.first() {
+            Some(action) => {
+                workflow
+                    .step("wait", self.wait_for_action(action.id, options))
+                    .await?;
+                workflow.step("fetch", self.droplet(id)).await
+            }
+            None => {
+                workflow
+                    .step(
+                        "wait",
+                        self.wait_for_droplet_status(id, DropletStatus::Active, options),
+                    )
+                    .await
+            }
+        }
+    }
+
+    /// Create a volume from `body` (as for `volumes_create`) and return it as
+    /// fetched afterwards.
+    ///
+    /// Volumes are provisioned by the create call itself, so there is nothing to poll.
+    pub async fn create_volume_and_wait(&self, body: &impl Serialize) -> Result<Volume, Error> {
+        let body = serde_json::to_value(body)?;
+        let name = body["name"].as_str().unwrap_or("volume").to_string();
+        let workflow = Workflow::new(
+            self.inner(),
+            "create_volume_and_wait",
+            format!("volume {name}"),
+        );
+        let created: VolumeEnvelope = workflow
+            .step(
+                "create",
+                self.send_json(ApiRequest::post("volumes_create", "/v2/volumes").json(body)),
+            )
+            .await?;
+        let path = format!("/v2/volumes/{}", created.volume.id);
+        let fetched: VolumeEnvelope = workflow
+            .step(
+                "fetch",
+                self.send_json(ApiRequest::get("volumes_get", path)),
+            )
+            .await?;
+        Ok(fetched.volume)
+    }
+
+    /// Create a load balancer from `body` (as for `loadBalancers_create`) and poll
+    /// it until its status is `active`.
+    ///
+    /// Fails if the load balancer turns `errored`, or with [`Error::Timeout`].
+    pub async fn create_load_balancer_and_wait(
+        &self,
+        body: &impl Serialize,
+        options: WaitOptions,
+    ) -> Result<LoadBalancer, Error> {
+        let body = serde_json::to_value(body)?;
+        let name = body["name"].as_str().unwrap_or("load balancer").to_string();
+        let workflow = Workflow::new(
+            self.inner(),
+            "create_load_balancer_and_wait",
+            format!("load balancer {name}"),
+        );
+        let created: LoadBalancerEnvelope = workflow
+            .step(
+                "create",
+                self.send_json(
+                    ApiRequest::post("loadBalancers_create", "/v2/load_balancers").json(body),
+                ),
+            )
+            .await?;
+        let id = created.load_balancer.id;
+        let path = format!("/v2/load_balancers/{id}");
+        let waiter = self
+            .waiter(format!("load balancer {id} to become active"), || async {
+                let fetched: LoadBalancerEnvelope = self
+                    .send_json(ApiRequest::get("loadBalancers_get", path.clone()))
+                    .await?;
+                Ok(fetched.load_balancer)
+            })
+            .until(|load_balancer| load_balancer.status == "active")
+            .fail_if(|load_balancer| {
+                (load_balancer.status == "errored")
+                    .then(|| Error::Other(format!("load balancer {id} errored")))
+            })
+            .state(|load_balancer| load_balancer.status.clone())
+            .typical_duration(|_| Some(Duration::from_secs(120)))
+            .options(options);
+        workflow.step("wait", waiter.wait()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_created_droplet_links_its_action() {
+        let created: CreatedDroplet = serde_json::from_str(
+            r#"{"droplet": {"id": 3164444, "name": "web-1", "status": "new",
+                "created_at": "2020-07-21T18:37:44Z"},
+                "links": {"actions": [{"id": 7515, "rel": "create",
+                "href": "https://api.digitalocean.com/v2/actions/7515"}]}}"#,
+        )
+        .unwrap();
+        assert_eq!(created.droplet.status, DropletStatus::New);
+        assert_eq!(created.links.actions[0].id, 7515);
+    }
+}